@@ -0,0 +1,9 @@
+-- Every row is a single pending delivery of an issue to one subscriber
+CREATE TABLE issue_delivery_queue(
+   newsletter_issue_id uuid NOT NULL
+      REFERENCES newsletter_issues (newsletter_issue_id),
+   subscriber_email TEXT NOT NULL,
+   n_retries INT NOT NULL DEFAULT 0,
+   execute_after timestamptz NOT NULL DEFAULT now(),
+   PRIMARY KEY (newsletter_issue_id, subscriber_email)
+);
@@ -0,0 +1,13 @@
+-- Saved responses of `POST /newsletters`, to answer retried requests. They
+-- go away with their user, old ones are deleted by
+-- `idempotency::run_expiry_until_stopped`
+CREATE TABLE idempotency(
+   user_id uuid NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+   idempotency_key TEXT NOT NULL,
+   response_status_code SMALLINT NULL,
+   response_headers TEXT NULL,
+   response_body BYTEA NULL,
+   created_at timestamptz NOT NULL,
+   PRIMARY KEY (user_id, idempotency_key)
+);
+CREATE INDEX idempotency_created_at_idx ON idempotency (created_at);
@@ -0,0 +1,11 @@
+-- Every subscriber gets a single token to leave the list
+CREATE TABLE unsubscribe_tokens(
+   unsubscribe_token TEXT NOT NULL,
+   subscriber_id uuid NOT NULL UNIQUE
+      REFERENCES subscriptions (id) ON DELETE CASCADE,
+   PRIMARY KEY (unsubscribe_token)
+);
+-- Existing subscribers: 25 alphanumeric chars, same as `SubscriberToken`
+INSERT INTO unsubscribe_tokens(unsubscribe_token, subscriber_id)
+SELECT substr(md5(random()::text || id::text), 1, 25), id
+FROM subscriptions;
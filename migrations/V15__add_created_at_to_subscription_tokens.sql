@@ -0,0 +1,2 @@
+-- Confirmation tokens expire, existing ones are counted from now
+ALTER TABLE subscription_tokens ADD COLUMN created_at timestamptz NOT NULL DEFAULT now();
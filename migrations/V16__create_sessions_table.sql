@@ -0,0 +1,10 @@
+-- Server-side admin sessions, the id is stored in the session cookie
+CREATE TABLE sessions(
+   session_id TEXT NOT NULL,
+   user_id uuid NOT NULL
+      REFERENCES users (user_id) ON DELETE CASCADE,
+   created_at timestamptz NOT NULL DEFAULT now(),
+   expires_at timestamptz NOT NULL,
+   PRIMARY KEY (session_id)
+);
+CREATE INDEX sessions_user_id_idx ON sessions (user_id);
@@ -0,0 +1,2 @@
+-- Admins need an address to receive password reset links
+ALTER TABLE users ADD COLUMN email TEXT NULL UNIQUE;
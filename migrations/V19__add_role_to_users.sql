@@ -0,0 +1,5 @@
+-- Admin roles: owners manage users, editors publish, viewers only read.
+-- Every existing user had full power, so they become owners.
+ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'owner'
+   CHECK (role IN ('owner', 'editor', 'viewer'));
+ALTER TABLE users ALTER COLUMN role DROP DEFAULT;
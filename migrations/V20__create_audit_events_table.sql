@@ -0,0 +1,15 @@
+-- Append-only log of security relevant admin actions, with where the
+-- request behind each came from. No foreign key on user_id, events must
+-- outlive the user who caused them.
+CREATE TABLE audit_events(
+   id uuid NOT NULL,
+   user_id uuid NULL,
+   action TEXT NOT NULL,
+   target TEXT NULL,
+   client_ip TEXT NULL,
+   user_agent TEXT NULL,
+   created_at timestamptz NOT NULL DEFAULT now(),
+   PRIMARY KEY (id)
+);
+CREATE INDEX audit_events_created_at_idx ON audit_events (created_at);
+CREATE INDEX audit_events_action_idx ON audit_events (action, created_at);
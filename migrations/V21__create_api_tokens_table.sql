@@ -0,0 +1,17 @@
+-- Per-user tokens for automation, sent as `Authorization: Bearer`.
+-- The prefix is stored in clear for lookup, the token only as SHA-256.
+CREATE TABLE api_tokens(
+   id uuid NOT NULL,
+   user_id uuid NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+   name TEXT NOT NULL,
+   prefix TEXT NOT NULL UNIQUE,
+   token_hash TEXT NOT NULL,
+   -- Space separated, e.g. 'newsletters:publish subscribers:read'
+   scopes TEXT NOT NULL,
+   created_at timestamptz NOT NULL DEFAULT now(),
+   expires_at timestamptz NULL,
+   revoked_at timestamptz NULL,
+   last_used_at timestamptz NULL,
+   PRIMARY KEY (id)
+);
+CREATE INDEX api_tokens_user_id_idx ON api_tokens (user_id);
@@ -0,0 +1,6 @@
+-- Optional TOTP second factor. The secret is set when enrollment starts
+-- and only enforced on login once the user confirmed a first code.
+ALTER TABLE users ADD COLUMN totp_secret TEXT NULL;
+ALTER TABLE users ADD COLUMN totp_enabled BOOLEAN NOT NULL DEFAULT false;
+-- Time step of the last accepted code, so a code can't be used twice.
+ALTER TABLE users ADD COLUMN totp_last_step BIGINT NULL;
@@ -0,0 +1,10 @@
+-- Single-use codes replacing a TOTP code, stored as HMAC-SHA256 with a key
+-- kept out of the database.
+CREATE TABLE recovery_codes(
+   id uuid NOT NULL,
+   user_id uuid NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+   code_hash TEXT NOT NULL,
+   used_at timestamptz NULL,
+   PRIMARY KEY (id)
+);
+CREATE INDEX recovery_codes_user_id_idx ON recovery_codes (user_id);
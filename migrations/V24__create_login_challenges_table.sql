@@ -0,0 +1,9 @@
+-- Logins which passed the password check and wait for the second factor.
+-- The browser keeps the challenge in a cookie, we keep its SHA-256.
+CREATE TABLE login_challenges(
+   challenge_hash TEXT NOT NULL,
+   user_id uuid NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+   expires_at timestamptz NOT NULL,
+   failed_attempts INT NOT NULL DEFAULT 0,
+   PRIMARY KEY (challenge_hash)
+);
@@ -0,0 +1,19 @@
+-- Failed password checks, counted per username and per client IP over a
+-- sliding window. The username is stored as typed, it may not exist.
+CREATE TABLE login_failures(
+   id uuid NOT NULL,
+   username TEXT NOT NULL,
+   client_ip TEXT NOT NULL,
+   failed_at timestamptz NOT NULL,
+   PRIMARY KEY (id)
+);
+CREATE INDEX login_failures_username_idx ON login_failures (username, failed_at);
+CREATE INDEX login_failures_client_ip_idx ON login_failures (client_ip, failed_at);
+
+-- Usernames and client IPs which exceeded their threshold. `subject` is
+-- `username:<name>` or `ip:<address>`.
+CREATE TABLE login_lockouts(
+   subject TEXT NOT NULL,
+   locked_until timestamptz NOT NULL,
+   PRIMARY KEY (subject)
+);
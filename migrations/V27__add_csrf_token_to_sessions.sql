@@ -0,0 +1,5 @@
+-- Synchronizer token for the forms of a session. Sessions started before
+-- this migration get a random one.
+ALTER TABLE sessions ADD COLUMN csrf_token TEXT NOT NULL
+   DEFAULT replace(gen_random_uuid()::text, '-', '');
+ALTER TABLE sessions ALTER COLUMN csrf_token DROP DEFAULT;
@@ -0,0 +1,11 @@
+-- Token buckets of the rate limiter in Postgres mode, shared by every
+-- instance. `key` is `<route>:ip:<address>` or `<route>:email:<address>`.
+-- Buckets are deleted once they are full again, a missing bucket is full.
+CREATE TABLE rate_limit_buckets(
+   key TEXT NOT NULL,
+   tokens DOUBLE PRECISION NOT NULL,
+   updated_at timestamptz NOT NULL,
+   full_at timestamptz NOT NULL,
+   PRIMARY KEY (key)
+);
+CREATE INDEX rate_limit_buckets_full_at_idx ON rate_limit_buckets (full_at);
@@ -0,0 +1,35 @@
+--! insert_api_token (expires_at?)
+INSERT INTO api_tokens(id, user_id, name, prefix, token_hash, scopes, expires_at)
+VALUES (:id, :user_id, :name, :prefix, :token_hash, :scopes, :expires_at);
+
+--! get_api_token_by_prefix : (expires_at?, revoked_at?)
+SELECT api_tokens.id, api_tokens.user_id, token_hash, scopes,
+   expires_at, revoked_at, users.role
+FROM api_tokens
+JOIN users ON users.user_id = api_tokens.user_id
+WHERE prefix = :prefix;
+
+--! touch_api_token
+UPDATE api_tokens SET last_used_at = now() WHERE id = :id;
+
+--! list_user_api_tokens : (expires_at?, revoked_at?, last_used_at?)
+SELECT id, name, prefix, scopes, created_at, expires_at, revoked_at,
+   last_used_at
+FROM api_tokens
+WHERE user_id = :user_id
+ORDER BY created_at DESC;
+
+--! list_api_tokens : (expires_at?, revoked_at?, last_used_at?)
+SELECT api_tokens.id, users.username, name, prefix, scopes, created_at,
+   expires_at, revoked_at, last_used_at
+FROM api_tokens
+JOIN users ON users.user_id = api_tokens.user_id
+ORDER BY users.username, created_at DESC;
+
+--! revoke_user_api_token
+UPDATE api_tokens SET revoked_at = now()
+WHERE id = :id AND user_id = :user_id AND revoked_at IS NULL;
+
+--! revoke_api_token
+UPDATE api_tokens SET revoked_at = now()
+WHERE id = :id AND revoked_at IS NULL;
@@ -0,0 +1,25 @@
+--! insert_audit_event (user_id?, target?, client_ip?, user_agent?)
+INSERT INTO audit_events(id, user_id, action, target, client_ip, user_agent)
+VALUES (:id, :user_id, :action, :target, :client_ip, :user_agent);
+
+--! list_audit_events (action?, username?, after_at?, after_id?) : (user_id?, username?, target?, client_ip?, user_agent?)
+SELECT
+    audit_events.id,
+    audit_events.user_id,
+    users.username,
+    audit_events.action,
+    audit_events.target,
+    audit_events.client_ip,
+    audit_events.user_agent,
+    audit_events.created_at
+FROM audit_events
+LEFT JOIN users ON users.user_id = audit_events.user_id
+WHERE (:action::text IS NULL OR audit_events.action = :action)
+    AND (:username::text IS NULL OR users.username = :username)
+    AND (
+        :after_at::timestamptz IS NULL
+        OR (audit_events.created_at, audit_events.id)
+            < (:after_at, :after_id::uuid)
+    )
+ORDER BY audit_events.created_at DESC, audit_events.id DESC
+LIMIT :limit;
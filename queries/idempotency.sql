@@ -0,0 +1,22 @@
+--! insert_idempotency_key
+INSERT INTO idempotency(user_id, idempotency_key, created_at)
+VALUES (:user_id, :idempotency_key, :created_at)
+ON CONFLICT DO NOTHING;
+
+--! get_saved_response
+SELECT response_status_code, response_headers, response_body
+FROM idempotency
+WHERE user_id = :user_id
+AND idempotency_key = :idempotency_key;
+
+--! save_response
+UPDATE idempotency
+SET response_status_code = :response_status_code,
+    response_headers = :response_headers,
+    response_body = :response_body
+WHERE user_id = :user_id
+AND idempotency_key = :idempotency_key;
+
+--! delete_expired_idempotency_keys
+DELETE FROM idempotency
+WHERE created_at < :expired_before;
@@ -0,0 +1,29 @@
+--! insert_login_failure
+INSERT INTO login_failures(id, username, client_ip, failed_at)
+VALUES (:id, :username, :client_ip, :failed_at);
+
+--! count_username_failures
+SELECT count(*) FROM login_failures
+WHERE username = :username AND failed_at > :since;
+
+--! count_client_ip_failures
+SELECT count(*) FROM login_failures
+WHERE client_ip = :client_ip AND failed_at > :since;
+
+--! delete_username_failures
+DELETE FROM login_failures WHERE username = :username;
+
+--! delete_old_login_failures
+DELETE FROM login_failures WHERE failed_at <= :since;
+
+--! lock_subject
+INSERT INTO login_lockouts(subject, locked_until)
+VALUES (:subject, :locked_until)
+ON CONFLICT (subject) DO UPDATE SET locked_until = EXCLUDED.locked_until;
+
+--! get_lockout
+SELECT locked_until FROM login_lockouts WHERE subject = :subject;
+
+--! unlock_expired_subject
+DELETE FROM login_lockouts
+WHERE subject = :subject AND locked_until <= :now;
@@ -7,3 +7,50 @@ WHERE status = 'confirmed';
 SELECT user_id, password_hash
 FROM users
 WHERE username = :name;
+
+--! insert_newsletter_issue
+INSERT INTO newsletter_issues(
+    newsletter_issue_id,
+    title,
+    text_content,
+    html_content,
+    published_at
+)
+VALUES (:newsletter_issue_id, :title, :text_content, :html_content, :published_at);
+
+--! enqueue_delivery_tasks
+INSERT INTO issue_delivery_queue(newsletter_issue_id, subscriber_email)
+SELECT :newsletter_issue_id, email
+FROM subscriptions
+WHERE status = 'confirmed';
+
+--! dequeue_task
+SELECT newsletter_issue_id, subscriber_email, n_retries
+FROM issue_delivery_queue
+WHERE execute_after <= now()
+LIMIT 1
+FOR UPDATE
+SKIP LOCKED;
+
+--! get_newsletter_issue
+SELECT title, text_content, html_content
+FROM newsletter_issues
+WHERE newsletter_issue_id = :newsletter_issue_id;
+
+--! delete_task
+DELETE FROM issue_delivery_queue
+WHERE newsletter_issue_id = :newsletter_issue_id
+AND subscriber_email = :subscriber_email;
+
+--! postpone_task
+UPDATE issue_delivery_queue
+SET n_retries = n_retries + 1, execute_after = :execute_after
+WHERE newsletter_issue_id = :newsletter_issue_id
+AND subscriber_email = :subscriber_email;
+
+--! delete_tasks_for_subscriber
+DELETE FROM issue_delivery_queue
+WHERE subscriber_email = :subscriber_email;
+
+--! count_delivery_tasks
+SELECT count(*) FROM issue_delivery_queue;
@@ -0,0 +1,17 @@
+--! delete_full_rate_limit_buckets
+DELETE FROM rate_limit_buckets WHERE full_at <= :now;
+
+--! insert_rate_limit_bucket
+INSERT INTO rate_limit_buckets(key, tokens, updated_at, full_at)
+VALUES (:key, :tokens, :now, :now)
+ON CONFLICT (key) DO NOTHING;
+
+--! get_rate_limit_bucket_for_update
+SELECT tokens, updated_at FROM rate_limit_buckets
+WHERE key = :key
+FOR UPDATE;
+
+--! update_rate_limit_bucket
+UPDATE rate_limit_buckets
+SET tokens = :tokens, updated_at = :updated_at, full_at = :full_at
+WHERE key = :key;
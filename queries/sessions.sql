@@ -0,0 +1,23 @@
+--! insert_session
+INSERT INTO sessions(session_id, user_id, csrf_token, expires_at)
+VALUES (:session_id, :user_id, :csrf_token, :expires_at);
+
+--! get_session_user
+SELECT sessions.user_id, users.role, sessions.csrf_token
+FROM sessions
+JOIN users ON users.user_id = sessions.user_id
+WHERE session_id = :session_id AND expires_at > now();
+
+--! delete_session
+DELETE FROM sessions
+WHERE session_id = :session_id;
+
+--! delete_expired_user_sessions
+DELETE FROM sessions
+WHERE user_id = :user_id AND expires_at <= now();
+
+--! delete_user_sessions
+DELETE FROM sessions WHERE user_id = :user_id;
+
+--! delete_other_user_sessions
+DELETE FROM sessions WHERE user_id = :user_id AND session_id <> :session_id;
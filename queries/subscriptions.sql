@@ -44,3 +44,80 @@ WITH RECURSIVE countdown(val) AS (
 SELECT *
 FROM countdown;
 
+
+--! insert_unsubscribe_token
+INSERT INTO unsubscribe_tokens(unsubscribe_token, subscriber_id)
+VALUES (:unsubscribe_token, :subscriber_id);
+
+--! get_unsubscribe_token
+SELECT unsubscribe_token
+FROM unsubscribe_tokens
+JOIN subscriptions ON subscriptions.id = unsubscribe_tokens.subscriber_id
+WHERE subscriptions.email = :email;
+
+--! get_subscriber_by_unsubscribe_token
+SELECT id, email, status
+FROM subscriptions
+WHERE id = (
+    SELECT subscriber_id
+    FROM unsubscribe_tokens
+    WHERE unsubscribe_token = :unsubscribe_token
+);
+
+--! unsubscribe_subscriber
+UPDATE subscriptions SET status = 'unsubscribed' WHERE id = :sub_id;
+
+--! resubscribe
+UPDATE subscriptions SET status = 'pending_confirmation' WHERE email = :email AND status = 'unsubscribed';
+
+--! get_token_details
+SELECT subscriber_id, created_at
+FROM subscription_tokens
+WHERE subscription_token = :sub_token;
+
+--! get_pending_subscriber
+SELECT subscriptions.name, subscription_tokens.created_at AS token_created_at
+FROM subscriptions
+JOIN subscription_tokens ON subscription_tokens.subscriber_id = subscriptions.id
+WHERE subscriptions.email = :email AND subscriptions.status = 'pending_confirmation'
+ORDER BY subscription_tokens.created_at DESC
+LIMIT 1;
+
+--! delete_expired_pending_subscribers
+DELETE FROM subscriptions
+WHERE status = 'pending_confirmation'
+AND NOT EXISTS (
+    SELECT 1
+    FROM subscription_tokens
+    WHERE subscription_tokens.subscriber_id = subscriptions.id
+    AND subscription_tokens.created_at > :expired_before
+);
+
+--! list_subscribers (status?, search?, after_at?, after_id?)
+SELECT id, email, name, status, subscribed_at
+FROM subscriptions
+WHERE (:status::text IS NULL OR status = :status)
+    AND (
+        :search::text IS NULL
+        OR starts_with(lower(email), lower(:search))
+        OR starts_with(lower(name), lower(:search))
+    )
+    AND (
+        :after_at::timestamptz IS NULL
+        OR (subscribed_at, id) < (:after_at, :after_id::uuid)
+    )
+ORDER BY subscribed_at DESC, id DESC
+LIMIT :limit;
+
+--! delete_subscriber
+DELETE FROM subscriptions WHERE id = :sub_id RETURNING email;
+
+--! import_subscriber
+INSERT INTO subscriptions(id, email, name, subscribed_at, status)
+VALUES (:id, :email, :name, :subscribed_at, :status)
+ON CONFLICT (email) DO NOTHING;
+
+--! export_subscribers
+SELECT email, name, status, subscribed_at
+FROM subscriptions
+ORDER BY subscribed_at, id;
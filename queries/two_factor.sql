@@ -0,0 +1,61 @@
+--! get_totp_settings : (totp_secret?, totp_last_step?)
+SELECT totp_secret, totp_enabled, totp_last_step
+FROM users
+WHERE user_id = :user_id;
+
+--! start_totp_enrollment
+UPDATE users
+SET totp_secret = :totp_secret, totp_enabled = false, totp_last_step = NULL
+WHERE user_id = :user_id AND totp_enabled = false;
+
+--! enable_totp
+UPDATE users
+SET totp_enabled = true, totp_last_step = :step
+WHERE user_id = :user_id AND totp_secret IS NOT NULL;
+
+--! disable_totp
+UPDATE users
+SET totp_secret = NULL, totp_enabled = false, totp_last_step = NULL
+WHERE user_id = :user_id;
+
+--! use_totp_step
+UPDATE users
+SET totp_last_step = :step
+WHERE user_id = :user_id
+    AND (totp_last_step IS NULL OR totp_last_step < :step);
+
+--! insert_recovery_code
+INSERT INTO recovery_codes(id, user_id, code_hash)
+VALUES (:id, :user_id, :code_hash);
+
+--! delete_recovery_codes
+DELETE FROM recovery_codes WHERE user_id = :user_id;
+
+--! use_recovery_code
+UPDATE recovery_codes
+SET used_at = :used_at
+WHERE user_id = :user_id AND code_hash = :code_hash AND used_at IS NULL;
+
+--! count_unused_recovery_codes
+SELECT count(*) FROM recovery_codes
+WHERE user_id = :user_id AND used_at IS NULL;
+
+--! insert_login_challenge
+INSERT INTO login_challenges(challenge_hash, user_id, expires_at)
+VALUES (:challenge_hash, :user_id, :expires_at);
+
+--! get_login_challenge_user
+SELECT user_id FROM login_challenges
+WHERE challenge_hash = :challenge_hash AND expires_at > :now;
+
+--! record_failed_challenge
+UPDATE login_challenges
+SET failed_attempts = failed_attempts + 1
+WHERE challenge_hash = :challenge_hash
+RETURNING failed_attempts;
+
+--! delete_login_challenge
+DELETE FROM login_challenges WHERE challenge_hash = :challenge_hash;
+
+--! delete_expired_login_challenges
+DELETE FROM login_challenges WHERE expires_at <= :now;
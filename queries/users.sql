@@ -0,0 +1,61 @@
+--! get_username
+SELECT username
+FROM users
+WHERE user_id = :user_id;
+
+--! update_password_hash
+UPDATE users SET password_hash = :password_hash WHERE user_id = :user_id;
+
+--! get_user_id_by_email
+SELECT user_id
+FROM users
+WHERE email = :email;
+
+--! insert_password_reset_token
+INSERT INTO password_reset_tokens(token_hash, user_id, expires_at)
+VALUES (:token_hash, :user_id, :expires_at);
+
+--! get_password_reset_token_expiry
+SELECT expires_at
+FROM password_reset_tokens
+WHERE token_hash = :token_hash;
+
+--! consume_password_reset_token
+DELETE FROM password_reset_tokens
+WHERE token_hash = :token_hash
+RETURNING user_id, expires_at;
+
+--! delete_password_reset_tokens
+DELETE FROM password_reset_tokens WHERE user_id = :user_id;
+
+--! insert_user (email?)
+INSERT INTO users(user_id, username, password_hash, email, role)
+VALUES (:user_id, :username, :password_hash, :email, :role);
+
+--! list_users : (email?)
+SELECT user_id, username, email, role
+FROM users
+ORDER BY username;
+
+--! delete_user_by_id
+DELETE FROM users WHERE user_id = :user_id RETURNING username;
+
+--! get_user_role
+SELECT role
+FROM users
+WHERE user_id = :user_id;
+
+--! update_user_role
+UPDATE users SET role = :role WHERE user_id = :user_id
+RETURNING username;
+
+--! get_user_by_username
+SELECT user_id, role
+FROM users
+WHERE username = :username;
+
+--! lock_owners
+SELECT user_id
+FROM users
+WHERE role = 'owner'
+FOR UPDATE;
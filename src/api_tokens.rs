@@ -0,0 +1,259 @@
+//! src/api_tokens.rs
+//!
+//! Per-user API tokens for automation, an alternative to sending the
+//! username and password with every request.
+
+use anyhow::Context;
+use axum::response::{IntoResponse, Response};
+use cornucopia_async::GenericClient;
+use deadpool_postgres::Pool;
+use http::{HeaderMap, StatusCode};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::audit::{self, RequestOrigin};
+use crate::authentication::{authorize, AuthError, Forbidden};
+use crate::cornucopia::queries::api_tokens;
+use crate::domain::{ApiScope, UserRole};
+use crate::validation::api_token::ApiToken;
+use crate::{constant_time_eq, error_chain_fmt};
+
+#[derive(thiserror::Error)]
+pub enum CreateApiTokenError {
+    /// Invalid input, the message can be shown to the user as is.
+    #[error("{0}")]
+    Rejected(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for CreateApiTokenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+/// The token was valid, but wasn't granted the scope of the request.
+#[derive(thiserror::Error, Debug)]
+pub enum ApiForbidden {
+    #[error("This API token doesn't have the {0} scope.")]
+    MissingScope(ApiScope),
+    #[error(transparent)]
+    Forbidden(#[from] Forbidden),
+}
+
+impl IntoResponse for ApiForbidden {
+    fn into_response(self) -> Response {
+        (StatusCode::FORBIDDEN, self.to_string()).into_response()
+    }
+}
+
+/// Owner of a valid API token, with what the token allows.
+#[derive(Debug)]
+pub struct ApiCaller {
+    pub user_id: Uuid,
+    pub token_id: Uuid,
+    pub role: UserRole,
+    pub scopes: Vec<ApiScope>,
+}
+
+impl ApiCaller {
+    /// The token needs the scope and its owner still needs the role for
+    /// it, a demoted user's tokens lose their write scopes. Refusals are
+    /// written to the audit log like `authentication::authorize` does.
+    pub async fn require(
+        &self,
+        pool: &Pool,
+        origin: &RequestOrigin,
+        scope: ApiScope,
+        target: &str,
+    ) -> Result<(), ApiForbidden> {
+        if !self.scopes.contains(&scope) {
+            tracing::warn!(%scope, "API token without the required scope");
+            let target = format!("{} {}", scope, target);
+            let recorded = match pool.get().await {
+                Ok(client) => {
+                    audit::record_event(
+                        &client,
+                        origin,
+                        Some(self.user_id),
+                        audit::FORBIDDEN,
+                        Some(&target),
+                    )
+                    .await
+                }
+                Err(e) => Err(anyhow::Error::new(e)
+                    .context("Failed to get connection from pool")),
+            };
+            // The request is refused either way.
+            if let Err(e) = recorded {
+                tracing::error!("{:?}", e);
+            }
+            return Err(ApiForbidden::MissingScope(scope));
+        }
+        if let Some(permission) = scope.permission() {
+            authorize(
+                pool,
+                origin,
+                self.user_id,
+                self.role,
+                permission,
+                target,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Take the token from an `Authorization: Bearer` header. `Ok(None)`
+/// if the request uses another scheme or no `Authorization` at all.
+pub fn bearer_token(
+    headers: &HeaderMap,
+) -> Result<Option<ApiToken>, anyhow::Error> {
+    let Some(header_value) = headers.get(http::header::AUTHORIZATION) else {
+        return Ok(None);
+    };
+    let header_value = header_value
+        .to_str()
+        .context("The 'Authorization' header was not a valid UTF8 string.")?;
+    let Some(token) = header_value.strip_prefix("Bearer ") else {
+        return Ok(None);
+    };
+    ApiToken::parse(token.trim())
+        .map(Some)
+        .map_err(anyhow::Error::msg)
+}
+
+/// Create a token for the user, only its hash is stored. The returned
+/// token can't be recovered later, it must be shown to the user now.
+#[tracing::instrument(name = "Create API token", skip(client))]
+pub async fn create_api_token<C: GenericClient>(
+    client: &C,
+    user_id: Uuid,
+    role: UserRole,
+    name: &str,
+    scopes: &[ApiScope],
+    expires_at: Option<OffsetDateTime>,
+) -> Result<(Uuid, ApiToken), CreateApiTokenError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > 64 {
+        return Err(CreateApiTokenError::Rejected(
+            "Token name must be from 1 to 64 characters long.".into(),
+        ));
+    }
+    if scopes.is_empty() {
+        return Err(CreateApiTokenError::Rejected(
+            "Choose at least one scope.".into(),
+        ));
+    }
+    if let Some(scope) = scopes.iter().find(|s| !s.allowed_for(role)) {
+        return Err(CreateApiTokenError::Rejected(format!(
+            "The {} role can't have the {} scope.",
+            role, scope
+        )));
+    }
+
+    let token_id = Uuid::new_v4();
+    let token = ApiToken::generate();
+    api_tokens::insert_api_token()
+        .bind(
+            client,
+            &token_id,
+            &user_id,
+            &name,
+            &token.prefix(),
+            &token.hash().as_str(),
+            &ApiScope::join(scopes).as_str(),
+            &expires_at,
+        )
+        .await
+        .context("Failed to store a new API token")?;
+    Ok((token_id, token))
+}
+
+/// Find the owner of a token. Unknown, revoked and expired tokens are
+/// all `InvalidCredentials`.
+#[tracing::instrument(name = "Authenticate API token", skip_all)]
+pub async fn authenticate<C: GenericClient>(
+    token: &ApiToken,
+    client: &C,
+) -> Result<ApiCaller, AuthError> {
+    let stored = api_tokens::get_api_token_by_prefix()
+        .bind(client, &token.prefix())
+        .opt()
+        .await
+        .context("Failed to fetch an API token")?
+        .ok_or_else(|| {
+            AuthError::InvalidCredentials(anyhow::anyhow!("Unknown API token"))
+        })?;
+    if !constant_time_eq(&stored.token_hash, &token.hash()) {
+        return Err(AuthError::InvalidCredentials(anyhow::anyhow!(
+            "API token doesn't match the stored hash"
+        )));
+    }
+    if stored.revoked_at.is_some() {
+        return Err(AuthError::InvalidCredentials(anyhow::anyhow!(
+            "API token was revoked"
+        )));
+    }
+    if stored
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= OffsetDateTime::now_utc())
+    {
+        return Err(AuthError::InvalidCredentials(anyhow::anyhow!(
+            "API token has expired"
+        )));
+    }
+    let role = UserRole::parse(&stored.role).map_err(anyhow::Error::msg)?;
+    let scopes =
+        ApiScope::parse_list(&stored.scopes).map_err(anyhow::Error::msg)?;
+    api_tokens::touch_api_token()
+        .bind(client, &stored.id)
+        .await
+        .context("Failed to update API token last use")?;
+    Ok(ApiCaller {
+        user_id: stored.user_id,
+        token_id: stored.id,
+        role,
+        scopes,
+    })
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use http::{HeaderMap, HeaderValue};
+
+    use super::bearer_token;
+    use crate::validation::api_token::ApiToken;
+
+    fn headers(authorization: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            http::header::AUTHORIZATION,
+            HeaderValue::from_str(authorization).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn bearer_token_is_taken_from_the_header() {
+        let token = ApiToken::generate();
+        let parsed =
+            bearer_token(&headers(&format!("Bearer {}", token.as_ref())))
+                .unwrap()
+                .unwrap();
+        assert_eq!(parsed.as_ref(), token.as_ref());
+    }
+
+    #[test]
+    fn other_schemes_are_not_bearer_tokens() {
+        assert!(bearer_token(&HeaderMap::new()).unwrap().is_none());
+        assert!(bearer_token(&headers("Basic dXNlcjpwYXNz"))
+            .unwrap()
+            .is_none());
+        assert!(bearer_token(&headers("Bearer nonsense")).is_err());
+    }
+}
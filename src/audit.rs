@@ -0,0 +1,244 @@
+//! src/audit.rs
+
+use anyhow::Context;
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use cornucopia_async::GenericClient;
+use http::request::Parts;
+use http::StatusCode;
+use uuid::Uuid;
+
+use crate::client_ip::ClientIp;
+use crate::cornucopia::queries::audit;
+use crate::cornucopia::queries::audit::ListAuditEvents;
+use crate::pagination::Cursor;
+use crate::startup::AppState;
+
+/// Action recorded when a user tries something their role doesn't allow.
+pub const FORBIDDEN: &str = "forbidden";
+/// Session started, after the second factor where it is on.
+pub const LOGIN: &str = "login";
+/// Wrong password. `target` is the username as typed.
+pub const LOGIN_FAILED: &str = "login_failed";
+pub const LOGOUT: &str = "logout";
+/// `target` is the issue id.
+pub const NEWSLETTER_PUBLISHED: &str = "newsletter_published";
+/// `target` is the subscriber id, not the email: deleted subscribers
+/// shouldn't live on in the log.
+pub const SUBSCRIBER_CONFIRMED: &str = "subscriber_confirmed";
+pub const SUBSCRIBER_DELETED: &str = "subscriber_deleted";
+/// `target` is the username of the changed user, with the new role where
+/// it applies.
+pub const USER_CREATED: &str = "user_created";
+pub const USER_ROLE_CHANGED: &str = "user_role_changed";
+pub const USER_DELETED: &str = "user_deleted";
+/// The user changed their own password.
+pub const PASSWORD_CHANGED: &str = "password_changed";
+/// The password was set through an emailed reset link.
+pub const PASSWORD_RESET: &str = "password_reset";
+/// `target` is the token id.
+pub const API_TOKEN_CREATED: &str = "api_token_created";
+pub const API_TOKEN_REVOKED: &str = "api_token_revoked";
+
+/// Every action the log may contain, for filters.
+pub const ACTIONS: [&str; 19] = [
+    FORBIDDEN,
+    LOGIN,
+    LOGIN_FAILED,
+    LOGOUT,
+    crate::login_lockout::LOGIN_LOCKED,
+    crate::login_lockout::LOGIN_UNLOCKED,
+    crate::two_factor::TWO_FACTOR_ENABLED,
+    crate::two_factor::TWO_FACTOR_DISABLED,
+    crate::two_factor::RECOVERY_CODE_USED,
+    NEWSLETTER_PUBLISHED,
+    SUBSCRIBER_CONFIRMED,
+    SUBSCRIBER_DELETED,
+    USER_CREATED,
+    USER_ROLE_CHANGED,
+    USER_DELETED,
+    API_TOKEN_CREATED,
+    API_TOKEN_REVOKED,
+    PASSWORD_CHANGED,
+    PASSWORD_RESET,
+];
+
+/// Longest user agent we store, longer ones are cut.
+const MAX_USER_AGENT_LENGTH: usize = 256;
+
+/// Where the request behind an event came from.
+#[derive(Debug, Clone)]
+pub struct RequestOrigin {
+    pub client_ip: ClientIp,
+    pub user_agent: Option<String>,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for RequestOrigin {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let client_ip = ClientIp::from_request_parts(parts, state).await?;
+        let user_agent = parts
+            .headers
+            .get(http::header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.chars().take(MAX_USER_AGENT_LENGTH).collect());
+        Ok(RequestOrigin {
+            client_ip,
+            user_agent,
+        })
+    }
+}
+
+/// Who a user management event is attributed to.
+#[derive(Debug, Clone, Copy)]
+pub enum Actor<'a> {
+    /// An admin user, over HTTP.
+    User {
+        user_id: Uuid,
+        origin: &'a RequestOrigin,
+    },
+    /// `zero2prod-admin`. There is no client IP, the user agent names the
+    /// binary and the OS user who ran it.
+    CommandLine,
+}
+
+impl Actor<'_> {
+    pub async fn record_event<C: GenericClient>(
+        &self,
+        client: &C,
+        action: &str,
+        target: Option<&str>,
+    ) -> Result<(), anyhow::Error> {
+        match self {
+            Actor::User { user_id, origin } => {
+                record_event(client, origin, Some(*user_id), action, target)
+                    .await
+            }
+            Actor::CommandLine => {
+                insert_event(
+                    client,
+                    None,
+                    action,
+                    target,
+                    None,
+                    Some(command_line_user_agent()),
+                )
+                .await
+            }
+        }
+    }
+}
+
+/// Append an event to the `audit_events` table. `user_id` is the actor,
+/// `target` describes what the action was applied to.
+pub async fn record_event<C: GenericClient>(
+    client: &C,
+    origin: &RequestOrigin,
+    user_id: Option<Uuid>,
+    action: &str,
+    target: Option<&str>,
+) -> Result<(), anyhow::Error> {
+    insert_event(
+        client,
+        user_id,
+        action,
+        target,
+        Some(origin.client_ip.to_string()),
+        origin.user_agent.clone(),
+    )
+    .await
+}
+
+#[tracing::instrument(
+    name = "Record audit event",
+    skip(client, client_ip, user_agent)
+)]
+async fn insert_event<C: GenericClient>(
+    client: &C,
+    user_id: Option<Uuid>,
+    action: &str,
+    target: Option<&str>,
+    client_ip: Option<String>,
+    user_agent: Option<String>,
+) -> Result<(), anyhow::Error> {
+    audit::insert_audit_event()
+        .bind(
+            client,
+            &Uuid::new_v4(),
+            &user_id,
+            &action,
+            &target,
+            &client_ip,
+            &user_agent,
+        )
+        .await
+        .context("Failed to store an audit event")?;
+    Ok(())
+}
+
+fn command_line_user_agent() -> String {
+    let os_user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string());
+    format!("zero2prod-admin (user {})", os_user)
+        .chars()
+        .take(MAX_USER_AGENT_LENGTH)
+        .collect()
+}
+
+/// Filters of the audit log pages, `None` matches everything.
+#[derive(Debug, Default)]
+pub struct EventFilter<'a> {
+    pub action: Option<&'a str>,
+    pub username: Option<&'a str>,
+    /// Position of the last event of the previous page.
+    pub after: Option<Cursor>,
+}
+
+impl EventFilter<'_> {
+    /// Only the actions from `ACTIONS` exist, anything else is a typo.
+    pub fn validate(&self) -> Result<(), String> {
+        match self.action {
+            Some(action) if !ACTIONS.contains(&action) => {
+                Err(format!("Unknown audit action: {}", action))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// One page of events, newest first, with the cursor to pass as `after`
+/// for the next page. Keyset pagination like the subscribers list, so pages
+/// stay stable while new events come in.
+#[tracing::instrument(name = "List audit events", skip(client))]
+pub async fn list_events<C: GenericClient>(
+    client: &C,
+    filter: &EventFilter<'_>,
+    limit: i64,
+) -> Result<(Vec<ListAuditEvents>, Option<Cursor>), anyhow::Error> {
+    // Fetch one extra row to know whether there is a next page.
+    let mut events = audit::list_audit_events()
+        .bind(
+            client,
+            &filter.action,
+            &filter.username,
+            &filter.after.map(|after| after.at),
+            &filter.after.map(|after| after.id),
+            &(limit + 1),
+        )
+        .all()
+        .await
+        .context("Failed to fetch audit events")?;
+    let next_after = if events.len() > limit as usize {
+        events.truncate(limit as usize);
+        events.last().map(|last| Cursor::new(last.created_at, last.id))
+    } else {
+        None
+    };
+    Ok((events, next_after))
+}
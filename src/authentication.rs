@@ -1,12 +1,22 @@
 //! src/authentication.rs
 
 use anyhow::Context;
-use argon2::{Argon2, PasswordHash, PasswordVerifier};
-use deadpool_postgres::Client;
+use argon2::password_hash::SaltString;
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher};
+use argon2::{PasswordVerifier, Version};
+use axum::response::{IntoResponse, Response};
+use cornucopia_async::GenericClient;
+use deadpool_postgres::{Client, Pool};
+use http::StatusCode;
 use secrecy::{ExposeSecret, Secret};
+use uuid::Uuid;
 
+use crate::audit::{self, RequestOrigin};
+use crate::configuration::PasswordHashSettings;
 use crate::cornucopia::queries::newsletters::query_user_id_by_credentials;
 use crate::cornucopia::queries::newsletters::QueryUserIdByCredentials;
+use crate::cornucopia::queries::users;
+use crate::domain::{AdminPassword, Permission, UserRole};
 use crate::telemetry::spawn_blocking_with_tracing;
 
 #[derive(thiserror::Error, Debug)]
@@ -17,20 +27,111 @@ pub enum AuthError {
     UnexpectedError(#[from] anyhow::Error),
 }
 
+/// The role of the user doesn't allow the requested action.
+#[derive(thiserror::Error, Debug)]
+#[error("Your role doesn't allow this action.")]
+pub struct Forbidden;
+
+impl IntoResponse for Forbidden {
+    fn into_response(self) -> Response {
+        (StatusCode::FORBIDDEN, self.to_string()).into_response()
+    }
+}
+
 #[allow(dead_code)]
 pub struct Credentials {
     pub username: String,
     pub password: Secret<String>,
 }
 
+/// Argon2id with the configured costs. Also keeps a dummy hash with the
+/// same costs: verifying it for unknown usernames takes as long as
+/// verifying a real hash.
+#[derive(Clone)]
+pub struct PasswordHashing {
+    params: Params,
+    dummy_hash: Secret<String>,
+}
+
+impl PasswordHashing {
+    pub fn new(settings: &PasswordHashSettings) -> Result<Self, anyhow::Error> {
+        let params = Params::new(
+            settings.memory_cost,
+            settings.time_cost,
+            settings.parallelism,
+            None,
+        )
+        .map_err(|e| anyhow::anyhow!("Invalid Argon2 parameters: {e}"))?;
+        let mut hashing = Self {
+            params,
+            dummy_hash: Secret::new(String::new()),
+        };
+        hashing.dummy_hash =
+            hashing.hash(Secret::new(Uuid::new_v4().to_string()))?;
+        Ok(hashing)
+    }
+
+    fn argon2(&self) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+
+    /// Hash a password as Argon2id PHC string with a fresh salt.
+    pub fn hash(
+        &self,
+        password: Secret<String>,
+    ) -> Result<Secret<String>, anyhow::Error> {
+        let salt = SaltString::generate(&mut rand::thread_rng());
+        let password_hash = self
+            .argon2()
+            .hash_password(password.expose_secret().as_bytes(), &salt)?
+            .to_string();
+        Ok(Secret::new(password_hash))
+    }
+
+    /// Hashes of another algorithm, or with any cost below the configured
+    /// one, have to be upgraded.
+    fn is_outdated(&self, password_hash: &PasswordHash) -> bool {
+        if password_hash.algorithm != Algorithm::Argon2id.ident()
+            || password_hash.version != Some(Version::V0x13 as u32)
+        {
+            return true;
+        }
+        match Params::try_from(password_hash) {
+            Ok(params) => {
+                params.m_cost() < self.params.m_cost()
+                    || params.t_cost() < self.params.t_cost()
+                    || params.p_cost() < self.params.p_cost()
+            }
+            Err(_) => true,
+        }
+    }
+
+    /// A new hash of the verified password when the stored one is
+    /// outdated.
+    fn upgrade(
+        &self,
+        expected_password_hash: &Secret<String>,
+        password: Secret<String>,
+    ) -> Result<Option<Secret<String>>, anyhow::Error> {
+        let expected_password_hash =
+            PasswordHash::new(expected_password_hash.expose_secret())
+                .context("Failed to parse hash in PHC string format.")?;
+        if !self.is_outdated(&expected_password_hash) {
+            return Ok(None);
+        }
+        self.hash(password).map(Some)
+    }
+}
+
 // You might have also noticed that we no longer deal with the
 // salt directly - PHC string format takes care of it for us, implicitly.
 #[tracing::instrument(
     name = "Validate credentials",
-    skip(username, password, client)
+    skip(username, password, hashing, client)
 )]
 pub async fn validate_credentials(
     Credentials { username, password }: Credentials,
+    hashing: &PasswordHashing,
     client: &Client,
 ) -> Result<uuid::Uuid, AuthError> {
     let (user_id, expected_password_hash) =
@@ -38,15 +139,7 @@ pub async fn validate_credentials(
             Ok(Some(query)) => {
                 (Some(query.user_id), Secret::new(query.password_hash))
             }
-            Ok(None) => (
-                None,
-                Secret::new(
-                    "$argon2id$v=19$m=15000,t=2,p=1$\
-            gZiV/M1gPc22ElAH/Jh1Hw$\
-            CWOrkoo7oJBQ/iyh7uJ0LO2aLEfrHwTWllSAxT0zRno"
-                        .to_string(),
-                ),
-            ),
+            Ok(None) => (None, hashing.dummy_hash.clone()),
             Err(e) => {
                 return Err(AuthError::InvalidCredentials(anyhow::anyhow!(
                     "Unknown username: {e}"
@@ -54,17 +147,33 @@ pub async fn validate_credentials(
             }
         };
 
-    spawn_blocking_with_tracing(move || {
-        verify_password_hash(expected_password_hash, password)
+    let hashing = hashing.clone();
+    let upgraded_password_hash = spawn_blocking_with_tracing(move || {
+        verify_password_hash(&expected_password_hash, &password)?;
+        Ok::<_, AuthError>(hashing.upgrade(&expected_password_hash, password))
     })
     .await
     .context("Invalid password.")
     .map_err(AuthError::UnexpectedError)??;
 
-    user_id.ok_or_else(|| {
+    let user_id = user_id.ok_or_else(|| {
         // We don't tell that it is unknown username
         AuthError::InvalidCredentials(anyhow::anyhow!("Failed to auth."))
-    })
+    })?;
+
+    // The password is right, failing to upgrade its hash must not
+    // lock the user out.
+    let upgraded = match upgraded_password_hash {
+        Ok(Some(password_hash)) => {
+            store_password_hash(user_id, &password_hash, client).await
+        }
+        Ok(None) => Ok(()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = upgraded {
+        tracing::error!("Failed to upgrade password hash: {:?}", e);
+    }
+    Ok(user_id)
 }
 
 #[tracing::instrument(name = "Get stored credentials", skip(username, client))]
@@ -85,13 +194,14 @@ async fn get_stored_credentials(
     skip(expected_password_hash, password_candidate)
 )]
 fn verify_password_hash(
-    expected_password_hash: Secret<String>,
-    password_candidate: Secret<String>,
+    expected_password_hash: &Secret<String>,
+    password_candidate: &Secret<String>,
 ) -> Result<(), AuthError> {
     let expected_password_hash =
-        PasswordHash::new(&expected_password_hash.expose_secret())
+        PasswordHash::new(expected_password_hash.expose_secret())
             .context("Failed to parse hash in PHC string format.")
             .map_err(AuthError::UnexpectedError)?;
+    // Costs are taken from the PHC string.
     Argon2::default()
         .verify_password(
             password_candidate.expose_secret().as_bytes(),
@@ -100,3 +210,160 @@ fn verify_password_hash(
         .context("Invalid password")
         .map_err(AuthError::InvalidCredentials)
 }
+
+#[tracing::instrument(
+    name = "Upgrade password hash",
+    skip(password_hash, client)
+)]
+async fn store_password_hash<C: GenericClient>(
+    user_id: Uuid,
+    password_hash: &Secret<String>,
+    client: &C,
+) -> Result<(), anyhow::Error> {
+    users::update_password_hash()
+        .bind(client, &password_hash.expose_secret().as_str(), &user_id)
+        .await
+        .context("Failed to store an upgraded password hash")?;
+    tracing::info!("Password hash upgraded");
+    Ok(())
+}
+
+/// Store a new password for the user as an Argon2id PHC string.
+#[tracing::instrument(
+    name = "Change password",
+    skip(password, hashing, client)
+)]
+pub async fn change_password<C: GenericClient>(
+    user_id: Uuid,
+    password: AdminPassword,
+    hashing: &PasswordHashing,
+    client: &C,
+) -> Result<(), anyhow::Error> {
+    let password = password.into_secret();
+    let hashing = hashing.clone();
+    let password_hash =
+        spawn_blocking_with_tracing(move || hashing.hash(password))
+            .await?
+            .context("Failed to hash password")?;
+    users::update_password_hash()
+        .bind(client, &password_hash.expose_secret().as_str(), &user_id)
+        .await
+        .context("Failed to change user's password in the database.")?;
+    Ok(())
+}
+
+pub async fn get_user_role<C: GenericClient>(
+    user_id: Uuid,
+    client: &C,
+) -> Result<UserRole, anyhow::Error> {
+    let role = users::get_user_role()
+        .bind(client, &user_id)
+        .one()
+        .await
+        .context("Failed to fetch user's role")?;
+    UserRole::parse(&role).map_err(anyhow::Error::msg)
+}
+
+/// Check that `role` allows `permission`. Refusals are written to the
+/// audit log, `target` tells what the user tried to act on.
+#[tracing::instrument(name = "Authorize", skip(pool, origin))]
+pub async fn authorize(
+    pool: &Pool,
+    origin: &RequestOrigin,
+    user_id: Uuid,
+    role: UserRole,
+    permission: Permission,
+    target: &str,
+) -> Result<(), Forbidden> {
+    if role.allows(permission) {
+        return Ok(());
+    }
+    tracing::warn!("Forbidden action");
+    let target = format!("{} {}", permission.as_str(), target);
+    let recorded =
+        match pool.get().await {
+            Ok(client) => {
+                audit::record_event(
+                    &client,
+                    origin,
+                    Some(user_id),
+                    audit::FORBIDDEN,
+                    Some(&target),
+                )
+                .await
+            }
+            Err(e) => Err(anyhow::Error::new(e)
+                .context("Failed to get connection from pool")),
+        };
+    // The request is refused either way.
+    if let Err(e) = recorded {
+        tracing::error!("{:?}", e);
+    }
+    Err(Forbidden)
+}
+
+#[cfg(test)]
+mod tests {
+    use argon2::PasswordHash;
+    use secrecy::{ExposeSecret, Secret};
+
+    use super::PasswordHashing;
+    use crate::configuration::PasswordHashSettings;
+
+    fn hashing(memory_cost: u32, time_cost: u32) -> PasswordHashing {
+        PasswordHashing::new(&PasswordHashSettings {
+            memory_cost,
+            time_cost,
+            parallelism: 1,
+        })
+        .unwrap()
+    }
+
+    fn hash(hashing: &PasswordHashing) -> Secret<String> {
+        hashing.hash(Secret::new("password".to_string())).unwrap()
+    }
+
+    #[test]
+    fn hashes_with_lower_costs_are_outdated() {
+        let target = hashing(2048, 2);
+        for weaker in [hashing(1024, 2), hashing(2048, 1)] {
+            let password_hash = hash(&weaker);
+            let password_hash =
+                PasswordHash::new(password_hash.expose_secret()).unwrap();
+            assert!(target.is_outdated(&password_hash));
+        }
+        for same_or_stronger in [hashing(2048, 2), hashing(4096, 3)] {
+            let password_hash = hash(&same_or_stronger);
+            let password_hash =
+                PasswordHash::new(password_hash.expose_secret()).unwrap();
+            assert!(!target.is_outdated(&password_hash));
+        }
+    }
+
+    #[test]
+    fn argon2i_hashes_are_outdated() {
+        let password_hash = "$argon2i$v=19$m=2048,t=2,p=1$\
+            c29tZXNhbHQ$fk0m+2h2zZ2jS4l5dWOn3b0VHxNC6cEQ5sGBqXk4jzU";
+        let password_hash = PasswordHash::new(password_hash).unwrap();
+        assert!(hashing(2048, 2).is_outdated(&password_hash));
+    }
+
+    #[test]
+    fn dummy_hash_uses_the_configured_costs() {
+        let hashing = hashing(3072, 3);
+        let dummy_hash =
+            PasswordHash::new(hashing.dummy_hash.expose_secret()).unwrap();
+        let params = argon2::Params::try_from(&dummy_hash).unwrap();
+        assert_eq!((params.m_cost(), params.t_cost()), (3072, 3));
+    }
+
+    #[test]
+    fn invalid_costs_are_rejected() {
+        assert!(PasswordHashing::new(&PasswordHashSettings {
+            memory_cost: 1,
+            time_cost: 0,
+            parallelism: 1,
+        })
+        .is_err());
+    }
+}
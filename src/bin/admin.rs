@@ -0,0 +1,314 @@
+//! Admin tasks: users, API tokens, migrations and subscribers, without
+//! starting the HTTP server. Uses the same configuration as `zero2prod`.
+
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use secrecy::Secret;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use zero2prod_axum::audit::Actor;
+use zero2prod_axum::authentication::PasswordHashing;
+use zero2prod_axum::configuration::{PasswordHashSettings, Settings};
+use zero2prod_axum::domain::{ApiScope, UserRole};
+use zero2prod_axum::management;
+use zero2prod_axum::startup::db_migration;
+use zero2prod_axum::startup::get_postgres_connection_pool;
+
+#[derive(Parser)]
+#[command(name = "zero2prod-admin", about = "Manage a zero2prod instance")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage admin users
+    #[command(subcommand)]
+    User(UserCommand),
+    /// Manage database migrations
+    #[command(subcommand)]
+    Migrations(MigrationsCommand),
+    /// Import or export subscribers as CSV
+    #[command(subcommand)]
+    Subscribers(SubscribersCommand),
+    /// Manage API tokens
+    #[command(subcommand)]
+    Token(TokenCommand),
+}
+
+#[derive(Subcommand)]
+enum UserCommand {
+    /// Create an admin user, the password is read from stdin
+    Create {
+        username: String,
+        /// Address for password reset links
+        #[arg(long)]
+        email: Option<String>,
+        /// One of `owner`, `editor` or `viewer`
+        #[arg(long, default_value = "owner", value_parser = UserRole::parse)]
+        role: UserRole,
+    },
+    /// List admin users
+    List,
+    /// Delete an admin user
+    Delete { username: String },
+}
+
+#[derive(Subcommand)]
+enum MigrationsCommand {
+    /// Apply pending migrations
+    Run,
+    /// Show pending migrations without applying them
+    DryRun,
+    /// Show applied migrations
+    Show,
+}
+
+#[derive(Subcommand)]
+enum SubscribersCommand {
+    /// Import subscribers from a CSV file with
+    /// `email,name[,status,subscribed_at]` header
+    Import { file: PathBuf },
+    /// Export subscribers as CSV to a file or stdout
+    Export { file: Option<PathBuf> },
+}
+
+#[derive(Subcommand)]
+enum TokenCommand {
+    /// Create an API token for a user and print it
+    Create {
+        username: String,
+        #[arg(long)]
+        name: String,
+        /// `newsletters:publish`, `subscribers:read` or `audit:read`, can be
+        /// repeated
+        #[arg(long = "scope", required = true, value_parser = ApiScope::parse)]
+        scopes: Vec<ApiScope>,
+        /// The token never expires if missing
+        #[arg(long, value_parser = clap::value_parser!(i64).range(1..))]
+        expires_in_days: Option<i64>,
+    },
+    /// List API tokens of every user
+    List,
+    /// Revoke an API token
+    Revoke { token_id: Uuid },
+}
+
+#[tokio::main]
+async fn main() -> Result<(), anyhow::Error> {
+    // Keep stdout for the command output.
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::WARN)
+        .with_writer(std::io::stderr)
+        .compact()
+        .finish();
+    tracing::subscriber::set_global_default(subscriber)
+        .expect("Failed to set up tracing");
+
+    let cli = Cli::parse();
+
+    let config = if std::env::var("RUSTEST").is_ok() {
+        Settings::load_configuration()
+            .context("Failed to load configuration")?
+    } else {
+        Settings::load_configuration_from_env()
+            .context("Failed to load configuration from env")?
+    };
+    let pool = get_postgres_connection_pool(&config.database);
+
+    match cli.command {
+        Command::User(command) => {
+            user(&pool, &config.authentication.password_hash, command).await
+        }
+        Command::Migrations(command) => migrations(&pool, command).await,
+        Command::Subscribers(command) => subscribers(&pool, command).await,
+        Command::Token(command) => token(&pool, command).await,
+    }
+}
+
+async fn user(
+    pool: &deadpool_postgres::Pool,
+    password_hash: &PasswordHashSettings,
+    command: UserCommand,
+) -> Result<(), anyhow::Error> {
+    match command {
+        UserCommand::Create {
+            username,
+            email,
+            role,
+        } => {
+            let password = read_password()?;
+            let hashing = PasswordHashing::new(password_hash)?;
+            let user_id = management::create_user(
+                pool,
+                &hashing,
+                &username,
+                password,
+                email.as_deref(),
+                role,
+                Actor::CommandLine,
+            )
+            .await?;
+            println!("Created {} {} ({})", role, username, user_id);
+        }
+        UserCommand::List => {
+            for user in management::list_users(pool).await? {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    user.user_id,
+                    user.username,
+                    user.role,
+                    user.email.as_deref().unwrap_or("-")
+                );
+            }
+        }
+        UserCommand::Delete { username } => {
+            if !management::delete_user(pool, &username).await? {
+                anyhow::bail!("No user named {}", username);
+            }
+            println!("Deleted user {}", username);
+        }
+    }
+    Ok(())
+}
+
+async fn migrations(
+    pool: &deadpool_postgres::Pool,
+    command: MigrationsCommand,
+) -> Result<(), anyhow::Error> {
+    match command {
+        MigrationsCommand::Run => {
+            let report = db_migration::apply_migrations(pool).await?;
+            if report.applied_migrations().is_empty() {
+                println!("No migrations applied");
+            }
+            for migration in report.applied_migrations() {
+                println!("Applied {}", migration);
+            }
+        }
+        MigrationsCommand::DryRun => {
+            let pending = db_migration::pending_migrations(pool).await?;
+            if pending.is_empty() {
+                println!("Database is up to date");
+            }
+            for migration in pending {
+                println!("Would apply {}", migration);
+            }
+        }
+        MigrationsCommand::Show => {
+            for migration in db_migration::applied_migrations(pool).await? {
+                let applied_on = migration
+                    .applied_on()
+                    .map(|date| date.to_string())
+                    .unwrap_or_default();
+                println!("{}\t{}", migration, applied_on);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn subscribers(
+    pool: &deadpool_postgres::Pool,
+    command: SubscribersCommand,
+) -> Result<(), anyhow::Error> {
+    match command {
+        SubscribersCommand::Import { file } => {
+            let file = std::fs::File::open(&file).with_context(|| {
+                format!("Failed to open {}", file.display())
+            })?;
+            let report = management::import_subscribers(pool, file).await?;
+            println!(
+                "Imported {} subscribers, skipped {} known addresses",
+                report.imported, report.skipped
+            );
+        }
+        SubscribersCommand::Export { file: Some(file) } => {
+            let writer = std::fs::File::create(&file).with_context(|| {
+                format!("Failed to create {}", file.display())
+            })?;
+            let exported = management::export_subscribers(pool, writer).await?;
+            eprintln!("Exported {} subscribers", exported);
+        }
+        SubscribersCommand::Export { file: None } => {
+            let exported =
+                management::export_subscribers(pool, std::io::stdout()).await?;
+            eprintln!("Exported {} subscribers", exported);
+        }
+    }
+    Ok(())
+}
+
+async fn token(
+    pool: &deadpool_postgres::Pool,
+    command: TokenCommand,
+) -> Result<(), anyhow::Error> {
+    match command {
+        TokenCommand::Create {
+            username,
+            name,
+            scopes,
+            expires_in_days,
+        } => {
+            let (token_id, token) = management::create_api_token(
+                pool,
+                &username,
+                &name,
+                &scopes,
+                expires_in_days,
+            )
+            .await?;
+            eprintln!("Created token {} for {}", token_id, username);
+            // Alone on stdout, so scripts can capture it.
+            println!("{}", token.as_ref());
+        }
+        TokenCommand::List => {
+            let now = OffsetDateTime::now_utc();
+            for token in management::list_api_tokens(pool).await? {
+                let state = if token.revoked_at.is_some() {
+                    "revoked"
+                } else if token.expires_at.is_some_and(|e| e <= now) {
+                    "expired"
+                } else {
+                    "active"
+                };
+                println!(
+                    "{}\t{}\t{}\tz2p_{}_…\t{}\t{}",
+                    token.id,
+                    token.username,
+                    token.name,
+                    token.prefix,
+                    token.scopes,
+                    state
+                );
+            }
+        }
+        TokenCommand::Revoke { token_id } => {
+            if !management::revoke_api_token(pool, token_id).await? {
+                anyhow::bail!("No active token with id {}", token_id);
+            }
+            println!("Revoked token {}", token_id);
+        }
+    }
+    Ok(())
+}
+
+/// Read the password from the first line of stdin, so it doesn't end up
+/// in the shell history.
+fn read_password() -> Result<Secret<String>, anyhow::Error> {
+    eprint!("Password: ");
+    std::io::stderr().flush()?;
+    let mut password = String::new();
+    std::io::stdin()
+        .lock()
+        .read_line(&mut password)
+        .context("Failed to read password from stdin")?;
+    Ok(Secret::new(
+        password.trim_end_matches(['\r', '\n']).to_string(),
+    ))
+}
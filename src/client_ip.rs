@@ -0,0 +1,105 @@
+//! src/client_ip.rs
+
+use std::net::{IpAddr, SocketAddr};
+
+use axum::async_trait;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use http::request::Parts;
+use http::{HeaderMap, StatusCode};
+
+use crate::startup::AppState;
+
+/// Address of the client which sent the request. It is the peer of the
+/// TCP connection, or the last `X-Forwarded-For` entry when
+/// `trust_forwarded_for` is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+impl ClientIp {
+    /// The proxy appends the peer it got the request from, everything
+    /// before that is whatever the client sent. The proxy may add its
+    /// entry as a header line of its own, so the last line counts.
+    fn from_forwarded_for(headers: &HeaderMap) -> Option<IpAddr> {
+        headers
+            .get_all("X-Forwarded-For")
+            .iter()
+            .next_back()?
+            .to_str()
+            .ok()?
+            .rsplit(',')
+            .next()?
+            .trim()
+            .parse()
+            .ok()
+    }
+}
+
+impl std::fmt::Display for ClientIp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for ClientIp {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        if state.trust_forwarded_for {
+            if let Some(ip) = Self::from_forwarded_for(&parts.headers) {
+                return Ok(ClientIp(ip));
+            }
+        }
+        match parts.extensions.get::<ConnectInfo<SocketAddr>>() {
+            Some(ConnectInfo(address)) => Ok(ClientIp(address.ip())),
+            None => {
+                tracing::error!("Server is not set up to provide ConnectInfo");
+                Err(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        }
+    }
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Forwarded-For", value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn last_forwarded_for_entry_is_the_client() {
+        let ip = ClientIp::from_forwarded_for(&headers("203.0.113.7"));
+        assert_eq!(ip, Some("203.0.113.7".parse().unwrap()));
+    }
+
+    #[test]
+    fn spoofed_leading_forwarded_for_entries_are_ignored() {
+        let ip = ClientIp::from_forwarded_for(&headers(
+            "127.0.0.1, 198.51.100.1, 203.0.113.7",
+        ));
+        assert_eq!(ip, Some("203.0.113.7".parse().unwrap()));
+    }
+
+    #[test]
+    fn spoofed_leading_forwarded_for_lines_are_ignored() {
+        let mut headers = headers("127.0.0.1");
+        headers.append("X-Forwarded-For", "203.0.113.7".parse().unwrap());
+        let ip = ClientIp::from_forwarded_for(&headers);
+        assert_eq!(ip, Some("203.0.113.7".parse().unwrap()));
+    }
+
+    #[test]
+    fn invalid_forwarded_for_is_ignored() {
+        assert_eq!(ClientIp::from_forwarded_for(&headers("unknown")), None);
+        assert_eq!(ClientIp::from_forwarded_for(&HeaderMap::new()), None);
+    }
+}
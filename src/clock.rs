@@ -0,0 +1,55 @@
+//! src/clock.rs
+
+use std::sync::{Arc, Mutex};
+
+use time::{Duration, OffsetDateTime};
+
+/// Source of the current time for time-based checks done in the
+/// application rather than in SQL, e.g. TOTP codes. Tests use a fixed
+/// clock and move it by hand.
+#[derive(Debug, Clone, Default)]
+pub enum Clock {
+    #[default]
+    System,
+    Fixed(Arc<Mutex<OffsetDateTime>>),
+}
+
+impl Clock {
+    pub fn fixed(now: OffsetDateTime) -> Clock {
+        Clock::Fixed(Arc::new(Mutex::new(now)))
+    }
+
+    pub fn now(&self) -> OffsetDateTime {
+        match self {
+            Clock::System => OffsetDateTime::now_utc(),
+            Clock::Fixed(now) => *now.lock().unwrap(),
+        }
+    }
+
+    /// Move a fixed clock forward, the system clock can't be moved.
+    pub fn advance(&self, by: Duration) {
+        match self {
+            Clock::System => panic!("Can't advance the system clock"),
+            Clock::Fixed(now) => *now.lock().unwrap() += by,
+        }
+    }
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use time::{Duration, OffsetDateTime};
+
+    use super::Clock;
+
+    #[test]
+    fn fixed_clock_only_moves_when_advanced() {
+        let start = OffsetDateTime::from_unix_timestamp(1_000_000).unwrap();
+        let clock = Clock::fixed(start);
+        let shared = clock.clone();
+        assert_eq!(clock.now(), start);
+        shared.advance(Duration::seconds(30));
+        assert_eq!(clock.now(), start + Duration::seconds(30));
+    }
+}
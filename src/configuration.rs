@@ -1,10 +1,15 @@
-use std::{env::VarError, net::Ipv4Addr, path::Path};
+use std::{
+    env::VarError,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    path::Path,
+};
 
 use config::FileFormat;
 use secrecy::{ExposeSecret, Secret};
 use serde::Deserialize;
 
-use crate::{domain::SubscriberEmail, email_client::EmailDeliveryService};
+use crate::domain::SubscriberEmail;
+use crate::email_client::{EmailDeliveryService, RetryPolicy};
 
 pub enum Environment {
     Local,
@@ -39,6 +44,30 @@ pub struct Settings {
     pub app_base_url: String,
     pub email_client: EmailClientSettings,
     pub email_delivery_service: EmailDeliveryService,
+    pub subscriptions: SubscriptionSettings,
+    pub session: SessionSettings,
+    #[serde(default)]
+    pub authentication: AuthenticationSettings,
+    #[serde(default)]
+    pub lockout: LockoutSettings,
+    /// Take the client IP from the last `X-Forwarded-For` entry. Enable
+    /// only behind a single reverse proxy which appends to this header.
+    #[serde(default)]
+    pub trust_forwarded_for: bool,
+    #[serde(default)]
+    pub security_headers: SecurityHeadersSettings,
+    #[serde(default)]
+    pub rate_limit: RateLimitSettings,
+    #[serde(default)]
+    pub shutdown: ShutdownSettings,
+    #[serde(default)]
+    pub telemetry: TelemetrySettings,
+    #[serde(default)]
+    pub metrics: MetricsSettings,
+    #[serde(default)]
+    pub health: HealthSettings,
+    #[serde(default)]
+    pub idempotency: IdempotencySettings,
 }
 
 impl Settings {
@@ -88,10 +117,80 @@ impl Settings {
                     std::env::var("AUTHORIZATION_TOKEN_FILE")?,
                 )),
                 timeout: 10000,
+                retry_policy: load_retry_policy_from_env()?,
+                smtp: load_smtp_settings_from_env()?,
+                outbox_dir: std::env::var("EMAIL_OUTBOX_DIR").ok(),
             },
             email_delivery_service: std::env::var("EMAIL_DELIVERY_SERVICE")?
                 .try_into()
                 .unwrap(),
+            subscriptions: load_subscription_settings_from_env()?,
+            session: SessionSettings {
+                ttl: 60 * 60 * 24,
+                secure_cookie: true,
+                hmac_secret: Secret::new(load_passwd_from_file(std::env::var(
+                    "HMAC_SECRET_FILE",
+                )?)),
+            },
+            authentication: AuthenticationSettings {
+                basic_auth_enabled: std::env::var("BASIC_AUTH_ENABLED")
+                    .map_or(true, |v| v != "false"),
+                password_hash: load_password_hash_settings_from_env()?,
+                recovery_code_key: Secret::new(load_passwd_from_file(
+                    std::env::var("RECOVERY_CODE_KEY_FILE")?,
+                )),
+                ..Default::default()
+            },
+            lockout: load_lockout_settings_from_env()?,
+            trust_forwarded_for: std::env::var("TRUST_FORWARDED_FOR")
+                .is_ok_and(|v| v == "true"),
+            security_headers: SecurityHeadersSettings {
+                hsts_enabled: std::env::var("HSTS_ENABLED")
+                    .map_or(true, |v| v != "false"),
+                ..Default::default()
+            },
+            rate_limit: load_rate_limit_settings_from_env()?,
+            shutdown: ShutdownSettings {
+                drain_timeout: match std::env::var("SHUTDOWN_DRAIN_TIMEOUT") {
+                    Ok(v) => v.parse().unwrap(),
+                    Err(_) => ShutdownSettings::default().drain_timeout,
+                },
+            },
+            telemetry: TelemetrySettings {
+                log_format: match std::env::var("LOG_FORMAT") {
+                    Ok(v) if v == "compact" => LogFormat::Compact,
+                    _ => LogFormat::Json,
+                },
+                log_filter: std::env::var("LOG_FILTER").unwrap_or_else(|_| {
+                    TelemetrySettings::default().log_filter
+                }),
+                jaeger_agent_endpoint: std::env::var("JAEGER_AGENT_ENDPOINT")
+                    .ok(),
+                ..Default::default()
+            },
+            metrics: MetricsSettings {
+                enabled: std::env::var("METRICS_ENABLED")
+                    .map_or(true, |v| v != "false"),
+                allowed_ips: match std::env::var("METRICS_ALLOWED_IPS") {
+                    Ok(v) => v
+                        .split(',')
+                        .filter(|ip| !ip.trim().is_empty())
+                        .map(|ip| ip.trim().parse().unwrap())
+                        .collect(),
+                    Err(_) => MetricsSettings::default().allowed_ips,
+                },
+                bearer_token: std::env::var("METRICS_BEARER_TOKEN_FILE")
+                    .ok()
+                    .map(|path| Secret::new(load_passwd_from_file(path))),
+            },
+            health: HealthSettings {
+                check_email_provider: std::env::var(
+                    "HEALTH_CHECK_EMAIL_PROVIDER",
+                )
+                .is_ok_and(|v| v == "true"),
+                ..Default::default()
+            },
+            idempotency: IdempotencySettings::default(),
         };
         Ok(settings)
     }
@@ -138,6 +237,13 @@ pub struct EmailClientSettings {
     pub authorization_token: Secret<String>,
     /// `request` crate will wait until this timeout when sends emails
     timeout: u64,
+    /// How failed requests to the email delivery service are retried
+    #[serde(default)]
+    pub retry_policy: RetryPolicy,
+    /// Relay used by the `smtp_relay` delivery service
+    pub smtp: Option<SmtpSettings>,
+    /// Directory where the `file` delivery service puts `.eml` files
+    pub outbox_dir: Option<String>,
 }
 
 impl EmailClientSettings {
@@ -151,6 +257,598 @@ impl EmailClientSettings {
     }
 }
 
+/// Lifecycle of pending subscriptions. All values are in seconds.
+#[derive(Debug, Deserialize, Clone)]
+pub struct SubscriptionSettings {
+    /// Confirmation link is valid for this long.
+    pub confirmation_token_ttl: u64,
+    /// Minimal pause between two confirmation emails to the same address.
+    pub resend_cooldown: u64,
+    /// Pending subscribers are deleted when their latest token has been
+    /// expired for this long.
+    pub cleanup_grace_period: u64,
+    /// How often we look for expired pending subscribers.
+    pub cleanup_interval: u64,
+}
+
+impl SubscriptionSettings {
+    pub fn confirmation_token_ttl(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.confirmation_token_ttl)
+    }
+
+    pub fn resend_cooldown(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.resend_cooldown)
+    }
+
+    pub fn cleanup_grace_period(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.cleanup_grace_period)
+    }
+
+    pub fn cleanup_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.cleanup_interval)
+    }
+}
+
+impl Default for SubscriptionSettings {
+    fn default() -> Self {
+        Self {
+            confirmation_token_ttl: 60 * 60 * 24,
+            resend_cooldown: 60,
+            cleanup_grace_period: 60 * 60 * 24 * 7,
+            cleanup_interval: 60 * 60,
+        }
+    }
+}
+
+/// Admin sessions, stored in Postgres.
+#[derive(Debug, Deserialize, Clone)]
+pub struct SessionSettings {
+    /// Session lifetime in seconds.
+    pub ttl: u64,
+    /// Send the cookie only over HTTPS. Disable for local HTTP setups.
+    pub secure_cookie: bool,
+    /// Key signing flash message cookies.
+    pub hmac_secret: Secret<String>,
+}
+
+impl SessionSettings {
+    pub fn ttl(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.ttl)
+    }
+}
+
+/// Admin credentials management. All durations are in seconds.
+#[derive(Debug, Deserialize, Clone)]
+pub struct AuthenticationSettings {
+    /// Password reset link is valid for this long.
+    pub password_reset_token_ttl: u64,
+    /// Accept `Authorization: Basic` on `POST /newsletters` besides API
+    /// tokens.
+    pub basic_auth_enabled: bool,
+    #[serde(default)]
+    pub password_hash: PasswordHashSettings,
+    /// Key of the HMAC the 2FA recovery codes are stored as. Codes hashed
+    /// with a previous key stop working.
+    pub recovery_code_key: Secret<String>,
+}
+
+impl AuthenticationSettings {
+    pub fn password_reset_token_ttl(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.password_reset_token_ttl)
+    }
+}
+
+impl Default for AuthenticationSettings {
+    fn default() -> Self {
+        Self {
+            password_reset_token_ttl: 60 * 60,
+            basic_auth_enabled: true,
+            password_hash: PasswordHashSettings::default(),
+            recovery_code_key: Secret::new(String::new()),
+        }
+    }
+}
+
+/// Argon2id costs of new password hashes. Stored hashes with lower costs
+/// are rehashed on the next successful login.
+#[derive(Debug, Deserialize, Clone)]
+pub struct PasswordHashSettings {
+    /// Memory size in KiB.
+    pub memory_cost: u32,
+    /// Number of iterations.
+    pub time_cost: u32,
+    /// Degree of parallelism.
+    pub parallelism: u32,
+}
+
+impl Default for PasswordHashSettings {
+    fn default() -> Self {
+        // OWASP recommendation for Argon2id.
+        Self {
+            memory_cost: 19 * 1024,
+            time_cost: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+/// Brute-force protection of password checks. Durations are in seconds.
+#[derive(Debug, Deserialize, Clone)]
+pub struct LockoutSettings {
+    /// Failed attempts are counted over this sliding window.
+    pub window: u64,
+    /// Failures for one username within `window` which lock it.
+    pub max_failures_per_username: i64,
+    /// Failures from one client IP within `window` which lock it.
+    pub max_failures_per_ip: i64,
+    /// A locked username or IP is rejected for this long.
+    pub lockout_duration: u64,
+}
+
+impl LockoutSettings {
+    pub fn window(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.window)
+    }
+
+    pub fn lockout_duration(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.lockout_duration)
+    }
+}
+
+impl Default for LockoutSettings {
+    fn default() -> Self {
+        Self {
+            window: 15 * 60,
+            max_failures_per_username: 5,
+            max_failures_per_ip: 20,
+            lockout_duration: 15 * 60,
+        }
+    }
+}
+
+/// Headers added to every response. A handler can override one by
+/// setting it itself, e.g. a more relaxed policy for newsletter previews.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct SecurityHeadersSettings {
+    pub content_security_policy: String,
+    /// Send `Strict-Transport-Security`. Enable only where the app is
+    /// served over HTTPS, browsers remember it for `hsts_max_age`.
+    pub hsts_enabled: bool,
+    /// Seconds.
+    pub hsts_max_age: u64,
+    pub referrer_policy: String,
+    pub frame_options: String,
+    pub permissions_policy: String,
+}
+
+impl Default for SecurityHeadersSettings {
+    fn default() -> Self {
+        Self {
+            content_security_policy: "default-src 'self'; \
+                img-src 'self' data:; object-src 'none'; base-uri 'none'; \
+                form-action 'self'; frame-ancestors 'none'"
+                .to_string(),
+            hsts_enabled: false,
+            hsts_max_age: 60 * 60 * 24 * 365,
+            referrer_policy: "same-origin".to_string(),
+            frame_options: "DENY".to_string(),
+            permissions_policy: "camera=(), microphone=(), geolocation=(), \
+                payment=(), usb=()"
+                .to_string(),
+        }
+    }
+}
+
+/// Token buckets of the public endpoints which send emails. A request
+/// takes a token from the bucket of its client IP and from the bucket of
+/// the email it targets.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct RateLimitSettings {
+    pub backend: RateLimitBackend,
+    /// `POST /subscriptions`.
+    pub subscriptions: RouteRateLimit,
+    /// `POST /subscriptions/resend`.
+    pub subscriptions_resend: RouteRateLimit,
+    /// `POST /password/forgot`.
+    pub password_forgot: RouteRateLimit,
+    /// How often we delete the buckets which refilled completely.
+    pub cleanup_interval: u64,
+}
+
+impl RateLimitSettings {
+    pub fn cleanup_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.cleanup_interval)
+    }
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        Self {
+            backend: RateLimitBackend::Memory,
+            subscriptions: RouteRateLimit {
+                per_ip: BucketSettings {
+                    capacity: 10,
+                    refill_interval: 6 * 60,
+                },
+                per_email: BucketSettings {
+                    capacity: 3,
+                    refill_interval: 20 * 60,
+                },
+            },
+            subscriptions_resend: RouteRateLimit {
+                per_ip: BucketSettings {
+                    capacity: 10,
+                    refill_interval: 6 * 60,
+                },
+                per_email: BucketSettings {
+                    capacity: 3,
+                    refill_interval: 20 * 60,
+                },
+            },
+            password_forgot: RouteRateLimit {
+                per_ip: BucketSettings {
+                    capacity: 10,
+                    refill_interval: 6 * 60,
+                },
+                per_email: BucketSettings {
+                    capacity: 3,
+                    refill_interval: 20 * 60,
+                },
+            },
+            cleanup_interval: 10 * 60,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitBackend {
+    /// Buckets in process memory, every instance counts on its own.
+    Memory,
+    /// Buckets in Postgres, shared by every instance.
+    Postgres,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct RouteRateLimit {
+    pub per_ip: BucketSettings,
+    pub per_email: BucketSettings,
+}
+
+/// Up to `capacity` requests at once, then one more every
+/// `refill_interval` seconds.
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct BucketSettings {
+    pub capacity: u32,
+    pub refill_interval: u64,
+}
+
+/// Saved responses of idempotent requests.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct IdempotencySettings {
+    /// Seconds a key is remembered, a retry after that is processed again.
+    pub ttl: u64,
+    /// How often we look for expired keys.
+    pub cleanup_interval: u64,
+}
+
+impl IdempotencySettings {
+    pub fn ttl(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.ttl)
+    }
+
+    pub fn cleanup_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.cleanup_interval)
+    }
+}
+
+impl Default for IdempotencySettings {
+    fn default() -> Self {
+        Self {
+            ttl: 60 * 60 * 48,
+            cleanup_interval: 60 * 60,
+        }
+    }
+}
+
+/// What happens on SIGTERM or SIGINT.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ShutdownSettings {
+    /// Seconds to wait for open requests and background tasks to finish
+    /// before they are cut off.
+    pub drain_timeout: u64,
+}
+
+impl ShutdownSettings {
+    pub fn drain_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.drain_timeout)
+    }
+}
+
+impl Default for ShutdownSettings {
+    fn default() -> Self {
+        Self { drain_timeout: 30 }
+    }
+}
+
+/// Logs and traces.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct TelemetrySettings {
+    pub log_format: LogFormat,
+    /// `EnvFilter` directives, e.g. `info,zero2prod_axum=debug`. `RUST_LOG`
+    /// takes precedence when it is set.
+    pub log_filter: String,
+    /// Export spans to the Jaeger agent at this `host:port`, e.g. the
+    /// collector on `127.0.0.1:6831`. Nothing is exported without it.
+    pub jaeger_agent_endpoint: Option<String>,
+    pub service_name: String,
+}
+
+impl Default for TelemetrySettings {
+    fn default() -> Self {
+        Self {
+            log_format: LogFormat::Compact,
+            log_filter: "info".to_string(),
+            jaeger_agent_endpoint: None,
+            service_name: "zero2prod".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// One human readable line per event.
+    Compact,
+    /// One JSON object per event, with the fields of its spans.
+    Json,
+}
+
+/// `GET /metrics`, in the Prometheus text format.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct MetricsSettings {
+    pub enabled: bool,
+    /// Clients allowed to scrape without a token, loopback only by
+    /// default.
+    pub allowed_ips: Vec<IpAddr>,
+    /// Other clients are allowed with this as `Authorization: Bearer`.
+    pub bearer_token: Option<Secret<String>>,
+}
+
+impl Default for MetricsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            allowed_ips: vec![
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+                IpAddr::V6(Ipv6Addr::LOCALHOST),
+            ],
+            bearer_token: None,
+        }
+    }
+}
+
+/// Checks of `GET /health/ready`.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct HealthSettings {
+    /// Milliseconds each check may take before it counts as failed.
+    pub timeout: u64,
+    /// Also check that the email delivery service can be reached.
+    pub check_email_provider: bool,
+}
+
+impl HealthSettings {
+    pub fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.timeout)
+    }
+}
+
+impl Default for HealthSettings {
+    fn default() -> Self {
+        Self {
+            timeout: 2000,
+            check_email_provider: false,
+        }
+    }
+}
+
+/// Connection to the SMTP relay, always upgraded with STARTTLS.
+#[derive(Debug, Deserialize, Clone)]
+pub struct SmtpSettings {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: Secret<String>,
+}
+
+/// SMTP settings are optional, but if `SMTP_HOST` is set,
+/// the rest is required.
+fn load_smtp_settings_from_env() -> Result<Option<SmtpSettings>, VarError> {
+    let host = match std::env::var("SMTP_HOST") {
+        Ok(host) => host,
+        Err(VarError::NotPresent) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    Ok(Some(SmtpSettings {
+        host,
+        port: std::env::var("SMTP_PORT")?.parse::<u16>().unwrap(),
+        username: std::env::var("SMTP_USERNAME")?,
+        password: Secret::new(load_passwd_from_file(std::env::var(
+            "SMTP_PASSWORD_FILE",
+        )?)),
+    }))
+}
+
+/// Every cost is optional, missing ones keep the default.
+fn load_password_hash_settings_from_env(
+) -> Result<PasswordHashSettings, VarError> {
+    fn cost(name: &str, default: u32) -> Result<u32, VarError> {
+        match std::env::var(name) {
+            Ok(value) => Ok(value.parse::<u32>().unwrap()),
+            Err(VarError::NotPresent) => Ok(default),
+            Err(e) => Err(e),
+        }
+    }
+    let default = PasswordHashSettings::default();
+    Ok(PasswordHashSettings {
+        memory_cost: cost("ARGON2_MEMORY_COST", default.memory_cost)?,
+        time_cost: cost("ARGON2_TIME_COST", default.time_cost)?,
+        parallelism: cost("ARGON2_PARALLELISM", default.parallelism)?,
+    })
+}
+
+/// Every limit is optional, missing ones keep the default. A route's
+/// limits are read from e.g. `RATE_LIMIT_SUBSCRIPTIONS_PER_IP_CAPACITY`
+/// and `RATE_LIMIT_SUBSCRIPTIONS_PER_EMAIL_REFILL_INTERVAL`.
+fn load_rate_limit_settings_from_env() -> Result<RateLimitSettings, VarError> {
+    fn bucket(
+        prefix: &str,
+        default: BucketSettings,
+    ) -> Result<BucketSettings, VarError> {
+        Ok(BucketSettings {
+            capacity: load_number_from_env(
+                &format!("{}_CAPACITY", prefix),
+                default.capacity,
+            )?,
+            refill_interval: load_number_from_env(
+                &format!("{}_REFILL_INTERVAL", prefix),
+                default.refill_interval,
+            )?,
+        })
+    }
+    fn route(
+        prefix: &str,
+        default: RouteRateLimit,
+    ) -> Result<RouteRateLimit, VarError> {
+        Ok(RouteRateLimit {
+            per_ip: bucket(&format!("{}_PER_IP", prefix), default.per_ip)?,
+            per_email: bucket(
+                &format!("{}_PER_EMAIL", prefix),
+                default.per_email,
+            )?,
+        })
+    }
+    let default = RateLimitSettings::default();
+    Ok(RateLimitSettings {
+        backend: if std::env::var("RATE_LIMIT_BACKEND")
+            .is_ok_and(|v| v == "postgres")
+        {
+            RateLimitBackend::Postgres
+        } else {
+            RateLimitBackend::Memory
+        },
+        subscriptions: route(
+            "RATE_LIMIT_SUBSCRIPTIONS",
+            default.subscriptions,
+        )?,
+        subscriptions_resend: route(
+            "RATE_LIMIT_SUBSCRIPTIONS_RESEND",
+            default.subscriptions_resend,
+        )?,
+        password_forgot: route(
+            "RATE_LIMIT_PASSWORD_FORGOT",
+            default.password_forgot,
+        )?,
+        cleanup_interval: load_number_from_env(
+            "RATE_LIMIT_CLEANUP_INTERVAL",
+            default.cleanup_interval,
+        )?,
+    })
+}
+
+/// Every duration is optional, missing ones keep the default. All are in
+/// seconds.
+fn load_subscription_settings_from_env(
+) -> Result<SubscriptionSettings, VarError> {
+    let default = SubscriptionSettings::default();
+    Ok(SubscriptionSettings {
+        confirmation_token_ttl: load_number_from_env(
+            "SUBSCRIPTION_CONFIRMATION_TOKEN_TTL",
+            default.confirmation_token_ttl,
+        )?,
+        resend_cooldown: load_number_from_env(
+            "SUBSCRIPTION_RESEND_COOLDOWN",
+            default.resend_cooldown,
+        )?,
+        cleanup_grace_period: load_number_from_env(
+            "SUBSCRIPTION_CLEANUP_GRACE_PERIOD",
+            default.cleanup_grace_period,
+        )?,
+        cleanup_interval: load_number_from_env(
+            "SUBSCRIPTION_CLEANUP_INTERVAL",
+            default.cleanup_interval,
+        )?,
+    })
+}
+
+/// Every field is optional, missing ones keep the default. Delays are in
+/// milliseconds.
+fn load_retry_policy_from_env() -> Result<RetryPolicy, VarError> {
+    let default = RetryPolicy::default();
+    Ok(RetryPolicy {
+        max_attempts: load_number_from_env(
+            "EMAIL_RETRY_MAX_ATTEMPTS",
+            default.max_attempts,
+        )?,
+        base_delay: load_number_from_env(
+            "EMAIL_RETRY_BASE_DELAY",
+            default.base_delay,
+        )?,
+        jitter: load_number_from_env("EMAIL_RETRY_JITTER", default.jitter)?,
+        max_delay: load_number_from_env(
+            "EMAIL_RETRY_MAX_DELAY",
+            default.max_delay,
+        )?,
+        retry_on_server_error: std::env::var("EMAIL_RETRY_ON_SERVER_ERROR")
+            .map_or(default.retry_on_server_error, |v| v != "false"),
+        retry_on_too_many_requests: std::env::var(
+            "EMAIL_RETRY_ON_TOO_MANY_REQUESTS",
+        )
+        .map_or(default.retry_on_too_many_requests, |v| v != "false"),
+        retry_on_timeout: std::env::var("EMAIL_RETRY_ON_TIMEOUT")
+            .map_or(default.retry_on_timeout, |v| v != "false"),
+    })
+}
+
+/// Every threshold is optional, missing ones keep the default.
+fn load_lockout_settings_from_env() -> Result<LockoutSettings, VarError> {
+    let default = LockoutSettings::default();
+    Ok(LockoutSettings {
+        window: load_number_from_env("LOCKOUT_WINDOW", default.window)?,
+        max_failures_per_username: load_number_from_env(
+            "LOCKOUT_MAX_FAILURES_PER_USERNAME",
+            default.max_failures_per_username,
+        )?,
+        max_failures_per_ip: load_number_from_env(
+            "LOCKOUT_MAX_FAILURES_PER_IP",
+            default.max_failures_per_ip,
+        )?,
+        lockout_duration: load_number_from_env(
+            "LOCKOUT_DURATION",
+            default.lockout_duration,
+        )?,
+    })
+}
+
+/// Optional number, `default` when the variable is not set.
+fn load_number_from_env<T>(name: &str, default: T) -> Result<T, VarError>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Debug,
+{
+    match std::env::var(name) {
+        Ok(value) => Ok(value.parse::<T>().unwrap()),
+        Err(VarError::NotPresent) => Ok(default),
+        Err(e) => Err(e),
+    }
+}
+
 fn load_passwd_from_file<T: AsRef<Path>>(path: T) -> String {
     std::fs::read_to_string(path).unwrap().trim().to_string()
 }
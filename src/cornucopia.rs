@@ -3,8 +3,2372 @@
 #[allow(clippy :: all, clippy :: pedantic)] #[allow(unused_variables)]
 #[allow(unused_imports)] #[allow(dead_code)] pub mod types { }#[allow(clippy :: all, clippy :: pedantic)] #[allow(unused_variables)]
 #[allow(unused_imports)] #[allow(dead_code)] pub mod queries
-{ pub mod newsletters
-{ use futures::{{StreamExt, TryStreamExt}};use futures; use cornucopia_async::GenericClient;pub struct StringQuery < 'a, C : GenericClient, T, const N : usize >
+{ pub mod api_tokens
+{ use futures::{{StreamExt, TryStreamExt}};use futures; use cornucopia_async::GenericClient;#[derive( Debug)] pub struct InsertApiTokenParams < T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,T3 : cornucopia_async::StringSql,T4 : cornucopia_async::StringSql,> { pub id : uuid::Uuid,pub user_id : uuid::Uuid,pub name : T1,pub prefix : T2,pub token_hash : T3,pub scopes : T4,pub expires_at : Option<time::OffsetDateTime>,}#[derive(Clone,Copy, Debug)] pub struct RevokeUserApiTokenParams < > { pub id : uuid::Uuid,pub user_id : uuid::Uuid,}#[derive( Debug, Clone, PartialEq, )] pub struct GetApiTokenByPrefix
+{ pub id : uuid::Uuid,pub user_id : uuid::Uuid,pub token_hash : String,pub scopes : String,pub expires_at : Option<time::OffsetDateTime>,pub revoked_at : Option<time::OffsetDateTime>,pub role : String,}pub struct GetApiTokenByPrefixBorrowed < 'a >
+{ pub id : uuid::Uuid,pub user_id : uuid::Uuid,pub token_hash : &'a str,pub scopes : &'a str,pub expires_at : Option<time::OffsetDateTime>,pub revoked_at : Option<time::OffsetDateTime>,pub role : &'a str,} impl < 'a > From < GetApiTokenByPrefixBorrowed <
+'a >> for GetApiTokenByPrefix
+{
+    fn
+    from(GetApiTokenByPrefixBorrowed { id,user_id,token_hash,scopes,expires_at,revoked_at,role,} : GetApiTokenByPrefixBorrowed < 'a >)
+    -> Self { Self { id,user_id,token_hash: token_hash.into(),scopes: scopes.into(),expires_at,revoked_at,role: role.into(),} }
+}pub struct GetApiTokenByPrefixQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> GetApiTokenByPrefixBorrowed,
+    mapper : fn(GetApiTokenByPrefixBorrowed) -> T,
+} impl < 'a, C, T : 'a, const N : usize > GetApiTokenByPrefixQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(GetApiTokenByPrefixBorrowed) -> R) -> GetApiTokenByPrefixQuery
+    < 'a, C, R, N >
+    {
+        GetApiTokenByPrefixQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}#[derive( Debug, Clone, PartialEq, )] pub struct ListUserApiTokens
+{ pub id : uuid::Uuid,pub name : String,pub prefix : String,pub scopes : String,pub created_at : time::OffsetDateTime,pub expires_at : Option<time::OffsetDateTime>,pub revoked_at : Option<time::OffsetDateTime>,pub last_used_at : Option<time::OffsetDateTime>,}pub struct ListUserApiTokensBorrowed < 'a >
+{ pub id : uuid::Uuid,pub name : &'a str,pub prefix : &'a str,pub scopes : &'a str,pub created_at : time::OffsetDateTime,pub expires_at : Option<time::OffsetDateTime>,pub revoked_at : Option<time::OffsetDateTime>,pub last_used_at : Option<time::OffsetDateTime>,} impl < 'a > From < ListUserApiTokensBorrowed <
+'a >> for ListUserApiTokens
+{
+    fn
+    from(ListUserApiTokensBorrowed { id,name,prefix,scopes,created_at,expires_at,revoked_at,last_used_at,} : ListUserApiTokensBorrowed < 'a >)
+    -> Self { Self { id,name: name.into(),prefix: prefix.into(),scopes: scopes.into(),created_at,expires_at,revoked_at,last_used_at,} }
+}pub struct ListUserApiTokensQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> ListUserApiTokensBorrowed,
+    mapper : fn(ListUserApiTokensBorrowed) -> T,
+} impl < 'a, C, T : 'a, const N : usize > ListUserApiTokensQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(ListUserApiTokensBorrowed) -> R) -> ListUserApiTokensQuery
+    < 'a, C, R, N >
+    {
+        ListUserApiTokensQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}#[derive( Debug, Clone, PartialEq, )] pub struct ListApiTokens
+{ pub id : uuid::Uuid,pub username : String,pub name : String,pub prefix : String,pub scopes : String,pub created_at : time::OffsetDateTime,pub expires_at : Option<time::OffsetDateTime>,pub revoked_at : Option<time::OffsetDateTime>,pub last_used_at : Option<time::OffsetDateTime>,}pub struct ListApiTokensBorrowed < 'a >
+{ pub id : uuid::Uuid,pub username : &'a str,pub name : &'a str,pub prefix : &'a str,pub scopes : &'a str,pub created_at : time::OffsetDateTime,pub expires_at : Option<time::OffsetDateTime>,pub revoked_at : Option<time::OffsetDateTime>,pub last_used_at : Option<time::OffsetDateTime>,} impl < 'a > From < ListApiTokensBorrowed <
+'a >> for ListApiTokens
+{
+    fn
+    from(ListApiTokensBorrowed { id,username,name,prefix,scopes,created_at,expires_at,revoked_at,last_used_at,} : ListApiTokensBorrowed < 'a >)
+    -> Self { Self { id,username: username.into(),name: name.into(),prefix: prefix.into(),scopes: scopes.into(),created_at,expires_at,revoked_at,last_used_at,} }
+}pub struct ListApiTokensQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> ListApiTokensBorrowed,
+    mapper : fn(ListApiTokensBorrowed) -> T,
+} impl < 'a, C, T : 'a, const N : usize > ListApiTokensQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(ListApiTokensBorrowed) -> R) -> ListApiTokensQuery
+    < 'a, C, R, N >
+    {
+        ListApiTokensQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}pub fn insert_api_token() -> InsertApiTokenStmt
+{ InsertApiTokenStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO api_tokens(id, user_id, name, prefix, token_hash, scopes, expires_at)
+VALUES ($1, $2, $3, $4, $5, $6, $7)")) } pub
+struct InsertApiTokenStmt(cornucopia_async :: private :: Stmt) ; impl
+InsertApiTokenStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,T3 : cornucopia_async::StringSql,T4 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+id : & 'a uuid::Uuid,user_id : & 'a uuid::Uuid,name : & 'a T1,prefix : & 'a T2,token_hash : & 'a T3,scopes : & 'a T4,expires_at : & 'a Option<time::OffsetDateTime>,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [id,user_id,name,prefix,token_hash,scopes,expires_at,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,T3 : cornucopia_async::StringSql,T4 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, InsertApiTokenParams < T1,T2,T3,T4,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for InsertApiTokenStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    InsertApiTokenParams < T1,T2,T3,T4,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.id,& params.user_id,& params.name,& params.prefix,& params.token_hash,& params.scopes,& params.expires_at,) ) }
+}pub fn get_api_token_by_prefix() -> GetApiTokenByPrefixStmt
+{ GetApiTokenByPrefixStmt(cornucopia_async :: private :: Stmt :: new("SELECT api_tokens.id, api_tokens.user_id, token_hash, scopes,
+   expires_at, revoked_at, users.role
+FROM api_tokens
+JOIN users ON users.user_id = api_tokens.user_id
+WHERE prefix = $1")) } pub
+struct GetApiTokenByPrefixStmt(cornucopia_async :: private :: Stmt) ; impl
+GetApiTokenByPrefixStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+prefix : & 'a T1,) -> GetApiTokenByPrefixQuery < 'a, C,
+GetApiTokenByPrefix, 1 >
+{
+    GetApiTokenByPrefixQuery
+    {
+        client, params : [prefix,], stmt : & mut self.0, extractor :
+        | row | { GetApiTokenByPrefixBorrowed { id : row.get(0),user_id : row.get(1),token_hash : row.get(2),scopes : row.get(3),expires_at : row.get(4),revoked_at : row.get(5),role : row.get(6),} }, mapper : | it | { <GetApiTokenByPrefix>::from(it) },
+    }
+} }pub fn touch_api_token() -> TouchApiTokenStmt
+{ TouchApiTokenStmt(cornucopia_async :: private :: Stmt :: new("UPDATE api_tokens SET last_used_at = now() WHERE id = $1")) } pub
+struct TouchApiTokenStmt(cornucopia_async :: private :: Stmt) ; impl
+TouchApiTokenStmt { pub async fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+id : & 'a uuid::Uuid,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [id,]) .await
+} }pub fn list_user_api_tokens() -> ListUserApiTokensStmt
+{ ListUserApiTokensStmt(cornucopia_async :: private :: Stmt :: new("SELECT id, name, prefix, scopes, created_at, expires_at, revoked_at,
+   last_used_at
+FROM api_tokens
+WHERE user_id = $1
+ORDER BY created_at DESC")) } pub
+struct ListUserApiTokensStmt(cornucopia_async :: private :: Stmt) ; impl
+ListUserApiTokensStmt { pub fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+user_id : & 'a uuid::Uuid,) -> ListUserApiTokensQuery < 'a, C,
+ListUserApiTokens, 1 >
+{
+    ListUserApiTokensQuery
+    {
+        client, params : [user_id,], stmt : & mut self.0, extractor :
+        | row | { ListUserApiTokensBorrowed { id : row.get(0),name : row.get(1),prefix : row.get(2),scopes : row.get(3),created_at : row.get(4),expires_at : row.get(5),revoked_at : row.get(6),last_used_at : row.get(7),} }, mapper : | it | { <ListUserApiTokens>::from(it) },
+    }
+} }pub fn list_api_tokens() -> ListApiTokensStmt
+{ ListApiTokensStmt(cornucopia_async :: private :: Stmt :: new("SELECT api_tokens.id, users.username, name, prefix, scopes, created_at,
+   expires_at, revoked_at, last_used_at
+FROM api_tokens
+JOIN users ON users.user_id = api_tokens.user_id
+ORDER BY users.username, created_at DESC")) } pub
+struct ListApiTokensStmt(cornucopia_async :: private :: Stmt) ; impl
+ListApiTokensStmt { pub fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+) -> ListApiTokensQuery < 'a, C,
+ListApiTokens, 0 >
+{
+    ListApiTokensQuery
+    {
+        client, params : [], stmt : & mut self.0, extractor :
+        | row | { ListApiTokensBorrowed { id : row.get(0),username : row.get(1),name : row.get(2),prefix : row.get(3),scopes : row.get(4),created_at : row.get(5),expires_at : row.get(6),revoked_at : row.get(7),last_used_at : row.get(8),} }, mapper : | it | { <ListApiTokens>::from(it) },
+    }
+} }pub fn revoke_user_api_token() -> RevokeUserApiTokenStmt
+{ RevokeUserApiTokenStmt(cornucopia_async :: private :: Stmt :: new("UPDATE api_tokens SET revoked_at = now()
+WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL")) } pub
+struct RevokeUserApiTokenStmt(cornucopia_async :: private :: Stmt) ; impl
+RevokeUserApiTokenStmt { pub async fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+id : & 'a uuid::Uuid,user_id : & 'a uuid::Uuid,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [id,user_id,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, >
+cornucopia_async :: Params < 'a, RevokeUserApiTokenParams < >, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for RevokeUserApiTokenStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    RevokeUserApiTokenParams < >) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.id,& params.user_id,) ) }
+}pub fn revoke_api_token() -> RevokeApiTokenStmt
+{ RevokeApiTokenStmt(cornucopia_async :: private :: Stmt :: new("UPDATE api_tokens SET revoked_at = now()
+WHERE id = $1 AND revoked_at IS NULL")) } pub
+struct RevokeApiTokenStmt(cornucopia_async :: private :: Stmt) ; impl
+RevokeApiTokenStmt { pub async fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+id : & 'a uuid::Uuid,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [id,]) .await
+} }}pub mod audit
+{ use futures::{{StreamExt, TryStreamExt}};use futures; use cornucopia_async::GenericClient;#[derive( Debug)] pub struct InsertAuditEventParams < T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,T3 : cornucopia_async::StringSql,T4 : cornucopia_async::StringSql,> { pub id : uuid::Uuid,pub user_id : Option<uuid::Uuid>,pub action : T1,pub target : Option<T2>,pub client_ip : Option<T3>,pub user_agent : Option<T4>,}#[derive( Debug)] pub struct ListAuditEventsParams < T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,> { pub action : Option<T1>,pub username : Option<T2>,pub after_at : Option<time::OffsetDateTime>,pub after_id : Option<uuid::Uuid>,pub limit : i64,}#[derive( Debug, Clone, PartialEq, )] pub struct ListAuditEvents
+{ pub id : uuid::Uuid,pub user_id : Option<uuid::Uuid>,pub username : Option<String>,pub action : String,pub target : Option<String>,pub client_ip : Option<String>,pub user_agent : Option<String>,pub created_at : time::OffsetDateTime,}pub struct ListAuditEventsBorrowed < 'a >
+{ pub id : uuid::Uuid,pub user_id : Option<uuid::Uuid>,pub username : Option<&'a str>,pub action : &'a str,pub target : Option<&'a str>,pub client_ip : Option<&'a str>,pub user_agent : Option<&'a str>,pub created_at : time::OffsetDateTime,} impl < 'a > From < ListAuditEventsBorrowed <
+'a >> for ListAuditEvents
+{
+    fn
+    from(ListAuditEventsBorrowed { id,user_id,username,action,target,client_ip,user_agent,created_at,} : ListAuditEventsBorrowed < 'a >)
+    -> Self { Self { id,user_id,username: username.map(|v| v.into()),action: action.into(),target: target.map(|v| v.into()),client_ip: client_ip.map(|v| v.into()),user_agent: user_agent.map(|v| v.into()),created_at,} }
+}pub struct ListAuditEventsQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> ListAuditEventsBorrowed,
+    mapper : fn(ListAuditEventsBorrowed) -> T,
+} impl < 'a, C, T : 'a, const N : usize > ListAuditEventsQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(ListAuditEventsBorrowed) -> R) -> ListAuditEventsQuery
+    < 'a, C, R, N >
+    {
+        ListAuditEventsQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}pub fn insert_audit_event() -> InsertAuditEventStmt
+{ InsertAuditEventStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO audit_events(id, user_id, action, target, client_ip, user_agent)
+VALUES ($1, $2, $3, $4, $5, $6)")) } pub
+struct InsertAuditEventStmt(cornucopia_async :: private :: Stmt) ; impl
+InsertAuditEventStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,T3 : cornucopia_async::StringSql,T4 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+id : & 'a uuid::Uuid,user_id : & 'a Option<uuid::Uuid>,action : & 'a T1,target : & 'a Option<T2>,client_ip : & 'a Option<T3>,user_agent : & 'a Option<T4>,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [id,user_id,action,target,client_ip,user_agent,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,T3 : cornucopia_async::StringSql,T4 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, InsertAuditEventParams < T1,T2,T3,T4,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for InsertAuditEventStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    InsertAuditEventParams < T1,T2,T3,T4,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.id,& params.user_id,& params.action,& params.target,& params.client_ip,& params.user_agent,) ) }
+}pub fn list_audit_events() -> ListAuditEventsStmt
+{ ListAuditEventsStmt(cornucopia_async :: private :: Stmt :: new("SELECT
+    audit_events.id,
+    audit_events.user_id,
+    users.username,
+    audit_events.action,
+    audit_events.target,
+    audit_events.client_ip,
+    audit_events.user_agent,
+    audit_events.created_at
+FROM audit_events
+LEFT JOIN users ON users.user_id = audit_events.user_id
+WHERE ($1::text IS NULL OR audit_events.action = $1)
+    AND ($2::text IS NULL OR users.username = $2)
+    AND (
+        $3::timestamptz IS NULL
+        OR (audit_events.created_at, audit_events.id)
+            < ($3, $4::uuid)
+    )
+ORDER BY audit_events.created_at DESC, audit_events.id DESC
+LIMIT $5")) } pub
+struct ListAuditEventsStmt(cornucopia_async :: private :: Stmt) ; impl
+ListAuditEventsStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+action : & 'a Option<T1>,username : & 'a Option<T2>,after_at : & 'a Option<time::OffsetDateTime>,after_id : & 'a Option<uuid::Uuid>,limit : & 'a i64,) -> ListAuditEventsQuery < 'a, C,
+ListAuditEvents, 5 >
+{
+    ListAuditEventsQuery
+    {
+        client, params : [action,username,after_at,after_id,limit,], stmt : & mut self.0, extractor :
+        | row | { ListAuditEventsBorrowed { id : row.get(0),user_id : row.get(1),username : row.get(2),action : row.get(3),target : row.get(4),client_ip : row.get(5),user_agent : row.get(6),created_at : row.get(7),} }, mapper : | it | { <ListAuditEvents>::from(it) },
+    }
+} }impl < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,> cornucopia_async ::
+Params < 'a, ListAuditEventsParams < T1,T2,>, ListAuditEventsQuery < 'a, C,
+ListAuditEvents, 5 >, C > for ListAuditEventsStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    ListAuditEventsParams < T1,T2,>) -> ListAuditEventsQuery < 'a, C,
+    ListAuditEvents, 5 >
+    { self.bind(client, & params.action,& params.username,& params.after_at,& params.after_id,& params.limit,) }
+}}pub mod idempotency
+{ use futures::{{StreamExt, TryStreamExt}};use futures; use cornucopia_async::GenericClient;#[derive( Debug)] pub struct InsertIdempotencyKeyParams < T1 : cornucopia_async::StringSql,> { pub user_id : uuid::Uuid,pub idempotency_key : T1,pub created_at : time::OffsetDateTime,}#[derive( Debug)] pub struct GetSavedResponseParams < T1 : cornucopia_async::StringSql,> { pub user_id : uuid::Uuid,pub idempotency_key : T1,}#[derive( Debug)] pub struct SaveResponseParams < T1 : cornucopia_async::StringSql,T2 : cornucopia_async::BytesSql,T3 : cornucopia_async::StringSql,> { pub response_status_code : i16,pub response_headers : T1,pub response_body : T2,pub user_id : uuid::Uuid,pub idempotency_key : T3,}#[derive( Debug, Clone, PartialEq, )] pub struct GetSavedResponse
+{ pub response_status_code : i16,pub response_headers : String,pub response_body : Vec<u8>,}pub struct GetSavedResponseBorrowed < 'a >
+{ pub response_status_code : i16,pub response_headers : &'a str,pub response_body : &'a [u8],} impl < 'a > From < GetSavedResponseBorrowed <
+'a >> for GetSavedResponse
+{
+    fn
+    from(GetSavedResponseBorrowed { response_status_code,response_headers,response_body,} : GetSavedResponseBorrowed < 'a >)
+    -> Self { Self { response_status_code,response_headers: response_headers.into(),response_body: response_body.into(),} }
+}pub struct GetSavedResponseQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> GetSavedResponseBorrowed,
+    mapper : fn(GetSavedResponseBorrowed) -> T,
+} impl < 'a, C, T : 'a, const N : usize > GetSavedResponseQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(GetSavedResponseBorrowed) -> R) -> GetSavedResponseQuery
+    < 'a, C, R, N >
+    {
+        GetSavedResponseQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}pub fn insert_idempotency_key() -> InsertIdempotencyKeyStmt
+{ InsertIdempotencyKeyStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO idempotency(user_id, idempotency_key, created_at)
+VALUES ($1, $2, $3)
+ON CONFLICT DO NOTHING")) } pub
+struct InsertIdempotencyKeyStmt(cornucopia_async :: private :: Stmt) ; impl
+InsertIdempotencyKeyStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+user_id : & 'a uuid::Uuid,idempotency_key : & 'a T1,created_at : & 'a time::OffsetDateTime,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [user_id,idempotency_key,created_at,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, InsertIdempotencyKeyParams < T1,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for InsertIdempotencyKeyStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    InsertIdempotencyKeyParams < T1,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.user_id,& params.idempotency_key,& params.created_at,) ) }
+}pub fn get_saved_response() -> GetSavedResponseStmt
+{ GetSavedResponseStmt(cornucopia_async :: private :: Stmt :: new("SELECT response_status_code, response_headers, response_body
+FROM idempotency
+WHERE user_id = $1
+AND idempotency_key = $2")) } pub
+struct GetSavedResponseStmt(cornucopia_async :: private :: Stmt) ; impl
+GetSavedResponseStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+user_id : & 'a uuid::Uuid,idempotency_key : & 'a T1,) -> GetSavedResponseQuery < 'a, C,
+GetSavedResponse, 2 >
+{
+    GetSavedResponseQuery
+    {
+        client, params : [user_id,idempotency_key,], stmt : & mut self.0, extractor :
+        | row | { GetSavedResponseBorrowed { response_status_code : row.get(0),response_headers : row.get(1),response_body : row.get(2),} }, mapper : | it | { <GetSavedResponse>::from(it) },
+    }
+} }impl < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,> cornucopia_async ::
+Params < 'a, GetSavedResponseParams < T1,>, GetSavedResponseQuery < 'a, C,
+GetSavedResponse, 2 >, C > for GetSavedResponseStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    GetSavedResponseParams < T1,>) -> GetSavedResponseQuery < 'a, C,
+    GetSavedResponse, 2 >
+    { self.bind(client, & params.user_id,& params.idempotency_key,) }
+}pub fn save_response() -> SaveResponseStmt
+{ SaveResponseStmt(cornucopia_async :: private :: Stmt :: new("UPDATE idempotency
+SET response_status_code = $1,
+    response_headers = $2,
+    response_body = $3
+WHERE user_id = $4
+AND idempotency_key = $5")) } pub
+struct SaveResponseStmt(cornucopia_async :: private :: Stmt) ; impl
+SaveResponseStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::BytesSql,T3 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+response_status_code : & 'a i16,response_headers : & 'a T1,response_body : & 'a T2,user_id : & 'a uuid::Uuid,idempotency_key : & 'a T3,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [response_status_code,response_headers,response_body,user_id,idempotency_key,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::BytesSql,T3 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, SaveResponseParams < T1,T2,T3,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for SaveResponseStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    SaveResponseParams < T1,T2,T3,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.response_status_code,& params.response_headers,& params.response_body,& params.user_id,& params.idempotency_key,) ) }
+}pub fn delete_expired_idempotency_keys() -> DeleteExpiredIdempotencyKeysStmt
+{ DeleteExpiredIdempotencyKeysStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM idempotency
+WHERE created_at < $1")) } pub
+struct DeleteExpiredIdempotencyKeysStmt(cornucopia_async :: private :: Stmt) ; impl
+DeleteExpiredIdempotencyKeysStmt { pub async fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+expired_before : & 'a time::OffsetDateTime,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [expired_before,]) .await
+} }}pub mod login_lockout
+{ use futures::{{StreamExt, TryStreamExt}};use futures; use cornucopia_async::GenericClient;#[derive( Debug)] pub struct InsertLoginFailureParams < T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,> { pub id : uuid::Uuid,pub username : T1,pub client_ip : T2,pub failed_at : time::OffsetDateTime,}#[derive( Debug)] pub struct CountUsernameFailuresParams < T1 : cornucopia_async::StringSql,> { pub username : T1,pub since : time::OffsetDateTime,}#[derive( Debug)] pub struct CountClientIpFailuresParams < T1 : cornucopia_async::StringSql,> { pub client_ip : T1,pub since : time::OffsetDateTime,}#[derive( Debug)] pub struct LockSubjectParams < T1 : cornucopia_async::StringSql,> { pub subject : T1,pub locked_until : time::OffsetDateTime,}#[derive( Debug)] pub struct UnlockExpiredSubjectParams < T1 : cornucopia_async::StringSql,> { pub subject : T1,pub now : time::OffsetDateTime,}pub struct I64Query < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> i64,
+    mapper : fn(i64) -> T,
+} impl < 'a, C, T : 'a, const N : usize > I64Query < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(i64) -> R) -> I64Query
+    < 'a, C, R, N >
+    {
+        I64Query
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}pub struct TimeOffsetDateTimeQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> time::OffsetDateTime,
+    mapper : fn(time::OffsetDateTime) -> T,
+} impl < 'a, C, T : 'a, const N : usize > TimeOffsetDateTimeQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(time::OffsetDateTime) -> R) -> TimeOffsetDateTimeQuery
+    < 'a, C, R, N >
+    {
+        TimeOffsetDateTimeQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}pub fn insert_login_failure() -> InsertLoginFailureStmt
+{ InsertLoginFailureStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO login_failures(id, username, client_ip, failed_at)
+VALUES ($1, $2, $3, $4)")) } pub
+struct InsertLoginFailureStmt(cornucopia_async :: private :: Stmt) ; impl
+InsertLoginFailureStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+id : & 'a uuid::Uuid,username : & 'a T1,client_ip : & 'a T2,failed_at : & 'a time::OffsetDateTime,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [id,username,client_ip,failed_at,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, InsertLoginFailureParams < T1,T2,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for InsertLoginFailureStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    InsertLoginFailureParams < T1,T2,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.id,& params.username,& params.client_ip,& params.failed_at,) ) }
+}pub fn count_username_failures() -> CountUsernameFailuresStmt
+{ CountUsernameFailuresStmt(cornucopia_async :: private :: Stmt :: new("SELECT count(*) FROM login_failures
+WHERE username = $1 AND failed_at > $2")) } pub
+struct CountUsernameFailuresStmt(cornucopia_async :: private :: Stmt) ; impl
+CountUsernameFailuresStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+username : & 'a T1,since : & 'a time::OffsetDateTime,) -> I64Query < 'a, C,
+i64, 2 >
+{
+    I64Query
+    {
+        client, params : [username,since,], stmt : & mut self.0, extractor :
+        | row | { row.get(0) }, mapper : | it | { it },
+    }
+} }impl < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,> cornucopia_async ::
+Params < 'a, CountUsernameFailuresParams < T1,>, I64Query < 'a, C,
+i64, 2 >, C > for CountUsernameFailuresStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    CountUsernameFailuresParams < T1,>) -> I64Query < 'a, C,
+    i64, 2 >
+    { self.bind(client, & params.username,& params.since,) }
+}pub fn count_client_ip_failures() -> CountClientIpFailuresStmt
+{ CountClientIpFailuresStmt(cornucopia_async :: private :: Stmt :: new("SELECT count(*) FROM login_failures
+WHERE client_ip = $1 AND failed_at > $2")) } pub
+struct CountClientIpFailuresStmt(cornucopia_async :: private :: Stmt) ; impl
+CountClientIpFailuresStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+client_ip : & 'a T1,since : & 'a time::OffsetDateTime,) -> I64Query < 'a, C,
+i64, 2 >
+{
+    I64Query
+    {
+        client, params : [client_ip,since,], stmt : & mut self.0, extractor :
+        | row | { row.get(0) }, mapper : | it | { it },
+    }
+} }impl < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,> cornucopia_async ::
+Params < 'a, CountClientIpFailuresParams < T1,>, I64Query < 'a, C,
+i64, 2 >, C > for CountClientIpFailuresStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    CountClientIpFailuresParams < T1,>) -> I64Query < 'a, C,
+    i64, 2 >
+    { self.bind(client, & params.client_ip,& params.since,) }
+}pub fn delete_username_failures() -> DeleteUsernameFailuresStmt
+{ DeleteUsernameFailuresStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM login_failures WHERE username = $1")) } pub
+struct DeleteUsernameFailuresStmt(cornucopia_async :: private :: Stmt) ; impl
+DeleteUsernameFailuresStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+username : & 'a T1,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [username,]) .await
+} }pub fn delete_old_login_failures() -> DeleteOldLoginFailuresStmt
+{ DeleteOldLoginFailuresStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM login_failures WHERE failed_at <= $1")) } pub
+struct DeleteOldLoginFailuresStmt(cornucopia_async :: private :: Stmt) ; impl
+DeleteOldLoginFailuresStmt { pub async fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+since : & 'a time::OffsetDateTime,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [since,]) .await
+} }pub fn lock_subject() -> LockSubjectStmt
+{ LockSubjectStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO login_lockouts(subject, locked_until)
+VALUES ($1, $2)
+ON CONFLICT (subject) DO UPDATE SET locked_until = EXCLUDED.locked_until")) } pub
+struct LockSubjectStmt(cornucopia_async :: private :: Stmt) ; impl
+LockSubjectStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+subject : & 'a T1,locked_until : & 'a time::OffsetDateTime,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [subject,locked_until,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, LockSubjectParams < T1,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for LockSubjectStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    LockSubjectParams < T1,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.subject,& params.locked_until,) ) }
+}pub fn get_lockout() -> GetLockoutStmt
+{ GetLockoutStmt(cornucopia_async :: private :: Stmt :: new("SELECT locked_until FROM login_lockouts WHERE subject = $1")) } pub
+struct GetLockoutStmt(cornucopia_async :: private :: Stmt) ; impl
+GetLockoutStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+subject : & 'a T1,) -> TimeOffsetDateTimeQuery < 'a, C,
+time::OffsetDateTime, 1 >
+{
+    TimeOffsetDateTimeQuery
+    {
+        client, params : [subject,], stmt : & mut self.0, extractor :
+        | row | { row.get(0) }, mapper : | it | { it },
+    }
+} }pub fn unlock_expired_subject() -> UnlockExpiredSubjectStmt
+{ UnlockExpiredSubjectStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM login_lockouts
+WHERE subject = $1 AND locked_until <= $2")) } pub
+struct UnlockExpiredSubjectStmt(cornucopia_async :: private :: Stmt) ; impl
+UnlockExpiredSubjectStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+subject : & 'a T1,now : & 'a time::OffsetDateTime,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [subject,now,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, UnlockExpiredSubjectParams < T1,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for UnlockExpiredSubjectStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    UnlockExpiredSubjectParams < T1,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.subject,& params.now,) ) }
+}}pub mod newsletters
+{ use futures::{{StreamExt, TryStreamExt}};use futures; use cornucopia_async::GenericClient;#[derive( Debug)] pub struct InsertNewsletterIssueParams < T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,T3 : cornucopia_async::StringSql,> { pub newsletter_issue_id : uuid::Uuid,pub title : T1,pub text_content : T2,pub html_content : T3,pub published_at : time::OffsetDateTime,}#[derive( Debug)] pub struct DeleteTaskParams < T1 : cornucopia_async::StringSql,> { pub newsletter_issue_id : uuid::Uuid,pub subscriber_email : T1,}#[derive( Debug)] pub struct PostponeTaskParams < T1 : cornucopia_async::StringSql,> { pub execute_after : time::OffsetDateTime,pub newsletter_issue_id : uuid::Uuid,pub subscriber_email : T1,}pub struct StringQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> & str,
+    mapper : fn(& str) -> T,
+} impl < 'a, C, T : 'a, const N : usize > StringQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(& str) -> R) -> StringQuery
+    < 'a, C, R, N >
+    {
+        StringQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}#[derive( Debug, Clone, PartialEq, )] pub struct QueryUserIdByCredentials
+{ pub user_id : uuid::Uuid,pub password_hash : String,}pub struct QueryUserIdByCredentialsBorrowed < 'a >
+{ pub user_id : uuid::Uuid,pub password_hash : &'a str,} impl < 'a > From < QueryUserIdByCredentialsBorrowed <
+'a >> for QueryUserIdByCredentials
+{
+    fn
+    from(QueryUserIdByCredentialsBorrowed { user_id,password_hash,} : QueryUserIdByCredentialsBorrowed < 'a >)
+    -> Self { Self { user_id,password_hash: password_hash.into(),} }
+}pub struct QueryUserIdByCredentialsQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> QueryUserIdByCredentialsBorrowed,
+    mapper : fn(QueryUserIdByCredentialsBorrowed) -> T,
+} impl < 'a, C, T : 'a, const N : usize > QueryUserIdByCredentialsQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(QueryUserIdByCredentialsBorrowed) -> R) -> QueryUserIdByCredentialsQuery
+    < 'a, C, R, N >
+    {
+        QueryUserIdByCredentialsQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}#[derive( Debug, Clone, PartialEq, )] pub struct DequeueTask
+{ pub newsletter_issue_id : uuid::Uuid,pub subscriber_email : String,pub n_retries : i32,}pub struct DequeueTaskBorrowed < 'a >
+{ pub newsletter_issue_id : uuid::Uuid,pub subscriber_email : &'a str,pub n_retries : i32,} impl < 'a > From < DequeueTaskBorrowed <
+'a >> for DequeueTask
+{
+    fn
+    from(DequeueTaskBorrowed { newsletter_issue_id,subscriber_email,n_retries,} : DequeueTaskBorrowed < 'a >)
+    -> Self { Self { newsletter_issue_id,subscriber_email: subscriber_email.into(),n_retries,} }
+}pub struct DequeueTaskQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> DequeueTaskBorrowed,
+    mapper : fn(DequeueTaskBorrowed) -> T,
+} impl < 'a, C, T : 'a, const N : usize > DequeueTaskQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(DequeueTaskBorrowed) -> R) -> DequeueTaskQuery
+    < 'a, C, R, N >
+    {
+        DequeueTaskQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}#[derive( Debug, Clone, PartialEq, )] pub struct GetNewsletterIssue
+{ pub title : String,pub text_content : String,pub html_content : String,}pub struct GetNewsletterIssueBorrowed < 'a >
+{ pub title : &'a str,pub text_content : &'a str,pub html_content : &'a str,} impl < 'a > From < GetNewsletterIssueBorrowed <
+'a >> for GetNewsletterIssue
+{
+    fn
+    from(GetNewsletterIssueBorrowed { title,text_content,html_content,} : GetNewsletterIssueBorrowed < 'a >)
+    -> Self { Self { title: title.into(),text_content: text_content.into(),html_content: html_content.into(),} }
+}pub struct GetNewsletterIssueQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> GetNewsletterIssueBorrowed,
+    mapper : fn(GetNewsletterIssueBorrowed) -> T,
+} impl < 'a, C, T : 'a, const N : usize > GetNewsletterIssueQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(GetNewsletterIssueBorrowed) -> R) -> GetNewsletterIssueQuery
+    < 'a, C, R, N >
+    {
+        GetNewsletterIssueQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}pub struct I64Query < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> i64,
+    mapper : fn(i64) -> T,
+} impl < 'a, C, T : 'a, const N : usize > I64Query < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(i64) -> R) -> I64Query
+    < 'a, C, R, N >
+    {
+        I64Query
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}pub fn query_confirmed_subscribers() -> QueryConfirmedSubscribersStmt
+{ QueryConfirmedSubscribersStmt(cornucopia_async :: private :: Stmt :: new("SELECT email
+FROM subscriptions
+WHERE status = 'confirmed'")) } pub
+struct QueryConfirmedSubscribersStmt(cornucopia_async :: private :: Stmt) ; impl
+QueryConfirmedSubscribersStmt { pub fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+) -> StringQuery < 'a, C,
+String, 0 >
+{
+    StringQuery
+    {
+        client, params : [], stmt : & mut self.0, extractor :
+        | row | { row.get(0) }, mapper : | it | { it.into() },
+    }
+} }pub fn query_user_id_by_credentials() -> QueryUserIdByCredentialsStmt
+{ QueryUserIdByCredentialsStmt(cornucopia_async :: private :: Stmt :: new("SELECT user_id, password_hash
+FROM users
+WHERE username = $1")) } pub
+struct QueryUserIdByCredentialsStmt(cornucopia_async :: private :: Stmt) ; impl
+QueryUserIdByCredentialsStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+name : & 'a T1,) -> QueryUserIdByCredentialsQuery < 'a, C,
+QueryUserIdByCredentials, 1 >
+{
+    QueryUserIdByCredentialsQuery
+    {
+        client, params : [name,], stmt : & mut self.0, extractor :
+        | row | { QueryUserIdByCredentialsBorrowed { user_id : row.get(0),password_hash : row.get(1),} }, mapper : | it | { <QueryUserIdByCredentials>::from(it) },
+    }
+} }pub fn insert_newsletter_issue() -> InsertNewsletterIssueStmt
+{ InsertNewsletterIssueStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO newsletter_issues(
+    newsletter_issue_id,
+    title,
+    text_content,
+    html_content,
+    published_at
+)
+VALUES ($1, $2, $3, $4, $5)")) } pub
+struct InsertNewsletterIssueStmt(cornucopia_async :: private :: Stmt) ; impl
+InsertNewsletterIssueStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,T3 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+newsletter_issue_id : & 'a uuid::Uuid,title : & 'a T1,text_content : & 'a T2,html_content : & 'a T3,published_at : & 'a time::OffsetDateTime,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [newsletter_issue_id,title,text_content,html_content,published_at,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,T3 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, InsertNewsletterIssueParams < T1,T2,T3,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for InsertNewsletterIssueStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    InsertNewsletterIssueParams < T1,T2,T3,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.newsletter_issue_id,& params.title,& params.text_content,& params.html_content,& params.published_at,) ) }
+}pub fn enqueue_delivery_tasks() -> EnqueueDeliveryTasksStmt
+{ EnqueueDeliveryTasksStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO issue_delivery_queue(newsletter_issue_id, subscriber_email)
+SELECT $1, email
+FROM subscriptions
+WHERE status = 'confirmed'")) } pub
+struct EnqueueDeliveryTasksStmt(cornucopia_async :: private :: Stmt) ; impl
+EnqueueDeliveryTasksStmt { pub async fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+newsletter_issue_id : & 'a uuid::Uuid,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [newsletter_issue_id,]) .await
+} }pub fn dequeue_task() -> DequeueTaskStmt
+{ DequeueTaskStmt(cornucopia_async :: private :: Stmt :: new("SELECT newsletter_issue_id, subscriber_email, n_retries
+FROM issue_delivery_queue
+WHERE execute_after <= now()
+LIMIT 1
+FOR UPDATE
+SKIP LOCKED")) } pub
+struct DequeueTaskStmt(cornucopia_async :: private :: Stmt) ; impl
+DequeueTaskStmt { pub fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+) -> DequeueTaskQuery < 'a, C,
+DequeueTask, 0 >
+{
+    DequeueTaskQuery
+    {
+        client, params : [], stmt : & mut self.0, extractor :
+        | row | { DequeueTaskBorrowed { newsletter_issue_id : row.get(0),subscriber_email : row.get(1),n_retries : row.get(2),} }, mapper : | it | { <DequeueTask>::from(it) },
+    }
+} }pub fn get_newsletter_issue() -> GetNewsletterIssueStmt
+{ GetNewsletterIssueStmt(cornucopia_async :: private :: Stmt :: new("SELECT title, text_content, html_content
+FROM newsletter_issues
+WHERE newsletter_issue_id = $1")) } pub
+struct GetNewsletterIssueStmt(cornucopia_async :: private :: Stmt) ; impl
+GetNewsletterIssueStmt { pub fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+newsletter_issue_id : & 'a uuid::Uuid,) -> GetNewsletterIssueQuery < 'a, C,
+GetNewsletterIssue, 1 >
+{
+    GetNewsletterIssueQuery
+    {
+        client, params : [newsletter_issue_id,], stmt : & mut self.0, extractor :
+        | row | { GetNewsletterIssueBorrowed { title : row.get(0),text_content : row.get(1),html_content : row.get(2),} }, mapper : | it | { <GetNewsletterIssue>::from(it) },
+    }
+} }pub fn delete_task() -> DeleteTaskStmt
+{ DeleteTaskStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM issue_delivery_queue
+WHERE newsletter_issue_id = $1
+AND subscriber_email = $2")) } pub
+struct DeleteTaskStmt(cornucopia_async :: private :: Stmt) ; impl
+DeleteTaskStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+newsletter_issue_id : & 'a uuid::Uuid,subscriber_email : & 'a T1,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [newsletter_issue_id,subscriber_email,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, DeleteTaskParams < T1,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for DeleteTaskStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    DeleteTaskParams < T1,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.newsletter_issue_id,& params.subscriber_email,) ) }
+}pub fn postpone_task() -> PostponeTaskStmt
+{ PostponeTaskStmt(cornucopia_async :: private :: Stmt :: new("UPDATE issue_delivery_queue
+SET n_retries = n_retries + 1, execute_after = $1
+WHERE newsletter_issue_id = $2
+AND subscriber_email = $3")) } pub
+struct PostponeTaskStmt(cornucopia_async :: private :: Stmt) ; impl
+PostponeTaskStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+execute_after : & 'a time::OffsetDateTime,newsletter_issue_id : & 'a uuid::Uuid,subscriber_email : & 'a T1,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [execute_after,newsletter_issue_id,subscriber_email,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, PostponeTaskParams < T1,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for PostponeTaskStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    PostponeTaskParams < T1,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.execute_after,& params.newsletter_issue_id,& params.subscriber_email,) ) }
+}pub fn delete_tasks_for_subscriber() -> DeleteTasksForSubscriberStmt
+{ DeleteTasksForSubscriberStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM issue_delivery_queue
+WHERE subscriber_email = $1")) } pub
+struct DeleteTasksForSubscriberStmt(cornucopia_async :: private :: Stmt) ; impl
+DeleteTasksForSubscriberStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+subscriber_email : & 'a T1,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [subscriber_email,]) .await
+} }pub fn count_delivery_tasks() -> CountDeliveryTasksStmt
+{ CountDeliveryTasksStmt(cornucopia_async :: private :: Stmt :: new("SELECT count(*) FROM issue_delivery_queue")) } pub
+struct CountDeliveryTasksStmt(cornucopia_async :: private :: Stmt) ; impl
+CountDeliveryTasksStmt { pub fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+) -> I64Query < 'a, C,
+i64, 0 >
+{
+    I64Query
+    {
+        client, params : [], stmt : & mut self.0, extractor :
+        | row | { row.get(0) }, mapper : | it | { it },
+    }
+} }}pub mod rate_limit
+{ use futures::{{StreamExt, TryStreamExt}};use futures; use cornucopia_async::GenericClient;#[derive( Debug)] pub struct InsertRateLimitBucketParams < T1 : cornucopia_async::StringSql,> { pub key : T1,pub tokens : f64,pub now : time::OffsetDateTime,}#[derive( Debug)] pub struct UpdateRateLimitBucketParams < T1 : cornucopia_async::StringSql,> { pub tokens : f64,pub updated_at : time::OffsetDateTime,pub full_at : time::OffsetDateTime,pub key : T1,}#[derive( Debug, Clone, PartialEq, Copy)] pub struct GetRateLimitBucketForUpdate
+{ pub tokens : f64,pub updated_at : time::OffsetDateTime,}pub struct GetRateLimitBucketForUpdateQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> GetRateLimitBucketForUpdate,
+    mapper : fn(GetRateLimitBucketForUpdate) -> T,
+} impl < 'a, C, T : 'a, const N : usize > GetRateLimitBucketForUpdateQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(GetRateLimitBucketForUpdate) -> R) -> GetRateLimitBucketForUpdateQuery
+    < 'a, C, R, N >
+    {
+        GetRateLimitBucketForUpdateQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}pub fn delete_full_rate_limit_buckets() -> DeleteFullRateLimitBucketsStmt
+{ DeleteFullRateLimitBucketsStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM rate_limit_buckets WHERE full_at <= $1")) } pub
+struct DeleteFullRateLimitBucketsStmt(cornucopia_async :: private :: Stmt) ; impl
+DeleteFullRateLimitBucketsStmt { pub async fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+now : & 'a time::OffsetDateTime,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [now,]) .await
+} }pub fn insert_rate_limit_bucket() -> InsertRateLimitBucketStmt
+{ InsertRateLimitBucketStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO rate_limit_buckets(key, tokens, updated_at, full_at)
+VALUES ($1, $2, $3, $3)
+ON CONFLICT (key) DO NOTHING")) } pub
+struct InsertRateLimitBucketStmt(cornucopia_async :: private :: Stmt) ; impl
+InsertRateLimitBucketStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+key : & 'a T1,tokens : & 'a f64,now : & 'a time::OffsetDateTime,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [key,tokens,now,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, InsertRateLimitBucketParams < T1,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for InsertRateLimitBucketStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    InsertRateLimitBucketParams < T1,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.key,& params.tokens,& params.now,) ) }
+}pub fn get_rate_limit_bucket_for_update() -> GetRateLimitBucketForUpdateStmt
+{ GetRateLimitBucketForUpdateStmt(cornucopia_async :: private :: Stmt :: new("SELECT tokens, updated_at FROM rate_limit_buckets
+WHERE key = $1
+FOR UPDATE")) } pub
+struct GetRateLimitBucketForUpdateStmt(cornucopia_async :: private :: Stmt) ; impl
+GetRateLimitBucketForUpdateStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+key : & 'a T1,) -> GetRateLimitBucketForUpdateQuery < 'a, C,
+GetRateLimitBucketForUpdate, 1 >
+{
+    GetRateLimitBucketForUpdateQuery
+    {
+        client, params : [key,], stmt : & mut self.0, extractor :
+        | row | { GetRateLimitBucketForUpdate { tokens : row.get(0),updated_at : row.get(1),} }, mapper : | it | { it },
+    }
+} }pub fn update_rate_limit_bucket() -> UpdateRateLimitBucketStmt
+{ UpdateRateLimitBucketStmt(cornucopia_async :: private :: Stmt :: new("UPDATE rate_limit_buckets
+SET tokens = $1, updated_at = $2, full_at = $3
+WHERE key = $4")) } pub
+struct UpdateRateLimitBucketStmt(cornucopia_async :: private :: Stmt) ; impl
+UpdateRateLimitBucketStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+tokens : & 'a f64,updated_at : & 'a time::OffsetDateTime,full_at : & 'a time::OffsetDateTime,key : & 'a T1,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [tokens,updated_at,full_at,key,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, UpdateRateLimitBucketParams < T1,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for UpdateRateLimitBucketStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    UpdateRateLimitBucketParams < T1,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.tokens,& params.updated_at,& params.full_at,& params.key,) ) }
+}}pub mod sessions
+{ use futures::{{StreamExt, TryStreamExt}};use futures; use cornucopia_async::GenericClient;#[derive( Debug)] pub struct InsertSessionParams < T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,> { pub session_id : T1,pub user_id : uuid::Uuid,pub csrf_token : T2,pub expires_at : time::OffsetDateTime,}#[derive( Debug)] pub struct DeleteOtherUserSessionsParams < T1 : cornucopia_async::StringSql,> { pub user_id : uuid::Uuid,pub session_id : T1,}#[derive( Debug, Clone, PartialEq, )] pub struct GetSessionUser
+{ pub user_id : uuid::Uuid,pub role : String,pub csrf_token : String,}pub struct GetSessionUserBorrowed < 'a >
+{ pub user_id : uuid::Uuid,pub role : &'a str,pub csrf_token : &'a str,} impl < 'a > From < GetSessionUserBorrowed <
+'a >> for GetSessionUser
+{
+    fn
+    from(GetSessionUserBorrowed { user_id,role,csrf_token,} : GetSessionUserBorrowed < 'a >)
+    -> Self { Self { user_id,role: role.into(),csrf_token: csrf_token.into(),} }
+}pub struct GetSessionUserQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> GetSessionUserBorrowed,
+    mapper : fn(GetSessionUserBorrowed) -> T,
+} impl < 'a, C, T : 'a, const N : usize > GetSessionUserQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(GetSessionUserBorrowed) -> R) -> GetSessionUserQuery
+    < 'a, C, R, N >
+    {
+        GetSessionUserQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}pub fn insert_session() -> InsertSessionStmt
+{ InsertSessionStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO sessions(session_id, user_id, csrf_token, expires_at)
+VALUES ($1, $2, $3, $4)")) } pub
+struct InsertSessionStmt(cornucopia_async :: private :: Stmt) ; impl
+InsertSessionStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+session_id : & 'a T1,user_id : & 'a uuid::Uuid,csrf_token : & 'a T2,expires_at : & 'a time::OffsetDateTime,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [session_id,user_id,csrf_token,expires_at,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, InsertSessionParams < T1,T2,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for InsertSessionStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    InsertSessionParams < T1,T2,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.session_id,& params.user_id,& params.csrf_token,& params.expires_at,) ) }
+}pub fn get_session_user() -> GetSessionUserStmt
+{ GetSessionUserStmt(cornucopia_async :: private :: Stmt :: new("SELECT sessions.user_id, users.role, sessions.csrf_token
+FROM sessions
+JOIN users ON users.user_id = sessions.user_id
+WHERE session_id = $1 AND expires_at > now()")) } pub
+struct GetSessionUserStmt(cornucopia_async :: private :: Stmt) ; impl
+GetSessionUserStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+session_id : & 'a T1,) -> GetSessionUserQuery < 'a, C,
+GetSessionUser, 1 >
+{
+    GetSessionUserQuery
+    {
+        client, params : [session_id,], stmt : & mut self.0, extractor :
+        | row | { GetSessionUserBorrowed { user_id : row.get(0),role : row.get(1),csrf_token : row.get(2),} }, mapper : | it | { <GetSessionUser>::from(it) },
+    }
+} }pub fn delete_session() -> DeleteSessionStmt
+{ DeleteSessionStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM sessions
+WHERE session_id = $1")) } pub
+struct DeleteSessionStmt(cornucopia_async :: private :: Stmt) ; impl
+DeleteSessionStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+session_id : & 'a T1,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [session_id,]) .await
+} }pub fn delete_expired_user_sessions() -> DeleteExpiredUserSessionsStmt
+{ DeleteExpiredUserSessionsStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM sessions
+WHERE user_id = $1 AND expires_at <= now()")) } pub
+struct DeleteExpiredUserSessionsStmt(cornucopia_async :: private :: Stmt) ; impl
+DeleteExpiredUserSessionsStmt { pub async fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+user_id : & 'a uuid::Uuid,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [user_id,]) .await
+} }pub fn delete_user_sessions() -> DeleteUserSessionsStmt
+{ DeleteUserSessionsStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM sessions WHERE user_id = $1")) } pub
+struct DeleteUserSessionsStmt(cornucopia_async :: private :: Stmt) ; impl
+DeleteUserSessionsStmt { pub async fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+user_id : & 'a uuid::Uuid,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [user_id,]) .await
+} }pub fn delete_other_user_sessions() -> DeleteOtherUserSessionsStmt
+{ DeleteOtherUserSessionsStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM sessions WHERE user_id = $1 AND session_id <> $2")) } pub
+struct DeleteOtherUserSessionsStmt(cornucopia_async :: private :: Stmt) ; impl
+DeleteOtherUserSessionsStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+user_id : & 'a uuid::Uuid,session_id : & 'a T1,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [user_id,session_id,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, DeleteOtherUserSessionsParams < T1,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for DeleteOtherUserSessionsStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    DeleteOtherUserSessionsParams < T1,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.user_id,& params.session_id,) ) }
+}}pub mod subscriptions
+{ use futures::{{StreamExt, TryStreamExt}};use futures; use cornucopia_async::GenericClient;#[derive( Debug)] pub struct InsertNewSubscriptionParams < T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,> { pub id : uuid::Uuid,pub email : T1,pub name : T2,pub subscribed_at : time::OffsetDateTime,}#[derive( Debug)] pub struct InsertNewTokenParams < T1 : cornucopia_async::StringSql,> { pub subscription_token : T1,pub subscriber_id : uuid::Uuid,}#[derive( Debug)] pub struct InsertTokenByEmailParams < T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,> { pub token : T1,pub email : T2,}#[derive( Debug)] pub struct InsertUnsubscribeTokenParams < T1 : cornucopia_async::StringSql,> { pub unsubscribe_token : T1,pub subscriber_id : uuid::Uuid,}#[derive( Debug)] pub struct ListSubscribersParams < T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,> { pub status : Option<T1>,pub search : Option<T2>,pub after_at : Option<time::OffsetDateTime>,pub after_id : Option<uuid::Uuid>,pub limit : i64,}#[derive( Debug)] pub struct ImportSubscriberParams < T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,T3 : cornucopia_async::StringSql,> { pub id : uuid::Uuid,pub email : T1,pub name : T2,pub subscribed_at : time::OffsetDateTime,pub status : T3,}pub struct StringQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> & str,
+    mapper : fn(& str) -> T,
+} impl < 'a, C, T : 'a, const N : usize > StringQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(& str) -> R) -> StringQuery
+    < 'a, C, R, N >
+    {
+        StringQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}pub struct UuidUuidQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> uuid::Uuid,
+    mapper : fn(uuid::Uuid) -> T,
+} impl < 'a, C, T : 'a, const N : usize > UuidUuidQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(uuid::Uuid) -> R) -> UuidUuidQuery
+    < 'a, C, R, N >
+    {
+        UuidUuidQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}pub struct I32Query < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> i32,
+    mapper : fn(i32) -> T,
+} impl < 'a, C, T : 'a, const N : usize > I32Query < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(i32) -> R) -> I32Query
+    < 'a, C, R, N >
+    {
+        I32Query
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}#[derive( Debug, Clone, PartialEq, )] pub struct GetSubscriberByUnsubscribeToken
+{ pub id : uuid::Uuid,pub email : String,pub status : String,}pub struct GetSubscriberByUnsubscribeTokenBorrowed < 'a >
+{ pub id : uuid::Uuid,pub email : &'a str,pub status : &'a str,} impl < 'a > From < GetSubscriberByUnsubscribeTokenBorrowed <
+'a >> for GetSubscriberByUnsubscribeToken
+{
+    fn
+    from(GetSubscriberByUnsubscribeTokenBorrowed { id,email,status,} : GetSubscriberByUnsubscribeTokenBorrowed < 'a >)
+    -> Self { Self { id,email: email.into(),status: status.into(),} }
+}pub struct GetSubscriberByUnsubscribeTokenQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> GetSubscriberByUnsubscribeTokenBorrowed,
+    mapper : fn(GetSubscriberByUnsubscribeTokenBorrowed) -> T,
+} impl < 'a, C, T : 'a, const N : usize > GetSubscriberByUnsubscribeTokenQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(GetSubscriberByUnsubscribeTokenBorrowed) -> R) -> GetSubscriberByUnsubscribeTokenQuery
+    < 'a, C, R, N >
+    {
+        GetSubscriberByUnsubscribeTokenQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}#[derive( Debug, Clone, PartialEq, Copy)] pub struct GetTokenDetails
+{ pub subscriber_id : uuid::Uuid,pub created_at : time::OffsetDateTime,}pub struct GetTokenDetailsQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> GetTokenDetails,
+    mapper : fn(GetTokenDetails) -> T,
+} impl < 'a, C, T : 'a, const N : usize > GetTokenDetailsQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(GetTokenDetails) -> R) -> GetTokenDetailsQuery
+    < 'a, C, R, N >
+    {
+        GetTokenDetailsQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}#[derive( Debug, Clone, PartialEq, )] pub struct GetPendingSubscriber
+{ pub name : String,pub token_created_at : time::OffsetDateTime,}pub struct GetPendingSubscriberBorrowed < 'a >
+{ pub name : &'a str,pub token_created_at : time::OffsetDateTime,} impl < 'a > From < GetPendingSubscriberBorrowed <
+'a >> for GetPendingSubscriber
+{
+    fn
+    from(GetPendingSubscriberBorrowed { name,token_created_at,} : GetPendingSubscriberBorrowed < 'a >)
+    -> Self { Self { name: name.into(),token_created_at,} }
+}pub struct GetPendingSubscriberQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> GetPendingSubscriberBorrowed,
+    mapper : fn(GetPendingSubscriberBorrowed) -> T,
+} impl < 'a, C, T : 'a, const N : usize > GetPendingSubscriberQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(GetPendingSubscriberBorrowed) -> R) -> GetPendingSubscriberQuery
+    < 'a, C, R, N >
+    {
+        GetPendingSubscriberQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}#[derive( Debug, Clone, PartialEq, )] pub struct ListSubscribers
+{ pub id : uuid::Uuid,pub email : String,pub name : String,pub status : String,pub subscribed_at : time::OffsetDateTime,}pub struct ListSubscribersBorrowed < 'a >
+{ pub id : uuid::Uuid,pub email : &'a str,pub name : &'a str,pub status : &'a str,pub subscribed_at : time::OffsetDateTime,} impl < 'a > From < ListSubscribersBorrowed <
+'a >> for ListSubscribers
+{
+    fn
+    from(ListSubscribersBorrowed { id,email,name,status,subscribed_at,} : ListSubscribersBorrowed < 'a >)
+    -> Self { Self { id,email: email.into(),name: name.into(),status: status.into(),subscribed_at,} }
+}pub struct ListSubscribersQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> ListSubscribersBorrowed,
+    mapper : fn(ListSubscribersBorrowed) -> T,
+} impl < 'a, C, T : 'a, const N : usize > ListSubscribersQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(ListSubscribersBorrowed) -> R) -> ListSubscribersQuery
+    < 'a, C, R, N >
+    {
+        ListSubscribersQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}#[derive( Debug, Clone, PartialEq, )] pub struct ExportSubscribers
+{ pub email : String,pub name : String,pub status : String,pub subscribed_at : time::OffsetDateTime,}pub struct ExportSubscribersBorrowed < 'a >
+{ pub email : &'a str,pub name : &'a str,pub status : &'a str,pub subscribed_at : time::OffsetDateTime,} impl < 'a > From < ExportSubscribersBorrowed <
+'a >> for ExportSubscribers
+{
+    fn
+    from(ExportSubscribersBorrowed { email,name,status,subscribed_at,} : ExportSubscribersBorrowed < 'a >)
+    -> Self { Self { email: email.into(),name: name.into(),status: status.into(),subscribed_at,} }
+}pub struct ExportSubscribersQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> ExportSubscribersBorrowed,
+    mapper : fn(ExportSubscribersBorrowed) -> T,
+} impl < 'a, C, T : 'a, const N : usize > ExportSubscribersQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(ExportSubscribersBorrowed) -> R) -> ExportSubscribersQuery
+    < 'a, C, R, N >
+    {
+        ExportSubscribersQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}pub fn insert_new_subscription() -> InsertNewSubscriptionStmt
+{ InsertNewSubscriptionStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO subscriptions(id, email, name, subscribed_at, status)
+VALUES ($1, $2, $3, $4, 'pending_confirmation')")) } pub
+struct InsertNewSubscriptionStmt(cornucopia_async :: private :: Stmt) ; impl
+InsertNewSubscriptionStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+id : & 'a uuid::Uuid,email : & 'a T1,name : & 'a T2,subscribed_at : & 'a time::OffsetDateTime,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [id,email,name,subscribed_at,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, InsertNewSubscriptionParams < T1,T2,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for InsertNewSubscriptionStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    InsertNewSubscriptionParams < T1,T2,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.id,& params.email,& params.name,& params.subscribed_at,) ) }
+}pub fn insert_new_token() -> InsertNewTokenStmt
+{ InsertNewTokenStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO subscription_tokens(subscription_token, subscriber_id)
+VALUES ($1, $2)")) } pub
+struct InsertNewTokenStmt(cornucopia_async :: private :: Stmt) ; impl
+InsertNewTokenStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+subscription_token : & 'a T1,subscriber_id : & 'a uuid::Uuid,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [subscription_token,subscriber_id,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, InsertNewTokenParams < T1,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for InsertNewTokenStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    InsertNewTokenParams < T1,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.subscription_token,& params.subscriber_id,) ) }
+}pub fn delete_token_by_email() -> DeleteTokenByEmailStmt
+{ DeleteTokenByEmailStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM subscription_tokens
+    WHERE subscriber_id = (
+    SELECT id
+    FROM subscriptions
+    WHERE email = $1
+)")) } pub
+struct DeleteTokenByEmailStmt(cornucopia_async :: private :: Stmt) ; impl
+DeleteTokenByEmailStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+email : & 'a T1,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [email,]) .await
+} }pub fn insert_token_by_email() -> InsertTokenByEmailStmt
+{ InsertTokenByEmailStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO subscription_tokens
+(subscription_token, subscriber_id)
+VALUES ($1, (
+        SELECT id
+        FROM subscriptions
+        WHERE email = $2
+    )
+)")) } pub
+struct InsertTokenByEmailStmt(cornucopia_async :: private :: Stmt) ; impl
+InsertTokenByEmailStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+token : & 'a T1,email : & 'a T2,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [token,email,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, InsertTokenByEmailParams < T1,T2,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for InsertTokenByEmailStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    InsertTokenByEmailParams < T1,T2,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.token,& params.email,) ) }
+}pub fn get_status() -> GetStatusStmt
+{ GetStatusStmt(cornucopia_async :: private :: Stmt :: new("SELECT status
+FROM subscriptions
+WHERE email = $1")) } pub
+struct GetStatusStmt(cornucopia_async :: private :: Stmt) ; impl
+GetStatusStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+email : & 'a T1,) -> StringQuery < 'a, C,
+String, 1 >
+{
+    StringQuery
+    {
+        client, params : [email,], stmt : & mut self.0, extractor :
+        | row | { row.get(0) }, mapper : | it | { it.into() },
+    }
+} }pub fn confirm_subscriber() -> ConfirmSubscriberStmt
+{ ConfirmSubscriberStmt(cornucopia_async :: private :: Stmt :: new("UPDATE subscriptions SET status = 'confirmed' WHERE id = $1 AND status = 'pending_confirmation'")) } pub
+struct ConfirmSubscriberStmt(cornucopia_async :: private :: Stmt) ; impl
+ConfirmSubscriberStmt { pub async fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+sub_id : & 'a uuid::Uuid,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [sub_id,]) .await
+} }pub fn get_subscriber_id_from_token() -> GetSubscriberIdFromTokenStmt
+{ GetSubscriberIdFromTokenStmt(cornucopia_async :: private :: Stmt :: new("SELECT subscriber_id FROM subscription_tokens WHERE subscription_token = $1")) } pub
+struct GetSubscriberIdFromTokenStmt(cornucopia_async :: private :: Stmt) ; impl
+GetSubscriberIdFromTokenStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+sub_token : & 'a T1,) -> UuidUuidQuery < 'a, C,
+uuid::Uuid, 1 >
+{
+    UuidUuidQuery
+    {
+        client, params : [sub_token,], stmt : & mut self.0, extractor :
+        | row | { row.get(0) }, mapper : | it | { it },
+    }
+} }pub fn get_countdown() -> GetCountdownStmt
+{ GetCountdownStmt(cornucopia_async :: private :: Stmt :: new("WITH RECURSIVE countdown(val) AS (
+    SELECT 10 AS val -- initial, non-recursive query
+    UNION -- every recursive CTE needs `UNION` keyword
+    SELECT val - 1 FROM countdown WHERE val > 1 -- recursive query
+)
+SELECT *
+FROM countdown")) } pub
+struct GetCountdownStmt(cornucopia_async :: private :: Stmt) ; impl
+GetCountdownStmt { pub fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+) -> I32Query < 'a, C,
+i32, 0 >
+{
+    I32Query
+    {
+        client, params : [], stmt : & mut self.0, extractor :
+        | row | { row.get(0) }, mapper : | it | { it },
+    }
+} }pub fn insert_unsubscribe_token() -> InsertUnsubscribeTokenStmt
+{ InsertUnsubscribeTokenStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO unsubscribe_tokens(unsubscribe_token, subscriber_id)
+VALUES ($1, $2)")) } pub
+struct InsertUnsubscribeTokenStmt(cornucopia_async :: private :: Stmt) ; impl
+InsertUnsubscribeTokenStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+unsubscribe_token : & 'a T1,subscriber_id : & 'a uuid::Uuid,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [unsubscribe_token,subscriber_id,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, InsertUnsubscribeTokenParams < T1,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for InsertUnsubscribeTokenStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    InsertUnsubscribeTokenParams < T1,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.unsubscribe_token,& params.subscriber_id,) ) }
+}pub fn get_unsubscribe_token() -> GetUnsubscribeTokenStmt
+{ GetUnsubscribeTokenStmt(cornucopia_async :: private :: Stmt :: new("SELECT unsubscribe_token
+FROM unsubscribe_tokens
+JOIN subscriptions ON subscriptions.id = unsubscribe_tokens.subscriber_id
+WHERE subscriptions.email = $1")) } pub
+struct GetUnsubscribeTokenStmt(cornucopia_async :: private :: Stmt) ; impl
+GetUnsubscribeTokenStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+email : & 'a T1,) -> StringQuery < 'a, C,
+String, 1 >
+{
+    StringQuery
+    {
+        client, params : [email,], stmt : & mut self.0, extractor :
+        | row | { row.get(0) }, mapper : | it | { it.into() },
+    }
+} }pub fn get_subscriber_by_unsubscribe_token() -> GetSubscriberByUnsubscribeTokenStmt
+{ GetSubscriberByUnsubscribeTokenStmt(cornucopia_async :: private :: Stmt :: new("SELECT id, email, status
+FROM subscriptions
+WHERE id = (
+    SELECT subscriber_id
+    FROM unsubscribe_tokens
+    WHERE unsubscribe_token = $1
+)")) } pub
+struct GetSubscriberByUnsubscribeTokenStmt(cornucopia_async :: private :: Stmt) ; impl
+GetSubscriberByUnsubscribeTokenStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+unsubscribe_token : & 'a T1,) -> GetSubscriberByUnsubscribeTokenQuery < 'a, C,
+GetSubscriberByUnsubscribeToken, 1 >
+{
+    GetSubscriberByUnsubscribeTokenQuery
+    {
+        client, params : [unsubscribe_token,], stmt : & mut self.0, extractor :
+        | row | { GetSubscriberByUnsubscribeTokenBorrowed { id : row.get(0),email : row.get(1),status : row.get(2),} }, mapper : | it | { <GetSubscriberByUnsubscribeToken>::from(it) },
+    }
+} }pub fn unsubscribe_subscriber() -> UnsubscribeSubscriberStmt
+{ UnsubscribeSubscriberStmt(cornucopia_async :: private :: Stmt :: new("UPDATE subscriptions SET status = 'unsubscribed' WHERE id = $1")) } pub
+struct UnsubscribeSubscriberStmt(cornucopia_async :: private :: Stmt) ; impl
+UnsubscribeSubscriberStmt { pub async fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+sub_id : & 'a uuid::Uuid,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [sub_id,]) .await
+} }pub fn resubscribe() -> ResubscribeStmt
+{ ResubscribeStmt(cornucopia_async :: private :: Stmt :: new("UPDATE subscriptions SET status = 'pending_confirmation' WHERE email = $1 AND status = 'unsubscribed'")) } pub
+struct ResubscribeStmt(cornucopia_async :: private :: Stmt) ; impl
+ResubscribeStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+email : & 'a T1,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [email,]) .await
+} }pub fn get_token_details() -> GetTokenDetailsStmt
+{ GetTokenDetailsStmt(cornucopia_async :: private :: Stmt :: new("SELECT subscriber_id, created_at
+FROM subscription_tokens
+WHERE subscription_token = $1")) } pub
+struct GetTokenDetailsStmt(cornucopia_async :: private :: Stmt) ; impl
+GetTokenDetailsStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+sub_token : & 'a T1,) -> GetTokenDetailsQuery < 'a, C,
+GetTokenDetails, 1 >
+{
+    GetTokenDetailsQuery
+    {
+        client, params : [sub_token,], stmt : & mut self.0, extractor :
+        | row | { GetTokenDetails { subscriber_id : row.get(0),created_at : row.get(1),} }, mapper : | it | { it },
+    }
+} }pub fn get_pending_subscriber() -> GetPendingSubscriberStmt
+{ GetPendingSubscriberStmt(cornucopia_async :: private :: Stmt :: new("SELECT subscriptions.name, subscription_tokens.created_at AS token_created_at
+FROM subscriptions
+JOIN subscription_tokens ON subscription_tokens.subscriber_id = subscriptions.id
+WHERE subscriptions.email = $1 AND subscriptions.status = 'pending_confirmation'
+ORDER BY subscription_tokens.created_at DESC
+LIMIT 1")) } pub
+struct GetPendingSubscriberStmt(cornucopia_async :: private :: Stmt) ; impl
+GetPendingSubscriberStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+email : & 'a T1,) -> GetPendingSubscriberQuery < 'a, C,
+GetPendingSubscriber, 1 >
+{
+    GetPendingSubscriberQuery
+    {
+        client, params : [email,], stmt : & mut self.0, extractor :
+        | row | { GetPendingSubscriberBorrowed { name : row.get(0),token_created_at : row.get(1),} }, mapper : | it | { <GetPendingSubscriber>::from(it) },
+    }
+} }pub fn delete_expired_pending_subscribers() -> DeleteExpiredPendingSubscribersStmt
+{ DeleteExpiredPendingSubscribersStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM subscriptions
+WHERE status = 'pending_confirmation'
+AND NOT EXISTS (
+    SELECT 1
+    FROM subscription_tokens
+    WHERE subscription_tokens.subscriber_id = subscriptions.id
+    AND subscription_tokens.created_at > $1
+)")) } pub
+struct DeleteExpiredPendingSubscribersStmt(cornucopia_async :: private :: Stmt) ; impl
+DeleteExpiredPendingSubscribersStmt { pub async fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+expired_before : & 'a time::OffsetDateTime,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [expired_before,]) .await
+} }pub fn list_subscribers() -> ListSubscribersStmt
+{ ListSubscribersStmt(cornucopia_async :: private :: Stmt :: new("SELECT id, email, name, status, subscribed_at
+FROM subscriptions
+WHERE ($1::text IS NULL OR status = $1)
+    AND (
+        $2::text IS NULL
+        OR starts_with(lower(email), lower($2))
+        OR starts_with(lower(name), lower($2))
+    )
+    AND (
+        $3::timestamptz IS NULL
+        OR (subscribed_at, id) < ($3, $4::uuid)
+    )
+ORDER BY subscribed_at DESC, id DESC
+LIMIT $5")) } pub
+struct ListSubscribersStmt(cornucopia_async :: private :: Stmt) ; impl
+ListSubscribersStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+status : & 'a Option<T1>,search : & 'a Option<T2>,after_at : & 'a Option<time::OffsetDateTime>,after_id : & 'a Option<uuid::Uuid>,limit : & 'a i64,) -> ListSubscribersQuery < 'a, C,
+ListSubscribers, 5 >
+{
+    ListSubscribersQuery
+    {
+        client, params : [status,search,after_at,after_id,limit,], stmt : & mut self.0, extractor :
+        | row | { ListSubscribersBorrowed { id : row.get(0),email : row.get(1),name : row.get(2),status : row.get(3),subscribed_at : row.get(4),} }, mapper : | it | { <ListSubscribers>::from(it) },
+    }
+} }impl < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,> cornucopia_async ::
+Params < 'a, ListSubscribersParams < T1,T2,>, ListSubscribersQuery < 'a, C,
+ListSubscribers, 5 >, C > for ListSubscribersStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    ListSubscribersParams < T1,T2,>) -> ListSubscribersQuery < 'a, C,
+    ListSubscribers, 5 >
+    { self.bind(client, & params.status,& params.search,& params.after_at,& params.after_id,& params.limit,) }
+}pub fn delete_subscriber() -> DeleteSubscriberStmt
+{ DeleteSubscriberStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM subscriptions WHERE id = $1 RETURNING email")) } pub
+struct DeleteSubscriberStmt(cornucopia_async :: private :: Stmt) ; impl
+DeleteSubscriberStmt { pub fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+sub_id : & 'a uuid::Uuid,) -> StringQuery < 'a, C,
+String, 1 >
+{
+    StringQuery
+    {
+        client, params : [sub_id,], stmt : & mut self.0, extractor :
+        | row | { row.get(0) }, mapper : | it | { it.into() },
+    }
+} }pub fn import_subscriber() -> ImportSubscriberStmt
+{ ImportSubscriberStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO subscriptions(id, email, name, subscribed_at, status)
+VALUES ($1, $2, $3, $4, $5)
+ON CONFLICT (email) DO NOTHING")) } pub
+struct ImportSubscriberStmt(cornucopia_async :: private :: Stmt) ; impl
+ImportSubscriberStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,T3 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+id : & 'a uuid::Uuid,email : & 'a T1,name : & 'a T2,subscribed_at : & 'a time::OffsetDateTime,status : & 'a T3,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [id,email,name,subscribed_at,status,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,T3 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, ImportSubscriberParams < T1,T2,T3,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for ImportSubscriberStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    ImportSubscriberParams < T1,T2,T3,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.id,& params.email,& params.name,& params.subscribed_at,& params.status,) ) }
+}pub fn export_subscribers() -> ExportSubscribersStmt
+{ ExportSubscribersStmt(cornucopia_async :: private :: Stmt :: new("SELECT email, name, status, subscribed_at
+FROM subscriptions
+ORDER BY subscribed_at, id")) } pub
+struct ExportSubscribersStmt(cornucopia_async :: private :: Stmt) ; impl
+ExportSubscribersStmt { pub fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+) -> ExportSubscribersQuery < 'a, C,
+ExportSubscribers, 0 >
+{
+    ExportSubscribersQuery
+    {
+        client, params : [], stmt : & mut self.0, extractor :
+        | row | { ExportSubscribersBorrowed { email : row.get(0),name : row.get(1),status : row.get(2),subscribed_at : row.get(3),} }, mapper : | it | { <ExportSubscribers>::from(it) },
+    }
+} }}pub mod two_factor
+{ use futures::{{StreamExt, TryStreamExt}};use futures; use cornucopia_async::GenericClient;#[derive( Debug)] pub struct StartTotpEnrollmentParams < T1 : cornucopia_async::StringSql,> { pub totp_secret : T1,pub user_id : uuid::Uuid,}#[derive(Clone,Copy, Debug)] pub struct EnableTotpParams < > { pub step : i64,pub user_id : uuid::Uuid,}#[derive(Clone,Copy, Debug)] pub struct UseTotpStepParams < > { pub step : i64,pub user_id : uuid::Uuid,}#[derive( Debug)] pub struct InsertRecoveryCodeParams < T1 : cornucopia_async::StringSql,> { pub id : uuid::Uuid,pub user_id : uuid::Uuid,pub code_hash : T1,}#[derive( Debug)] pub struct UseRecoveryCodeParams < T1 : cornucopia_async::StringSql,> { pub used_at : time::OffsetDateTime,pub user_id : uuid::Uuid,pub code_hash : T1,}#[derive( Debug)] pub struct InsertLoginChallengeParams < T1 : cornucopia_async::StringSql,> { pub challenge_hash : T1,pub user_id : uuid::Uuid,pub expires_at : time::OffsetDateTime,}#[derive( Debug)] pub struct GetLoginChallengeUserParams < T1 : cornucopia_async::StringSql,> { pub challenge_hash : T1,pub now : time::OffsetDateTime,}#[derive( Debug, Clone, PartialEq, )] pub struct GetTotpSettings
+{ pub totp_secret : Option<String>,pub totp_enabled : bool,pub totp_last_step : Option<i64>,}pub struct GetTotpSettingsBorrowed < 'a >
+{ pub totp_secret : Option<&'a str>,pub totp_enabled : bool,pub totp_last_step : Option<i64>,} impl < 'a > From < GetTotpSettingsBorrowed <
+'a >> for GetTotpSettings
+{
+    fn
+    from(GetTotpSettingsBorrowed { totp_secret,totp_enabled,totp_last_step,} : GetTotpSettingsBorrowed < 'a >)
+    -> Self { Self { totp_secret: totp_secret.map(|v| v.into()),totp_enabled,totp_last_step,} }
+}pub struct GetTotpSettingsQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> GetTotpSettingsBorrowed,
+    mapper : fn(GetTotpSettingsBorrowed) -> T,
+} impl < 'a, C, T : 'a, const N : usize > GetTotpSettingsQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(GetTotpSettingsBorrowed) -> R) -> GetTotpSettingsQuery
+    < 'a, C, R, N >
+    {
+        GetTotpSettingsQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}pub struct I64Query < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> i64,
+    mapper : fn(i64) -> T,
+} impl < 'a, C, T : 'a, const N : usize > I64Query < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(i64) -> R) -> I64Query
+    < 'a, C, R, N >
+    {
+        I64Query
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}pub struct UuidUuidQuery < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> uuid::Uuid,
+    mapper : fn(uuid::Uuid) -> T,
+} impl < 'a, C, T : 'a, const N : usize > UuidUuidQuery < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(uuid::Uuid) -> R) -> UuidUuidQuery
+    < 'a, C, R, N >
+    {
+        UuidUuidQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}pub struct I32Query < 'a, C : GenericClient, T, const N : usize >
+{
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> i32,
+    mapper : fn(i32) -> T,
+} impl < 'a, C, T : 'a, const N : usize > I32Query < 'a, C, T, N >
+where C : GenericClient
+{
+    pub fn map < R > (self, mapper : fn(i32) -> R) -> I32Query
+    < 'a, C, R, N >
+    {
+        I32Query
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
+    }
+}pub fn get_totp_settings() -> GetTotpSettingsStmt
+{ GetTotpSettingsStmt(cornucopia_async :: private :: Stmt :: new("SELECT totp_secret, totp_enabled, totp_last_step
+FROM users
+WHERE user_id = $1")) } pub
+struct GetTotpSettingsStmt(cornucopia_async :: private :: Stmt) ; impl
+GetTotpSettingsStmt { pub fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+user_id : & 'a uuid::Uuid,) -> GetTotpSettingsQuery < 'a, C,
+GetTotpSettings, 1 >
+{
+    GetTotpSettingsQuery
+    {
+        client, params : [user_id,], stmt : & mut self.0, extractor :
+        | row | { GetTotpSettingsBorrowed { totp_secret : row.get(0),totp_enabled : row.get(1),totp_last_step : row.get(2),} }, mapper : | it | { <GetTotpSettings>::from(it) },
+    }
+} }pub fn start_totp_enrollment() -> StartTotpEnrollmentStmt
+{ StartTotpEnrollmentStmt(cornucopia_async :: private :: Stmt :: new("UPDATE users
+SET totp_secret = $1, totp_enabled = false, totp_last_step = NULL
+WHERE user_id = $2 AND totp_enabled = false")) } pub
+struct StartTotpEnrollmentStmt(cornucopia_async :: private :: Stmt) ; impl
+StartTotpEnrollmentStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+totp_secret : & 'a T1,user_id : & 'a uuid::Uuid,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [totp_secret,user_id,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, StartTotpEnrollmentParams < T1,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for StartTotpEnrollmentStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    StartTotpEnrollmentParams < T1,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.totp_secret,& params.user_id,) ) }
+}pub fn enable_totp() -> EnableTotpStmt
+{ EnableTotpStmt(cornucopia_async :: private :: Stmt :: new("UPDATE users
+SET totp_enabled = true, totp_last_step = $1
+WHERE user_id = $2 AND totp_secret IS NOT NULL")) } pub
+struct EnableTotpStmt(cornucopia_async :: private :: Stmt) ; impl
+EnableTotpStmt { pub async fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+step : & 'a i64,user_id : & 'a uuid::Uuid,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [step,user_id,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, >
+cornucopia_async :: Params < 'a, EnableTotpParams < >, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for EnableTotpStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    EnableTotpParams < >) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.step,& params.user_id,) ) }
+}pub fn disable_totp() -> DisableTotpStmt
+{ DisableTotpStmt(cornucopia_async :: private :: Stmt :: new("UPDATE users
+SET totp_secret = NULL, totp_enabled = false, totp_last_step = NULL
+WHERE user_id = $1")) } pub
+struct DisableTotpStmt(cornucopia_async :: private :: Stmt) ; impl
+DisableTotpStmt { pub async fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+user_id : & 'a uuid::Uuid,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [user_id,]) .await
+} }pub fn use_totp_step() -> UseTotpStepStmt
+{ UseTotpStepStmt(cornucopia_async :: private :: Stmt :: new("UPDATE users
+SET totp_last_step = $1
+WHERE user_id = $2
+    AND (totp_last_step IS NULL OR totp_last_step < $1)")) } pub
+struct UseTotpStepStmt(cornucopia_async :: private :: Stmt) ; impl
+UseTotpStepStmt { pub async fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+step : & 'a i64,user_id : & 'a uuid::Uuid,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [step,user_id,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, >
+cornucopia_async :: Params < 'a, UseTotpStepParams < >, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for UseTotpStepStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    UseTotpStepParams < >) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.step,& params.user_id,) ) }
+}pub fn insert_recovery_code() -> InsertRecoveryCodeStmt
+{ InsertRecoveryCodeStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO recovery_codes(id, user_id, code_hash)
+VALUES ($1, $2, $3)")) } pub
+struct InsertRecoveryCodeStmt(cornucopia_async :: private :: Stmt) ; impl
+InsertRecoveryCodeStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+id : & 'a uuid::Uuid,user_id : & 'a uuid::Uuid,code_hash : & 'a T1,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [id,user_id,code_hash,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, InsertRecoveryCodeParams < T1,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for InsertRecoveryCodeStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    InsertRecoveryCodeParams < T1,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.id,& params.user_id,& params.code_hash,) ) }
+}pub fn delete_recovery_codes() -> DeleteRecoveryCodesStmt
+{ DeleteRecoveryCodesStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM recovery_codes WHERE user_id = $1")) } pub
+struct DeleteRecoveryCodesStmt(cornucopia_async :: private :: Stmt) ; impl
+DeleteRecoveryCodesStmt { pub async fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+user_id : & 'a uuid::Uuid,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [user_id,]) .await
+} }pub fn use_recovery_code() -> UseRecoveryCodeStmt
+{ UseRecoveryCodeStmt(cornucopia_async :: private :: Stmt :: new("UPDATE recovery_codes
+SET used_at = $1
+WHERE user_id = $2 AND code_hash = $3 AND used_at IS NULL")) } pub
+struct UseRecoveryCodeStmt(cornucopia_async :: private :: Stmt) ; impl
+UseRecoveryCodeStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+used_at : & 'a time::OffsetDateTime,user_id : & 'a uuid::Uuid,code_hash : & 'a T1,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [used_at,user_id,code_hash,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, UseRecoveryCodeParams < T1,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for UseRecoveryCodeStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    UseRecoveryCodeParams < T1,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.used_at,& params.user_id,& params.code_hash,) ) }
+}pub fn count_unused_recovery_codes() -> CountUnusedRecoveryCodesStmt
+{ CountUnusedRecoveryCodesStmt(cornucopia_async :: private :: Stmt :: new("SELECT count(*) FROM recovery_codes
+WHERE user_id = $1 AND used_at IS NULL")) } pub
+struct CountUnusedRecoveryCodesStmt(cornucopia_async :: private :: Stmt) ; impl
+CountUnusedRecoveryCodesStmt { pub fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+user_id : & 'a uuid::Uuid,) -> I64Query < 'a, C,
+i64, 1 >
+{
+    I64Query
+    {
+        client, params : [user_id,], stmt : & mut self.0, extractor :
+        | row | { row.get(0) }, mapper : | it | { it },
+    }
+} }pub fn insert_login_challenge() -> InsertLoginChallengeStmt
+{ InsertLoginChallengeStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO login_challenges(challenge_hash, user_id, expires_at)
+VALUES ($1, $2, $3)")) } pub
+struct InsertLoginChallengeStmt(cornucopia_async :: private :: Stmt) ; impl
+InsertLoginChallengeStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+challenge_hash : & 'a T1,user_id : & 'a uuid::Uuid,expires_at : & 'a time::OffsetDateTime,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [challenge_hash,user_id,expires_at,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, InsertLoginChallengeParams < T1,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for InsertLoginChallengeStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    InsertLoginChallengeParams < T1,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.challenge_hash,& params.user_id,& params.expires_at,) ) }
+}pub fn get_login_challenge_user() -> GetLoginChallengeUserStmt
+{ GetLoginChallengeUserStmt(cornucopia_async :: private :: Stmt :: new("SELECT user_id FROM login_challenges
+WHERE challenge_hash = $1 AND expires_at > $2")) } pub
+struct GetLoginChallengeUserStmt(cornucopia_async :: private :: Stmt) ; impl
+GetLoginChallengeUserStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+challenge_hash : & 'a T1,now : & 'a time::OffsetDateTime,) -> UuidUuidQuery < 'a, C,
+uuid::Uuid, 2 >
+{
+    UuidUuidQuery
+    {
+        client, params : [challenge_hash,now,], stmt : & mut self.0, extractor :
+        | row | { row.get(0) }, mapper : | it | { it },
+    }
+} }impl < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,> cornucopia_async ::
+Params < 'a, GetLoginChallengeUserParams < T1,>, UuidUuidQuery < 'a, C,
+uuid::Uuid, 2 >, C > for GetLoginChallengeUserStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    GetLoginChallengeUserParams < T1,>) -> UuidUuidQuery < 'a, C,
+    uuid::Uuid, 2 >
+    { self.bind(client, & params.challenge_hash,& params.now,) }
+}pub fn record_failed_challenge() -> RecordFailedChallengeStmt
+{ RecordFailedChallengeStmt(cornucopia_async :: private :: Stmt :: new("UPDATE login_challenges
+SET failed_attempts = failed_attempts + 1
+WHERE challenge_hash = $1
+RETURNING failed_attempts")) } pub
+struct RecordFailedChallengeStmt(cornucopia_async :: private :: Stmt) ; impl
+RecordFailedChallengeStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+challenge_hash : & 'a T1,) -> I32Query < 'a, C,
+i32, 1 >
+{
+    I32Query
+    {
+        client, params : [challenge_hash,], stmt : & mut self.0, extractor :
+        | row | { row.get(0) }, mapper : | it | { it },
+    }
+} }pub fn delete_login_challenge() -> DeleteLoginChallengeStmt
+{ DeleteLoginChallengeStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM login_challenges WHERE challenge_hash = $1")) } pub
+struct DeleteLoginChallengeStmt(cornucopia_async :: private :: Stmt) ; impl
+DeleteLoginChallengeStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+challenge_hash : & 'a T1,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [challenge_hash,]) .await
+} }pub fn delete_expired_login_challenges() -> DeleteExpiredLoginChallengesStmt
+{ DeleteExpiredLoginChallengesStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM login_challenges WHERE expires_at <= $1")) } pub
+struct DeleteExpiredLoginChallengesStmt(cornucopia_async :: private :: Stmt) ; impl
+DeleteExpiredLoginChallengesStmt { pub async fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+now : & 'a time::OffsetDateTime,) -> Result < u64, tokio_postgres :: Error >
+{
+    let stmt = self.0.prepare(client) .await ? ;
+    client.execute(stmt, & [now,]) .await
+} }}pub mod users
+{ use futures::{{StreamExt, TryStreamExt}};use futures; use cornucopia_async::GenericClient;#[derive( Debug)] pub struct UpdatePasswordHashParams < T1 : cornucopia_async::StringSql,> { pub password_hash : T1,pub user_id : uuid::Uuid,}#[derive( Debug)] pub struct InsertPasswordResetTokenParams < T1 : cornucopia_async::StringSql,> { pub token_hash : T1,pub user_id : uuid::Uuid,pub expires_at : time::OffsetDateTime,}#[derive( Debug)] pub struct InsertUserParams < T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,T3 : cornucopia_async::StringSql,T4 : cornucopia_async::StringSql,> { pub user_id : uuid::Uuid,pub username : T1,pub password_hash : T2,pub email : Option<T3>,pub role : T4,}#[derive( Debug)] pub struct UpdateUserRoleParams < T1 : cornucopia_async::StringSql,> { pub role : T1,pub user_id : uuid::Uuid,}pub struct StringQuery < 'a, C : GenericClient, T, const N : usize >
 {
     client : & 'a  C, params :
     [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
@@ -43,27 +2407,19 @@ where C : GenericClient
         res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
         Ok(it)
     }
-}#[derive( Debug, Clone, PartialEq, )] pub struct QueryUserIdByCredentials
-{ pub user_id : uuid::Uuid,pub password_hash : String,}pub struct QueryUserIdByCredentialsBorrowed < 'a >
-{ pub user_id : uuid::Uuid,pub password_hash : &'a str,} impl < 'a > From < QueryUserIdByCredentialsBorrowed <
-'a >> for QueryUserIdByCredentials
-{
-    fn
-    from(QueryUserIdByCredentialsBorrowed { user_id,password_hash,} : QueryUserIdByCredentialsBorrowed < 'a >)
-    -> Self { Self { user_id,password_hash: password_hash.into(),} }
-}pub struct QueryUserIdByCredentialsQuery < 'a, C : GenericClient, T, const N : usize >
+}pub struct UuidUuidQuery < 'a, C : GenericClient, T, const N : usize >
 {
     client : & 'a  C, params :
     [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
-    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> QueryUserIdByCredentialsBorrowed,
-    mapper : fn(QueryUserIdByCredentialsBorrowed) -> T,
-} impl < 'a, C, T : 'a, const N : usize > QueryUserIdByCredentialsQuery < 'a, C, T, N >
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> uuid::Uuid,
+    mapper : fn(uuid::Uuid) -> T,
+} impl < 'a, C, T : 'a, const N : usize > UuidUuidQuery < 'a, C, T, N >
 where C : GenericClient
 {
-    pub fn map < R > (self, mapper : fn(QueryUserIdByCredentialsBorrowed) -> R) -> QueryUserIdByCredentialsQuery
+    pub fn map < R > (self, mapper : fn(uuid::Uuid) -> R) -> UuidUuidQuery
     < 'a, C, R, N >
     {
-        QueryUserIdByCredentialsQuery
+        UuidUuidQuery
         {
             client : self.client, params : self.params, stmt : self.stmt,
             extractor : self.extractor, mapper,
@@ -90,50 +2446,59 @@ where C : GenericClient
         res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
         Ok(it)
     }
-}pub fn query_confirmed_subscribers() -> QueryConfirmedSubscribersStmt
-{ QueryConfirmedSubscribersStmt(cornucopia_async :: private :: Stmt :: new("SELECT email
-FROM subscriptions
-WHERE status = 'confirmed'")) } pub
-struct QueryConfirmedSubscribersStmt(cornucopia_async :: private :: Stmt) ; impl
-QueryConfirmedSubscribersStmt { pub fn bind < 'a, C : GenericClient, >
-(& 'a mut self, client : & 'a  C,
-) -> StringQuery < 'a, C,
-String, 0 >
+}pub struct TimeOffsetDateTimeQuery < 'a, C : GenericClient, T, const N : usize >
 {
-    StringQuery
-    {
-        client, params : [], stmt : & mut self.0, extractor :
-        | row | { row.get(0) }, mapper : | it | { it.into() },
-    }
-} }pub fn query_user_id_by_credentials() -> QueryUserIdByCredentialsStmt
-{ QueryUserIdByCredentialsStmt(cornucopia_async :: private :: Stmt :: new("SELECT user_id, password_hash
-FROM users
-WHERE username = $1")) } pub
-struct QueryUserIdByCredentialsStmt(cornucopia_async :: private :: Stmt) ; impl
-QueryUserIdByCredentialsStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
-(& 'a mut self, client : & 'a  C,
-name : & 'a T1,) -> QueryUserIdByCredentialsQuery < 'a, C,
-QueryUserIdByCredentials, 1 >
+    client : & 'a  C, params :
+    [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> time::OffsetDateTime,
+    mapper : fn(time::OffsetDateTime) -> T,
+} impl < 'a, C, T : 'a, const N : usize > TimeOffsetDateTimeQuery < 'a, C, T, N >
+where C : GenericClient
 {
-    QueryUserIdByCredentialsQuery
+    pub fn map < R > (self, mapper : fn(time::OffsetDateTime) -> R) -> TimeOffsetDateTimeQuery
+    < 'a, C, R, N >
     {
-        client, params : [name,], stmt : & mut self.0, extractor :
-        | row | { QueryUserIdByCredentialsBorrowed { user_id : row.get(0),password_hash : row.get(1),} }, mapper : | it | { <QueryUserIdByCredentials>::from(it) },
+        TimeOffsetDateTimeQuery
+        {
+            client : self.client, params : self.params, stmt : self.stmt,
+            extractor : self.extractor, mapper,
+        }
+    } pub async fn one(self) -> Result < T, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let row =
+        self.client.query_one(stmt, & self.params) .await ? ;
+        Ok((self.mapper) ((self.extractor) (& row)))
+    } pub async fn all(self) -> Result < Vec < T >, tokio_postgres :: Error >
+    { self.iter() .await ?.try_collect().await } pub async fn opt(self) -> Result
+    < Option < T >, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ;
+        Ok(self.client.query_opt(stmt, & self.params) .await
+        ?.map(| row | (self.mapper) ((self.extractor) (& row))))
+    } pub async fn iter(self,) -> Result < impl futures::Stream < Item = Result
+    < T, tokio_postgres :: Error >> + 'a, tokio_postgres :: Error >
+    {
+        let stmt = self.stmt.prepare(self.client) .await ? ; let it =
+        self.client.query_raw(stmt, cornucopia_async :: private ::
+        slice_iter(& self.params)) .await ?
+        .map(move | res |
+        res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
+        Ok(it)
     }
-} }}pub mod subscriptions
-{ use futures::{{StreamExt, TryStreamExt}};use futures; use cornucopia_async::GenericClient;#[derive( Debug)] pub struct InsertNewSubscriptionParams < T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,> { pub id : uuid::Uuid,pub email : T1,pub name : T2,pub subscribed_at : time::OffsetDateTime,}#[derive( Debug)] pub struct InsertNewTokenParams < T1 : cornucopia_async::StringSql,> { pub subscription_token : T1,pub subscriber_id : uuid::Uuid,}#[derive( Debug)] pub struct InsertTokenByEmailParams < T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,> { pub token : T1,pub email : T2,}pub struct StringQuery < 'a, C : GenericClient, T, const N : usize >
+}#[derive( Debug, Clone, PartialEq, Copy)] pub struct ConsumePasswordResetToken
+{ pub user_id : uuid::Uuid,pub expires_at : time::OffsetDateTime,}pub struct ConsumePasswordResetTokenQuery < 'a, C : GenericClient, T, const N : usize >
 {
     client : & 'a  C, params :
     [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
-    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> & str,
-    mapper : fn(& str) -> T,
-} impl < 'a, C, T : 'a, const N : usize > StringQuery < 'a, C, T, N >
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> ConsumePasswordResetToken,
+    mapper : fn(ConsumePasswordResetToken) -> T,
+} impl < 'a, C, T : 'a, const N : usize > ConsumePasswordResetTokenQuery < 'a, C, T, N >
 where C : GenericClient
 {
-    pub fn map < R > (self, mapper : fn(& str) -> R) -> StringQuery
+    pub fn map < R > (self, mapper : fn(ConsumePasswordResetToken) -> R) -> ConsumePasswordResetTokenQuery
     < 'a, C, R, N >
     {
-        StringQuery
+        ConsumePasswordResetTokenQuery
         {
             client : self.client, params : self.params, stmt : self.stmt,
             extractor : self.extractor, mapper,
@@ -160,19 +2525,27 @@ where C : GenericClient
         res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
         Ok(it)
     }
-}pub struct UuidUuidQuery < 'a, C : GenericClient, T, const N : usize >
+}#[derive( Debug, Clone, PartialEq, )] pub struct ListUsers
+{ pub user_id : uuid::Uuid,pub username : String,pub email : Option<String>,pub role : String,}pub struct ListUsersBorrowed < 'a >
+{ pub user_id : uuid::Uuid,pub username : &'a str,pub email : Option<&'a str>,pub role : &'a str,} impl < 'a > From < ListUsersBorrowed <
+'a >> for ListUsers
+{
+    fn
+    from(ListUsersBorrowed { user_id,username,email,role,} : ListUsersBorrowed < 'a >)
+    -> Self { Self { user_id,username: username.into(),email: email.map(|v| v.into()),role: role.into(),} }
+}pub struct ListUsersQuery < 'a, C : GenericClient, T, const N : usize >
 {
     client : & 'a  C, params :
     [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
-    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> uuid::Uuid,
-    mapper : fn(uuid::Uuid) -> T,
-} impl < 'a, C, T : 'a, const N : usize > UuidUuidQuery < 'a, C, T, N >
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> ListUsersBorrowed,
+    mapper : fn(ListUsersBorrowed) -> T,
+} impl < 'a, C, T : 'a, const N : usize > ListUsersQuery < 'a, C, T, N >
 where C : GenericClient
 {
-    pub fn map < R > (self, mapper : fn(uuid::Uuid) -> R) -> UuidUuidQuery
+    pub fn map < R > (self, mapper : fn(ListUsersBorrowed) -> R) -> ListUsersQuery
     < 'a, C, R, N >
     {
-        UuidUuidQuery
+        ListUsersQuery
         {
             client : self.client, params : self.params, stmt : self.stmt,
             extractor : self.extractor, mapper,
@@ -199,19 +2572,27 @@ where C : GenericClient
         res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
         Ok(it)
     }
-}pub struct I32Query < 'a, C : GenericClient, T, const N : usize >
+}#[derive( Debug, Clone, PartialEq, )] pub struct GetUserByUsername
+{ pub user_id : uuid::Uuid,pub role : String,}pub struct GetUserByUsernameBorrowed < 'a >
+{ pub user_id : uuid::Uuid,pub role : &'a str,} impl < 'a > From < GetUserByUsernameBorrowed <
+'a >> for GetUserByUsername
+{
+    fn
+    from(GetUserByUsernameBorrowed { user_id,role,} : GetUserByUsernameBorrowed < 'a >)
+    -> Self { Self { user_id,role: role.into(),} }
+}pub struct GetUserByUsernameQuery < 'a, C : GenericClient, T, const N : usize >
 {
     client : & 'a  C, params :
     [& 'a (dyn postgres_types :: ToSql + Sync) ; N], stmt : & 'a mut cornucopia_async
-    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> i32,
-    mapper : fn(i32) -> T,
-} impl < 'a, C, T : 'a, const N : usize > I32Query < 'a, C, T, N >
+    :: private :: Stmt, extractor : fn(& tokio_postgres :: Row) -> GetUserByUsernameBorrowed,
+    mapper : fn(GetUserByUsernameBorrowed) -> T,
+} impl < 'a, C, T : 'a, const N : usize > GetUserByUsernameQuery < 'a, C, T, N >
 where C : GenericClient
 {
-    pub fn map < R > (self, mapper : fn(i32) -> R) -> I32Query
+    pub fn map < R > (self, mapper : fn(GetUserByUsernameBorrowed) -> R) -> GetUserByUsernameQuery
     < 'a, C, R, N >
     {
-        I32Query
+        GetUserByUsernameQuery
         {
             client : self.client, params : self.params, stmt : self.stmt,
             extractor : self.extractor, mapper,
@@ -238,132 +2619,221 @@ where C : GenericClient
         res.map(| row | (self.mapper) ((self.extractor) (& row)))) .into_stream() ;
         Ok(it)
     }
-}pub fn insert_new_subscription() -> InsertNewSubscriptionStmt
-{ InsertNewSubscriptionStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO subscriptions(id, email, name, subscribed_at, status)
-VALUES ($1, $2, $3, $4, 'pending_confirmation')")) } pub
-struct InsertNewSubscriptionStmt(cornucopia_async :: private :: Stmt) ; impl
-InsertNewSubscriptionStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,>
+}pub fn get_username() -> GetUsernameStmt
+{ GetUsernameStmt(cornucopia_async :: private :: Stmt :: new("SELECT username
+FROM users
+WHERE user_id = $1")) } pub
+struct GetUsernameStmt(cornucopia_async :: private :: Stmt) ; impl
+GetUsernameStmt { pub fn bind < 'a, C : GenericClient, >
 (& 'a mut self, client : & 'a  C,
-id : & 'a uuid::Uuid,email : & 'a T1,name : & 'a T2,subscribed_at : & 'a time::OffsetDateTime,) -> Result < u64, tokio_postgres :: Error >
+user_id : & 'a uuid::Uuid,) -> StringQuery < 'a, C,
+String, 1 >
+{
+    StringQuery
+    {
+        client, params : [user_id,], stmt : & mut self.0, extractor :
+        | row | { row.get(0) }, mapper : | it | { it.into() },
+    }
+} }pub fn update_password_hash() -> UpdatePasswordHashStmt
+{ UpdatePasswordHashStmt(cornucopia_async :: private :: Stmt :: new("UPDATE users SET password_hash = $1 WHERE user_id = $2")) } pub
+struct UpdatePasswordHashStmt(cornucopia_async :: private :: Stmt) ; impl
+UpdatePasswordHashStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+password_hash : & 'a T1,user_id : & 'a uuid::Uuid,) -> Result < u64, tokio_postgres :: Error >
 {
     let stmt = self.0.prepare(client) .await ? ;
-    client.execute(stmt, & [id,email,name,subscribed_at,]) .await
-} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,>
-cornucopia_async :: Params < 'a, InsertNewSubscriptionParams < T1,T2,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
-u64, tokio_postgres :: Error > > + Send + 'a>>, C > for InsertNewSubscriptionStmt
+    client.execute(stmt, & [password_hash,user_id,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, UpdatePasswordHashParams < T1,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for UpdatePasswordHashStmt
 {
     fn
     params(& 'a mut self, client : & 'a  C, params : & 'a
-    InsertNewSubscriptionParams < T1,T2,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
-    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.id,& params.email,& params.name,& params.subscribed_at,) ) }
-}pub fn insert_new_token() -> InsertNewTokenStmt
-{ InsertNewTokenStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO subscription_tokens(subscription_token, subscriber_id)
-VALUES ($1, $2)")) } pub
-struct InsertNewTokenStmt(cornucopia_async :: private :: Stmt) ; impl
-InsertNewTokenStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+    UpdatePasswordHashParams < T1,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.password_hash,& params.user_id,) ) }
+}pub fn get_user_id_by_email() -> GetUserIdByEmailStmt
+{ GetUserIdByEmailStmt(cornucopia_async :: private :: Stmt :: new("SELECT user_id
+FROM users
+WHERE email = $1")) } pub
+struct GetUserIdByEmailStmt(cornucopia_async :: private :: Stmt) ; impl
+GetUserIdByEmailStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
 (& 'a mut self, client : & 'a  C,
-subscription_token : & 'a T1,subscriber_id : & 'a uuid::Uuid,) -> Result < u64, tokio_postgres :: Error >
+email : & 'a T1,) -> UuidUuidQuery < 'a, C,
+uuid::Uuid, 1 >
+{
+    UuidUuidQuery
+    {
+        client, params : [email,], stmt : & mut self.0, extractor :
+        | row | { row.get(0) }, mapper : | it | { it },
+    }
+} }pub fn insert_password_reset_token() -> InsertPasswordResetTokenStmt
+{ InsertPasswordResetTokenStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO password_reset_tokens(token_hash, user_id, expires_at)
+VALUES ($1, $2, $3)")) } pub
+struct InsertPasswordResetTokenStmt(cornucopia_async :: private :: Stmt) ; impl
+InsertPasswordResetTokenStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+token_hash : & 'a T1,user_id : & 'a uuid::Uuid,expires_at : & 'a time::OffsetDateTime,) -> Result < u64, tokio_postgres :: Error >
 {
     let stmt = self.0.prepare(client) .await ? ;
-    client.execute(stmt, & [subscription_token,subscriber_id,]) .await
+    client.execute(stmt, & [token_hash,user_id,expires_at,]) .await
 } }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,>
-cornucopia_async :: Params < 'a, InsertNewTokenParams < T1,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
-u64, tokio_postgres :: Error > > + Send + 'a>>, C > for InsertNewTokenStmt
+cornucopia_async :: Params < 'a, InsertPasswordResetTokenParams < T1,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for InsertPasswordResetTokenStmt
 {
     fn
     params(& 'a mut self, client : & 'a  C, params : & 'a
-    InsertNewTokenParams < T1,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
-    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.subscription_token,& params.subscriber_id,) ) }
-}pub fn delete_token_by_email() -> DeleteTokenByEmailStmt
-{ DeleteTokenByEmailStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM subscription_tokens
-    WHERE subscriber_id = (
-    SELECT id
-    FROM subscriptions
-    WHERE email = $1
-)")) } pub
-struct DeleteTokenByEmailStmt(cornucopia_async :: private :: Stmt) ; impl
-DeleteTokenByEmailStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+    InsertPasswordResetTokenParams < T1,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.token_hash,& params.user_id,& params.expires_at,) ) }
+}pub fn get_password_reset_token_expiry() -> GetPasswordResetTokenExpiryStmt
+{ GetPasswordResetTokenExpiryStmt(cornucopia_async :: private :: Stmt :: new("SELECT expires_at
+FROM password_reset_tokens
+WHERE token_hash = $1")) } pub
+struct GetPasswordResetTokenExpiryStmt(cornucopia_async :: private :: Stmt) ; impl
+GetPasswordResetTokenExpiryStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
 (& 'a mut self, client : & 'a  C,
-email : & 'a T1,) -> Result < u64, tokio_postgres :: Error >
+token_hash : & 'a T1,) -> TimeOffsetDateTimeQuery < 'a, C,
+time::OffsetDateTime, 1 >
+{
+    TimeOffsetDateTimeQuery
+    {
+        client, params : [token_hash,], stmt : & mut self.0, extractor :
+        | row | { row.get(0) }, mapper : | it | { it },
+    }
+} }pub fn consume_password_reset_token() -> ConsumePasswordResetTokenStmt
+{ ConsumePasswordResetTokenStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM password_reset_tokens
+WHERE token_hash = $1
+RETURNING user_id, expires_at")) } pub
+struct ConsumePasswordResetTokenStmt(cornucopia_async :: private :: Stmt) ; impl
+ConsumePasswordResetTokenStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+(& 'a mut self, client : & 'a  C,
+token_hash : & 'a T1,) -> ConsumePasswordResetTokenQuery < 'a, C,
+ConsumePasswordResetToken, 1 >
+{
+    ConsumePasswordResetTokenQuery
+    {
+        client, params : [token_hash,], stmt : & mut self.0, extractor :
+        | row | { ConsumePasswordResetToken { user_id : row.get(0),expires_at : row.get(1),} }, mapper : | it | { it },
+    }
+} }pub fn delete_password_reset_tokens() -> DeletePasswordResetTokensStmt
+{ DeletePasswordResetTokensStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM password_reset_tokens WHERE user_id = $1")) } pub
+struct DeletePasswordResetTokensStmt(cornucopia_async :: private :: Stmt) ; impl
+DeletePasswordResetTokensStmt { pub async fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+user_id : & 'a uuid::Uuid,) -> Result < u64, tokio_postgres :: Error >
 {
     let stmt = self.0.prepare(client) .await ? ;
-    client.execute(stmt, & [email,]) .await
-} }pub fn insert_token_by_email() -> InsertTokenByEmailStmt
-{ InsertTokenByEmailStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO subscription_tokens
-(subscription_token, subscriber_id)
-VALUES ($1, (
-        SELECT id
-        FROM subscriptions
-        WHERE email = $2
-    )
-)")) } pub
-struct InsertTokenByEmailStmt(cornucopia_async :: private :: Stmt) ; impl
-InsertTokenByEmailStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,>
+    client.execute(stmt, & [user_id,]) .await
+} }pub fn insert_user() -> InsertUserStmt
+{ InsertUserStmt(cornucopia_async :: private :: Stmt :: new("INSERT INTO users(user_id, username, password_hash, email, role)
+VALUES ($1, $2, $3, $4, $5)")) } pub
+struct InsertUserStmt(cornucopia_async :: private :: Stmt) ; impl
+InsertUserStmt { pub async fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,T3 : cornucopia_async::StringSql,T4 : cornucopia_async::StringSql,>
 (& 'a mut self, client : & 'a  C,
-token : & 'a T1,email : & 'a T2,) -> Result < u64, tokio_postgres :: Error >
+user_id : & 'a uuid::Uuid,username : & 'a T1,password_hash : & 'a T2,email : & 'a Option<T3>,role : & 'a T4,) -> Result < u64, tokio_postgres :: Error >
 {
     let stmt = self.0.prepare(client) .await ? ;
-    client.execute(stmt, & [token,email,]) .await
-} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,>
-cornucopia_async :: Params < 'a, InsertTokenByEmailParams < T1,T2,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
-u64, tokio_postgres :: Error > > + Send + 'a>>, C > for InsertTokenByEmailStmt
+    client.execute(stmt, & [user_id,username,password_hash,email,role,]) .await
+} }impl < 'a, C : GenericClient + Send + Sync, T1 : cornucopia_async::StringSql,T2 : cornucopia_async::StringSql,T3 : cornucopia_async::StringSql,T4 : cornucopia_async::StringSql,>
+cornucopia_async :: Params < 'a, InsertUserParams < T1,T2,T3,T4,>, std::pin::Pin<Box<dyn futures::Future<Output = Result <
+u64, tokio_postgres :: Error > > + Send + 'a>>, C > for InsertUserStmt
 {
     fn
     params(& 'a mut self, client : & 'a  C, params : & 'a
-    InsertTokenByEmailParams < T1,T2,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
-    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.token,& params.email,) ) }
-}pub fn get_status() -> GetStatusStmt
-{ GetStatusStmt(cornucopia_async :: private :: Stmt :: new("SELECT status
-FROM subscriptions
-WHERE email = $1")) } pub
-struct GetStatusStmt(cornucopia_async :: private :: Stmt) ; impl
-GetStatusStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+    InsertUserParams < T1,T2,T3,T4,>) -> std::pin::Pin<Box<dyn futures::Future<Output = Result < u64, tokio_postgres ::
+    Error > > + Send + 'a>> { Box::pin(self.bind(client, & params.user_id,& params.username,& params.password_hash,& params.email,& params.role,) ) }
+}pub fn list_users() -> ListUsersStmt
+{ ListUsersStmt(cornucopia_async :: private :: Stmt :: new("SELECT user_id, username, email, role
+FROM users
+ORDER BY username")) } pub
+struct ListUsersStmt(cornucopia_async :: private :: Stmt) ; impl
+ListUsersStmt { pub fn bind < 'a, C : GenericClient, >
 (& 'a mut self, client : & 'a  C,
-email : & 'a T1,) -> StringQuery < 'a, C,
+) -> ListUsersQuery < 'a, C,
+ListUsers, 0 >
+{
+    ListUsersQuery
+    {
+        client, params : [], stmt : & mut self.0, extractor :
+        | row | { ListUsersBorrowed { user_id : row.get(0),username : row.get(1),email : row.get(2),role : row.get(3),} }, mapper : | it | { <ListUsers>::from(it) },
+    }
+} }pub fn delete_user_by_id() -> DeleteUserByIdStmt
+{ DeleteUserByIdStmt(cornucopia_async :: private :: Stmt :: new("DELETE FROM users WHERE user_id = $1 RETURNING username")) } pub
+struct DeleteUserByIdStmt(cornucopia_async :: private :: Stmt) ; impl
+DeleteUserByIdStmt { pub fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+user_id : & 'a uuid::Uuid,) -> StringQuery < 'a, C,
 String, 1 >
 {
     StringQuery
     {
-        client, params : [email,], stmt : & mut self.0, extractor :
+        client, params : [user_id,], stmt : & mut self.0, extractor :
         | row | { row.get(0) }, mapper : | it | { it.into() },
     }
-} }pub fn confirm_subscriber() -> ConfirmSubscriberStmt
-{ ConfirmSubscriberStmt(cornucopia_async :: private :: Stmt :: new("UPDATE subscriptions SET status = 'confirmed' WHERE id = $1 AND status = 'pending_confirmation'")) } pub
-struct ConfirmSubscriberStmt(cornucopia_async :: private :: Stmt) ; impl
-ConfirmSubscriberStmt { pub async fn bind < 'a, C : GenericClient, >
+} }pub fn get_user_role() -> GetUserRoleStmt
+{ GetUserRoleStmt(cornucopia_async :: private :: Stmt :: new("SELECT role
+FROM users
+WHERE user_id = $1")) } pub
+struct GetUserRoleStmt(cornucopia_async :: private :: Stmt) ; impl
+GetUserRoleStmt { pub fn bind < 'a, C : GenericClient, >
 (& 'a mut self, client : & 'a  C,
-sub_id : & 'a uuid::Uuid,) -> Result < u64, tokio_postgres :: Error >
+user_id : & 'a uuid::Uuid,) -> StringQuery < 'a, C,
+String, 1 >
 {
-    let stmt = self.0.prepare(client) .await ? ;
-    client.execute(stmt, & [sub_id,]) .await
-} }pub fn get_subscriber_id_from_token() -> GetSubscriberIdFromTokenStmt
-{ GetSubscriberIdFromTokenStmt(cornucopia_async :: private :: Stmt :: new("SELECT subscriber_id FROM subscription_tokens WHERE subscription_token = $1")) } pub
-struct GetSubscriberIdFromTokenStmt(cornucopia_async :: private :: Stmt) ; impl
-GetSubscriberIdFromTokenStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
+    StringQuery
+    {
+        client, params : [user_id,], stmt : & mut self.0, extractor :
+        | row | { row.get(0) }, mapper : | it | { it.into() },
+    }
+} }pub fn update_user_role() -> UpdateUserRoleStmt
+{ UpdateUserRoleStmt(cornucopia_async :: private :: Stmt :: new("UPDATE users SET role = $1 WHERE user_id = $2
+RETURNING username")) } pub
+struct UpdateUserRoleStmt(cornucopia_async :: private :: Stmt) ; impl
+UpdateUserRoleStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
 (& 'a mut self, client : & 'a  C,
-sub_token : & 'a T1,) -> UuidUuidQuery < 'a, C,
-uuid::Uuid, 1 >
+role : & 'a T1,user_id : & 'a uuid::Uuid,) -> StringQuery < 'a, C,
+String, 2 >
 {
-    UuidUuidQuery
+    StringQuery
     {
-        client, params : [sub_token,], stmt : & mut self.0, extractor :
-        | row | { row.get(0) }, mapper : | it | { it },
+        client, params : [role,user_id,], stmt : & mut self.0, extractor :
+        | row | { row.get(0) }, mapper : | it | { it.into() },
     }
-} }pub fn get_countdown() -> GetCountdownStmt
-{ GetCountdownStmt(cornucopia_async :: private :: Stmt :: new("WITH RECURSIVE countdown(val) AS (
-    SELECT 10 AS val -- initial, non-recursive query
-    UNION -- every recursive CTE needs `UNION` keyword
-    SELECT val - 1 FROM countdown WHERE val > 1 -- recursive query
-)
-SELECT *
-FROM countdown")) } pub
-struct GetCountdownStmt(cornucopia_async :: private :: Stmt) ; impl
-GetCountdownStmt { pub fn bind < 'a, C : GenericClient, >
+} }impl < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,> cornucopia_async ::
+Params < 'a, UpdateUserRoleParams < T1,>, StringQuery < 'a, C,
+String, 2 >, C > for UpdateUserRoleStmt
+{
+    fn
+    params(& 'a mut self, client : & 'a  C, params : & 'a
+    UpdateUserRoleParams < T1,>) -> StringQuery < 'a, C,
+    String, 2 >
+    { self.bind(client, & params.role,& params.user_id,) }
+}pub fn get_user_by_username() -> GetUserByUsernameStmt
+{ GetUserByUsernameStmt(cornucopia_async :: private :: Stmt :: new("SELECT user_id, role
+FROM users
+WHERE username = $1")) } pub
+struct GetUserByUsernameStmt(cornucopia_async :: private :: Stmt) ; impl
+GetUserByUsernameStmt { pub fn bind < 'a, C : GenericClient, T1 : cornucopia_async::StringSql,>
 (& 'a mut self, client : & 'a  C,
-) -> I32Query < 'a, C,
-i32, 0 >
+username : & 'a T1,) -> GetUserByUsernameQuery < 'a, C,
+GetUserByUsername, 1 >
 {
-    I32Query
+    GetUserByUsernameQuery
+    {
+        client, params : [username,], stmt : & mut self.0, extractor :
+        | row | { GetUserByUsernameBorrowed { user_id : row.get(0),role : row.get(1),} }, mapper : | it | { <GetUserByUsername>::from(it) },
+    }
+} }pub fn lock_owners() -> LockOwnersStmt
+{ LockOwnersStmt(cornucopia_async :: private :: Stmt :: new("SELECT user_id
+FROM users
+WHERE role = 'owner'
+FOR UPDATE")) } pub
+struct LockOwnersStmt(cornucopia_async :: private :: Stmt) ; impl
+LockOwnersStmt { pub fn bind < 'a, C : GenericClient, >
+(& 'a mut self, client : & 'a  C,
+) -> UuidUuidQuery < 'a, C,
+uuid::Uuid, 0 >
+{
+    UuidUuidQuery
     {
         client, params : [], stmt : & mut self.0, extractor :
         | row | { row.get(0) }, mapper : | it | { it },
@@ -0,0 +1,273 @@
+//! src/csrf.rs
+
+use anyhow::Context;
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use cookie::{Cookie, SameSite};
+use http::{HeaderMap, HeaderValue, Method, StatusCode};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+
+use crate::configuration::SessionSettings;
+use crate::cornucopia::queries::sessions;
+use crate::session::SessionId;
+use crate::startup::AppState;
+use crate::{constant_time_eq, error_chain_fmt};
+
+/// Name of the hidden form field carrying the token.
+pub const CSRF_FIELD: &str = "csrf_token";
+/// Name of the cookie with the token of the login and password reset
+/// forms.
+pub const LOGIN_CSRF_COOKIE: &str = "login_csrf";
+
+/// Same as the default body limit of the `Form` extractor.
+const MAX_FORM_SIZE: usize = 2 * 1024 * 1024;
+
+/// Random token every state-changing html form sends back in the
+/// `csrf_token` field. Admin forms get the token stored with the session
+/// (synchronizer token pattern). The login and password reset forms are
+/// shown without a session, their token is compared with the `login_csrf`
+/// cookie.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsrfToken(String);
+
+impl CsrfToken {
+    /// 32 alphanumeric characters give us ~190 bits of entropy.
+    pub fn generate() -> CsrfToken {
+        let mut rng = thread_rng();
+        CsrfToken(
+            std::iter::repeat_with(|| rng.sample(Alphanumeric))
+                .map(char::from)
+                .take(32)
+                .collect(),
+        )
+    }
+
+    /// Compared in constant time, see `constant_time_eq`.
+    pub fn matches(&self, submitted: &str) -> bool {
+        constant_time_eq(&self.0, submitted)
+    }
+
+    /// Take the token of the login forms from the `Cookie` header, if any.
+    pub fn from_login_cookie(headers: &HeaderMap) -> Option<CsrfToken> {
+        headers
+            .get_all(http::header::COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(Cookie::split_parse)
+            .filter_map(Result::ok)
+            .find(|cookie| cookie.name() == LOGIN_CSRF_COOKIE)
+            .map(|cookie| CsrfToken(cookie.value().to_string()))
+            .filter(|token| !token.0.is_empty())
+    }
+
+    /// Token for a login form: the one the browser already has, or a new
+    /// one together with the `Set-Cookie` value storing it.
+    pub fn for_login_form(
+        headers: &HeaderMap,
+        settings: &SessionSettings,
+    ) -> (CsrfToken, Option<HeaderValue>) {
+        match CsrfToken::from_login_cookie(headers) {
+            Some(token) => (token, None),
+            None => {
+                let token = CsrfToken::generate();
+                let cookie = token.login_cookie(settings);
+                (token, Some(cookie))
+            }
+        }
+    }
+
+    /// `Set-Cookie` value storing the token of the login forms. Sent to
+    /// `/password` too, so it's not limited to `/login`.
+    pub fn login_cookie(&self, settings: &SessionSettings) -> HeaderValue {
+        let cookie = Cookie::build((LOGIN_CSRF_COOKIE, self.0.as_str()))
+            .path("/")
+            .http_only(true)
+            .secure(settings.secure_cookie)
+            .same_site(SameSite::Strict)
+            .build();
+        HeaderValue::from_str(&cookie.to_string()).unwrap()
+    }
+}
+
+impl From<String> for CsrfToken {
+    fn from(token: String) -> Self {
+        CsrfToken(token)
+    }
+}
+
+impl AsRef<str> for CsrfToken {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for CsrfToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(thiserror::Error)]
+pub enum CsrfError {
+    #[error("The form has expired, please reload the page and try again.")]
+    InvalidToken,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for CsrfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl IntoResponse for CsrfError {
+    fn into_response(self) -> Response {
+        match self {
+            CsrfError::InvalidToken => {
+                (StatusCode::FORBIDDEN, self.to_string()).into_response()
+            }
+            CsrfError::UnexpectedError(e) => {
+                tracing::error!("{:?}", e);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+/// Middleware for the admin forms: the token must be the one of the
+/// session. Requests without a session are passed on, the handlers
+/// redirect them to the login form.
+pub async fn require_session_csrf_token(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, CsrfError> {
+    if is_safe(request.method()) {
+        return Ok(next.run(request).await);
+    }
+    let Some(session_id) = SessionId::from_headers(request.headers()) else {
+        return Ok(next.run(request).await);
+    };
+    let session = {
+        let client = state
+            .pool
+            .get()
+            .await
+            .context("Failed to get connection from pool")?;
+        sessions::get_session_user()
+            .bind(&client, &session_id.as_ref())
+            .opt()
+            .await
+            .context("Failed to fetch a session")?
+    };
+    let Some(session) = session else {
+        return Ok(next.run(request).await);
+    };
+    let request =
+        check_submitted_token(request, &CsrfToken(session.csrf_token)).await?;
+    Ok(next.run(request).await)
+}
+
+/// Middleware for the login and password reset forms: the token must be
+/// the one of the `login_csrf` cookie.
+pub async fn require_login_csrf_token(
+    request: Request,
+    next: Next,
+) -> Result<Response, CsrfError> {
+    if is_safe(request.method()) {
+        return Ok(next.run(request).await);
+    }
+    let expected = CsrfToken::from_login_cookie(request.headers())
+        .ok_or(CsrfError::InvalidToken)?;
+    let request = check_submitted_token(request, &expected).await?;
+    Ok(next.run(request).await)
+}
+
+fn is_safe(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// Reads the token from the urlencoded body and hands the body over to
+/// the handler untouched.
+async fn check_submitted_token(
+    request: Request,
+    expected: &CsrfToken,
+) -> Result<Request, CsrfError> {
+    let (parts, body) = request.into_parts();
+    let body = axum::body::to_bytes(body, MAX_FORM_SIZE)
+        .await
+        .map_err(|_| CsrfError::InvalidToken)?;
+    let valid = form_field(&body, CSRF_FIELD)
+        .is_some_and(|submitted| expected.matches(&submitted));
+    if !valid {
+        tracing::warn!(path = %parts.uri.path(), "Invalid CSRF token");
+        return Err(CsrfError::InvalidToken);
+    }
+    Ok(Request::from_parts(parts, Body::from(body)))
+}
+
+/// Value of the first `name` field of an `x-www-form-urlencoded` body.
+fn form_field(body: &[u8], name: &str) -> Option<String> {
+    std::str::from_utf8(body)
+        .ok()?
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .and_then(|(_, value)| {
+            urlencoding::decode(&value.replace('+', " "))
+                .ok()
+                .map(|value| value.into_owned())
+        })
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use http::header::COOKIE;
+    use http::HeaderMap;
+    use secrecy::Secret;
+
+    use super::{form_field, CsrfToken};
+    use crate::configuration::SessionSettings;
+
+    #[test]
+    fn token_matches_only_itself() {
+        let token = CsrfToken::generate();
+        assert!(token.matches(token.as_ref()));
+        assert!(!token.matches(CsrfToken::generate().as_ref()));
+        assert!(!token.matches(&token.as_ref()[1..]));
+        assert!(!token.matches(""));
+    }
+
+    #[test]
+    fn token_is_read_from_the_form_body() {
+        let body = b"title=a+b&csrf_token=abc%2B1&content=x";
+        assert_eq!(form_field(body, "csrf_token").as_deref(), Some("abc+1"));
+        assert_eq!(form_field(body, "title").as_deref(), Some("a b"));
+        assert_eq!(form_field(b"title=a", "csrf_token"), None);
+        assert_eq!(form_field(&[0xff, 0xfe], "csrf_token"), None);
+    }
+
+    #[test]
+    fn login_token_survives_cookie_round_trip() {
+        let settings = SessionSettings {
+            ttl: 60,
+            secure_cookie: true,
+            hmac_secret: Secret::new("key".to_string()),
+        };
+        let token = CsrfToken::generate();
+        let set_cookie = token.login_cookie(&settings);
+        let set_cookie = set_cookie.to_str().unwrap();
+        assert!(set_cookie.split("; ").any(|attribute| attribute == "Path=/"));
+        assert!(set_cookie.contains("HttpOnly"));
+
+        let mut headers = HeaderMap::new();
+        let pair = set_cookie.split(';').next().unwrap();
+        headers.insert(COOKIE, pair.parse().unwrap());
+        assert_eq!(CsrfToken::from_login_cookie(&headers), Some(token));
+    }
+}
@@ -0,0 +1,67 @@
+use secrecy::{ExposeSecret, Secret};
+
+/// Shortest password we accept for admin users.
+pub const MIN_PASSWORD_LENGTH: usize = 12;
+/// Longest password we accept, hashing unbounded input is a DoS vector.
+pub const MAX_PASSWORD_LENGTH: usize = 128;
+
+/// This type guarantees that a new admin password satisfies
+/// our password policy.
+pub struct AdminPassword(Secret<String>);
+
+impl AdminPassword {
+    pub fn parse(password: Secret<String>) -> Result<AdminPassword, String> {
+        let length = password.expose_secret().chars().count();
+        if length < MIN_PASSWORD_LENGTH {
+            Err(format!(
+                "The password must be at least {} characters long.",
+                MIN_PASSWORD_LENGTH
+            ))
+        } else if length > MAX_PASSWORD_LENGTH {
+            Err(format!(
+                "The password must be at most {} characters long.",
+                MAX_PASSWORD_LENGTH
+            ))
+        } else {
+            Ok(AdminPassword(password))
+        }
+    }
+
+    pub fn into_secret(self) -> Secret<String> {
+        self.0
+    }
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use secrecy::Secret;
+
+    use super::{AdminPassword, MAX_PASSWORD_LENGTH, MIN_PASSWORD_LENGTH};
+
+    fn parse(password: String) -> Result<AdminPassword, String> {
+        AdminPassword::parse(Secret::new(password))
+    }
+
+    #[test]
+    fn passwords_within_length_bounds_are_accepted() {
+        assert!(parse("a".repeat(MIN_PASSWORD_LENGTH)).is_ok());
+        assert!(parse("a".repeat(MAX_PASSWORD_LENGTH)).is_ok());
+    }
+
+    #[test]
+    fn short_passwords_are_rejected() {
+        assert!(parse("a".repeat(MIN_PASSWORD_LENGTH - 1)).is_err());
+    }
+
+    #[test]
+    fn long_passwords_are_rejected() {
+        assert!(parse("a".repeat(MAX_PASSWORD_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn length_is_counted_in_characters() {
+        assert!(parse("ё".repeat(MIN_PASSWORD_LENGTH)).is_ok());
+    }
+}
@@ -0,0 +1,105 @@
+use super::{Permission, UserRole};
+
+/// What an API token may be used for, stored space separated in the
+/// `scopes` column of `api_tokens`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiScope {
+    /// `POST /newsletters`.
+    NewslettersPublish,
+    /// `GET /subscribers`.
+    SubscribersRead,
+    /// `GET /audit_events`.
+    AuditRead,
+}
+
+impl ApiScope {
+    pub const ALL: [ApiScope; 3] = [
+        ApiScope::NewslettersPublish,
+        ApiScope::SubscribersRead,
+        ApiScope::AuditRead,
+    ];
+
+    pub fn parse(s: &str) -> Result<ApiScope, String> {
+        match s {
+            "newsletters:publish" => Ok(ApiScope::NewslettersPublish),
+            "subscribers:read" => Ok(ApiScope::SubscribersRead),
+            "audit:read" => Ok(ApiScope::AuditRead),
+            other => Err(format!("{} is not a valid API token scope.", other)),
+        }
+    }
+
+    /// Parse the content of the `scopes` column.
+    pub fn parse_list(s: &str) -> Result<Vec<ApiScope>, String> {
+        s.split_whitespace().map(ApiScope::parse).collect()
+    }
+
+    /// Inverse of `parse_list`.
+    pub fn join(scopes: &[ApiScope]) -> String {
+        scopes
+            .iter()
+            .map(ApiScope::as_str)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiScope::NewslettersPublish => "newsletters:publish",
+            ApiScope::SubscribersRead => "subscribers:read",
+            ApiScope::AuditRead => "audit:read",
+        }
+    }
+
+    /// Permission the owner of the token needs on top of the scope,
+    /// `None` for scopes every role has.
+    pub fn permission(&self) -> Option<Permission> {
+        match self {
+            ApiScope::NewslettersPublish => {
+                Some(Permission::PublishNewsletters)
+            }
+            ApiScope::SubscribersRead => None,
+            ApiScope::AuditRead => Some(Permission::ViewAuditLog),
+        }
+    }
+
+    /// A token can't be granted more than the role of its owner allows.
+    pub fn allowed_for(&self, role: UserRole) -> bool {
+        self.permission().is_none_or(|p| role.allows(p))
+    }
+}
+
+impl std::fmt::Display for ApiScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use super::ApiScope;
+    use crate::domain::UserRole;
+
+    #[test]
+    fn scopes_round_trip_through_the_column() {
+        let column = ApiScope::join(&ApiScope::ALL);
+        assert_eq!(column, "newsletters:publish subscribers:read audit:read");
+        assert_eq!(ApiScope::parse_list(&column).unwrap(), ApiScope::ALL);
+        assert_eq!(ApiScope::parse_list("").unwrap(), vec![]);
+        assert!(ApiScope::parse_list("subscribers:read admin").is_err());
+    }
+
+    #[test]
+    fn viewers_can_only_get_read_scopes() {
+        assert!(ApiScope::SubscribersRead.allowed_for(UserRole::Viewer));
+        assert!(!ApiScope::NewslettersPublish.allowed_for(UserRole::Viewer));
+        assert!(ApiScope::NewslettersPublish.allowed_for(UserRole::Editor));
+    }
+
+    #[test]
+    fn only_owners_can_get_the_audit_scope() {
+        assert!(ApiScope::AuditRead.allowed_for(UserRole::Owner));
+        assert!(!ApiScope::AuditRead.allowed_for(UserRole::Editor));
+    }
+}
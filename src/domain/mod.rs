@@ -1,8 +1,14 @@
+pub use admin_password::AdminPassword;
+pub use api_scope::ApiScope;
 pub use new_subscriber::NewSubscriber;
 pub use subscriber_email::SubscriberEmail;
 pub use subscriber_name::SubscriberName;
+pub use user_role::{Permission, UserRole};
 
 // Top-level modules
+mod admin_password;
+mod api_scope;
 mod new_subscriber;
 mod subscriber_email;
 mod subscriber_name;
+mod user_role;
@@ -0,0 +1,114 @@
+/// Role of an admin user, stored in the `role` column of `users`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserRole {
+    /// Everything an editor can do, plus user management and the audit
+    /// log.
+    Owner,
+    /// Publishes newsletters and manages subscribers.
+    Editor,
+    /// Read-only access to the admin pages.
+    Viewer,
+}
+
+/// Actions guarded by a role check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    PublishNewsletters,
+    ManageSubscribers,
+    ManageUsers,
+    ViewAuditLog,
+}
+
+impl UserRole {
+    pub const ALL: [UserRole; 3] =
+        [UserRole::Owner, UserRole::Editor, UserRole::Viewer];
+
+    pub fn parse(s: &str) -> Result<UserRole, String> {
+        match s {
+            "owner" => Ok(UserRole::Owner),
+            "editor" => Ok(UserRole::Editor),
+            "viewer" => Ok(UserRole::Viewer),
+            other => Err(format!("{} is not a valid user role.", other)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRole::Owner => "owner",
+            UserRole::Editor => "editor",
+            UserRole::Viewer => "viewer",
+        }
+    }
+
+    pub fn allows(&self, permission: Permission) -> bool {
+        match permission {
+            Permission::PublishNewsletters | Permission::ManageSubscribers => {
+                matches!(self, UserRole::Owner | UserRole::Editor)
+            }
+            Permission::ManageUsers | Permission::ViewAuditLog => {
+                matches!(self, UserRole::Owner)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for UserRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Permission {
+    /// Name of the permission in audit events.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::PublishNewsletters => "publish_newsletters",
+            Permission::ManageSubscribers => "manage_subscribers",
+            Permission::ManageUsers => "manage_users",
+            Permission::ViewAuditLog => "view_audit_log",
+        }
+    }
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use super::{Permission, UserRole};
+
+    #[test]
+    fn roles_round_trip_through_their_names() {
+        for role in UserRole::ALL {
+            assert_eq!(UserRole::parse(role.as_str()), Ok(role));
+        }
+        assert!(UserRole::parse("admin").is_err());
+        assert!(UserRole::parse("Owner").is_err());
+    }
+
+    #[test]
+    fn only_owners_manage_users() {
+        assert!(UserRole::Owner.allows(Permission::ManageUsers));
+        assert!(!UserRole::Editor.allows(Permission::ManageUsers));
+        assert!(!UserRole::Viewer.allows(Permission::ManageUsers));
+    }
+
+    #[test]
+    fn only_owners_view_the_audit_log() {
+        assert!(UserRole::Owner.allows(Permission::ViewAuditLog));
+        assert!(!UserRole::Editor.allows(Permission::ViewAuditLog));
+        assert!(!UserRole::Viewer.allows(Permission::ViewAuditLog));
+    }
+
+    #[test]
+    fn viewers_can_not_change_anything() {
+        for permission in [
+            Permission::PublishNewsletters,
+            Permission::ManageSubscribers,
+            Permission::ManageUsers,
+        ] {
+            assert!(!UserRole::Viewer.allows(permission));
+        }
+        assert!(UserRole::Editor.allows(Permission::PublishNewsletters));
+        assert!(UserRole::Editor.allows(Permission::ManageSubscribers));
+    }
+}
@@ -0,0 +1,100 @@
+//! src/email_client/file.rs
+
+use std::path::{Path, PathBuf};
+
+use lettre::{AsyncFileTransport, AsyncTransport, Tokio1Executor};
+
+use super::smtp::build_message;
+use super::{Email, EmailTransport, TransportError};
+
+/// Writes every email into the `outbox_dir` as `<uuid>.eml` file instead
+/// of sending it. Useful for local development, files can be opened with
+/// any mail client.
+pub struct FileTransport {
+    outbox_dir: PathBuf,
+    writer: AsyncFileTransport<Tokio1Executor>,
+}
+
+impl FileTransport {
+    /// Creates `outbox_dir` if it does not exist.
+    pub fn new<P: AsRef<Path>>(outbox_dir: P) -> Result<Self, String> {
+        let outbox_dir = outbox_dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&outbox_dir).map_err(|e| {
+            format!("Failed to create {}: {e}", outbox_dir.display())
+        })?;
+        Ok(Self {
+            writer: AsyncFileTransport::new(&outbox_dir),
+            outbox_dir,
+        })
+    }
+
+    pub fn outbox_dir(&self) -> &Path {
+        &self.outbox_dir
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailTransport for FileTransport {
+    fn service(&self) -> &'static str {
+        "file"
+    }
+
+    async fn send(&self, email: &Email<'_>) -> Result<(), TransportError> {
+        let message = build_message(email)?;
+        let id = self
+            .writer
+            .send(message)
+            .await
+            .map_err(|e| TransportError::Permanent(e.into()))?;
+        tracing::info!(
+            "Email to {} saved as {}/{id}.eml",
+            email.to,
+            self.outbox_dir.display()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileTransport;
+    use crate::domain::SubscriberEmail;
+    use crate::email_client::{Email, EmailTransport};
+
+    #[tokio::test]
+    async fn email_is_written_to_outbox_dir_as_eml_file() {
+        // Arrange
+        let outbox_dir = std::env::temp_dir()
+            .join(format!("outbox-{}", uuid::Uuid::new_v4()));
+        let transport = FileTransport::new(&outbox_dir).unwrap();
+        let from = SubscriberEmail::parse("sender@example.com").unwrap();
+        let to = SubscriberEmail::parse("recipient@example.com").unwrap();
+
+        // Act
+        let result = transport
+            .send(&Email {
+                from: &from,
+                to: &to,
+                subject: "Greetings",
+                html_content: "<p>Hello!</p>",
+                text_content: "Hello!",
+                headers: &[("List-Unsubscribe", "<https://example.com/u>")],
+            })
+            .await;
+
+        // Assert
+        assert!(result.is_ok());
+        let files = std::fs::read_dir(&outbox_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].extension().unwrap(), "eml");
+        let contents = std::fs::read_to_string(&files[0]).unwrap();
+        assert!(contents.contains("To: recipient@example.com"));
+        assert!(contents.contains("Subject: Greetings"));
+        assert!(contents.contains("List-Unsubscribe: <https://example.com/u>"));
+
+        std::fs::remove_dir_all(&outbox_dir).unwrap();
+    }
+}
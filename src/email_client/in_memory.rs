@@ -0,0 +1,53 @@
+//! src/email_client/in_memory.rs
+
+use std::sync::{Arc, Mutex};
+
+use super::{Email, EmailTransport, TransportError};
+
+/// Owned copy of an email kept by `InMemoryTransport`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredEmail {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub html_content: String,
+    pub text_content: String,
+    pub headers: Vec<(String, String)>,
+}
+
+/// Keeps all emails in memory, so tests can inspect them.
+/// Clones share the same storage.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryTransport {
+    emails: Arc<Mutex<Vec<StoredEmail>>>,
+}
+
+impl InMemoryTransport {
+    /// All emails sent so far, oldest first.
+    pub fn sent_emails(&self) -> Vec<StoredEmail> {
+        self.emails.lock().unwrap().clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailTransport for InMemoryTransport {
+    fn service(&self) -> &'static str {
+        "in_memory"
+    }
+
+    async fn send(&self, email: &Email<'_>) -> Result<(), TransportError> {
+        self.emails.lock().unwrap().push(StoredEmail {
+            from: email.from.to_string(),
+            to: email.to.to_string(),
+            subject: email.subject.to_string(),
+            html_content: email.html_content.to_string(),
+            text_content: email.text_content.to_string(),
+            headers: email
+                .headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,864 @@
+//! src/email_client/mod.rs
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::configuration::EmailClientSettings;
+use crate::domain::SubscriberEmail;
+use crate::error_chain_fmt;
+use crate::metrics::Metrics;
+
+mod file;
+mod in_memory;
+mod postmark;
+mod smtp;
+mod smtp_bz;
+mod transport;
+
+pub use file::FileTransport;
+pub use in_memory::{InMemoryTransport, StoredEmail};
+pub use postmark::PostmarkTransport;
+pub use smtp::SmtpTransport;
+pub use smtp_bz::SmtpBzTransport;
+pub use transport::{Email, EmailTransport, TransientReason, TransportError};
+
+/// Backend used to deliver emails, see `EmailTransport` implementors.
+///
+/// `smtp` keeps selecting the smtp.bz HTTP API, as it always did, so
+/// existing deployments don't change behaviour. A plain SMTP relay is
+/// chosen with `smtp_relay`.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(try_from = "String")]
+pub enum EmailDeliveryService {
+    /// Postmark HTTP API.
+    Postmark,
+    /// smtp.bz HTTP API.
+    SmtpBz,
+    /// Any SMTP relay, with STARTTLS and AUTH.
+    SmtpRelay,
+    /// Writes `.eml` files into a directory, for local development.
+    File,
+    /// Keeps emails in memory, for tests.
+    InMemory,
+}
+
+impl TryFrom<String> for EmailDeliveryService {
+    type Error = String;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "postmark" | "Postmark" => Ok(Self::Postmark),
+            "smtp" | "SMTP" | "smtp_bz" | "smtp.bz" => Ok(Self::SmtpBz),
+            "smtp_relay" => Ok(Self::SmtpRelay),
+            "file" => Ok(Self::File),
+            "in_memory" | "memory" => Ok(Self::InMemory),
+            _ => Err(format!(
+                "Can't construct EmailDeliveryService type from {}",
+                value
+            )),
+        }
+    }
+}
+
+/// Describes how `EmailClient` retries failed requests to the email
+/// delivery service. Durations are in milliseconds.
+#[derive(Deserialize, Clone, Debug)]
+pub struct RetryPolicy {
+    /// Total number of requests for a single email, the first one included.
+    pub max_attempts: u32,
+    /// Delay before the first retry, it doubles on every next attempt.
+    pub base_delay: u64,
+    /// Upper bound of the random delay added to every backoff step.
+    pub jitter: u64,
+    /// We never wait longer than this between two attempts. If the service
+    /// asks for a longer pause with `Retry-After`, we give up instead.
+    pub max_delay: u64,
+    /// Retry on `5xx` responses.
+    pub retry_on_server_error: bool,
+    /// Retry on `429 Too Many Requests`, respecting `Retry-After`.
+    pub retry_on_too_many_requests: bool,
+    /// Retry on timeouts and failed connections.
+    pub retry_on_timeout: bool,
+}
+
+impl RetryPolicy {
+    /// Policy which makes exactly one request.
+    pub fn no_retries() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(2u64.saturating_pow(attempt.saturating_sub(1)));
+        let jitter = if self.jitter > 0 {
+            rand::thread_rng().gen_range(0..=self.jitter)
+        } else {
+            0
+        };
+        Duration::from_millis(
+            exponential.saturating_add(jitter).min(self.max_delay),
+        )
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: 500,
+            jitter: 250,
+            max_delay: 10000,
+            retry_on_server_error: true,
+            retry_on_too_many_requests: true,
+            retry_on_timeout: true,
+        }
+    }
+}
+
+/// Failure of `EmailClient::send_email`.
+///
+/// `Transient` failures may succeed if the same email is sent later,
+/// `Permanent` ones (e.g. the service rejected our request as invalid)
+/// will fail again, so there is no point in retrying them.
+#[derive(thiserror::Error)]
+pub enum SendEmailError {
+    #[error("Email delivery failed after {attempts} attempt(s)")]
+    Transient {
+        attempts: u32,
+        /// Pause requested by the service with `Retry-After`, if any.
+        retry_after: Option<Duration>,
+        #[source]
+        source: anyhow::Error,
+    },
+    #[error("Email delivery service rejected the request")]
+    Permanent(#[source] anyhow::Error),
+}
+
+impl SendEmailError {
+    pub fn is_transient(&self) -> bool {
+        matches!(self, SendEmailError::Transient { .. })
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            SendEmailError::Transient { retry_after, .. } => *retry_after,
+            SendEmailError::Permanent(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Debug for SendEmailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+/// This type handles the sending of emails.
+/// The actual delivery is done by the `EmailTransport`, this type
+/// retries transient failures according to the `RetryPolicy`.
+#[derive(Clone)]
+pub struct EmailClient {
+    /// Transports are cheap to share, e.g. the HTTP ones keep a single
+    /// `reqwest::Client` with its connection pool inside.
+    transport: Arc<dyn EmailTransport>,
+    sender: SubscriberEmail,
+    retry_policy: RetryPolicy,
+    metrics: Metrics,
+    /// Set when the client was built for `in_memory` delivery.
+    outbox: Option<InMemoryTransport>,
+}
+
+impl EmailClient {
+    pub fn new(
+        sender: SubscriberEmail,
+        transport: Arc<dyn EmailTransport>,
+        retry_policy: RetryPolicy,
+    ) -> Self {
+        Self {
+            transport,
+            sender,
+            retry_policy,
+            metrics: Metrics::default(),
+            outbox: None,
+        }
+    }
+
+    /// Record send attempts into `metrics`, e.g. the registry of the
+    /// application, instead of a registry of its own.
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// The transport chosen by `in_memory` delivery, with the emails sent
+    /// so far. `None` for every other delivery service.
+    pub fn outbox(&self) -> Option<&InMemoryTransport> {
+        self.outbox.as_ref()
+    }
+
+    /// Check that the delivery service can be reached, e.g. for the
+    /// readiness probe.
+    pub async fn check_connection(&self) -> Result<(), anyhow::Error> {
+        self.transport.check_connection().await
+    }
+
+    /// Build the client with the transport chosen by `delivery_service`.
+    pub fn from_settings(
+        settings: &EmailClientSettings,
+        delivery_service: &EmailDeliveryService,
+    ) -> Result<Self, String> {
+        let sender = settings.sender().map_err(|e| e.to_string())?;
+        let timeout = settings.timeout_millis();
+        let mut outbox = None;
+        let transport: Arc<dyn EmailTransport> = match delivery_service {
+            EmailDeliveryService::Postmark => Arc::new(PostmarkTransport::new(
+                &settings.base_url,
+                settings.authorization_token.clone(),
+                timeout,
+            )?),
+            EmailDeliveryService::SmtpBz => Arc::new(SmtpBzTransport::new(
+                &settings.base_url,
+                settings.authorization_token.clone(),
+                timeout,
+            )?),
+            EmailDeliveryService::SmtpRelay => {
+                let smtp = settings.smtp.as_ref().ok_or(
+                    "`email_client.smtp` is required for the smtp_relay service",
+                )?;
+                Arc::new(SmtpTransport::new(smtp, timeout)?)
+            }
+            EmailDeliveryService::File => {
+                let outbox_dir = settings.outbox_dir.as_ref().ok_or(
+                    "`email_client.outbox_dir` is required for the file service",
+                )?;
+                Arc::new(FileTransport::new(outbox_dir)?)
+            }
+            EmailDeliveryService::InMemory => {
+                let transport = InMemoryTransport::default();
+                outbox = Some(transport.clone());
+                Arc::new(transport)
+            }
+        };
+        Ok(Self {
+            outbox,
+            ..Self::new(sender, transport, settings.retry_policy.clone())
+        })
+    }
+
+    /// Send an email to the recipient through the configured transport.
+    ///
+    /// Transient failures are retried with exponential backoff according
+    /// to the `RetryPolicy`.
+    pub async fn send_email(
+        &self,
+        recipient: &SubscriberEmail,
+        subject: &str,
+        html_content: &str,
+        text_content: &str,
+    ) -> Result<(), SendEmailError> {
+        self.send_email_with_headers(
+            recipient,
+            subject,
+            html_content,
+            text_content,
+            &[],
+        )
+        .await
+    }
+
+    /// Same as `send_email`, with extra headers as `(name, value)` pairs.
+    pub async fn send_email_with_headers(
+        &self,
+        recipient: &SubscriberEmail,
+        subject: &str,
+        html_content: &str,
+        text_content: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<(), SendEmailError> {
+        self.send_email_with_retry_policy(
+            recipient,
+            subject,
+            html_content,
+            text_content,
+            headers,
+            &self.retry_policy,
+        )
+        .await
+    }
+
+    /// Same as `send_email_with_headers`, retrying by `retry_policy`
+    /// instead of the configured one. E.g. callers with retries of their
+    /// own pass `RetryPolicy::no_retries()`.
+    #[tracing::instrument(skip_all, fields(attempts = tracing::field::Empty))]
+    pub async fn send_email_with_retry_policy(
+        &self,
+        recipient: &SubscriberEmail,
+        subject: &str,
+        html_content: &str,
+        text_content: &str,
+        headers: &[(&str, &str)],
+        retry_policy: &RetryPolicy,
+    ) -> Result<(), SendEmailError> {
+        let email = Email {
+            from: &self.sender,
+            to: recipient,
+            subject,
+            html_content,
+            text_content,
+            headers,
+        };
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            tracing::Span::current().record("attempts", attempt);
+            let start = std::time::Instant::now();
+            let result = self.transport.send(&email).await;
+            self.metrics.record_email_attempt(
+                self.transport.service(),
+                start.elapsed(),
+                result.is_ok(),
+            );
+            let (reason, retry_after, source) = match result {
+                Ok(()) => return Ok(()),
+                Err(TransportError::Permanent(e)) => {
+                    return Err(SendEmailError::Permanent(e))
+                }
+                Err(TransportError::Transient {
+                    reason,
+                    retry_after,
+                    source,
+                }) => (reason, retry_after, source),
+            };
+
+            let retryable = match reason {
+                TransientReason::TooManyRequests => {
+                    retry_policy.retry_on_too_many_requests
+                }
+                TransientReason::ServerError => {
+                    retry_policy.retry_on_server_error
+                }
+                TransientReason::Timeout => retry_policy.retry_on_timeout,
+            };
+            let delay =
+                retry_after.unwrap_or_else(|| retry_policy.backoff(attempt));
+            let max_delay = Duration::from_millis(retry_policy.max_delay);
+
+            if !retryable
+                || attempt >= retry_policy.max_attempts
+                || delay > max_delay
+            {
+                return Err(SendEmailError::Transient {
+                    attempts: attempt,
+                    retry_after,
+                    source,
+                });
+            }
+
+            tracing::warn!(
+                "Email delivery attempt {attempt} failed, \
+                retrying in {delay:?}: {source}"
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+// It is unit tests module, because it needs private type `SendEmailRequest`.
+#[cfg(test)]
+mod email_client_tests {
+    use std::sync::Arc;
+
+    use super::postmark::SendEmailRequest;
+    use super::{
+        EmailDeliveryService, EmailTransport, PostmarkTransport, RetryPolicy,
+        SmtpBzTransport,
+    };
+    use crate::domain::SubscriberEmail;
+    use crate::email_client::EmailClient;
+    use fake::faker::internet::en::SafeEmail;
+    use fake::faker::lorem::en::{Paragraph, Sentence};
+    use fake::{Fake, Faker};
+    use secrecy::Secret;
+    use wiremock::matchers::{
+        any, body_string_contains, header, header_exists, method, path,
+    };
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    struct SendEmailBodyMatcher;
+
+    impl wiremock::Match for SendEmailBodyMatcher {
+        fn matches(&self, request: &wiremock::Request) -> bool {
+            let request =
+                serde_json::from_slice::<SendEmailRequest>(&request.body);
+            match request {
+                Ok(_r) => true,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    false
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn send_email_sends_the_expected_request_postmark() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client =
+            email_client(mock_server.uri(), EmailDeliveryService::Postmark)
+                .unwrap();
+
+        Mock::given(header_exists("X-Postmark-Server-Token"))
+            .and(header("Content-Type", "application/json"))
+            .and(path("/email"))
+            .and(SendEmailBodyMatcher)
+            .and(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let _ = email_client
+            .send_email(&email(), &subject(), &content(), &content())
+            .await;
+
+        // Assert
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn send_email_sends_the_expected_request_smtp() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client =
+            email_client(mock_server.uri(), EmailDeliveryService::SmtpBz)
+                .unwrap();
+
+        Mock::given(header_exists("Authorization"))
+            .and(header("Content-Type", "application/x-www-form-urlencoded"))
+            .and(path("/v1/smtp/send"))
+            .and(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let _ = email_client
+            .send_email(&email(), &subject(), &content(), &content())
+            .await;
+
+        // Assert
+        assert_eq!(mock_server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn send_email_with_headers_passes_headers_to_both_http_apis() {
+        for (delivery_service, expected_body) in [
+            (
+                EmailDeliveryService::Postmark,
+                r#""headers":[{"name":"List-Unsubscribe","value":"<link>"}]"#,
+            ),
+            (
+                EmailDeliveryService::SmtpBz,
+                "headers=%7B%22List-Unsubscribe%22%3A%22%3Clink%3E%22%7D",
+            ),
+        ] {
+            // Arrange
+            let mock_server = MockServer::start().await;
+            let email_client =
+                email_client(mock_server.uri(), delivery_service).unwrap();
+
+            Mock::given(body_string_contains(expected_body))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+
+            // Act
+            let result = email_client
+                .send_email_with_headers(
+                    &email(),
+                    &subject(),
+                    &content(),
+                    &content(),
+                    &[("List-Unsubscribe", "<link>")],
+                )
+                .await;
+
+            // Assert
+            assert!(result.is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn send_email_returns_ok_when_request_succeeds() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client =
+            email_client(mock_server.uri(), EmailDeliveryService::Postmark)
+                .unwrap();
+
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let result = email_client
+            .send_email(&email(), &subject(), &content(), &content())
+            .await;
+
+        // Assert
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn send_email_returns_error_when_request_fails() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client =
+            email_client(mock_server.uri(), EmailDeliveryService::Postmark)
+                .unwrap();
+
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let result = email_client
+            .send_email(&email(), &subject(), &content(), &content())
+            .await;
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn send_email_times_out_if_the_server_takes_too_long() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client =
+            email_client(mock_server.uri(), EmailDeliveryService::Postmark)
+                .unwrap();
+
+        let response = ResponseTemplate::new(200)
+            .set_delay(std::time::Duration::from_secs(60));
+
+        Mock::given(any())
+            .respond_with(response)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let result = email_client
+            .send_email(&email(), &subject(), &content(), &content())
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn send_email_retries_server_errors_until_success() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client =
+            email_client_with_retries(mock_server.uri(), retry_policy());
+
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let result = email_client
+            .send_email(&email(), &subject(), &content(), &content())
+            .await;
+
+        // Assert
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn send_email_gives_up_after_max_attempts_with_transient_error() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client =
+            email_client_with_retries(mock_server.uri(), retry_policy());
+
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(503))
+            .expect(3)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let result = email_client
+            .send_email(&email(), &subject(), &content(), &content())
+            .await;
+
+        // Assert
+        let error = result.unwrap_err();
+        assert!(error.is_transient());
+    }
+
+    #[tokio::test]
+    async fn retry_policy_of_a_single_call_overrides_the_configured_one() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client =
+            email_client_with_retries(mock_server.uri(), retry_policy());
+
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let result = email_client
+            .send_email_with_retry_policy(
+                &email(),
+                &subject(),
+                &content(),
+                &content(),
+                &[],
+                &RetryPolicy::no_retries(),
+            )
+            .await;
+
+        // Assert
+        let error = result.unwrap_err();
+        assert!(error.is_transient());
+    }
+
+    #[tokio::test]
+    async fn send_email_does_not_retry_client_errors() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client =
+            email_client_with_retries(mock_server.uri(), retry_policy());
+
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(422))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let result = email_client
+            .send_email(&email(), &subject(), &content(), &content())
+            .await;
+
+        // Assert
+        let error = result.unwrap_err();
+        assert!(!error.is_transient());
+    }
+
+    #[tokio::test]
+    async fn send_email_waits_for_retry_after_on_too_many_requests() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client =
+            email_client_with_retries(mock_server.uri(), retry_policy());
+
+        Mock::given(any())
+            .respond_with(
+                ResponseTemplate::new(429).insert_header("Retry-After", "1"),
+            )
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let started = std::time::Instant::now();
+        let result = email_client
+            .send_email(&email(), &subject(), &content(), &content())
+            .await;
+
+        // Assert
+        assert!(result.is_ok());
+        assert!(started.elapsed() >= std::time::Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn send_email_gives_up_when_retry_after_exceeds_max_delay() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client =
+            email_client_with_retries(mock_server.uri(), retry_policy());
+
+        Mock::given(any())
+            .respond_with(
+                ResponseTemplate::new(429).insert_header("Retry-After", "120"),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let result = email_client
+            .send_email(&email(), &subject(), &content(), &content())
+            .await;
+
+        // Assert
+        let error = result.unwrap_err();
+        assert!(error.is_transient());
+        assert_eq!(
+            error.retry_after(),
+            Some(std::time::Duration::from_secs(120))
+        );
+    }
+
+    #[tokio::test]
+    async fn send_email_retries_timeouts() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client =
+            email_client_with_retries(mock_server.uri(), retry_policy());
+
+        let response = ResponseTemplate::new(200)
+            .set_delay(std::time::Duration::from_secs(60));
+        Mock::given(any())
+            .respond_with(response)
+            .expect(3)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let result = email_client
+            .send_email(&email(), &subject(), &content(), &content())
+            .await;
+
+        // Assert
+        let error = result.unwrap_err();
+        assert!(error.is_transient());
+    }
+
+    #[tokio::test]
+    async fn send_email_does_not_retry_server_errors_if_disabled() {
+        // Arrange
+        let mock_server = MockServer::start().await;
+        let email_client = email_client_with_retries(
+            mock_server.uri(),
+            RetryPolicy {
+                retry_on_server_error: false,
+                ..retry_policy()
+            },
+        );
+
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Act
+        let result = email_client
+            .send_email(&email(), &subject(), &content(), &content())
+            .await;
+
+        // Assert
+        let error = result.unwrap_err();
+        assert!(error.is_transient());
+    }
+
+    #[test]
+    fn smtp_still_selects_the_smtp_bz_api() {
+        assert!(matches!(
+            EmailDeliveryService::try_from("smtp".to_string()),
+            Ok(EmailDeliveryService::SmtpBz)
+        ));
+        assert!(matches!(
+            EmailDeliveryService::try_from("smtp_relay".to_string()),
+            Ok(EmailDeliveryService::SmtpRelay)
+        ));
+    }
+
+    fn subject() -> String {
+        Sentence(1..2).fake()
+    }
+
+    fn content() -> String {
+        Paragraph(1..10).fake()
+    }
+
+    fn email() -> SubscriberEmail {
+        SubscriberEmail::parse(&SafeEmail().fake::<String>()).unwrap()
+    }
+
+    fn email_client(
+        base_url: String,
+        delivery_service: EmailDeliveryService,
+    ) -> Result<EmailClient, String> {
+        Ok(EmailClient::new(
+            email(),
+            transport(base_url, delivery_service)?,
+            RetryPolicy::no_retries(),
+        ))
+    }
+
+    fn email_client_with_retries(
+        base_url: String,
+        retry_policy: RetryPolicy,
+    ) -> EmailClient {
+        EmailClient::new(
+            email(),
+            transport(base_url, EmailDeliveryService::Postmark).unwrap(),
+            retry_policy,
+        )
+    }
+
+    fn transport(
+        base_url: String,
+        delivery_service: EmailDeliveryService,
+    ) -> Result<Arc<dyn EmailTransport>, String> {
+        let timeout = std::time::Duration::from_millis(200);
+        let token = Secret::new(Faker.fake());
+        match delivery_service {
+            EmailDeliveryService::Postmark => {
+                Ok(Arc::new(PostmarkTransport::new(&base_url, token, timeout)?))
+            }
+            EmailDeliveryService::SmtpBz => {
+                Ok(Arc::new(SmtpBzTransport::new(&base_url, token, timeout)?))
+            }
+            _ => Err("Only HTTP transports are served by MockServer".into()),
+        }
+    }
+
+    /// Short delays to keep tests fast.
+    fn retry_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: 10,
+            jitter: 10,
+            max_delay: 2000,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! src/email_client/postmark.rs
+
+use std::borrow::Cow;
+
+use reqwest::Client;
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+
+use super::transport::{check_http_connection, execute_http_request};
+use super::{Email, EmailTransport, TransportError};
+
+/// Delivers emails through the Postmark HTTP API.
+pub struct PostmarkTransport {
+    /// Every time a `Client` instance is created, `reqwest` initialises a
+    /// connection pool under the hood.
+    http_client: Client,
+    base_url: reqwest::Url,
+    authorization_token: Secret<String>,
+}
+
+impl PostmarkTransport {
+    pub fn new(
+        base_url: &str,
+        authorization_token: Secret<String>,
+        timeout: std::time::Duration,
+    ) -> Result<Self, String> {
+        let base_url = reqwest::Url::try_from(base_url).map_err(|e| {
+            format!("Error in `PostmarkTransport`s new fn: {}", e)
+        })?;
+        let http_client = Client::builder().timeout(timeout).build().unwrap();
+        Ok(Self {
+            http_client,
+            base_url,
+            authorization_token,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailTransport for PostmarkTransport {
+    fn service(&self) -> &'static str {
+        "postmark"
+    }
+
+    async fn check_connection(&self) -> Result<(), anyhow::Error> {
+        check_http_connection(&self.http_client, &self.base_url).await
+    }
+
+    async fn send(&self, email: &Email<'_>) -> Result<(), TransportError> {
+        let url = self.base_url.join("/email").unwrap();
+        let request_body = SendEmailRequest {
+            from: Cow::Borrowed(email.from.as_ref()),
+            to: Cow::Borrowed(email.to.as_ref()),
+            subject: Cow::Borrowed(email.subject),
+            html_body: Cow::Borrowed(email.html_content),
+            text_body: Cow::Borrowed(email.text_content),
+            headers: email
+                .headers
+                .iter()
+                .map(|(name, value)| EmailHeader {
+                    name: Cow::Borrowed(name),
+                    value: Cow::Borrowed(value),
+                })
+                .collect(),
+        };
+        let request = self
+            .http_client
+            .post(url)
+            .header(
+                "X-Postmark-Server-Token",
+                self.authorization_token.expose_secret(),
+            )
+            .json(&request_body);
+        execute_http_request(request).await
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub(super) struct SendEmailRequest<'a> {
+    from: Cow<'a, str>,
+    to: Cow<'a, str>,
+    subject: Cow<'a, str>,
+    html_body: Cow<'a, str>,
+    text_body: Cow<'a, str>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    headers: Vec<EmailHeader<'a>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct EmailHeader<'a> {
+    name: Cow<'a, str>,
+    value: Cow<'a, str>,
+}
@@ -0,0 +1,107 @@
+//! src/email_client/smtp.rs
+
+use lettre::message::header::{HeaderName, HeaderValue};
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use secrecy::ExposeSecret;
+
+use super::{Email, EmailTransport, TransientReason, TransportError};
+use crate::configuration::SmtpSettings;
+
+/// Delivers emails to an SMTP relay. The connection is upgraded with
+/// STARTTLS (required) before we authenticate with `AUTH`.
+pub struct SmtpTransport {
+    /// `lettre` keeps a pool of connections to the relay inside.
+    mailer: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpTransport {
+    pub fn new(
+        settings: &SmtpSettings,
+        timeout: std::time::Duration,
+    ) -> Result<Self, String> {
+        let credentials = Credentials::new(
+            settings.username.clone(),
+            settings.password.expose_secret().clone(),
+        );
+        let mailer = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(
+            &settings.host,
+        )
+        .map_err(|e| format!("Error in `SmtpTransport`s new fn: {e}"))?
+        .port(settings.port)
+        .credentials(credentials)
+        .timeout(Some(timeout))
+        .build();
+        Ok(Self { mailer })
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailTransport for SmtpTransport {
+    fn service(&self) -> &'static str {
+        "smtp"
+    }
+
+    async fn check_connection(&self) -> Result<(), anyhow::Error> {
+        if self.mailer.test_connection().await? {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("The SMTP relay refused the connection"))
+        }
+    }
+
+    async fn send(&self, email: &Email<'_>) -> Result<(), TransportError> {
+        let message = build_message(email)?;
+        self.mailer.send(message).await.map_err(|e| {
+            if e.is_permanent() || e.is_client() || e.is_response() {
+                TransportError::Permanent(e.into())
+            } else if e.is_transient() {
+                TransportError::Transient {
+                    reason: TransientReason::ServerError,
+                    retry_after: None,
+                    source: e.into(),
+                }
+            } else {
+                // Timeouts, refused connections, failed TLS handshakes.
+                TransportError::Transient {
+                    reason: TransientReason::Timeout,
+                    retry_after: None,
+                    source: e.into(),
+                }
+            }
+        })?;
+        Ok(())
+    }
+}
+
+/// Build `multipart/alternative` message with plain text and HTML bodies.
+pub(super) fn build_message(
+    email: &Email<'_>,
+) -> Result<Message, TransportError> {
+    let from = parse_mailbox(email.from.as_ref())?;
+    let to = parse_mailbox(email.to.as_ref())?;
+    let mut message = Message::builder()
+        .from(from)
+        .to(to)
+        .subject(email.subject)
+        .multipart(MultiPart::alternative_plain_html(
+            email.text_content.to_string(),
+            email.html_content.to_string(),
+        ))
+        .map_err(|e| TransportError::Permanent(e.into()))?;
+    for (name, value) in email.headers {
+        let name = HeaderName::new_from_ascii(name.to_string())
+            .map_err(|e| TransportError::Permanent(e.into()))?;
+        message
+            .headers_mut()
+            .insert_raw(HeaderValue::new(name, value.to_string()));
+    }
+    Ok(message)
+}
+
+fn parse_mailbox(address: &str) -> Result<Mailbox, TransportError> {
+    address
+        .parse::<Mailbox>()
+        .map_err(|e| TransportError::Permanent(e.into()))
+}
@@ -0,0 +1,73 @@
+//! src/email_client/smtp_bz.rs
+
+use std::collections::HashMap;
+
+use reqwest::Client;
+use secrecy::{ExposeSecret, Secret};
+
+use super::transport::{check_http_connection, execute_http_request};
+use super::{Email, EmailTransport, TransportError};
+
+/// Delivers emails through the `smtp.bz` HTTP API.
+pub struct SmtpBzTransport {
+    http_client: Client,
+    base_url: reqwest::Url,
+    authorization_token: Secret<String>,
+}
+
+impl SmtpBzTransport {
+    pub fn new(
+        base_url: &str,
+        authorization_token: Secret<String>,
+        timeout: std::time::Duration,
+    ) -> Result<Self, String> {
+        let base_url = reqwest::Url::try_from(base_url).map_err(|e| {
+            format!("Error in `SmtpBzTransport`s new fn: {}", e)
+        })?;
+        let http_client = Client::builder().timeout(timeout).build().unwrap();
+        Ok(Self {
+            http_client,
+            base_url,
+            authorization_token,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl EmailTransport for SmtpBzTransport {
+    fn service(&self) -> &'static str {
+        "smtp_bz"
+    }
+
+    async fn check_connection(&self) -> Result<(), anyhow::Error> {
+        check_http_connection(&self.http_client, &self.base_url).await
+    }
+
+    async fn send(&self, email: &Email<'_>) -> Result<(), TransportError> {
+        let url = self.base_url.join("/v1/smtp/send").unwrap();
+        let mut map = HashMap::new();
+        map.insert("name", "info");
+        map.insert("from", email.from.as_ref());
+        map.insert("subject", email.subject);
+        map.insert("to", email.to.as_ref());
+        map.insert("html", email.html_content);
+        map.insert("text", email.text_content);
+        // The API expects extra headers as a JSON object.
+        let headers;
+        if !email.headers.is_empty() {
+            headers = serde_json::to_string(
+                &email.headers.iter().copied().collect::<HashMap<_, _>>(),
+            )
+            .map_err(|e| TransportError::Permanent(e.into()))?;
+            map.insert("headers", &headers);
+        }
+
+        let pass = self.authorization_token.expose_secret();
+        let request = self
+            .http_client
+            .post(url)
+            .header("Authorization", pass)
+            .form(&map);
+        execute_http_request(request).await
+    }
+}
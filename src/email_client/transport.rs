@@ -0,0 +1,118 @@
+//! src/email_client/transport.rs
+
+use std::time::Duration;
+
+use reqwest::{RequestBuilder, StatusCode};
+
+use crate::domain::SubscriberEmail;
+use crate::request_id::{RequestId, REQUEST_ID_HEADER};
+
+/// Everything a transport needs to deliver a single email.
+#[derive(Debug, Clone, Copy)]
+pub struct Email<'a> {
+    pub from: &'a SubscriberEmail,
+    pub to: &'a SubscriberEmail,
+    pub subject: &'a str,
+    pub html_content: &'a str,
+    pub text_content: &'a str,
+    /// Extra headers, e.g. `List-Unsubscribe`, as `(name, value)` pairs.
+    pub headers: &'a [(&'a str, &'a str)],
+}
+
+/// Backend delivering emails, one attempt per `send` call.
+/// Retries are the business of `EmailClient`.
+#[async_trait::async_trait]
+pub trait EmailTransport: Send + Sync {
+    /// Name of the delivery service, labels the metrics of `EmailClient`.
+    fn service(&self) -> &'static str;
+
+    async fn send(&self, email: &Email<'_>) -> Result<(), TransportError>;
+
+    /// Check that the delivery service can be reached, without sending
+    /// anything. Local transports always can.
+    async fn check_connection(&self) -> Result<(), anyhow::Error> {
+        Ok(())
+    }
+}
+
+/// Why a transient failure happened, `RetryPolicy` decides
+/// which reasons are worth retrying.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransientReason {
+    /// `5xx` response or SMTP `4xx` reply.
+    ServerError,
+    /// `429 Too Many Requests`.
+    TooManyRequests,
+    /// Timeout or failed connection.
+    Timeout,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum TransportError {
+    #[error("Transient email transport failure: {source}")]
+    Transient {
+        reason: TransientReason,
+        /// Pause requested by the service, if any.
+        retry_after: Option<Duration>,
+        #[source]
+        source: anyhow::Error,
+    },
+    #[error("Permanent email transport failure: {0}")]
+    Permanent(#[source] anyhow::Error),
+}
+
+/// Sends a request to an HTTP email API and classifies the failure, if any.
+/// Within a request to the app, its ID goes along as `X-Request-Id`.
+pub(super) async fn execute_http_request(
+    mut request: RequestBuilder,
+) -> Result<(), TransportError> {
+    if let Some(request_id) = RequestId::current() {
+        request =
+            request.header(REQUEST_ID_HEADER.as_str(), request_id.as_str());
+    }
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) if e.is_timeout() || e.is_connect() => {
+            return Err(TransportError::Transient {
+                reason: TransientReason::Timeout,
+                retry_after: None,
+                source: e.into(),
+            })
+        }
+        Err(e) => return Err(TransportError::Permanent(e.into())),
+    };
+
+    let status = response.status();
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs);
+
+    match response.error_for_status() {
+        Ok(_) => Ok(()),
+        Err(e) if status == StatusCode::TOO_MANY_REQUESTS => {
+            Err(TransportError::Transient {
+                reason: TransientReason::TooManyRequests,
+                retry_after,
+                source: e.into(),
+            })
+        }
+        Err(e) if status.is_server_error() => Err(TransportError::Transient {
+            reason: TransientReason::ServerError,
+            retry_after: None,
+            source: e.into(),
+        }),
+        Err(e) => Err(TransportError::Permanent(e.into())),
+    }
+}
+
+/// Any response counts, the API only has to be reachable.
+pub(super) async fn check_http_connection(
+    http_client: &reqwest::Client,
+    url: &reqwest::Url,
+) -> Result<(), anyhow::Error> {
+    http_client.head(url.clone()).send().await?;
+    Ok(())
+}
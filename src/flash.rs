@@ -0,0 +1,216 @@
+//! src/flash.rs
+
+use std::convert::Infallible;
+
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use cookie::{Cookie, SameSite};
+use hmac::{Hmac, Mac};
+use http::request::Parts;
+use http::{HeaderMap, HeaderValue};
+use secrecy::{ExposeSecret, Secret};
+use sha2::Sha256;
+
+use crate::configuration::SessionSettings;
+use crate::startup::AppState;
+
+/// Name of the cookie carrying a flash message.
+pub const FLASH_COOKIE: &str = "flash";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Error,
+}
+
+impl Level {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Info => "info",
+            Level::Error => "error",
+        }
+    }
+}
+
+/// One-time message shown on the page we redirect to, e.g. the result
+/// of a form submission. It lives in a cookie until it is displayed. The
+/// cookie is signed, so a message can't be planted by someone else.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlashMessage {
+    pub level: Level,
+    pub message: String,
+}
+
+impl FlashMessage {
+    pub fn info<S: Into<String>>(message: S) -> FlashMessage {
+        FlashMessage {
+            level: Level::Info,
+            message: message.into(),
+        }
+    }
+
+    pub fn error<S: Into<String>>(message: S) -> FlashMessage {
+        FlashMessage {
+            level: Level::Error,
+            message: message.into(),
+        }
+    }
+
+    /// Take the flash message from the `Cookie` request header, if any.
+    /// Messages with a missing or wrong signature are dropped.
+    pub fn from_headers(
+        headers: &HeaderMap,
+        settings: &SessionSettings,
+    ) -> Option<FlashMessage> {
+        let cookie = headers
+            .get_all(http::header::COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(Cookie::split_parse_encoded)
+            .filter_map(Result::ok)
+            .find(|cookie| cookie.name() == FLASH_COOKIE)?;
+        let (signature, payload) = cookie.value().split_once('.')?;
+        let signature = hex::decode(signature).ok()?;
+        mac(payload, &settings.hmac_secret)
+            .verify_slice(&signature)
+            .ok()?;
+        let (level, message) = payload.split_once(':')?;
+        let level = match level {
+            "info" => Level::Info,
+            "error" => Level::Error,
+            _ => return None,
+        };
+        Some(FlashMessage {
+            level,
+            message: message.to_string(),
+        })
+    }
+
+    /// `Set-Cookie` value storing this message until the next page load.
+    pub fn cookie(&self, settings: &SessionSettings) -> HeaderValue {
+        let payload = format!("{}:{}", self.level.as_str(), self.message);
+        let signature =
+            mac(&payload, &settings.hmac_secret).finalize().into_bytes();
+        let value = format!("{}.{}", hex::encode(signature), payload);
+        let cookie = Cookie::build((FLASH_COOKIE, value))
+            .path("/")
+            .http_only(true)
+            .secure(settings.secure_cookie)
+            .same_site(SameSite::Strict)
+            .build();
+        HeaderValue::from_str(&cookie.encoded().to_string()).unwrap()
+    }
+
+    /// `Set-Cookie` value removing a displayed message from the browser.
+    pub fn removal_cookie(settings: &SessionSettings) -> HeaderValue {
+        let mut cookie = Cookie::build((FLASH_COOKIE, ""))
+            .path("/")
+            .http_only(true)
+            .secure(settings.secure_cookie)
+            .same_site(SameSite::Strict)
+            .build();
+        cookie.make_removal();
+        HeaderValue::from_str(&cookie.to_string()).unwrap()
+    }
+}
+
+/// HMAC-SHA256 of the cookie payload.
+fn mac(payload: &str, key: &Secret<String>) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key.expose_secret().as_bytes())
+            .expect("HMAC accepts keys of any length");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+/// Extracts the flash message sent with the request. Handlers rendering
+/// it should respond with `FlashMessage::removal_cookie`.
+#[derive(Debug)]
+pub struct IncomingFlashMessage(pub Option<FlashMessage>);
+
+#[async_trait]
+impl FromRequestParts<AppState> for IncomingFlashMessage {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(IncomingFlashMessage(FlashMessage::from_headers(
+            &parts.headers,
+            &state.session_settings,
+        )))
+    }
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use http::header::COOKIE;
+    use http::HeaderMap;
+    use secrecy::Secret;
+
+    use super::FlashMessage;
+    use crate::configuration::SessionSettings;
+
+    fn settings(key: &str) -> SessionSettings {
+        SessionSettings {
+            ttl: 60,
+            secure_cookie: false,
+            hmac_secret: Secret::new(key.to_string()),
+        }
+    }
+
+    /// `Cookie` request header sending back the `Set-Cookie` value.
+    fn cookie_header(set_cookie: &http::HeaderValue) -> HeaderMap {
+        let pair = set_cookie.to_str().unwrap().split(';').next().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(COOKIE, pair.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn flash_message_survives_cookie_round_trip() {
+        let settings = settings("key");
+        let message = FlashMessage::error("Oops; title is empty: 100%");
+        let headers = cookie_header(&message.cookie(&settings));
+
+        assert_eq!(
+            FlashMessage::from_headers(&headers, &settings),
+            Some(message)
+        );
+    }
+
+    #[test]
+    fn garbage_in_flash_cookie_is_ignored() {
+        let mut headers = HeaderMap::new();
+        headers.insert(COOKIE, "flash=whatever".parse().unwrap());
+
+        assert_eq!(
+            FlashMessage::from_headers(&headers, &settings("key")),
+            None
+        );
+    }
+
+    #[test]
+    fn unsigned_or_tampered_flash_cookie_is_ignored() {
+        let settings = settings("key");
+        let set_cookie = FlashMessage::info("Saved").cookie(&settings);
+        let forged = set_cookie.to_str().unwrap().replace("Saved", "Hacked");
+        let forged = http::HeaderValue::from_str(&forged).unwrap();
+
+        for headers in [
+            cookie_header(&forged),
+            cookie_header(&"flash=error:Hacked".parse().unwrap()),
+        ] {
+            assert_eq!(FlashMessage::from_headers(&headers, &settings), None);
+        }
+        // Signed with another key.
+        let headers = cookie_header(&set_cookie);
+        assert_eq!(
+            FlashMessage::from_headers(&headers, &self::settings("other")),
+            None
+        );
+    }
+}
@@ -1,5 +1,13 @@
 use askama::Template; // bring trait in scope
 
+use crate::cornucopia::queries::api_tokens::ListUserApiTokens;
+use crate::cornucopia::queries::audit::ListAuditEvents;
+use crate::cornucopia::queries::subscriptions::ListSubscribers;
+use crate::cornucopia::queries::users::ListUsers;
+use crate::csrf::CsrfToken;
+use crate::domain::{ApiScope, UserRole};
+use crate::flash::FlashMessage;
+
 #[derive(Template)] // this will generate the code...
 #[template(path = "email_verification.html")] // using the template in this path, relative
                                               // to the `templates` dir in the crate root
@@ -16,3 +24,350 @@ impl<'a> VerifyEmailTemplate<'a> {
         }
     }
 }
+
+/// Page behind the `List-Unsubscribe` link: the form asking to confirm,
+/// or the result of the unsubscription.
+#[derive(Template)]
+#[template(path = "unsubscribe.html")]
+pub struct UnsubscribeTemplate<'a> {
+    email: &'a str,
+    action: &'a str,
+    unsubscribed: bool,
+}
+
+impl<'a> UnsubscribeTemplate<'a> {
+    pub fn form(email: &'a str, action: &'a str) -> Self {
+        UnsubscribeTemplate {
+            email,
+            action,
+            unsubscribed: false,
+        }
+    }
+
+    pub fn done(email: &'a str) -> Self {
+        UnsubscribeTemplate {
+            email,
+            action: "",
+            unsubscribed: true,
+        }
+    }
+}
+
+/// `/admin/subscribers` page: filters, one page of subscribers
+/// and the link to the next one.
+#[derive(Template)]
+#[template(path = "admin_subscribers.html")]
+pub struct SubscribersTemplate<'a> {
+    subscribers: &'a [ListSubscribers],
+    /// `(status, selected)` pairs for the filter.
+    status_options: &'a [(&'a str, bool)],
+    search: &'a str,
+    next_page: Option<&'a str>,
+    /// Show confirm and delete buttons.
+    can_manage: bool,
+    csrf_token: &'a CsrfToken,
+}
+
+impl<'a> SubscribersTemplate<'a> {
+    pub fn new(
+        subscribers: &'a [ListSubscribers],
+        status_options: &'a [(&'a str, bool)],
+        search: &'a str,
+        next_page: Option<&'a str>,
+        can_manage: bool,
+        csrf_token: &'a CsrfToken,
+    ) -> Self {
+        SubscribersTemplate {
+            subscribers,
+            status_options,
+            search,
+            next_page,
+            can_manage,
+            csrf_token,
+        }
+    }
+}
+
+/// `/admin/audit` page: filters, one page of events and the link to the
+/// next one.
+#[derive(Template)]
+#[template(path = "admin_audit.html")]
+pub struct AuditTemplate<'a> {
+    events: &'a [ListAuditEvents],
+    /// `(action, selected)` pairs for the filter.
+    action_options: &'a [(&'a str, bool)],
+    username: &'a str,
+    next_page: Option<&'a str>,
+}
+
+impl<'a> AuditTemplate<'a> {
+    pub fn new(
+        events: &'a [ListAuditEvents],
+        action_options: &'a [(&'a str, bool)],
+        username: &'a str,
+        next_page: Option<&'a str>,
+    ) -> Self {
+        AuditTemplate {
+            events,
+            action_options,
+            username,
+            next_page,
+        }
+    }
+}
+
+/// `/admin/newsletters` page: the compose form, optionally with a preview
+/// of both bodies.
+#[derive(Template)]
+#[template(path = "admin_newsletters.html")]
+pub struct NewsletterFormTemplate<'a> {
+    flash: Option<&'a FlashMessage>,
+    title: &'a str,
+    html: &'a str,
+    text: &'a str,
+    idempotency_key: &'a str,
+    preview: bool,
+    csrf_token: &'a CsrfToken,
+}
+
+impl<'a> NewsletterFormTemplate<'a> {
+    pub fn new(
+        flash: Option<&'a FlashMessage>,
+        title: &'a str,
+        html: &'a str,
+        text: &'a str,
+        idempotency_key: &'a str,
+        preview: bool,
+        csrf_token: &'a CsrfToken,
+    ) -> Self {
+        NewsletterFormTemplate {
+            flash,
+            title,
+            html,
+            text,
+            idempotency_key,
+            preview,
+            csrf_token,
+        }
+    }
+}
+
+/// `/admin/password` page.
+#[derive(Template)]
+#[template(path = "admin_password.html")]
+pub struct ChangePasswordTemplate<'a> {
+    flash: Option<&'a FlashMessage>,
+    csrf_token: &'a CsrfToken,
+}
+
+impl<'a> ChangePasswordTemplate<'a> {
+    pub fn new(
+        flash: Option<&'a FlashMessage>,
+        csrf_token: &'a CsrfToken,
+    ) -> Self {
+        ChangePasswordTemplate { flash, csrf_token }
+    }
+}
+
+/// `/admin/users` page: every admin user and the form for a new one.
+#[derive(Template)]
+#[template(path = "admin_users.html")]
+pub struct UsersTemplate<'a> {
+    flash: Option<&'a FlashMessage>,
+    users: &'a [ListUsers],
+    /// The own row has no controls, nobody can demote or delete themselves.
+    current_user_id: uuid::Uuid,
+    roles: [UserRole; 3],
+    csrf_token: &'a CsrfToken,
+}
+
+impl<'a> UsersTemplate<'a> {
+    pub fn new(
+        flash: Option<&'a FlashMessage>,
+        users: &'a [ListUsers],
+        current_user_id: uuid::Uuid,
+        csrf_token: &'a CsrfToken,
+    ) -> Self {
+        UsersTemplate {
+            flash,
+            users,
+            current_user_id,
+            roles: UserRole::ALL,
+            csrf_token,
+        }
+    }
+}
+
+/// `/admin/tokens` page: API tokens of the user, the form for a new one
+/// and, right after creation, the new token itself.
+#[derive(Template)]
+#[template(path = "admin_tokens.html")]
+pub struct TokensTemplate<'a> {
+    flash: Option<&'a FlashMessage>,
+    tokens: &'a [ListUserApiTokens],
+    /// Scopes the role of the user allows.
+    scopes: &'a [ApiScope],
+    created: Option<&'a str>,
+    now: time::OffsetDateTime,
+    csrf_token: &'a CsrfToken,
+}
+
+impl<'a> TokensTemplate<'a> {
+    pub fn new(
+        flash: Option<&'a FlashMessage>,
+        tokens: &'a [ListUserApiTokens],
+        scopes: &'a [ApiScope],
+        created: Option<&'a str>,
+        now: time::OffsetDateTime,
+        csrf_token: &'a CsrfToken,
+    ) -> Self {
+        TokensTemplate {
+            flash,
+            tokens,
+            scopes,
+            created,
+            now,
+            csrf_token,
+        }
+    }
+
+    fn is_expired(&self, token: &ListUserApiTokens) -> bool {
+        token
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= self.now)
+    }
+}
+
+/// Secret of a TOTP enrollment, in the forms authenticator apps accept.
+pub struct TotpSetup {
+    pub uri: String,
+    /// Base32, to be typed in by hand.
+    pub secret: String,
+    pub qr_code_svg: String,
+}
+
+/// `/admin/2fa` page: 2FA status, or the setup step, and recovery codes
+/// right after they were generated.
+#[derive(Template)]
+#[template(path = "admin_2fa.html")]
+pub struct TwoFactorTemplate<'a> {
+    flash: Option<&'a FlashMessage>,
+    enabled: bool,
+    unused_codes: i64,
+    setup: Option<&'a TotpSetup>,
+    recovery_codes: &'a [&'a str],
+    csrf_token: &'a CsrfToken,
+}
+
+impl<'a> TwoFactorTemplate<'a> {
+    pub fn new(
+        flash: Option<&'a FlashMessage>,
+        enabled: bool,
+        unused_codes: i64,
+        setup: Option<&'a TotpSetup>,
+        recovery_codes: &'a [&'a str],
+        csrf_token: &'a CsrfToken,
+    ) -> Self {
+        TwoFactorTemplate {
+            flash,
+            enabled,
+            unused_codes,
+            setup,
+            recovery_codes,
+            csrf_token,
+        }
+    }
+}
+
+/// `/login` page.
+#[derive(Template)]
+#[template(path = "login.html")]
+pub struct LoginTemplate<'a> {
+    flash: Option<&'a FlashMessage>,
+    csrf_token: &'a CsrfToken,
+}
+
+impl<'a> LoginTemplate<'a> {
+    pub fn new(
+        flash: Option<&'a FlashMessage>,
+        csrf_token: &'a CsrfToken,
+    ) -> Self {
+        LoginTemplate { flash, csrf_token }
+    }
+}
+
+/// `/login/2fa` page, the second login step.
+#[derive(Template)]
+#[template(path = "login_2fa.html")]
+pub struct LoginTwoFactorTemplate<'a> {
+    flash: Option<&'a FlashMessage>,
+    csrf_token: &'a CsrfToken,
+}
+
+impl<'a> LoginTwoFactorTemplate<'a> {
+    pub fn new(
+        flash: Option<&'a FlashMessage>,
+        csrf_token: &'a CsrfToken,
+    ) -> Self {
+        LoginTwoFactorTemplate { flash, csrf_token }
+    }
+}
+
+/// `/password/forgot` page: the form, or the note that a link was sent.
+#[derive(Template)]
+#[template(path = "password_forgot.html")]
+pub struct ForgotPasswordTemplate<'a> {
+    csrf_token: &'a str,
+    sent: bool,
+}
+
+impl<'a> ForgotPasswordTemplate<'a> {
+    pub fn form(csrf_token: &'a CsrfToken) -> Self {
+        ForgotPasswordTemplate {
+            csrf_token: csrf_token.as_ref(),
+            sent: false,
+        }
+    }
+
+    pub fn sent() -> Self {
+        ForgotPasswordTemplate {
+            csrf_token: "",
+            sent: true,
+        }
+    }
+}
+
+/// Page behind the password reset link: the form for a new password,
+/// or the result of the reset.
+#[derive(Template)]
+#[template(path = "password_reset.html")]
+pub struct ResetPasswordTemplate<'a> {
+    token: &'a str,
+    csrf_token: &'a str,
+    error: Option<&'a str>,
+    done: bool,
+}
+
+impl<'a> ResetPasswordTemplate<'a> {
+    pub fn form(
+        token: &'a str,
+        csrf_token: &'a CsrfToken,
+        error: Option<&'a str>,
+    ) -> Self {
+        ResetPasswordTemplate {
+            token,
+            csrf_token: csrf_token.as_ref(),
+            error,
+            done: false,
+        }
+    }
+
+    pub fn done() -> Self {
+        ResetPasswordTemplate {
+            token: "",
+            csrf_token: "",
+            error: None,
+            done: true,
+        }
+    }
+}
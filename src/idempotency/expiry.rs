@@ -0,0 +1,53 @@
+//! src/idempotency/expiry.rs
+
+use anyhow::Context;
+use deadpool_postgres::Pool;
+use time::OffsetDateTime;
+use tokio::sync::watch;
+
+use crate::configuration::IdempotencySettings;
+use crate::cornucopia::queries::idempotency;
+
+/// Deletes expired idempotency keys once in `cleanup_interval`, until
+/// `stop` turns true.
+pub async fn run_expiry_until_stopped(
+    pool: Pool,
+    settings: IdempotencySettings,
+    mut stop: watch::Receiver<bool>,
+) {
+    while !*stop.borrow() {
+        if let Err(e) = delete_expired_idempotency_keys(&pool, &settings).await
+        {
+            tracing::error!(
+                "Failed to delete expired idempotency keys: {:?}",
+                e
+            );
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(settings.cleanup_interval()) => {}
+            _ = stop.wait_for(|stop| *stop) => {}
+        }
+    }
+}
+
+/// Deletes saved responses older than `ttl`. Returns the number of deleted
+/// rows.
+#[tracing::instrument(skip_all, err)]
+pub async fn delete_expired_idempotency_keys(
+    pool: &Pool,
+    settings: &IdempotencySettings,
+) -> Result<u64, anyhow::Error> {
+    let client = pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let expired_before = OffsetDateTime::now_utc() - settings.ttl();
+    let deleted = idempotency::delete_expired_idempotency_keys()
+        .bind(&client, &expired_before)
+        .await
+        .context("Failed to delete expired idempotency keys")?;
+    if deleted > 0 {
+        tracing::info!("Deleted {deleted} expired idempotency keys");
+    }
+    Ok(deleted)
+}
@@ -0,0 +1,54 @@
+/// Client-provided key, which identifies retries of the same request.
+#[derive(Debug)]
+pub struct IdempotencyKey(String);
+
+impl IdempotencyKey {
+    pub fn parse(key: &str) -> Result<IdempotencyKey, &'static str> {
+        let is_empty_or_whitespace = key.trim().is_empty();
+        // We don't want to store unbounded input in our database.
+        let is_too_long = key.chars().count() > 50;
+
+        if is_empty_or_whitespace {
+            Err("The idempotency key cannot be empty")
+        } else if is_too_long {
+            Err("The idempotency key must be at most 50 characters long")
+        } else {
+            Ok(IdempotencyKey(key.to_string()))
+        }
+    }
+}
+
+impl AsRef<str> for IdempotencyKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use super::IdempotencyKey;
+
+    #[test]
+    fn a_50_char_long_key_is_valid() {
+        let key = "a".repeat(50);
+        assert!(IdempotencyKey::parse(&key).is_ok());
+    }
+
+    #[test]
+    fn a_key_longer_than_50_chars_is_rejected() {
+        let key = "a".repeat(51);
+        assert!(IdempotencyKey::parse(&key).is_err());
+    }
+
+    #[test]
+    fn whitespace_only_keys_are_rejected() {
+        assert!(IdempotencyKey::parse("  ").is_err());
+    }
+
+    #[test]
+    fn emtpy_key_is_rejected() {
+        assert!(IdempotencyKey::parse("").is_err());
+    }
+}
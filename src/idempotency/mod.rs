@@ -0,0 +1,7 @@
+pub use expiry::{delete_expired_idempotency_keys, run_expiry_until_stopped};
+pub use key::IdempotencyKey;
+pub use persistence::{save_response, try_processing, NextAction};
+
+mod expiry;
+mod key;
+mod persistence;
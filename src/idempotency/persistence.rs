@@ -0,0 +1,138 @@
+//! src/idempotency/persistence.rs
+
+use anyhow::Context;
+use axum::body::Body;
+use axum::response::{IntoResponse, Response};
+use deadpool_postgres::Transaction;
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use super::IdempotencyKey;
+use crate::cornucopia::queries::idempotency;
+
+/// Response headers are stored as a JSON list of these pairs.
+#[derive(Serialize, Deserialize)]
+struct HeaderPairRecord {
+    name: String,
+    value: Vec<u8>,
+}
+
+pub enum NextAction {
+    /// This is the first request with the given key, the caller should
+    /// process it and call `save_response` within the same transaction.
+    StartProcessing,
+    ReturnSavedResponse(Response),
+}
+
+/// Tries to claim the idempotency key for `user_id`.
+///
+/// A concurrent request with the same key blocks on the `INSERT` until the
+/// first transaction is committed, and then gets the saved response, so the
+/// request is never processed twice.
+#[tracing::instrument(name = "Try processing idempotent request", skip_all)]
+pub async fn try_processing(
+    transaction: &Transaction<'_>,
+    idempotency_key: &IdempotencyKey,
+    user_id: Uuid,
+) -> Result<NextAction, anyhow::Error> {
+    let n_inserted_rows = idempotency::insert_idempotency_key()
+        .bind(
+            transaction,
+            &user_id,
+            &idempotency_key.as_ref(),
+            &OffsetDateTime::now_utc(),
+        )
+        .await
+        .context("Failed to store the idempotency key")?;
+
+    if n_inserted_rows > 0 {
+        Ok(NextAction::StartProcessing)
+    } else {
+        let saved_response =
+            get_saved_response(transaction, idempotency_key, user_id)
+                .await?
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "We expected a saved response, we didn't find it"
+                    )
+                })?;
+        Ok(NextAction::ReturnSavedResponse(saved_response))
+    }
+}
+
+#[tracing::instrument(name = "Get saved response", skip_all)]
+async fn get_saved_response(
+    transaction: &Transaction<'_>,
+    idempotency_key: &IdempotencyKey,
+    user_id: Uuid,
+) -> Result<Option<Response>, anyhow::Error> {
+    let saved = idempotency::get_saved_response()
+        .bind(transaction, &user_id, &idempotency_key.as_ref())
+        .opt()
+        .await
+        .context("Failed to fetch a saved response")?;
+
+    let saved = match saved {
+        Some(s) => s,
+        None => return Ok(None),
+    };
+
+    let status_code =
+        StatusCode::from_u16(saved.response_status_code.try_into()?)?;
+    let headers: Vec<HeaderPairRecord> =
+        serde_json::from_str(&saved.response_headers)
+            .context("Failed to deserialize saved response headers")?;
+
+    let mut response = Response::builder().status(status_code);
+    for HeaderPairRecord { name, value } in headers {
+        response = response.header(name, value);
+    }
+    let response = response
+        .body(Body::from(saved.response_body))
+        .context("Failed to build a saved response")?;
+    Ok(Some(response))
+}
+
+/// Stores the response in the database and returns it back, so it can be
+/// sent to the caller.
+#[tracing::instrument(name = "Save response", skip_all)]
+pub async fn save_response(
+    transaction: &Transaction<'_>,
+    idempotency_key: &IdempotencyKey,
+    user_id: Uuid,
+    response: Response,
+) -> Result<Response, anyhow::Error> {
+    let (parts, body) = response.into_parts();
+    let body = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .context("Failed to read response body")?;
+    let status_code = parts.status.as_u16() as i16;
+    let headers = parts
+        .headers
+        .iter()
+        .map(|(name, value)| HeaderPairRecord {
+            name: name.as_str().to_owned(),
+            value: value.as_bytes().to_owned(),
+        })
+        .collect::<Vec<_>>();
+    let headers = serde_json::to_string(&headers)
+        .context("Failed to serialize response headers")?;
+
+    idempotency::save_response()
+        .bind(
+            transaction,
+            &status_code,
+            &headers.as_str(),
+            &body.as_ref(),
+            &user_id,
+            &idempotency_key.as_ref(),
+        )
+        .await
+        .context("Failed to save a response")?;
+
+    // We need `Body` back, as we've consumed it to save.
+    let response = (parts, Body::from(body)).into_response();
+    Ok(response)
+}
@@ -0,0 +1,289 @@
+//! src/issue_delivery_worker.rs
+
+use std::time::Duration;
+
+use anyhow::Context;
+use deadpool_postgres::Pool;
+use time::OffsetDateTime;
+use tokio::sync::watch;
+use tokio_postgres::Transaction;
+use uuid::Uuid;
+
+use crate::cornucopia::queries::newsletters;
+use crate::cornucopia::queries::newsletters::{
+    DequeueTask, GetNewsletterIssue,
+};
+use crate::cornucopia::queries::subscriptions;
+use crate::domain::SubscriberEmail;
+use crate::email_client::{EmailClient, RetryPolicy};
+use crate::metrics::DeliveryOutcome;
+use crate::routes::unsubscribe_link;
+
+/// How many times we try to deliver an issue to a single subscriber
+/// before the task is dropped from the queue.
+const MAX_DELIVERY_ATTEMPTS: i32 = 5;
+
+/// Delay before the first retry, it doubles on every next attempt.
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(30);
+
+pub enum ExecutionOutcome {
+    TaskCompleted,
+    EmptyQueue,
+}
+
+/// Dequeues delivery tasks one by one and sends them through the
+/// `EmailClient`, until `stop` turns true. A task being sent then is
+/// finished first. `base_url` is used to build unsubscribe links.
+pub async fn run_worker_until_stopped(
+    pool: Pool,
+    email_client: EmailClient,
+    base_url: String,
+    mut stop: watch::Receiver<bool>,
+) {
+    while !*stop.borrow() {
+        let pause =
+            match try_execute_task(&pool, &email_client, &base_url).await {
+                Ok(ExecutionOutcome::EmptyQueue) => Duration::from_secs(10),
+                Err(e) => {
+                    tracing::error!("Failed to execute delivery task: {:?}", e);
+                    Duration::from_secs(1)
+                }
+                Ok(ExecutionOutcome::TaskCompleted) => continue,
+            };
+        tokio::select! {
+            _ = tokio::time::sleep(pause) => {}
+            _ = stop.wait_for(|stop| *stop) => {}
+        }
+    }
+    tracing::info!("Delivery worker stopped");
+}
+
+/// Takes a single task from the `issue_delivery_queue`, rows locked by other
+/// workers are skipped. The task is deleted when the email is sent or can't
+/// be sent at all, otherwise it is postponed for a later retry. Tasks of
+/// subscribers who are no longer confirmed, e.g. unsubscribed since the
+/// issue was published, are dropped unsent. Outcomes are counted in the
+/// metrics of the `EmailClient`.
+#[tracing::instrument(
+    skip_all,
+    fields(
+        newsletter_issue_id=tracing::field::Empty,
+        subscriber_email=tracing::field::Empty
+    ),
+    err
+)]
+pub async fn try_execute_task(
+    pool: &Pool,
+    email_client: &EmailClient,
+    base_url: &str,
+) -> Result<ExecutionOutcome, anyhow::Error> {
+    let mut connection = pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let mut transaction = connection
+        .transaction()
+        .await
+        .context("Failed to start transaction on pg connection")?;
+
+    let task = match dequeue_task(&mut transaction).await? {
+        Some(task) => task,
+        None => return Ok(ExecutionOutcome::EmptyQueue),
+    };
+    tracing::Span::current()
+        .record(
+            "newsletter_issue_id",
+            tracing::field::display(&task.newsletter_issue_id),
+        )
+        .record(
+            "subscriber_email",
+            tracing::field::display(&task.subscriber_email),
+        );
+
+    let confirmed =
+        is_confirmed(&mut transaction, &task.subscriber_email).await?;
+    let outcome = if !confirmed {
+        tracing::info!("Skipping a subscriber who is no longer confirmed");
+        delete_task(&mut transaction, &task).await?;
+        DeliveryOutcome::Skipped
+    } else {
+        match SubscriberEmail::parse(&task.subscriber_email) {
+            Ok(email) => {
+                deliver_issue(
+                    &mut transaction,
+                    email_client,
+                    base_url,
+                    &task,
+                    &email,
+                )
+                .await?
+            }
+            Err(e) => {
+                tracing::error!(
+                    "Skipping a confirmed subscriber, \
+                    their stored contact details are invalid: {e}"
+                );
+                delete_task(&mut transaction, &task).await?;
+                DeliveryOutcome::Skipped
+            }
+        }
+    };
+
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit delivery task transaction")?;
+    email_client.metrics().record_newsletter_delivery(outcome);
+    Ok(ExecutionOutcome::TaskCompleted)
+}
+
+/// Sends the issue of `task` to `email`, then deletes or postpones the task
+/// depending on the result.
+async fn deliver_issue<'a>(
+    transaction: &mut Transaction<'a>,
+    email_client: &EmailClient,
+    base_url: &str,
+    task: &DequeueTask,
+    email: &SubscriberEmail,
+) -> Result<DeliveryOutcome, anyhow::Error> {
+    let issue = get_issue(transaction, task.newsletter_issue_id).await?;
+    let list_unsubscribe =
+        get_unsubscribe_token(transaction, &task.subscriber_email)
+            .await?
+            .map(|token| format!("<{}>", unsubscribe_link(base_url, &token)));
+    // RFC 8058 one-click unsubscribe
+    let headers = match list_unsubscribe {
+        Some(ref link) => vec![
+            ("List-Unsubscribe", link.as_str()),
+            ("List-Unsubscribe-Post", "List-Unsubscribe=One-Click"),
+        ],
+        None => Vec::new(),
+    };
+    // A single attempt, the task row stays locked meanwhile. Retries
+    // happen through the queue, see `postpone_task`.
+    let outcome = match email_client
+        .send_email_with_retry_policy(
+            email,
+            &issue.title,
+            &issue.html_content,
+            &issue.text_content,
+            &headers,
+            &RetryPolicy::no_retries(),
+        )
+        .await
+    {
+        Ok(()) => {
+            delete_task(transaction, task).await?;
+            DeliveryOutcome::Sent
+        }
+        Err(e) if !e.is_transient() => {
+            tracing::error!(
+                "Giving up delivering an issue, \
+                it was rejected by the email delivery service: {e}"
+            );
+            delete_task(transaction, task).await?;
+            DeliveryOutcome::Failed
+        }
+        Err(e) if task.n_retries + 1 >= MAX_DELIVERY_ATTEMPTS => {
+            tracing::error!(
+                "Giving up delivering an issue after {} attempts: {e}",
+                task.n_retries + 1
+            );
+            delete_task(transaction, task).await?;
+            DeliveryOutcome::Failed
+        }
+        Err(e) => {
+            tracing::warn!("Failed to deliver an issue, will retry later: {e}");
+            postpone_task(transaction, task, e.retry_after()).await?;
+            DeliveryOutcome::Retried
+        }
+    };
+    Ok(outcome)
+}
+
+#[tracing::instrument(skip_all)]
+async fn dequeue_task<'a>(
+    transaction: &mut Transaction<'a>,
+) -> Result<Option<DequeueTask>, anyhow::Error> {
+    newsletters::dequeue_task()
+        .bind(transaction)
+        .opt()
+        .await
+        .context("Failed to dequeue a delivery task")
+}
+
+#[tracing::instrument(skip(transaction))]
+async fn get_issue<'a>(
+    transaction: &mut Transaction<'a>,
+    newsletter_issue_id: Uuid,
+) -> Result<GetNewsletterIssue, anyhow::Error> {
+    newsletters::get_newsletter_issue()
+        .bind(transaction, &newsletter_issue_id)
+        .one()
+        .await
+        .context("Failed to fetch a newsletter issue")
+}
+
+/// Also false when the subscriber was deleted.
+#[tracing::instrument(skip(transaction))]
+async fn is_confirmed<'a>(
+    transaction: &mut Transaction<'a>,
+    subscriber_email: &str,
+) -> Result<bool, anyhow::Error> {
+    let status = subscriptions::get_status()
+        .bind(transaction, &subscriber_email)
+        .opt()
+        .await
+        .context("Failed to fetch a subscriber status")?;
+    Ok(status.as_deref() == Some("confirmed"))
+}
+
+#[tracing::instrument(skip(transaction))]
+async fn get_unsubscribe_token<'a>(
+    transaction: &mut Transaction<'a>,
+    subscriber_email: &str,
+) -> Result<Option<String>, anyhow::Error> {
+    subscriptions::get_unsubscribe_token()
+        .bind(transaction, &subscriber_email)
+        .opt()
+        .await
+        .context("Failed to fetch an unsubscribe token")
+}
+
+#[tracing::instrument(skip_all)]
+async fn delete_task<'a>(
+    transaction: &mut Transaction<'a>,
+    task: &DequeueTask,
+) -> Result<(), anyhow::Error> {
+    newsletters::delete_task()
+        .bind(
+            transaction,
+            &task.newsletter_issue_id,
+            &task.subscriber_email.as_str(),
+        )
+        .await
+        .context("Failed to delete a delivery task")?;
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+async fn postpone_task<'a>(
+    transaction: &mut Transaction<'a>,
+    task: &DequeueTask,
+    retry_after: Option<Duration>,
+) -> Result<(), anyhow::Error> {
+    let backoff = RETRY_BASE_DELAY * 2u32.pow(task.n_retries as u32);
+    // Respect the pause requested by the email delivery service.
+    let delay = retry_after.map_or(backoff, |pause| pause.max(backoff));
+    let execute_after = OffsetDateTime::now_utc() + delay;
+    newsletters::postpone_task()
+        .bind(
+            transaction,
+            &execute_after,
+            &task.newsletter_issue_id,
+            &task.subscriber_email.as_str(),
+        )
+        .await
+        .context("Failed to postpone a delivery task")?;
+    Ok(())
+}
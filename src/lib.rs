@@ -1,14 +1,32 @@
 mod cornucopia;
 mod routes;
 
+pub mod api_tokens;
+pub mod audit;
 pub mod authentication;
+pub mod client_ip;
+pub mod clock;
 pub mod configuration;
 pub mod connection_pool;
+pub mod csrf;
 pub mod domain;
 pub mod email_client;
+pub mod flash;
 pub mod html_template_gen;
+pub mod idempotency;
+pub mod issue_delivery_worker;
+pub mod login_lockout;
+pub mod management;
+pub mod metrics;
+pub mod pagination;
+pub mod pending_subscribers_cleanup;
+pub mod rate_limit;
+pub mod request_id;
+pub mod security_headers;
+pub mod session;
 pub mod startup;
 pub mod telemetry;
+pub mod two_factor;
 pub mod validation;
 
 pub fn error_chain_fmt(
@@ -23,3 +41,29 @@ pub fn error_chain_fmt(
     }
     Ok(())
 }
+
+/// Compare secrets in constant time, so they can't be guessed byte by byte
+/// from response times. Only their length shows.
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use super::constant_time_eq;
+
+    #[test]
+    fn only_identical_strings_are_equal() {
+        assert!(constant_time_eq("s3cret", "s3cret"));
+        assert!(constant_time_eq("", ""));
+        assert!(!constant_time_eq("s3cret", "s3creT"));
+        assert!(!constant_time_eq("s3cret", "s3cret!"));
+        assert!(!constant_time_eq("s3cret", ""));
+    }
+}
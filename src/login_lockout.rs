@@ -0,0 +1,198 @@
+//! src/login_lockout.rs
+
+use anyhow::Context;
+use cornucopia_async::GenericClient;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::audit::{self, RequestOrigin};
+use crate::configuration::LockoutSettings;
+use crate::cornucopia::queries::login_lockout;
+use crate::error_chain_fmt;
+
+/// Audit action recorded when a username or client IP gets locked.
+pub const LOGIN_LOCKED: &str = "login_locked";
+/// Audit action recorded when an expired lockout is lifted.
+pub const LOGIN_UNLOCKED: &str = "login_unlocked";
+
+#[derive(thiserror::Error)]
+pub enum LockoutError {
+    #[error("Too many failed login attempts, please try again later.")]
+    Locked { retry_after: u64 },
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for LockoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+/// Username and client IP of one password check. Failures are counted
+/// for both, and either of them can be locked.
+pub struct LoginAttempt<'a> {
+    pub username: &'a str,
+    pub origin: &'a RequestOrigin,
+}
+
+impl LoginAttempt<'_> {
+    fn subjects(&self) -> [String; 2] {
+        [
+            format!("username:{}", self.username),
+            format!("ip:{}", self.origin.client_ip),
+        ]
+    }
+
+    /// Fails with `Locked` while the username or the client IP is locked.
+    /// Lockouts which ran out are lifted here.
+    #[tracing::instrument(
+        name = "Check login lockout",
+        skip_all,
+        fields(client_ip = %self.origin.client_ip)
+    )]
+    pub async fn ensure_not_locked<C: GenericClient>(
+        &self,
+        client: &C,
+        now: OffsetDateTime,
+    ) -> Result<(), LockoutError> {
+        let mut locked_until = None;
+        for subject in self.subjects() {
+            let Some(until) = login_lockout::get_lockout()
+                .bind(client, &subject.as_str())
+                .opt()
+                .await
+                .context("Failed to fetch a login lockout")?
+            else {
+                continue;
+            };
+            if until > now {
+                locked_until = locked_until.max(Some(until));
+                continue;
+            }
+            let lifted = login_lockout::unlock_expired_subject()
+                .bind(client, &subject.as_str(), &now)
+                .await
+                .context("Failed to lift a login lockout")?;
+            if lifted > 0 {
+                tracing::info!(subject, "Login lockout lifted");
+                audit::record_event(
+                    client,
+                    self.origin,
+                    None,
+                    LOGIN_UNLOCKED,
+                    Some(&subject),
+                )
+                .await?;
+            }
+        }
+        match locked_until {
+            Some(until) => {
+                tracing::warn!("Login attempt while locked");
+                Err(LockoutError::Locked {
+                    retry_after: (until - now).whole_seconds().max(0) as u64
+                        + 1,
+                })
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Counts a wrong password and writes it to the audit log. Locks the
+    /// username or the client IP once its failures within the window
+    /// reach the threshold.
+    #[tracing::instrument(
+        name = "Record login failure",
+        skip_all,
+        fields(client_ip = %self.origin.client_ip)
+    )]
+    pub async fn record_failure<C: GenericClient>(
+        &self,
+        client: &C,
+        settings: &LockoutSettings,
+        now: OffsetDateTime,
+    ) -> Result<(), anyhow::Error> {
+        let since = now - settings.window();
+        let client_ip = self.origin.client_ip.to_string();
+        login_lockout::delete_old_login_failures()
+            .bind(client, &since)
+            .await
+            .context("Failed to delete old login failures")?;
+        login_lockout::insert_login_failure()
+            .bind(
+                client,
+                &Uuid::new_v4(),
+                &self.username,
+                &client_ip.as_str(),
+                &now,
+            )
+            .await
+            .context("Failed to store a login failure")?;
+        audit::record_event(
+            client,
+            self.origin,
+            None,
+            audit::LOGIN_FAILED,
+            Some(self.username),
+        )
+        .await?;
+
+        let username_failures = login_lockout::count_username_failures()
+            .bind(client, &self.username, &since)
+            .one()
+            .await
+            .context("Failed to count login failures of a username")?;
+        let client_ip_failures = login_lockout::count_client_ip_failures()
+            .bind(client, &client_ip.as_str(), &since)
+            .one()
+            .await
+            .context("Failed to count login failures of a client IP")?;
+
+        let [username_subject, client_ip_subject] = self.subjects();
+        let locked_until = now + settings.lockout_duration();
+        for (subject, failures, max_failures) in [
+            (
+                username_subject,
+                username_failures,
+                settings.max_failures_per_username,
+            ),
+            (
+                client_ip_subject,
+                client_ip_failures,
+                settings.max_failures_per_ip,
+            ),
+        ] {
+            if failures < max_failures {
+                continue;
+            }
+            login_lockout::lock_subject()
+                .bind(client, &subject.as_str(), &locked_until)
+                .await
+                .context("Failed to store a login lockout")?;
+            tracing::warn!(subject, failures, "Login locked");
+            audit::record_event(
+                client,
+                self.origin,
+                None,
+                LOGIN_LOCKED,
+                Some(&subject),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// A correct password resets the failures of the username. Failures
+    /// of the client IP keep counting, a single valid account must not
+    /// unlock guessing for everything else.
+    pub async fn record_success<C: GenericClient>(
+        &self,
+        client: &C,
+    ) -> Result<(), anyhow::Error> {
+        login_lockout::delete_username_failures()
+            .bind(client, &self.username)
+            .await
+            .context("Failed to reset login failures of a username")?;
+        Ok(())
+    }
+}
@@ -1,29 +1,30 @@
-use zero2prod_axum::{configuration::Settings, startup::Application};
+use zero2prod_axum::{
+    configuration::Settings,
+    startup::Application,
+    telemetry::{init_telemetry, shutdown_telemetry},
+};
 
 #[tokio::main]
 async fn main() {
-    let subscriber = tracing_subscriber::fmt()
-        .with_timer(tracing_subscriber::fmt::time::ChronoLocal::default())
-        .with_max_level(tracing::Level::INFO)
-        .compact()
-        .with_level(true)
-        .finish();
-
-    tracing::subscriber::set_global_default(subscriber)
-        .expect("Failed to set up tracing");
-
-    let config = if let Ok(_) = std::env::var("RUSTEST") {
+    let config = if std::env::var("RUSTEST").is_ok() {
         Settings::load_configuration().unwrap()
     } else {
         Settings::load_configuration_from_env().unwrap()
     };
 
-    if let Err(e) = Application::build(config)
+    init_telemetry(&config.telemetry).expect("Failed to set up tracing");
+
+    let result = Application::build(config)
         .await
         .expect("Failed to build application")
         .run_until_stopped()
-        .await
-    {
+        .await;
+
+    shutdown_telemetry();
+
+    // A failed drain must show in the exit status too.
+    if let Err(e) = result {
         eprintln!("Error: {}", e);
+        std::process::exit(1);
     }
 }
@@ -0,0 +1,492 @@
+//! src/management.rs
+//!
+//! Operations behind the `zero2prod-admin` binary, which work directly
+//! with the database and don't need the HTTP server.
+
+use anyhow::Context;
+use deadpool_postgres::{Pool, Transaction};
+use secrecy::{ExposeSecret, Secret};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tokio_postgres::error::SqlState;
+use uuid::Uuid;
+
+use crate::api_tokens::CreateApiTokenError;
+use crate::audit::{self, Actor};
+use crate::authentication::PasswordHashing;
+use crate::cornucopia::queries::api_tokens;
+use crate::cornucopia::queries::subscriptions;
+use crate::cornucopia::queries::users;
+use crate::domain::{AdminPassword, ApiScope, SubscriberEmail};
+use crate::domain::{SubscriberName, UserRole};
+use crate::error_chain_fmt;
+use crate::telemetry::spawn_blocking_with_tracing;
+use crate::validation::api_token::ApiToken;
+use crate::validation::subscriber_token::SubscriberToken;
+
+pub use crate::cornucopia::queries::api_tokens::ListApiTokens as ApiTokenInfo;
+pub use crate::cornucopia::queries::users::ListUsers as AdminUser;
+
+/// Values of the `status` column we accept on import.
+const STATUSES: [&str; 3] =
+    ["pending_confirmation", "confirmed", "unsubscribed"];
+
+/// Row of the subscribers CSV file, both for import and export.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct SubscriberRecord {
+    pub email: String,
+    pub name: String,
+    /// `confirmed` if missing on import.
+    #[serde(default)]
+    pub status: Option<String>,
+    /// RFC 3339, the time of import if missing.
+    #[serde(default)]
+    pub subscribed_at: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    pub imported: u64,
+    /// Addresses which are already in the list.
+    pub skipped: u64,
+}
+
+#[derive(thiserror::Error)]
+pub enum CreateUserError {
+    /// Invalid input, the message can be shown to the user as is.
+    #[error("{0}")]
+    Rejected(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for CreateUserError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+#[derive(thiserror::Error)]
+pub enum ChangeUserError {
+    #[error("The last owner can't be deleted or lose their role.")]
+    LastOwner,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for ChangeUserError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+/// Create an admin user, the password is stored as Argon2id PHC string
+/// with the configured costs, as `validate_credentials` expects.
+pub async fn create_user(
+    pool: &Pool,
+    hashing: &PasswordHashing,
+    username: &str,
+    password: Secret<String>,
+    email: Option<&str>,
+    role: UserRole,
+    actor: Actor<'_>,
+) -> Result<Uuid, CreateUserError> {
+    let username = username.trim();
+    if username.is_empty() || username.chars().count() > 64 {
+        return Err(CreateUserError::Rejected(
+            "Username must be from 1 to 64 characters long.".into(),
+        ));
+    }
+    let email = email
+        .map(|e| SubscriberEmail::parse(e.trim()))
+        .transpose()
+        .map_err(|e| CreateUserError::Rejected(e.into()))?;
+    let password =
+        AdminPassword::parse(password).map_err(CreateUserError::Rejected)?;
+    let hashing = hashing.clone();
+    let password_hash = spawn_blocking_with_tracing(move || {
+        hashing.hash(password.into_secret())
+    })
+    .await
+    .context("Failed to spawn blocking task")?
+    .context("Failed to hash password")?;
+
+    let mut client = pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let transaction = client
+        .transaction()
+        .await
+        .context("Failed to start transaction on pg connection")?;
+    let user_id = Uuid::new_v4();
+    let inserted = users::insert_user()
+        .bind(
+            &transaction,
+            &user_id,
+            &username,
+            &password_hash.expose_secret().as_str(),
+            &email.as_ref().map(|e| e.as_ref()),
+            &role.as_str(),
+        )
+        .await;
+    match inserted {
+        Ok(_) => {}
+        Err(e) if e.code() == Some(&SqlState::UNIQUE_VIOLATION) => {
+            return Err(CreateUserError::Rejected(
+                "This username or email is already taken.".into(),
+            ));
+        }
+        Err(e) => {
+            return Err(anyhow::Error::new(e)
+                .context("Failed to store a new user")
+                .into())
+        }
+    }
+    actor
+        .record_event(
+            &transaction,
+            audit::USER_CREATED,
+            Some(&format!("{} {}", username, role)),
+        )
+        .await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit a new user")?;
+    Ok(user_id)
+}
+
+pub async fn list_users(pool: &Pool) -> Result<Vec<AdminUser>, anyhow::Error> {
+    let client = pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    users::list_users()
+        .bind(&client)
+        .all()
+        .await
+        .context("Failed to fetch users")
+}
+
+/// Returns `false` if there is no such user. The last owner is never
+/// deleted, see `ensure_not_last_owner`. Recorded in the audit log as
+/// done by `zero2prod-admin`, like the API token changes below.
+pub async fn delete_user(
+    pool: &Pool,
+    username: &str,
+) -> Result<bool, ChangeUserError> {
+    let mut client = pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let transaction = client
+        .transaction()
+        .await
+        .context("Failed to start transaction on pg connection")?;
+    let Some(user) = users::get_user_by_username()
+        .bind(&transaction, &username)
+        .opt()
+        .await
+        .context("Failed to fetch a user")?
+    else {
+        return Ok(false);
+    };
+    ensure_not_last_owner(&transaction, user.user_id).await?;
+    users::delete_user_by_id()
+        .bind(&transaction, &user.user_id)
+        .opt()
+        .await
+        .context("Failed to delete a user")?;
+    Actor::CommandLine
+        .record_event(&transaction, audit::USER_DELETED, Some(username))
+        .await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit a user deletion")?;
+    Ok(true)
+}
+
+/// Call before deleting `user_id` or taking the owner role from them.
+/// Two owners could still demote or delete each other at the same time,
+/// so the owners stay locked until the transaction ends and the second
+/// change sees the first one.
+pub async fn ensure_not_last_owner(
+    transaction: &Transaction<'_>,
+    user_id: Uuid,
+) -> Result<(), ChangeUserError> {
+    let owners = users::lock_owners()
+        .bind(transaction)
+        .all()
+        .await
+        .context("Failed to lock the owners")?;
+    if owners == [user_id] {
+        return Err(ChangeUserError::LastOwner);
+    }
+    Ok(())
+}
+
+/// Create an API token for the user named `username`. The token is only
+/// returned here, the database keeps its hash.
+pub async fn create_api_token(
+    pool: &Pool,
+    username: &str,
+    name: &str,
+    scopes: &[ApiScope],
+    expires_in_days: Option<i64>,
+) -> Result<(Uuid, ApiToken), CreateApiTokenError> {
+    let mut client = pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let transaction = client
+        .transaction()
+        .await
+        .context("Failed to start transaction on pg connection")?;
+    let user = users::get_user_by_username()
+        .bind(&transaction, &username)
+        .opt()
+        .await
+        .context("Failed to fetch a user")?
+        .ok_or_else(|| {
+            CreateApiTokenError::Rejected(format!("No user named {}", username))
+        })?;
+    let role = UserRole::parse(&user.role).map_err(anyhow::Error::msg)?;
+    let expires_at = expires_in_days
+        .map(|days| OffsetDateTime::now_utc() + time::Duration::days(days));
+    let (token_id, token) = crate::api_tokens::create_api_token(
+        &transaction,
+        user.user_id,
+        role,
+        name,
+        scopes,
+        expires_at,
+    )
+    .await?;
+    Actor::CommandLine
+        .record_event(
+            &transaction,
+            audit::API_TOKEN_CREATED,
+            Some(&token_id.to_string()),
+        )
+        .await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit a new API token")?;
+    Ok((token_id, token))
+}
+
+/// Tokens of every user, revoked and expired ones included.
+pub async fn list_api_tokens(
+    pool: &Pool,
+) -> Result<Vec<ApiTokenInfo>, anyhow::Error> {
+    let client = pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    api_tokens::list_api_tokens()
+        .bind(&client)
+        .all()
+        .await
+        .context("Failed to fetch API tokens")
+}
+
+/// Returns `false` if there is no such token or it was already revoked.
+pub async fn revoke_api_token(
+    pool: &Pool,
+    token_id: Uuid,
+) -> Result<bool, anyhow::Error> {
+    let mut client = pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let transaction = client
+        .transaction()
+        .await
+        .context("Failed to start transaction on pg connection")?;
+    let revoked = api_tokens::revoke_api_token()
+        .bind(&transaction, &token_id)
+        .await
+        .context("Failed to revoke an API token")?;
+    if revoked > 0 {
+        Actor::CommandLine
+            .record_event(
+                &transaction,
+                audit::API_TOKEN_REVOKED,
+                Some(&token_id.to_string()),
+            )
+            .await?;
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit an API token revocation")?;
+    Ok(revoked > 0)
+}
+
+/// Import subscribers from CSV with `email,name[,status,subscribed_at]`
+/// header. Every row is validated before anything is written, the import
+/// is a single transaction. Known addresses are skipped.
+pub async fn import_subscribers<R: std::io::Read>(
+    pool: &Pool,
+    reader: R,
+) -> Result<ImportReport, anyhow::Error> {
+    let mut records = Vec::new();
+    for (i, record) in csv::Reader::from_reader(reader)
+        .deserialize::<SubscriberRecord>()
+        .enumerate()
+    {
+        // Line 1 is the header.
+        let line = i + 2;
+        let record =
+            record.with_context(|| format!("Malformed CSV at line {line}"))?;
+        records.push(
+            validate_record(record).with_context(|| {
+                format!("Invalid subscriber at line {line}")
+            })?,
+        );
+    }
+
+    let mut client = pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let transaction = client
+        .transaction()
+        .await
+        .context("Failed to start transaction on pg connection")?;
+    let mut report = ImportReport::default();
+    for (email, name, status, subscribed_at) in records {
+        let id = Uuid::new_v4();
+        let inserted = subscriptions::import_subscriber()
+            .bind(
+                &transaction,
+                &id,
+                &email.as_ref(),
+                &name.as_ref(),
+                &subscribed_at,
+                &status.as_str(),
+            )
+            .await
+            .context("Failed to insert a subscriber")?;
+        if inserted == 0 {
+            report.skipped += 1;
+            continue;
+        }
+        subscriptions::insert_unsubscribe_token()
+            .bind(&transaction, &SubscriberToken::generate().as_ref(), &id)
+            .await
+            .context("Failed to store unsubscribe token")?;
+        // Without a confirmation token they could not ask for the email
+        // again, and the cleanup task would delete them right away.
+        if status == "pending_confirmation" {
+            subscriptions::insert_new_token()
+                .bind(&transaction, &SubscriberToken::generate().as_ref(), &id)
+                .await
+                .context("Failed to store confirmation token")?;
+        }
+        report.imported += 1;
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit import transaction")?;
+    Ok(report)
+}
+
+/// Export every subscriber as CSV, in the format `import_subscribers`
+/// accepts. Returns the number of exported rows.
+pub async fn export_subscribers<W: std::io::Write>(
+    pool: &Pool,
+    writer: W,
+) -> Result<u64, anyhow::Error> {
+    let client = pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let subscribers = subscriptions::export_subscribers()
+        .bind(&client)
+        .all()
+        .await
+        .context("Failed to fetch subscribers")?;
+
+    let mut writer = csv::Writer::from_writer(writer);
+    for subscriber in &subscribers {
+        writer
+            .serialize(SubscriberRecord {
+                email: subscriber.email.clone(),
+                name: subscriber.name.clone(),
+                status: Some(subscriber.status.clone()),
+                subscribed_at: Some(
+                    subscriber
+                        .subscribed_at
+                        .format(&Rfc3339)
+                        .context("Failed to format subscription date")?,
+                ),
+            })
+            .context("Failed to write CSV row")?;
+    }
+    writer.flush().context("Failed to flush CSV writer")?;
+    Ok(subscribers.len() as u64)
+}
+
+fn validate_record(
+    record: SubscriberRecord,
+) -> Result<
+    (SubscriberEmail, SubscriberName, String, OffsetDateTime),
+    anyhow::Error,
+> {
+    let email = SubscriberEmail::parse(record.email.trim())
+        .map_err(anyhow::Error::msg)?;
+    let name = SubscriberName::parse(record.name.trim())
+        .map_err(anyhow::Error::msg)?;
+    let status = match record.status.filter(|s| !s.is_empty()) {
+        Some(status) if STATUSES.contains(&status.as_str()) => status,
+        Some(status) => anyhow::bail!("Unknown subscriber status: {status}"),
+        None => "confirmed".to_string(),
+    };
+    let subscribed_at = match record.subscribed_at.filter(|s| !s.is_empty()) {
+        Some(date) => OffsetDateTime::parse(&date, &Rfc3339)
+            .with_context(|| format!("Invalid subscription date: {date}"))?,
+        None => OffsetDateTime::now_utc(),
+    };
+    Ok((email, name, status, subscribed_at))
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use super::{validate_record, SubscriberRecord};
+
+    fn record(status: Option<&str>, date: Option<&str>) -> SubscriberRecord {
+        SubscriberRecord {
+            email: "ursula@example.com".into(),
+            name: "Ursula".into(),
+            status: status.map(Into::into),
+            subscribed_at: date.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn missing_status_defaults_to_confirmed() {
+        let (_, _, status, _) = validate_record(record(None, None)).unwrap();
+        assert_eq!(status, "confirmed");
+    }
+
+    #[test]
+    fn unknown_status_is_rejected() {
+        assert!(validate_record(record(Some("bogus"), None)).is_err());
+    }
+
+    #[test]
+    fn subscription_date_is_parsed_as_rfc3339() {
+        let date = "2023-12-01T10:00:00Z";
+        let (_, _, _, subscribed_at) =
+            validate_record(record(None, Some(date))).unwrap();
+        assert_eq!(subscribed_at.unix_timestamp(), 1701424800);
+        assert!(validate_record(record(None, Some("yesterday"))).is_err());
+    }
+}
@@ -0,0 +1,429 @@
+//! src/metrics.rs
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{MatchedPath, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+
+/// Upper bounds of the latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// Label of the requests no route matched, so random paths don't make
+/// new series.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Methods with their own label, any other is counted as `OTHER_METHOD`
+/// for the same reason.
+const STANDARD_METHODS: [&str; 9] = [
+    "GET", "HEAD", "POST", "PUT", "DELETE", "CONNECT", "OPTIONS", "TRACE",
+    "PATCH",
+];
+const OTHER_METHOD: &str = "other";
+
+/// What happened to a single newsletter delivery task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DeliveryOutcome {
+    Sent,
+    /// Postponed after a transient failure.
+    Retried,
+    /// Dropped after a permanent failure or too many attempts.
+    Failed,
+    /// Dropped because the stored email is invalid or the subscriber is no
+    /// longer confirmed.
+    Skipped,
+}
+
+impl DeliveryOutcome {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryOutcome::Sent => "sent",
+            DeliveryOutcome::Retried => "retried",
+            DeliveryOutcome::Failed => "failed",
+            DeliveryOutcome::Skipped => "skipped",
+        }
+    }
+}
+
+/// State of the connection pool and the delivery queue, read at scrape
+/// time.
+#[derive(Debug, Clone, Copy)]
+pub struct Gauges {
+    /// From `Pool::status`.
+    pub pool_max_size: usize,
+    pub pool_size: usize,
+    pub pool_available: usize,
+    pub pool_waiting: usize,
+    /// `None` if the queue couldn't be counted.
+    pub pending_deliveries: Option<i64>,
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    /// Observations per bucket, not cumulative.
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        if let Some(i) = LATENCY_BUCKETS.iter().position(|le| seconds <= *le) {
+            self.buckets[i] += 1;
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let mut cumulative = 0;
+        for (le, count) in LATENCY_BUCKETS.iter().zip(self.buckets) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "{name}_bucket{{{labels},le=\"{le}\"}} {cumulative}"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{name}_bucket{{{labels},le=\"+Inf\"}} {}\n\
+            {name}_sum{{{labels}}} {}\n\
+            {name}_count{{{labels}}} {}",
+            self.count, self.sum, self.count
+        );
+    }
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    /// By method, route and status.
+    http_requests: BTreeMap<(String, String, u16), Histogram>,
+    /// By delivery service.
+    email_attempts: BTreeMap<&'static str, Histogram>,
+    email_failures: BTreeMap<&'static str, u64>,
+    newsletter_deliveries: BTreeMap<DeliveryOutcome, u64>,
+}
+
+/// Counters and histograms of the application, shown by `GET /metrics` in
+/// the Prometheus text format. Clones share the same registry.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics(Arc<Mutex<Registry>>);
+
+impl Metrics {
+    pub fn record_http_request(
+        &self,
+        method: &str,
+        route: &str,
+        status: u16,
+        duration: Duration,
+    ) {
+        let method = if STANDARD_METHODS.contains(&method) {
+            method
+        } else {
+            OTHER_METHOD
+        };
+        self.0
+            .lock()
+            .unwrap()
+            .http_requests
+            .entry((method.to_string(), route.to_string(), status))
+            .or_default()
+            .observe(duration);
+    }
+
+    /// A single attempt of `EmailClient` to hand an email over to the
+    /// delivery service.
+    pub fn record_email_attempt(
+        &self,
+        service: &'static str,
+        duration: Duration,
+        succeeded: bool,
+    ) {
+        let mut registry = self.0.lock().unwrap();
+        registry
+            .email_attempts
+            .entry(service)
+            .or_default()
+            .observe(duration);
+        let failures = registry.email_failures.entry(service).or_default();
+        if !succeeded {
+            *failures += 1;
+        }
+    }
+
+    pub fn record_newsletter_delivery(&self, outcome: DeliveryOutcome) {
+        *self
+            .0
+            .lock()
+            .unwrap()
+            .newsletter_deliveries
+            .entry(outcome)
+            .or_default() += 1;
+    }
+
+    pub fn render(&self, gauges: &Gauges) -> String {
+        let registry = self.0.lock().unwrap();
+        let mut out = String::new();
+
+        out.push_str(
+            "# HELP http_requests_total HTTP requests by route and status.\n\
+            # TYPE http_requests_total counter\n",
+        );
+        for ((method, route, status), histogram) in &registry.http_requests {
+            let _ = writeln!(
+                out,
+                "http_requests_total{{{}}} {}",
+                http_labels(method, route, *status),
+                histogram.count
+            );
+        }
+        out.push_str(
+            "# HELP http_request_duration_seconds Time to respond to HTTP \
+            requests.\n\
+            # TYPE http_request_duration_seconds histogram\n",
+        );
+        for ((method, route, status), histogram) in &registry.http_requests {
+            histogram.render(
+                &mut out,
+                "http_request_duration_seconds",
+                &http_labels(method, route, *status),
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP db_pool_max_size Maximum connections of the pool.\n\
+            # TYPE db_pool_max_size gauge\n\
+            db_pool_max_size {}\n\
+            # HELP db_pool_size Open connections of the pool.\n\
+            # TYPE db_pool_size gauge\n\
+            db_pool_size {}\n\
+            # HELP db_pool_available Idle connections of the pool.\n\
+            # TYPE db_pool_available gauge\n\
+            db_pool_available {}\n\
+            # HELP db_pool_waiting Tasks waiting for a connection.\n\
+            # TYPE db_pool_waiting gauge\n\
+            db_pool_waiting {}",
+            gauges.pool_max_size,
+            gauges.pool_size,
+            gauges.pool_available,
+            gauges.pool_waiting
+        );
+
+        out.push_str(
+            "# HELP email_send_attempts_total Attempts to hand an email over \
+            to the delivery service.\n\
+            # TYPE email_send_attempts_total counter\n",
+        );
+        for (service, histogram) in &registry.email_attempts {
+            let _ = writeln!(
+                out,
+                "email_send_attempts_total{{service=\"{}\"}} {}",
+                escape(service),
+                histogram.count
+            );
+        }
+        out.push_str(
+            "# HELP email_send_failures_total Failed attempts to hand an \
+            email over to the delivery service.\n\
+            # TYPE email_send_failures_total counter\n",
+        );
+        for (service, failures) in &registry.email_failures {
+            let _ = writeln!(
+                out,
+                "email_send_failures_total{{service=\"{}\"}} {}",
+                escape(service),
+                failures
+            );
+        }
+        out.push_str(
+            "# HELP email_send_duration_seconds Time of a single attempt to \
+            hand an email over to the delivery service.\n\
+            # TYPE email_send_duration_seconds histogram\n",
+        );
+        for (service, histogram) in &registry.email_attempts {
+            histogram.render(
+                &mut out,
+                "email_send_duration_seconds",
+                &format!("service=\"{}\"", escape(service)),
+            );
+        }
+
+        out.push_str(
+            "# HELP newsletter_deliveries_total Newsletter delivery tasks by \
+            outcome.\n\
+            # TYPE newsletter_deliveries_total counter\n",
+        );
+        for (outcome, count) in &registry.newsletter_deliveries {
+            let _ = writeln!(
+                out,
+                "newsletter_deliveries_total{{outcome=\"{}\"}} {}",
+                outcome.as_str(),
+                count
+            );
+        }
+        if let Some(pending) = gauges.pending_deliveries {
+            let _ = writeln!(
+                out,
+                "# HELP newsletter_deliveries_pending Newsletter delivery \
+                tasks still in the queue.\n\
+                # TYPE newsletter_deliveries_pending gauge\n\
+                newsletter_deliveries_pending {}",
+                pending
+            );
+        }
+        out
+    }
+}
+
+fn http_labels(method: &str, route: &str, status: u16) -> String {
+    format!(
+        "method=\"{}\",route=\"{}\",status=\"{}\"",
+        escape(method),
+        escape(route),
+        status
+    )
+}
+
+/// Label values are quoted, so quotes, backslashes and line breaks in
+/// them are escaped.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Counts requests and their latency by matched route, not by path, so
+/// path parameters don't make new series.
+pub async fn track_http_metrics(
+    State(metrics): State<Metrics>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(UNMATCHED_ROUTE, MatchedPath::as_str)
+        .to_string();
+    let method = request.method().clone();
+    let start = Instant::now();
+    let response = next.run(request).await;
+    metrics.record_http_request(
+        method.as_str(),
+        &route,
+        response.status().as_u16(),
+        start.elapsed(),
+    );
+    response
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{DeliveryOutcome, Gauges, Metrics};
+
+    fn gauges() -> Gauges {
+        Gauges {
+            pool_max_size: 16,
+            pool_size: 3,
+            pool_available: 2,
+            pool_waiting: 0,
+            pending_deliveries: Some(7),
+        }
+    }
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let metrics = Metrics::default();
+        for millis in [3, 20, 20, 40_000] {
+            metrics.record_http_request(
+                "GET",
+                "/health_check",
+                200,
+                Duration::from_millis(millis),
+            );
+        }
+        let out = metrics.render(&gauges());
+        let labels = r#"method="GET",route="/health_check",status="200""#;
+        for expected in [
+            format!("http_requests_total{{{labels}}} 4"),
+            format!("http_request_duration_seconds_bucket{{{labels},le=\"0.005\"}} 1"),
+            format!("http_request_duration_seconds_bucket{{{labels},le=\"0.025\"}} 3"),
+            format!("http_request_duration_seconds_bucket{{{labels},le=\"30\"}} 3"),
+            format!("http_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} 4"),
+            format!("http_request_duration_seconds_count{{{labels}}} 4"),
+        ] {
+            assert!(out.lines().any(|l| l == expected), "{expected}\n{out}");
+        }
+    }
+
+    #[test]
+    fn email_attempts_and_failures_are_counted_per_service() {
+        let metrics = Metrics::default();
+        metrics.record_email_attempt("postmark", Duration::ZERO, false);
+        metrics.record_email_attempt("postmark", Duration::ZERO, true);
+        metrics.record_email_attempt("smtp", Duration::ZERO, true);
+        let out = metrics.render(&gauges());
+        for expected in [
+            r#"email_send_attempts_total{service="postmark"} 2"#,
+            r#"email_send_failures_total{service="postmark"} 1"#,
+            r#"email_send_attempts_total{service="smtp"} 1"#,
+            r#"email_send_failures_total{service="smtp"} 0"#,
+            r#"email_send_duration_seconds_count{service="smtp"} 1"#,
+        ] {
+            assert!(out.lines().any(|l| l == expected), "{expected}\n{out}");
+        }
+    }
+
+    #[test]
+    fn gauges_and_deliveries_are_rendered() {
+        let metrics = Metrics::default();
+        metrics.record_newsletter_delivery(DeliveryOutcome::Sent);
+        metrics.record_newsletter_delivery(DeliveryOutcome::Sent);
+        metrics.record_newsletter_delivery(DeliveryOutcome::Retried);
+        let out = metrics.render(&gauges());
+        for expected in [
+            "db_pool_max_size 16",
+            "db_pool_size 3",
+            "db_pool_available 2",
+            "db_pool_waiting 0",
+            r#"newsletter_deliveries_total{outcome="sent"} 2"#,
+            r#"newsletter_deliveries_total{outcome="retried"} 1"#,
+            "newsletter_deliveries_pending 7",
+        ] {
+            assert!(out.lines().any(|l| l == expected), "{expected}\n{out}");
+        }
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        let metrics = Metrics::default();
+        metrics.record_http_request("GET", "/a\"b\\c", 404, Duration::ZERO);
+        let out = metrics.render(&gauges());
+        assert!(out.contains(r#"route="/a\"b\\c""#));
+    }
+
+    #[test]
+    fn unknown_methods_share_one_label() {
+        let metrics = Metrics::default();
+        for method in ["PROPFIND", "X-RANDOM-1", "X-RANDOM-2"] {
+            metrics.record_http_request(method, "/", 405, Duration::ZERO);
+        }
+        metrics.record_http_request("PATCH", "/", 405, Duration::ZERO);
+        let out = metrics.render(&gauges());
+        assert!(out.contains(r#"method="other",route="/",status="405""#));
+        assert!(out.contains(r#"method="PATCH""#));
+        assert!(!out.contains("PROPFIND"));
+        assert!(!out.contains("X-RANDOM"));
+    }
+}
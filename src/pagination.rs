@@ -0,0 +1,98 @@
+//! src/pagination.rs
+
+use std::fmt;
+use std::str::FromStr;
+
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+/// Keyset position of the last row of a page, in lists sorted newest
+/// first by a timestamp and then by id. It keeps both values, so the next
+/// page doesn't need that row to still exist. Written as
+/// `<unix microseconds>_<id>`, Postgres keeps no finer timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub at: OffsetDateTime,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn new(at: OffsetDateTime, id: Uuid) -> Cursor {
+        Cursor { at, id }
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let micros = self.at.unix_timestamp_nanos().div_euclid(1000);
+        write!(f, "{}_{}", micros, self.id)
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Cursor, Self::Err> {
+        const INVALID: &str = "Page cursor is not valid";
+        let (micros, id) = s.split_once('_').ok_or(INVALID)?;
+        let at = micros
+            .parse::<i128>()
+            .ok()
+            .and_then(|micros| micros.checked_mul(1000))
+            .and_then(|nanos| {
+                OffsetDateTime::from_unix_timestamp_nanos(nanos).ok()
+            })
+            .ok_or(INVALID)?;
+        let id = Uuid::parse_str(id).map_err(|_| INVALID)?;
+        Ok(Cursor { at, id })
+    }
+}
+
+impl serde::Serialize for Cursor {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Cursor {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Cursor, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+    use uuid::Uuid;
+
+    use super::Cursor;
+
+    #[test]
+    fn cursor_survives_a_round_trip() {
+        let at = OffsetDateTime::from_unix_timestamp_nanos(
+            1_700_000_000_123_456_000,
+        )
+        .unwrap();
+        let cursor = Cursor::new(at, Uuid::new_v4());
+        let written = cursor.to_string();
+        assert!(written.starts_with("1700000000123456_"));
+        assert_eq!(written.parse::<Cursor>(), Ok(cursor));
+    }
+
+    #[test]
+    fn malformed_cursors_are_rejected() {
+        let id = Uuid::new_v4();
+        assert!(id.to_string().parse::<Cursor>().is_err());
+        assert!(format!("now_{}", id).parse::<Cursor>().is_err());
+        assert!("1700000000123456_bogus".parse::<Cursor>().is_err());
+        assert!(format!("{}_{}", i128::MAX, id).parse::<Cursor>().is_err());
+    }
+}
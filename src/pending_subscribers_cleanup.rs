@@ -0,0 +1,53 @@
+//! src/pending_subscribers_cleanup.rs
+
+use anyhow::Context;
+use deadpool_postgres::Pool;
+use time::OffsetDateTime;
+use tokio::sync::watch;
+
+use crate::configuration::SubscriptionSettings;
+use crate::cornucopia::queries::subscriptions;
+
+/// Deletes pending subscribers who never confirmed their email once in
+/// `cleanup_interval`, until `stop` turns true.
+pub async fn run_cleanup_until_stopped(
+    pool: Pool,
+    settings: SubscriptionSettings,
+    mut stop: watch::Receiver<bool>,
+) {
+    while !*stop.borrow() {
+        if let Err(e) =
+            delete_expired_pending_subscribers(&pool, &settings).await
+        {
+            tracing::error!("Failed to clean up pending subscribers: {:?}", e);
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(settings.cleanup_interval()) => {}
+            _ = stop.wait_for(|stop| *stop) => {}
+        }
+    }
+}
+
+/// Deletes pending subscribers whose latest confirmation token expired
+/// more than `cleanup_grace_period` ago. Returns the number of deleted rows.
+#[tracing::instrument(skip_all, err)]
+pub async fn delete_expired_pending_subscribers(
+    pool: &Pool,
+    settings: &SubscriptionSettings,
+) -> Result<u64, anyhow::Error> {
+    let client = pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let expired_before = OffsetDateTime::now_utc()
+        - settings.confirmation_token_ttl()
+        - settings.cleanup_grace_period();
+    let deleted = subscriptions::delete_expired_pending_subscribers()
+        .bind(&client, &expired_before)
+        .await
+        .context("Failed to delete expired pending subscribers")?;
+    if deleted > 0 {
+        tracing::info!("Deleted {deleted} expired pending subscribers");
+    }
+    Ok(deleted)
+}
@@ -0,0 +1,404 @@
+//! src/rate_limit.rs
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use axum::response::{IntoResponse, Response};
+use deadpool_postgres::Pool;
+use http::StatusCode;
+use time::{Duration, OffsetDateTime};
+use tokio::sync::watch;
+
+use crate::client_ip::ClientIp;
+use crate::clock::Clock;
+use crate::configuration::{
+    BucketSettings, RateLimitBackend, RateLimitSettings, RouteRateLimit,
+};
+use crate::cornucopia::queries::rate_limit;
+use crate::error_chain_fmt;
+
+/// In memory buckets are pruned once there are more of them than this.
+const MAX_MEMORY_BUCKETS: usize = 10_000;
+
+#[derive(thiserror::Error)]
+pub enum RateLimitError {
+    #[error("Too many requests, please try again later.")]
+    Exceeded { retry_after: u64 },
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for RateLimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl IntoResponse for RateLimitError {
+    fn into_response(self) -> Response {
+        match self {
+            RateLimitError::Exceeded { retry_after } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(http::header::RETRY_AFTER, retry_after.to_string())],
+                self.to_string(),
+            )
+                .into_response(),
+            RateLimitError::UnexpectedError(e) => {
+                tracing::error!("{:?}", e);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+/// Token bucket: it holds up to `capacity` tokens, a request takes one
+/// and one comes back every `refill_interval`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Bucket {
+    tokens: f64,
+    updated_at: OffsetDateTime,
+}
+
+impl Bucket {
+    fn full(settings: &BucketSettings, now: OffsetDateTime) -> Bucket {
+        Bucket {
+            tokens: settings.capacity as f64,
+            updated_at: now,
+        }
+    }
+
+    /// Refill for the time passed and take a token. Without one left, the
+    /// bucket stays as it is and the seconds until the next are returned.
+    fn take(
+        &mut self,
+        settings: &BucketSettings,
+        now: OffsetDateTime,
+    ) -> Result<(), u64> {
+        let interval = settings.refill_interval.max(1) as f64;
+        let elapsed = (now - self.updated_at).as_seconds_f64().max(0.0);
+        let tokens =
+            (self.tokens + elapsed / interval).min(settings.capacity as f64);
+        if tokens < 1.0 {
+            return Err(((1.0 - tokens) * interval).ceil() as u64);
+        }
+        self.tokens = tokens - 1.0;
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// From then on the bucket is the same as a new one.
+    fn full_at(&self, settings: &BucketSettings) -> OffsetDateTime {
+        let missing = settings.capacity as f64 - self.tokens;
+        self.updated_at
+            + Duration::seconds_f64(
+                missing.max(0.0) * settings.refill_interval as f64,
+            )
+    }
+}
+
+#[derive(Clone)]
+enum Backend {
+    /// Buckets with the time they are full again.
+    Memory(Arc<Mutex<HashMap<String, (Bucket, OffsetDateTime)>>>),
+    Postgres(Pool),
+}
+
+/// Limits requests per client IP and per email with token buckets, kept
+/// in memory or, to share them between instances, in Postgres.
+#[derive(Clone)]
+pub struct RateLimiter {
+    backend: Backend,
+}
+
+impl RateLimiter {
+    pub fn new(settings: &RateLimitSettings, pool: &Pool) -> RateLimiter {
+        let backend = match settings.backend {
+            RateLimitBackend::Memory => Backend::Memory(Default::default()),
+            RateLimitBackend::Postgres => Backend::Postgres(pool.clone()),
+        };
+        RateLimiter { backend }
+    }
+
+    /// Takes a token for the client IP and one for the email, or none
+    /// when either bucket is empty. `route` keeps the buckets of
+    /// different endpoints apart.
+    #[tracing::instrument(name = "Check rate limits", skip(self, limits))]
+    pub async fn check(
+        &self,
+        route: &str,
+        limits: &RouteRateLimit,
+        client_ip: ClientIp,
+        email: &str,
+        now: OffsetDateTime,
+    ) -> Result<(), RateLimitError> {
+        let ip_key = format!("{}:ip:{}", route, client_ip);
+        let email_key =
+            format!("{}:email:{}", route, email.trim().to_lowercase());
+        let keys = [
+            (ip_key.as_str(), &limits.per_ip),
+            (email_key.as_str(), &limits.per_email),
+        ];
+        let result = match &self.backend {
+            Backend::Memory(buckets) => take_from_memory(buckets, &keys, now),
+            Backend::Postgres(pool) => {
+                take_from_postgres(pool, &keys, now).await?
+            }
+        };
+        result.map_err(|retry_after| {
+            tracing::warn!(retry_after, "Rate limit exceeded");
+            RateLimitError::Exceeded { retry_after }
+        })
+    }
+
+    /// Forgets the buckets which refilled completely, they are the same
+    /// as missing ones. Returns how many were deleted.
+    #[tracing::instrument(skip_all, err)]
+    pub async fn delete_full_buckets(
+        &self,
+        now: OffsetDateTime,
+    ) -> Result<u64, anyhow::Error> {
+        let deleted = match &self.backend {
+            Backend::Memory(buckets) => {
+                let mut buckets = buckets.lock().unwrap();
+                let before = buckets.len();
+                buckets.retain(|_, (_, full_at)| *full_at > now);
+                (before - buckets.len()) as u64
+            }
+            Backend::Postgres(pool) => {
+                let client = pool
+                    .get()
+                    .await
+                    .context("Failed to get connection from pool")?;
+                rate_limit::delete_full_rate_limit_buckets()
+                    .bind(&client, &now)
+                    .await
+                    .context("Failed to delete full rate limit buckets")?
+            }
+        };
+        if deleted > 0 {
+            tracing::info!("Deleted {deleted} full rate limit buckets");
+        }
+        Ok(deleted)
+    }
+}
+
+/// Deletes full rate limit buckets once in `cleanup_interval`, until
+/// `stop` turns true.
+pub async fn run_bucket_cleanup_until_stopped(
+    rate_limiter: RateLimiter,
+    settings: RateLimitSettings,
+    clock: Clock,
+    mut stop: watch::Receiver<bool>,
+) {
+    while !*stop.borrow() {
+        if let Err(e) = rate_limiter.delete_full_buckets(clock.now()).await {
+            tracing::error!("Failed to clean up rate limit buckets: {:?}", e);
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(settings.cleanup_interval()) => {}
+            _ = stop.wait_for(|stop| *stop) => {}
+        }
+    }
+}
+
+/// Takes a token from every bucket, or from none of them. Without a token
+/// in some bucket the longest wait is returned.
+fn take_all(
+    buckets: &[(Bucket, &BucketSettings)],
+    now: OffsetDateTime,
+) -> Result<Vec<Bucket>, u64> {
+    let mut taken = Vec::with_capacity(buckets.len());
+    let mut retry_after = None;
+    for (bucket, settings) in buckets {
+        let mut bucket = *bucket;
+        match bucket.take(settings, now) {
+            Ok(()) => taken.push(bucket),
+            Err(wait) => retry_after = retry_after.max(Some(wait)),
+        }
+    }
+    match retry_after {
+        Some(retry_after) => Err(retry_after),
+        None => Ok(taken),
+    }
+}
+
+fn take_from_memory(
+    buckets: &Mutex<HashMap<String, (Bucket, OffsetDateTime)>>,
+    keys: &[(&str, &BucketSettings)],
+    now: OffsetDateTime,
+) -> Result<(), u64> {
+    let mut buckets = buckets.lock().unwrap();
+    if buckets.len() >= MAX_MEMORY_BUCKETS {
+        // A bucket which refilled completely is the same as a missing one.
+        buckets.retain(|_, (_, full_at)| *full_at > now);
+    }
+    let current: Vec<_> = keys
+        .iter()
+        .map(|(key, settings)| {
+            let bucket = buckets
+                .get(*key)
+                .map_or_else(|| Bucket::full(settings, now), |(b, _)| *b);
+            (bucket, *settings)
+        })
+        .collect();
+    let taken = take_all(&current, now)?;
+    for ((key, settings), bucket) in keys.iter().zip(taken) {
+        buckets.insert(key.to_string(), (bucket, bucket.full_at(settings)));
+    }
+    Ok(())
+}
+
+/// The bucket rows are locked for the update, so concurrent requests of
+/// all instances take their tokens one after another. They are locked in
+/// the order of their keys, two requests can't wait for each other.
+async fn take_from_postgres(
+    pool: &Pool,
+    keys: &[(&str, &BucketSettings)],
+    now: OffsetDateTime,
+) -> Result<Result<(), u64>, anyhow::Error> {
+    let mut keys = keys.to_vec();
+    keys.sort_by_key(|(key, _)| *key);
+    let mut client = pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let transaction = client
+        .transaction()
+        .await
+        .context("Failed to start transaction on pg connection")?;
+    let mut current = Vec::with_capacity(keys.len());
+    for (key, settings) in &keys {
+        rate_limit::insert_rate_limit_bucket()
+            .bind(&transaction, key, &(settings.capacity as f64), &now)
+            .await
+            .context("Failed to store a rate limit bucket")?;
+        let row = rate_limit::get_rate_limit_bucket_for_update()
+            .bind(&transaction, key)
+            .one()
+            .await
+            .context("Failed to fetch a rate limit bucket")?;
+        let bucket = Bucket {
+            tokens: row.tokens,
+            updated_at: row.updated_at,
+        };
+        current.push((bucket, *settings));
+    }
+    let result = take_all(&current, now);
+    if let Ok(ref taken) = result {
+        for ((key, settings), bucket) in keys.iter().zip(taken) {
+            rate_limit::update_rate_limit_bucket()
+                .bind(
+                    &transaction,
+                    &bucket.tokens,
+                    &bucket.updated_at,
+                    &bucket.full_at(settings),
+                    key,
+                )
+                .await
+                .context("Failed to update a rate limit bucket")?;
+        }
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit a rate limit check")?;
+    Ok(result.map(|_| ()))
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use time::{Duration, OffsetDateTime};
+
+    use super::{take_from_memory, Bucket};
+    use crate::configuration::BucketSettings;
+
+    const SETTINGS: BucketSettings = BucketSettings {
+        capacity: 3,
+        refill_interval: 60,
+    };
+
+    fn start() -> OffsetDateTime {
+        OffsetDateTime::from_unix_timestamp(1_000_000).unwrap()
+    }
+
+    #[test]
+    fn full_bucket_allows_a_burst_of_capacity() {
+        let mut bucket = Bucket::full(&SETTINGS, start());
+        for _ in 0..3 {
+            assert_eq!(bucket.take(&SETTINGS, start()), Ok(()));
+        }
+        assert_eq!(bucket.take(&SETTINGS, start()), Err(60));
+    }
+
+    #[test]
+    fn tokens_come_back_over_time() {
+        let mut bucket = Bucket::full(&SETTINGS, start());
+        for _ in 0..3 {
+            bucket.take(&SETTINGS, start()).unwrap();
+        }
+        let later = start() + Duration::seconds(45);
+        assert_eq!(bucket.take(&SETTINGS, later), Err(15));
+        let later = start() + Duration::seconds(60);
+        assert_eq!(bucket.take(&SETTINGS, later), Ok(()));
+        assert_eq!(bucket.take(&SETTINGS, later), Err(60));
+    }
+
+    #[test]
+    fn bucket_never_holds_more_than_capacity() {
+        let mut bucket = Bucket::full(&SETTINGS, start());
+        bucket.take(&SETTINGS, start()).unwrap();
+        let much_later = start() + Duration::days(1);
+        for _ in 0..3 {
+            assert_eq!(bucket.take(&SETTINGS, much_later), Ok(()));
+        }
+        assert!(bucket.take(&SETTINGS, much_later).is_err());
+    }
+
+    #[test]
+    fn bucket_is_full_again_after_the_missing_tokens_refill() {
+        let mut bucket = Bucket::full(&SETTINGS, start());
+        assert_eq!(bucket.full_at(&SETTINGS), start());
+        bucket.take(&SETTINGS, start()).unwrap();
+        bucket.take(&SETTINGS, start()).unwrap();
+        assert_eq!(bucket.full_at(&SETTINGS), start() + Duration::minutes(2));
+    }
+
+    #[test]
+    fn memory_buckets_are_kept_per_key() {
+        let buckets = Mutex::new(HashMap::new());
+        let take =
+            |key| take_from_memory(&buckets, &[(key, &SETTINGS)], start());
+        for _ in 0..3 {
+            take("a").unwrap();
+        }
+        assert!(take("a").is_err());
+        assert!(take("b").is_ok());
+    }
+
+    #[test]
+    fn no_token_is_taken_when_one_bucket_is_empty() {
+        let buckets = Mutex::new(HashMap::new());
+        let take = |keys: &[&str]| {
+            let keys: Vec<_> =
+                keys.iter().map(|key| (*key, &SETTINGS)).collect();
+            take_from_memory(&buckets, &keys, start())
+        };
+        for _ in 0..3 {
+            take(&["email"]).unwrap();
+        }
+        for _ in 0..5 {
+            assert_eq!(take(&["ip", "email"]), Err(60));
+        }
+        // The bucket of the IP is still full
+        for _ in 0..3 {
+            take(&["ip"]).unwrap();
+        }
+        assert!(take(&["ip"]).is_err());
+    }
+}
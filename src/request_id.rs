@@ -0,0 +1,132 @@
+//! src/request_id.rs
+
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use http::{HeaderName, HeaderValue};
+use tracing::Instrument;
+
+pub const REQUEST_ID_HEADER: HeaderName =
+    HeaderName::from_static("x-request-id");
+
+/// Longer IDs from clients are replaced, they only bloat the logs.
+const MAX_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static CURRENT: RequestId;
+}
+
+/// Identifies a request in the logs of every service it passes, taken from
+/// `X-Request-Id` or generated. Handlers can extract it with
+/// `Extension<RequestId>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(String);
+
+impl RequestId {
+    pub fn generate() -> RequestId {
+        RequestId(uuid::Uuid::new_v4().to_string())
+    }
+
+    /// IDs from clients go into logs and headers, so only short ones made
+    /// of letters, digits and `-_.:` are accepted.
+    pub fn parse(s: &str) -> Option<RequestId> {
+        let valid = !s.is_empty()
+            && s.len() <= MAX_LENGTH
+            && s.bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b"-_.:".contains(&b));
+        valid.then(|| RequestId(s.to_string()))
+    }
+
+    /// ID of the request being handled by the current task, if any. The
+    /// delivery worker, for example, runs outside of requests.
+    pub fn current() -> Option<RequestId> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Runs every request in a root span carrying its ID, and echoes the ID in
+/// the response.
+pub async fn propagate_request_id(
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let request_id = request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(RequestId::parse)
+        .unwrap_or_else(RequestId::generate);
+    let span = tracing::info_span!(
+        "HTTP request",
+        request_id = %request_id,
+        method = %request.method(),
+        path = %request.uri().path(),
+        status = tracing::field::Empty,
+    );
+    request.extensions_mut().insert(request_id.clone());
+
+    let mut response = CURRENT
+        .scope(request_id.clone(), next.run(request))
+        .instrument(span.clone())
+        .await;
+    span.record("status", response.status().as_u16());
+    // Only made of visible ASCII, see `RequestId::parse`.
+    if let Ok(value) = HeaderValue::from_str(request_id.as_str()) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use super::RequestId;
+
+    #[test]
+    fn ids_of_clients_and_proxies_are_accepted() {
+        for id in [
+            "7d3c5b0e-0d9a-4c4e-9a1e-3f8f2b6a1c2d",
+            "1-67891233-abcdef012345678912345678",
+            "req_01HZX.3",
+        ] {
+            assert_eq!(RequestId::parse(id).unwrap().as_str(), id);
+        }
+    }
+
+    #[test]
+    fn empty_long_or_unusual_ids_are_rejected() {
+        for id in ["", "with space", "line\nbreak", "ünïcode", "quote\""] {
+            assert!(RequestId::parse(id).is_none(), "{id:?}");
+        }
+        assert!(RequestId::parse(&"a".repeat(129)).is_none());
+        assert!(RequestId::parse(&"a".repeat(128)).is_some());
+    }
+
+    #[test]
+    fn generated_ids_are_unique_and_valid() {
+        let (a, b) = (RequestId::generate(), RequestId::generate());
+        assert_ne!(a, b);
+        assert!(RequestId::parse(a.as_str()).is_some());
+    }
+
+    #[tokio::test]
+    async fn current_id_is_only_set_within_a_request() {
+        assert!(RequestId::current().is_none());
+        let id = RequestId::generate();
+        let current = super::CURRENT
+            .scope(id.clone(), async { RequestId::current() })
+            .await;
+        assert_eq!(current, Some(id));
+    }
+}
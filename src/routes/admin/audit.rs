@@ -0,0 +1,148 @@
+//! src/routes/admin/audit.rs
+
+use anyhow::Context;
+use askama::Template;
+use axum::extract::{Query, State};
+use axum::response::{Html, IntoResponse, Response};
+use http::StatusCode;
+
+use crate::audit::{self, EventFilter};
+use crate::authentication::Forbidden;
+use crate::domain::Permission;
+use crate::error_chain_fmt;
+use crate::html_template_gen::AuditTemplate;
+use crate::pagination::Cursor;
+use crate::session::AuthenticatedUser;
+use crate::startup::AppState;
+
+/// How many events we show on a single page.
+const PAGE_SIZE: i64 = 50;
+
+#[derive(serde::Deserialize, Debug, Default)]
+pub struct AuditParameters {
+    action: Option<String>,
+    username: Option<String>,
+    /// Position of the last event on the previous page.
+    after: Option<Cursor>,
+}
+
+#[derive(thiserror::Error)]
+pub enum AdminAuditError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error(transparent)]
+    Forbidden(#[from] Forbidden),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for AdminAuditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl IntoResponse for AdminAuditError {
+    fn into_response(self) -> Response {
+        match self {
+            AdminAuditError::ValidationError(e) => {
+                (StatusCode::BAD_REQUEST, e).into_response()
+            }
+            AdminAuditError::Forbidden(e) => e.into_response(),
+            AdminAuditError::UnexpectedError(e) => {
+                tracing::error!("{:?}", e);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+/// Lists audit events, newest first, filtered by action and username.
+#[tracing::instrument(skip_all, fields(user_id = %user.user_id))]
+pub async fn audit_log(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Query(parameters): Query<AuditParameters>,
+) -> Result<Html<String>, AdminAuditError> {
+    user.require(&state, Permission::ViewAuditLog, "/admin/audit")
+        .await?;
+    // Html forms send empty fields, treat them as missing.
+    let action = parameters.action.filter(|s| !s.is_empty());
+    let username = parameters
+        .username
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    let filter = EventFilter {
+        action: action.as_deref(),
+        username: username.as_deref(),
+        after: parameters.after,
+    };
+    filter
+        .validate()
+        .map_err(AdminAuditError::ValidationError)?;
+
+    let client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let (events, next_after) =
+        audit::list_events(&client, &filter, PAGE_SIZE).await?;
+    let next_page = next_after.map(|after| {
+        next_page_link(action.as_deref(), username.as_deref(), after)
+    });
+
+    let action_options: Vec<(&str, bool)> = audit::ACTIONS
+        .iter()
+        .map(|a| (*a, action.as_deref() == Some(*a)))
+        .collect();
+    let page = AuditTemplate::new(
+        &events,
+        &action_options,
+        username.as_deref().unwrap_or_default(),
+        next_page.as_deref(),
+    )
+    .render()
+    .context("Failed to render audit log page")?;
+    Ok(Html(page))
+}
+
+fn next_page_link(
+    action: Option<&str>,
+    username: Option<&str>,
+    after: Cursor,
+) -> String {
+    let mut link = format!("/admin/audit?after={}", after);
+    if let Some(action) = action {
+        link.push_str(&format!("&action={}", urlencoding::encode(action)));
+    }
+    if let Some(username) = username {
+        link.push_str(&format!("&username={}", urlencoding::encode(username)));
+    }
+    link
+}
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+    use uuid::Uuid;
+
+    use super::next_page_link;
+    use crate::pagination::Cursor;
+
+    #[test]
+    fn next_page_link_keeps_filters() {
+        let after = Cursor::new(OffsetDateTime::now_utc(), Uuid::new_v4());
+        assert_eq!(
+            next_page_link(Some("login"), Some("a b&"), after),
+            format!(
+                "/admin/audit?after={}&action=login&username=a%20b%26",
+                after
+            )
+        );
+        assert_eq!(
+            next_page_link(None, None, after),
+            format!("/admin/audit?after={}", after)
+        );
+    }
+}
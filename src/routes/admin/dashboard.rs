@@ -0,0 +1,83 @@
+//! src/routes/admin/dashboard.rs
+
+use anyhow::Context;
+use axum::extract::State;
+use axum::response::{Html, IntoResponse, Response};
+use http::StatusCode;
+
+use crate::cornucopia::queries::users;
+use crate::domain::Permission;
+use crate::session::AuthenticatedUser;
+use crate::startup::AppState;
+
+/// Links only to the pages the role of the user allows.
+#[tracing::instrument(skip_all, fields(user_id = %user.user_id))]
+pub async fn admin_dashboard(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+) -> Response {
+    let mut actions =
+        vec![r#"<li><a href="/admin/subscribers">Subscribers</a></li>"#];
+    if user.role.allows(Permission::PublishNewsletters) {
+        actions.push(
+            r#"<li><a href="/admin/newsletters">Publish a newsletter issue</a></li>"#,
+        );
+    }
+    if user.role.allows(Permission::ManageUsers) {
+        actions.push(r#"<li><a href="/admin/users">Users</a></li>"#);
+    }
+    if user.role.allows(Permission::ViewAuditLog) {
+        actions.push(r#"<li><a href="/admin/audit">Audit log</a></li>"#);
+    }
+    actions.push(r#"<li><a href="/admin/tokens">API tokens</a></li>"#);
+    actions.push(r#"<li><a href="/admin/password">Change password</a></li>"#);
+    actions
+        .push(r#"<li><a href="/admin/2fa">Two-factor authentication</a></li>"#);
+    let actions = actions.join("\n        ");
+    let role = user.role;
+    let csrf_token = &user.csrf_token;
+
+    match get_username(&state, &user).await {
+        Ok(username) => Html(format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta http-equiv="content-type" content="text/html; charset=utf-8">
+    <title>Admin dashboard</title>
+</head>
+<body>
+    <p>Welcome {username}! You are signed in as {role}.</p>
+    <p>Available actions:</p>
+    <ol>
+        {actions}
+    </ol>
+    <form name="logoutForm" action="/admin/logout" method="post">
+        <input type="hidden" name="csrf_token" value="{csrf_token}">
+        <input type="submit" value="Logout">
+    </form>
+</body>
+</html>"#
+        ))
+        .into_response(),
+        Err(e) => {
+            tracing::error!("{:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn get_username(
+    state: &AppState,
+    user: &AuthenticatedUser,
+) -> Result<String, anyhow::Error> {
+    let client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    users::get_username()
+        .bind(&client, &user.user_id)
+        .one()
+        .await
+        .context("Failed to fetch a username")
+}
@@ -0,0 +1,50 @@
+//! src/routes/admin/logout.rs
+
+use axum::extract::State;
+use axum::response::{IntoResponse, Redirect, Response};
+use http::header::SET_COOKIE;
+use http::StatusCode;
+
+use crate::audit;
+use crate::session::{end_session, AuthenticatedUser, SessionId};
+use crate::startup::AppState;
+
+/// Deletes the session and the cookie, then redirects to the login form.
+#[tracing::instrument(skip_all, fields(user_id = %user.user_id))]
+pub async fn log_out(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+) -> Response {
+    let client = match state.pool.get().await {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::error!("Failed to get connection from pool: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    if let Err(e) = end_session(&client, &user.session_id).await {
+        tracing::error!("{:?}", e);
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+    tracing::info!("User logged out");
+    // The session is gone either way, a missing event isn't worth an error.
+    if let Err(e) = audit::record_event(
+        &client,
+        &user.origin,
+        Some(user.user_id),
+        audit::LOGOUT,
+        None,
+    )
+    .await
+    {
+        tracing::error!("{:?}", e);
+    }
+    (
+        [(
+            SET_COOKIE,
+            SessionId::removal_cookie(&state.session_settings),
+        )],
+        Redirect::to("/login"),
+    )
+        .into_response()
+}
@@ -0,0 +1,19 @@
+pub use audit::*;
+pub use dashboard::admin_dashboard;
+pub use logout::log_out;
+pub use newsletters::*;
+pub use password::*;
+pub use subscribers::*;
+pub use tokens::*;
+pub use two_factor::*;
+pub use users::*;
+
+mod audit;
+mod dashboard;
+mod logout;
+mod newsletters;
+mod password;
+mod subscribers;
+mod tokens;
+mod two_factor;
+mod users;
@@ -0,0 +1,187 @@
+//! src/routes/admin/newsletters.rs
+
+use anyhow::Context;
+use askama::Template;
+use axum::extract::State;
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::Form;
+use http::header::{CONTENT_SECURITY_POLICY, SET_COOKIE};
+use http::{HeaderValue, StatusCode};
+use uuid::Uuid;
+
+use crate::domain::Permission;
+use crate::flash::{FlashMessage, IncomingFlashMessage};
+use crate::html_template_gen::NewsletterFormTemplate;
+use crate::idempotency::IdempotencyKey;
+use crate::routes::newsletters::{publish_issue, BodyData, Content};
+use crate::routes::PublishError;
+use crate::security_headers::PREVIEW_CONTENT_SECURITY_POLICY;
+use crate::session::AuthenticatedUser;
+use crate::startup::AppState;
+
+#[derive(serde::Deserialize, Debug)]
+pub struct NewsletterFormData {
+    title: String,
+    html: String,
+    text: String,
+    /// Generated with the form, so double submits publish only once.
+    idempotency_key: String,
+}
+
+/// Shows an empty compose form and the result of the last publishing.
+#[tracing::instrument(skip_all, fields(user_id = %user.user_id))]
+pub async fn publish_newsletter_form(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    IncomingFlashMessage(flash): IncomingFlashMessage,
+) -> Response {
+    if let Err(e) = user
+        .require(&state, Permission::PublishNewsletters, "/admin/newsletters")
+        .await
+    {
+        return e.into_response();
+    }
+    let idempotency_key = Uuid::new_v4().to_string();
+    let page = NewsletterFormTemplate::new(
+        flash.as_ref(),
+        "",
+        "",
+        "",
+        &idempotency_key,
+        false,
+        &user.csrf_token,
+    );
+    render(page, &state)
+}
+
+/// Shows the form again, filled in, with both bodies rendered below it.
+#[tracing::instrument(skip_all, fields(user_id = %user.user_id))]
+pub async fn preview_newsletter(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Form(form): Form<NewsletterFormData>,
+) -> Response {
+    if let Err(e) = user
+        .require(
+            &state,
+            Permission::PublishNewsletters,
+            "/admin/newsletters/preview",
+        )
+        .await
+    {
+        return e.into_response();
+    }
+    let page = NewsletterFormTemplate::new(
+        None,
+        &form.title,
+        &form.html,
+        &form.text,
+        &form.idempotency_key,
+        true,
+        &user.csrf_token,
+    );
+    // The issue may use inline styles and remote images.
+    let mut response = render(page, &state);
+    response.headers_mut().insert(
+        CONTENT_SECURITY_POLICY,
+        HeaderValue::from_static(PREVIEW_CONTENT_SECURITY_POLICY),
+    );
+    response
+}
+
+/// Publishes the issue with the same logic as `POST /newsletters` and
+/// redirects back to the form with a flash message.
+#[tracing::instrument(
+    name = "Publish a newsletter issue from the admin form",
+    skip_all,
+    fields(user_id = %user.user_id)
+)]
+pub async fn publish_newsletter(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Form(form): Form<NewsletterFormData>,
+) -> Response {
+    if let Err(e) = user
+        .require(&state, Permission::PublishNewsletters, "/admin/newsletters")
+        .await
+    {
+        return e.into_response();
+    }
+    let flash = match try_publish(&state, &user, form).await {
+        Ok(()) => FlashMessage::info(
+            "The newsletter issue has been accepted - \
+            emails will go out shortly.",
+        ),
+        Err(PublishError::ValidationError(e)) => FlashMessage::error(e),
+        Err(e) => {
+            tracing::error!("{:?}", e);
+            FlashMessage::error("Failed to publish the newsletter issue.")
+        }
+    };
+    (
+        [(SET_COOKIE, flash.cookie(&state.session_settings))],
+        Redirect::to("/admin/newsletters"),
+    )
+        .into_response()
+}
+
+async fn try_publish(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    form: NewsletterFormData,
+) -> Result<(), PublishError> {
+    if form.title.trim().is_empty() {
+        return Err(PublishError::ValidationError(
+            "The title must not be empty.".into(),
+        ));
+    }
+    if form.html.trim().is_empty() || form.text.trim().is_empty() {
+        return Err(PublishError::ValidationError(
+            "Both the HTML and the text content must be provided.".into(),
+        ));
+    }
+    let idempotency_key = IdempotencyKey::parse(&form.idempotency_key)
+        .map_err(|e| PublishError::ValidationError(e.to_string()))?;
+
+    let mut connection = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let body = BodyData {
+        title: form.title,
+        content: Content {
+            html: form.html,
+            text: form.text,
+        },
+    };
+    // The response itself is not used, the user gets a flash message
+    // either way, but it is saved for retried submissions.
+    publish_issue(
+        &mut connection,
+        &user.origin,
+        user.user_id,
+        Some(&idempotency_key),
+        &body,
+        StatusCode::ACCEPTED.into_response(),
+    )
+    .await?;
+    Ok(())
+}
+
+fn render(page: NewsletterFormTemplate<'_>, state: &AppState) -> Response {
+    match page.render() {
+        Ok(page) => (
+            [(
+                SET_COOKIE,
+                FlashMessage::removal_cookie(&state.session_settings),
+            )],
+            Html(page),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to render newsletter form: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
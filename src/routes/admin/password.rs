@@ -0,0 +1,170 @@
+//! src/routes/admin/password.rs
+
+use anyhow::Context;
+use askama::Template;
+use axum::extract::State;
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::Form;
+use http::header::SET_COOKIE;
+use http::StatusCode;
+use secrecy::{ExposeSecret, Secret};
+
+use crate::audit;
+use crate::authentication::{self, validate_credentials};
+use crate::authentication::{AuthError, Credentials};
+use crate::cornucopia::queries::{sessions, users};
+use crate::domain::AdminPassword;
+use crate::flash::{FlashMessage, IncomingFlashMessage};
+use crate::html_template_gen::ChangePasswordTemplate;
+use crate::session::AuthenticatedUser;
+use crate::startup::AppState;
+
+#[derive(serde::Deserialize)]
+pub struct ChangePasswordFormData {
+    current_password: Secret<String>,
+    new_password: Secret<String>,
+    new_password_check: Secret<String>,
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %user.user_id))]
+pub async fn change_password_form(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    IncomingFlashMessage(flash): IncomingFlashMessage,
+) -> Response {
+    match ChangePasswordTemplate::new(flash.as_ref(), &user.csrf_token).render()
+    {
+        Ok(page) => (
+            [(
+                SET_COOKIE,
+                FlashMessage::removal_cookie(&state.session_settings),
+            )],
+            Html(page),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to render change password form: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Checks the current password, then stores the new one.
+/// The outcome is reported with a flash message on the form.
+/// Only the session which changed the password stays logged in.
+#[tracing::instrument(skip_all, fields(user_id = %user.user_id))]
+pub async fn change_password(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Form(form): Form<ChangePasswordFormData>,
+) -> Response {
+    let flash = match try_change_password(&state, &user, form).await {
+        Ok(()) => {
+            tracing::info!("Password changed");
+            FlashMessage::info("Your password has been changed.")
+        }
+        Err(ChangePasswordError::Rejected(message)) => {
+            FlashMessage::error(message)
+        }
+        Err(ChangePasswordError::UnexpectedError(e)) => {
+            tracing::error!("{:?}", e);
+            FlashMessage::error("Failed to change the password.")
+        }
+    };
+    (
+        [(SET_COOKIE, flash.cookie(&state.session_settings))],
+        Redirect::to("/admin/password"),
+    )
+        .into_response()
+}
+
+enum ChangePasswordError {
+    /// Shown to the user as is.
+    Rejected(String),
+    UnexpectedError(anyhow::Error),
+}
+
+impl From<anyhow::Error> for ChangePasswordError {
+    fn from(e: anyhow::Error) -> Self {
+        ChangePasswordError::UnexpectedError(e)
+    }
+}
+
+async fn try_change_password(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    form: ChangePasswordFormData,
+) -> Result<(), ChangePasswordError> {
+    if form.new_password.expose_secret()
+        != form.new_password_check.expose_secret()
+    {
+        return Err(ChangePasswordError::Rejected(
+            "You entered two different new passwords - \
+            the field values must match."
+                .into(),
+        ));
+    }
+    let new_password = AdminPassword::parse(form.new_password)
+        .map_err(ChangePasswordError::Rejected)?;
+
+    let mut client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let username = users::get_username()
+        .bind(&client, &user.user_id)
+        .one()
+        .await
+        .context("Failed to fetch a username")?;
+    let credentials = Credentials {
+        username,
+        password: form.current_password,
+    };
+    match validate_credentials(credentials, &state.password_hashing, &client)
+        .await
+    {
+        Ok(_) => {}
+        Err(AuthError::InvalidCredentials(_)) => {
+            return Err(ChangePasswordError::Rejected(
+                "The current password is incorrect.".into(),
+            ))
+        }
+        Err(AuthError::UnexpectedError(e)) => return Err(e.into()),
+    }
+
+    // Like a reset, the change logs out every other session and voids
+    // the reset links sent before.
+    let transaction = client
+        .transaction()
+        .await
+        .context("Failed to start transaction on pg connection")?;
+    authentication::change_password(
+        user.user_id,
+        new_password,
+        &state.password_hashing,
+        &transaction,
+    )
+    .await?;
+    users::delete_password_reset_tokens()
+        .bind(&transaction, &user.user_id)
+        .await
+        .context("Failed to delete password reset tokens")?;
+    sessions::delete_other_user_sessions()
+        .bind(&transaction, &user.user_id, &user.session_id.as_ref())
+        .await
+        .context("Failed to end user's other sessions")?;
+    audit::record_event(
+        &transaction,
+        &user.origin,
+        Some(user.user_id),
+        audit::PASSWORD_CHANGED,
+        None,
+    )
+    .await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit a password change")?;
+    Ok(())
+}
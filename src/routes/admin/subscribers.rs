@@ -0,0 +1,269 @@
+//! src/routes/admin/subscribers.rs
+
+use anyhow::Context;
+use askama::Template;
+use axum::extract::{Path, Query, State};
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use http::StatusCode;
+use uuid::Uuid;
+
+use crate::audit;
+use crate::authentication::Forbidden;
+use crate::cornucopia::queries::newsletters;
+use crate::cornucopia::queries::subscriptions;
+use crate::domain::Permission;
+use crate::error_chain_fmt;
+use crate::html_template_gen::SubscribersTemplate;
+use crate::pagination::Cursor;
+use crate::session::AuthenticatedUser;
+use crate::startup::AppState;
+
+/// How many subscribers we show on a single page.
+const PAGE_SIZE: i64 = 20;
+
+/// Values of the `status` column an admin can filter by.
+const STATUSES: [&str; 3] =
+    ["pending_confirmation", "confirmed", "unsubscribed"];
+
+#[derive(serde::Deserialize, Debug, Default)]
+pub struct ListParameters {
+    status: Option<String>,
+    search: Option<String>,
+    /// Position of the last subscriber on the previous page.
+    after: Option<Cursor>,
+}
+
+#[derive(thiserror::Error)]
+pub enum AdminSubscribersError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error("Subscriber not found")]
+    NotFound,
+    #[error(transparent)]
+    Forbidden(#[from] Forbidden),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for AdminSubscribersError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl IntoResponse for AdminSubscribersError {
+    fn into_response(self) -> Response {
+        match self {
+            AdminSubscribersError::ValidationError(e) => {
+                (StatusCode::BAD_REQUEST, e).into_response()
+            }
+            AdminSubscribersError::NotFound => {
+                StatusCode::NOT_FOUND.into_response()
+            }
+            AdminSubscribersError::Forbidden(e) => e.into_response(),
+            AdminSubscribersError::UnexpectedError(e) => {
+                tracing::error!("{:?}", e);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+/// Lists subscribers, newest first. Pagination is keyset based: the next
+/// page starts right after the last shown `(subscribed_at, id)` pair, so
+/// pages stay stable while new subscribers come in.
+#[tracing::instrument(skip_all, fields(user_id = %user.user_id))]
+pub async fn list_subscribers(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Query(parameters): Query<ListParameters>,
+) -> Result<Html<String>, AdminSubscribersError> {
+    // Html forms send empty fields, treat them as missing.
+    let status = parameters.status.filter(|s| !s.is_empty());
+    let search = parameters
+        .search
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    if let Some(ref status) = status {
+        if !STATUSES.contains(&status.as_str()) {
+            return Err(AdminSubscribersError::ValidationError(format!(
+                "Unknown subscriber status: {}",
+                status
+            )));
+        }
+    }
+
+    let client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    // Fetch one extra row to know whether there is a next page.
+    let mut subscribers = subscriptions::list_subscribers()
+        .bind(
+            &client,
+            &status.as_deref(),
+            &search.as_deref(),
+            &parameters.after.map(|after| after.at),
+            &parameters.after.map(|after| after.id),
+            &(PAGE_SIZE + 1),
+        )
+        .all()
+        .await
+        .context("Failed to fetch subscribers")?;
+
+    let next_page = if subscribers.len() > PAGE_SIZE as usize {
+        subscribers.truncate(PAGE_SIZE as usize);
+        subscribers.last().map(|last| {
+            next_page_link(
+                status.as_deref(),
+                search.as_deref(),
+                Cursor::new(last.subscribed_at, last.id),
+            )
+        })
+    } else {
+        None
+    };
+
+    let status_options: Vec<(&str, bool)> = STATUSES
+        .iter()
+        .map(|s| (*s, status.as_deref() == Some(*s)))
+        .collect();
+    let page = SubscribersTemplate::new(
+        &subscribers,
+        &status_options,
+        search.as_deref().unwrap_or_default(),
+        next_page.as_deref(),
+        user.role.allows(Permission::ManageSubscribers),
+        &user.csrf_token,
+    )
+    .render()
+    .context("Failed to render subscribers page")?;
+    Ok(Html(page))
+}
+
+/// Deletes a subscriber together with their tokens and queued deliveries.
+#[tracing::instrument(skip(state, user), fields(user_id = %user.user_id))]
+pub async fn delete_subscriber(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(subscriber_id): Path<Uuid>,
+) -> Result<Redirect, AdminSubscribersError> {
+    user.require(
+        &state,
+        Permission::ManageSubscribers,
+        &format!("/admin/subscribers/{}/delete", subscriber_id),
+    )
+    .await?;
+    let mut client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let transaction = client
+        .transaction()
+        .await
+        .context("Failed to start transaction on pg connection")?;
+    // Tokens are removed by `ON DELETE CASCADE`.
+    let email = subscriptions::delete_subscriber()
+        .bind(&transaction, &subscriber_id)
+        .opt()
+        .await
+        .context("Failed to delete subscriber")?
+        .ok_or(AdminSubscribersError::NotFound)?;
+    newsletters::delete_tasks_for_subscriber()
+        .bind(&transaction, &email.as_str())
+        .await
+        .context("Failed to delete pending delivery tasks")?;
+    audit::record_event(
+        &transaction,
+        &user.origin,
+        Some(user.user_id),
+        audit::SUBSCRIBER_DELETED,
+        Some(&subscriber_id.to_string()),
+    )
+    .await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit delete subscriber transaction")?;
+    tracing::info!("Subscriber deleted by admin");
+    Ok(Redirect::to("/admin/subscribers"))
+}
+
+/// Confirms a pending subscriber without the confirmation link,
+/// subscribers in other states are left untouched.
+#[tracing::instrument(skip(state, user), fields(user_id = %user.user_id))]
+pub async fn confirm_subscriber(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(subscriber_id): Path<Uuid>,
+) -> Result<Redirect, AdminSubscribersError> {
+    user.require(
+        &state,
+        Permission::ManageSubscribers,
+        &format!("/admin/subscribers/{}/confirm", subscriber_id),
+    )
+    .await?;
+    let client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let confirmed = subscriptions::confirm_subscriber()
+        .bind(&client, &subscriber_id)
+        .await
+        .context("Failed to confirm subscriber")?;
+    if confirmed > 0 {
+        tracing::info!("Subscriber confirmed by admin");
+        audit::record_event(
+            &client,
+            &user.origin,
+            Some(user.user_id),
+            audit::SUBSCRIBER_CONFIRMED,
+            Some(&subscriber_id.to_string()),
+        )
+        .await?;
+    }
+    Ok(Redirect::to("/admin/subscribers"))
+}
+
+fn next_page_link(
+    status: Option<&str>,
+    search: Option<&str>,
+    after: Cursor,
+) -> String {
+    let mut link = format!("/admin/subscribers?after={}", after);
+    if let Some(status) = status {
+        link.push_str(&format!("&status={}", urlencoding::encode(status)));
+    }
+    if let Some(search) = search {
+        link.push_str(&format!("&search={}", urlencoding::encode(search)));
+    }
+    link
+}
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+    use uuid::Uuid;
+
+    use super::next_page_link;
+    use crate::pagination::Cursor;
+
+    #[test]
+    fn next_page_link_keeps_filters() {
+        let after = Cursor::new(OffsetDateTime::now_utc(), Uuid::new_v4());
+        assert_eq!(
+            next_page_link(Some("confirmed"), Some("jo hn&"), after),
+            format!(
+                "/admin/subscribers?after={}&status=confirmed&search=jo%20hn%26",
+                after
+            )
+        );
+        assert_eq!(
+            next_page_link(None, None, after),
+            format!("/admin/subscribers?after={}", after)
+        );
+    }
+}
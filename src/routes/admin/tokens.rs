@@ -0,0 +1,229 @@
+//! src/routes/admin/tokens.rs
+
+use anyhow::Context;
+use askama::Template;
+use axum::extract::{Path, State};
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::Form;
+use http::header::SET_COOKIE;
+use http::StatusCode;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::api_tokens::{create_api_token, CreateApiTokenError};
+use crate::audit;
+use crate::cornucopia::queries::api_tokens;
+use crate::domain::ApiScope;
+use crate::flash::{FlashMessage, IncomingFlashMessage};
+use crate::html_template_gen::TokensTemplate;
+use crate::session::AuthenticatedUser;
+use crate::startup::AppState;
+use crate::validation::api_token::ApiToken;
+
+/// Longest lifetime of a token with an expiry date, about ten years.
+const MAX_EXPIRES_IN_DAYS: i64 = 3650;
+
+/// Lists the API tokens of the current user.
+#[tracing::instrument(skip_all, fields(user_id = %user.user_id))]
+pub async fn list_tokens(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    IncomingFlashMessage(flash): IncomingFlashMessage,
+) -> Response {
+    match render_tokens_page(&state, &user, flash.as_ref(), None).await {
+        Ok(page) => (
+            [(
+                SET_COOKIE,
+                FlashMessage::removal_cookie(&state.session_settings),
+            )],
+            Html(page),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("{:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Creates a token and renders the page with it right away instead of
+/// redirecting: the token is shown this one time and never stored in
+/// clear, not even in a flash cookie.
+///
+/// The form has one `scope` field per checked scope, so it is taken as
+/// a list of pairs.
+#[tracing::instrument(skip_all, fields(user_id = %user.user_id))]
+pub async fn create_token(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Form(form): Form<Vec<(String, String)>>,
+) -> Response {
+    let created = match try_create_token(&state, &user, &form).await {
+        Ok(token) => token,
+        Err(CreateApiTokenError::Rejected(message)) => {
+            return back_to_tokens_page(FlashMessage::error(message), &state)
+        }
+        Err(e) => {
+            tracing::error!("{:?}", e);
+            return back_to_tokens_page(
+                FlashMessage::error("Failed to create the token."),
+                &state,
+            );
+        }
+    };
+    match render_tokens_page(&state, &user, None, Some(&created)).await {
+        Ok(page) => Html(page).into_response(),
+        Err(e) => {
+            tracing::error!("{:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Users can revoke their own tokens only.
+#[tracing::instrument(skip(state, user), fields(user_id = %user.user_id))]
+pub async fn revoke_token(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(token_id): Path<Uuid>,
+) -> Response {
+    let flash = match revoke(&state, &user, token_id).await {
+        Ok(true) => {
+            tracing::info!("API token revoked");
+            FlashMessage::info("The token has been revoked.")
+        }
+        Ok(false) => FlashMessage::error("Token not found."),
+        Err(e) => {
+            tracing::error!("{:?}", e);
+            FlashMessage::error("Failed to revoke the token.")
+        }
+    };
+    back_to_tokens_page(flash, &state)
+}
+
+async fn render_tokens_page(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    flash: Option<&FlashMessage>,
+    created: Option<&ApiToken>,
+) -> Result<String, anyhow::Error> {
+    let client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let tokens = api_tokens::list_user_api_tokens()
+        .bind(&client, &user.user_id)
+        .all()
+        .await
+        .context("Failed to fetch API tokens")?;
+    let scopes: Vec<ApiScope> = ApiScope::ALL
+        .into_iter()
+        .filter(|scope| scope.allowed_for(user.role))
+        .collect();
+    TokensTemplate::new(
+        flash,
+        &tokens,
+        &scopes,
+        created.map(AsRef::as_ref),
+        OffsetDateTime::now_utc(),
+        &user.csrf_token,
+    )
+    .render()
+    .context("Failed to render tokens page")
+}
+
+async fn try_create_token(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    form: &[(String, String)],
+) -> Result<ApiToken, CreateApiTokenError> {
+    let field = |name: &str| {
+        form.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.trim())
+            .unwrap_or_default()
+    };
+    let scopes = form
+        .iter()
+        .filter(|(key, _)| key == "scope")
+        .map(|(_, value)| ApiScope::parse(value))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(CreateApiTokenError::Rejected)?;
+    let expires_at = match field("expires_in_days") {
+        "" => None,
+        days => {
+            let days = days
+                .parse::<i64>()
+                .ok()
+                .filter(|d| (1..=MAX_EXPIRES_IN_DAYS).contains(d))
+                .ok_or_else(|| {
+                    CreateApiTokenError::Rejected(format!(
+                        "Expiry must be from 1 to {} days.",
+                        MAX_EXPIRES_IN_DAYS
+                    ))
+                })?;
+            Some(OffsetDateTime::now_utc() + time::Duration::days(days))
+        }
+    };
+
+    let client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let (token_id, token) = create_api_token(
+        &client,
+        user.user_id,
+        user.role,
+        field("name"),
+        &scopes,
+        expires_at,
+    )
+    .await?;
+    tracing::info!(%token_id, "API token created");
+    audit::record_event(
+        &client,
+        &user.origin,
+        Some(user.user_id),
+        audit::API_TOKEN_CREATED,
+        Some(&token_id.to_string()),
+    )
+    .await?;
+    Ok(token)
+}
+
+async fn revoke(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    token_id: Uuid,
+) -> Result<bool, anyhow::Error> {
+    let client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let revoked = api_tokens::revoke_user_api_token()
+        .bind(&client, &token_id, &user.user_id)
+        .await
+        .context("Failed to revoke an API token")?;
+    if revoked > 0 {
+        audit::record_event(
+            &client,
+            &user.origin,
+            Some(user.user_id),
+            audit::API_TOKEN_REVOKED,
+            Some(&token_id.to_string()),
+        )
+        .await?;
+    }
+    Ok(revoked > 0)
+}
+
+fn back_to_tokens_page(flash: FlashMessage, state: &AppState) -> Response {
+    (
+        [(SET_COOKIE, flash.cookie(&state.session_settings))],
+        Redirect::to("/admin/tokens"),
+    )
+        .into_response()
+}
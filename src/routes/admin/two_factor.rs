@@ -0,0 +1,360 @@
+//! src/routes/admin/two_factor.rs
+
+use anyhow::Context;
+use askama::Template;
+use axum::extract::State;
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::Form;
+use deadpool_postgres::{Client, Transaction};
+use http::header::SET_COOKIE;
+use http::StatusCode;
+
+use crate::cornucopia::queries::users;
+use crate::error_chain_fmt;
+use crate::flash::{FlashMessage, IncomingFlashMessage};
+use crate::html_template_gen::{TotpSetup, TwoFactorTemplate};
+use crate::login_lockout::{LockoutError, LoginAttempt};
+use crate::session::AuthenticatedUser;
+use crate::startup::AppState;
+use crate::two_factor::{self, TwoFactorError, TOTP_ISSUER};
+use crate::validation::recovery_code::RecoveryCode;
+
+#[derive(serde::Deserialize)]
+pub struct CodeFormData {
+    /// TOTP code, or a recovery code where the current factor is asked.
+    code: String,
+}
+
+/// Failure of a change which needs the current code.
+#[derive(thiserror::Error)]
+enum CurrentCodeError {
+    #[error("The code is not valid.")]
+    InvalidCode,
+    #[error(transparent)]
+    Locked(LockoutError),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for CurrentCodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl From<LockoutError> for CurrentCodeError {
+    fn from(e: LockoutError) -> Self {
+        match e {
+            LockoutError::Locked { .. } => CurrentCodeError::Locked(e),
+            LockoutError::UnexpectedError(e) => {
+                CurrentCodeError::UnexpectedError(e)
+            }
+        }
+    }
+}
+
+/// 2FA status of the current user and the buttons to change it.
+#[tracing::instrument(skip_all, fields(user_id = %user.user_id))]
+pub async fn two_factor_settings(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    IncomingFlashMessage(flash): IncomingFlashMessage,
+) -> Response {
+    match render_page(&state, &user, flash.as_ref(), None, &[]).await {
+        Ok(page) => (
+            [(
+                SET_COOKIE,
+                FlashMessage::removal_cookie(&state.session_settings),
+            )],
+            Html(page),
+        )
+            .into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Generates a new secret and shows it as a QR code. 2FA stays off
+/// until the user confirms a first code.
+#[tracing::instrument(skip_all, fields(user_id = %user.user_id))]
+pub async fn start_two_factor_setup(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+) -> Response {
+    match try_start_setup(&state, &user).await {
+        Ok(Some(page)) => Html(page).into_response(),
+        Ok(None) => back_to_settings(
+            FlashMessage::error("Two-factor authentication is already on."),
+            &state,
+        ),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Turns 2FA on and shows the recovery codes, the only time they are
+/// visible.
+#[tracing::instrument(skip_all, fields(user_id = %user.user_id))]
+pub async fn enable_two_factor(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Form(form): Form<CodeFormData>,
+) -> Response {
+    let codes = match try_enable(&state, &user, &form.code).await {
+        Ok(codes) => codes,
+        Err(TwoFactorError::InvalidCode) => {
+            return back_to_settings(
+                FlashMessage::error(
+                    "The code is not valid, please start the setup again.",
+                ),
+                &state,
+            )
+        }
+        Err(TwoFactorError::UnexpectedError(e)) => return internal_error(e),
+    };
+    tracing::info!("Two-factor authentication enabled");
+    let flash = FlashMessage::info("Two-factor authentication is now on.");
+    match render_page(&state, &user, Some(&flash), None, &codes).await {
+        Ok(page) => Html(page).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Needs a current code, a stolen session alone can't turn 2FA off. Wrong
+/// codes count towards the login lockout.
+#[tracing::instrument(skip_all, fields(user_id = %user.user_id))]
+pub async fn disable_two_factor(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Form(form): Form<CodeFormData>,
+) -> Response {
+    let flash = match try_disable(&state, &user, &form.code).await {
+        Ok(()) => {
+            tracing::info!("Two-factor authentication disabled");
+            FlashMessage::info("Two-factor authentication is now off.")
+        }
+        Err(
+            e @ (CurrentCodeError::InvalidCode | CurrentCodeError::Locked(_)),
+        ) => FlashMessage::error(e.to_string()),
+        Err(CurrentCodeError::UnexpectedError(e)) => return internal_error(e),
+    };
+    back_to_settings(flash, &state)
+}
+
+/// Replaces the recovery codes, e.g. when most of them are used. Needs
+/// a current code, like turning 2FA off.
+#[tracing::instrument(skip_all, fields(user_id = %user.user_id))]
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Form(form): Form<CodeFormData>,
+) -> Response {
+    let codes = match try_regenerate(&state, &user, &form.code).await {
+        Ok(codes) => codes,
+        Err(
+            e @ (CurrentCodeError::InvalidCode | CurrentCodeError::Locked(_)),
+        ) => {
+            return back_to_settings(FlashMessage::error(e.to_string()), &state)
+        }
+        Err(CurrentCodeError::UnexpectedError(e)) => return internal_error(e),
+    };
+    let flash = FlashMessage::info("Your old recovery codes no longer work.");
+    match render_page(&state, &user, Some(&flash), None, &codes).await {
+        Ok(page) => Html(page).into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+async fn render_page(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    flash: Option<&FlashMessage>,
+    setup: Option<&TotpSetup>,
+    recovery_codes: &[RecoveryCode],
+) -> Result<String, anyhow::Error> {
+    let client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let enabled = two_factor::is_enabled(&client, user.user_id).await?;
+    let unused_codes =
+        two_factor::unused_recovery_codes(&client, user.user_id).await?;
+    let recovery_codes: Vec<&str> =
+        recovery_codes.iter().map(AsRef::as_ref).collect();
+    TwoFactorTemplate::new(
+        flash,
+        enabled,
+        unused_codes,
+        setup,
+        &recovery_codes,
+        &user.csrf_token,
+    )
+    .render()
+    .context("Failed to render 2FA page")
+}
+
+async fn try_start_setup(
+    state: &AppState,
+    user: &AuthenticatedUser,
+) -> Result<Option<String>, anyhow::Error> {
+    let client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let Some(secret) =
+        two_factor::start_enrollment(&client, user.user_id).await?
+    else {
+        return Ok(None);
+    };
+    let username = users::get_username()
+        .bind(&client, &user.user_id)
+        .one()
+        .await
+        .context("Failed to fetch a username")?;
+    let uri = secret.otpauth_uri(TOTP_ISSUER, &username);
+    let setup = TotpSetup {
+        qr_code_svg: two_factor::qr_code_svg(&uri)?,
+        secret: secret.to_base32(),
+        uri,
+    };
+    render_page(state, user, None, Some(&setup), &[])
+        .await
+        .map(Some)
+}
+
+async fn try_enable(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    code: &str,
+) -> Result<Vec<RecoveryCode>, TwoFactorError> {
+    let mut client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let transaction = client
+        .transaction()
+        .await
+        .context("Failed to start transaction on pg connection")?;
+    let codes = two_factor::confirm_enrollment(
+        &transaction,
+        &user.origin,
+        user.user_id,
+        code,
+        &state.authentication_settings.recovery_code_key,
+        state.clock.now(),
+    )
+    .await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit 2FA enrollment")?;
+    Ok(codes)
+}
+
+async fn try_disable(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    code: &str,
+) -> Result<(), CurrentCodeError> {
+    let mut client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let transaction =
+        verify_current_code(state, user, &mut client, code).await?;
+    two_factor::disable(&transaction, &user.origin, user.user_id).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit disabling 2FA")?;
+    Ok(())
+}
+
+async fn try_regenerate(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    code: &str,
+) -> Result<Vec<RecoveryCode>, CurrentCodeError> {
+    let mut client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let transaction =
+        verify_current_code(state, user, &mut client, code).await?;
+    let codes = two_factor::replace_recovery_codes(
+        &transaction,
+        user.user_id,
+        &state.authentication_settings.recovery_code_key,
+    )
+    .await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit new recovery codes")?;
+    Ok(codes)
+}
+
+/// Checks the code the user typed to confirm a change, and returns the
+/// transaction to make the change in. Wrong codes count as failed logins
+/// like on `/login/2fa`, so a session alone can't guess codes endlessly.
+async fn verify_current_code<'a>(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    client: &'a mut Client,
+    code: &str,
+) -> Result<Transaction<'a>, CurrentCodeError> {
+    let now = state.clock.now();
+    let username = users::get_username()
+        .bind(&*client, &user.user_id)
+        .one()
+        .await
+        .context("Failed to fetch a username")?;
+    let attempt = LoginAttempt {
+        username: &username,
+        origin: &user.origin,
+    };
+    attempt.ensure_not_locked(&*client, now).await?;
+    let transaction = client
+        .transaction()
+        .await
+        .context("Failed to start transaction on pg connection")?;
+    match two_factor::verify(
+        &transaction,
+        &user.origin,
+        user.user_id,
+        code,
+        &state.authentication_settings.recovery_code_key,
+        now,
+    )
+    .await
+    {
+        Ok(()) => Ok(transaction),
+        Err(TwoFactorError::InvalidCode) => {
+            attempt
+                .record_failure(&transaction, &state.lockout_settings, now)
+                .await?;
+            transaction
+                .commit()
+                .await
+                .context("Failed to commit a failed 2FA attempt")?;
+            Err(CurrentCodeError::InvalidCode)
+        }
+        Err(TwoFactorError::UnexpectedError(e)) => Err(e.into()),
+    }
+}
+
+fn back_to_settings(flash: FlashMessage, state: &AppState) -> Response {
+    (
+        [(SET_COOKIE, flash.cookie(&state.session_settings))],
+        Redirect::to("/admin/2fa"),
+    )
+        .into_response()
+}
+
+fn internal_error(e: anyhow::Error) -> Response {
+    tracing::error!("{:?}", e);
+    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+}
@@ -0,0 +1,294 @@
+//! src/routes/admin/users.rs
+
+use anyhow::Context;
+use askama::Template;
+use axum::extract::{Path, State};
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::Form;
+use http::header::SET_COOKIE;
+use http::StatusCode;
+use secrecy::Secret;
+use uuid::Uuid;
+
+use crate::audit::{self, Actor};
+use crate::cornucopia::queries::users;
+use crate::domain::{Permission, UserRole};
+use crate::flash::{FlashMessage, IncomingFlashMessage};
+use crate::html_template_gen::UsersTemplate;
+use crate::management::{self, ChangeUserError, CreateUserError};
+use crate::session::AuthenticatedUser;
+use crate::startup::AppState;
+
+#[derive(serde::Deserialize)]
+pub struct NewUserFormData {
+    username: String,
+    /// Empty if the user doesn't need password reset links.
+    email: String,
+    password: Secret<String>,
+    role: String,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct UserRoleFormData {
+    role: String,
+}
+
+/// Owners only: lists admin users with their roles.
+#[tracing::instrument(skip_all, fields(user_id = %user.user_id))]
+pub async fn list_users(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    IncomingFlashMessage(flash): IncomingFlashMessage,
+) -> Response {
+    if let Err(e) = user
+        .require(&state, Permission::ManageUsers, "/admin/users")
+        .await
+    {
+        return e.into_response();
+    }
+    match render_users_page(&state, &user, flash.as_ref()).await {
+        Ok(page) => (
+            [(
+                SET_COOKIE,
+                FlashMessage::removal_cookie(&state.session_settings),
+            )],
+            Html(page),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("{:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %user.user_id))]
+pub async fn create_user(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Form(form): Form<NewUserFormData>,
+) -> Response {
+    if let Err(e) = user
+        .require(&state, Permission::ManageUsers, "/admin/users")
+        .await
+    {
+        return e.into_response();
+    }
+    let flash = match try_create_user(&state, &user, &form).await {
+        Ok(new_user_id) => {
+            tracing::info!(%new_user_id, "Admin user created");
+            FlashMessage::info(format!(
+                "User {} has been created.",
+                form.username.trim()
+            ))
+        }
+        Err(CreateUserError::Rejected(message)) => FlashMessage::error(message),
+        Err(e) => {
+            tracing::error!("{:?}", e);
+            FlashMessage::error("Failed to create the user.")
+        }
+    };
+    back_to_users_page(flash, &state)
+}
+
+/// Takes effect on the next request of the user, their sessions are kept.
+#[tracing::instrument(skip(state, user), fields(user_id = %user.user_id))]
+pub async fn change_user_role(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(target_user_id): Path<Uuid>,
+    Form(form): Form<UserRoleFormData>,
+) -> Response {
+    let target = format!("/admin/users/{}/role", target_user_id);
+    if let Err(e) = user.require(&state, Permission::ManageUsers, &target).await
+    {
+        return e.into_response();
+    }
+    let flash = if target_user_id == user.user_id {
+        FlashMessage::error("You can't change your own role.")
+    } else {
+        match UserRole::parse(&form.role) {
+            Ok(role) => {
+                match update_role(&state, &user, target_user_id, role).await {
+                    Ok(Some(_)) => {
+                        tracing::info!(%role, "User role changed");
+                        FlashMessage::info("The role has been changed.")
+                    }
+                    Ok(None) => FlashMessage::error("User not found."),
+                    Err(e @ ChangeUserError::LastOwner) => {
+                        FlashMessage::error(e.to_string())
+                    }
+                    Err(e) => {
+                        tracing::error!("{:?}", e);
+                        FlashMessage::error("Failed to change the role.")
+                    }
+                }
+            }
+            Err(e) => FlashMessage::error(e),
+        }
+    };
+    back_to_users_page(flash, &state)
+}
+
+/// Deletes a user, their sessions and reset links go with them.
+#[tracing::instrument(skip(state, user), fields(user_id = %user.user_id))]
+pub async fn delete_user(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(target_user_id): Path<Uuid>,
+) -> Response {
+    let target = format!("/admin/users/{}/delete", target_user_id);
+    if let Err(e) = user.require(&state, Permission::ManageUsers, &target).await
+    {
+        return e.into_response();
+    }
+    let flash = if target_user_id == user.user_id {
+        FlashMessage::error("You can't delete yourself.")
+    } else {
+        match remove_user(&state, &user, target_user_id).await {
+            Ok(Some(_)) => {
+                tracing::info!("User deleted");
+                FlashMessage::info("The user has been deleted.")
+            }
+            Ok(None) => FlashMessage::error("User not found."),
+            Err(e @ ChangeUserError::LastOwner) => {
+                FlashMessage::error(e.to_string())
+            }
+            Err(e) => {
+                tracing::error!("{:?}", e);
+                FlashMessage::error("Failed to delete the user.")
+            }
+        }
+    };
+    back_to_users_page(flash, &state)
+}
+
+async fn render_users_page(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    flash: Option<&FlashMessage>,
+) -> Result<String, anyhow::Error> {
+    let client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let users = users::list_users()
+        .bind(&client)
+        .all()
+        .await
+        .context("Failed to fetch users")?;
+    UsersTemplate::new(flash, &users, user.user_id, &user.csrf_token)
+        .render()
+        .context("Failed to render users page")
+}
+
+async fn try_create_user(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    form: &NewUserFormData,
+) -> Result<Uuid, CreateUserError> {
+    let role =
+        UserRole::parse(&form.role).map_err(CreateUserError::Rejected)?;
+    let email = Some(form.email.trim()).filter(|e| !e.is_empty());
+    management::create_user(
+        &state.pool,
+        &state.password_hashing,
+        &form.username,
+        form.password.clone(),
+        email,
+        role,
+        Actor::User {
+            user_id: user.user_id,
+            origin: &user.origin,
+        },
+    )
+    .await
+}
+
+/// Returns the username of the changed user, `None` if there is none.
+async fn update_role(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    user_id: Uuid,
+    role: UserRole,
+) -> Result<Option<String>, ChangeUserError> {
+    let mut client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let transaction = client
+        .transaction()
+        .await
+        .context("Failed to start transaction on pg connection")?;
+    if role != UserRole::Owner {
+        management::ensure_not_last_owner(&transaction, user_id).await?;
+    }
+    let username = users::update_user_role()
+        .bind(&transaction, &role.as_str(), &user_id)
+        .opt()
+        .await
+        .context("Failed to update user's role")?;
+    if let Some(ref username) = username {
+        audit::record_event(
+            &transaction,
+            &user.origin,
+            Some(user.user_id),
+            audit::USER_ROLE_CHANGED,
+            Some(&format!("{} {}", username, role)),
+        )
+        .await?;
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit a role change")?;
+    Ok(username)
+}
+
+/// Returns the username of the deleted user, `None` if there is none.
+async fn remove_user(
+    state: &AppState,
+    user: &AuthenticatedUser,
+    user_id: Uuid,
+) -> Result<Option<String>, ChangeUserError> {
+    let mut client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let transaction = client
+        .transaction()
+        .await
+        .context("Failed to start transaction on pg connection")?;
+    management::ensure_not_last_owner(&transaction, user_id).await?;
+    let username = users::delete_user_by_id()
+        .bind(&transaction, &user_id)
+        .opt()
+        .await
+        .context("Failed to delete a user")?;
+    if let Some(ref username) = username {
+        audit::record_event(
+            &transaction,
+            &user.origin,
+            Some(user.user_id),
+            audit::USER_DELETED,
+            Some(username),
+        )
+        .await?;
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit a user deletion")?;
+    Ok(username)
+}
+
+fn back_to_users_page(flash: FlashMessage, state: &AppState) -> Response {
+    (
+        [(SET_COOKIE, flash.cookie(&state.session_settings))],
+        Redirect::to("/admin/users"),
+    )
+        .into_response()
+}
@@ -0,0 +1,167 @@
+//! src/routes/audit_events.rs
+
+use anyhow::Context;
+use axum::extract::{Query, State};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use http::{HeaderMap, StatusCode};
+use time::format_description::well_known::Rfc3339;
+use uuid::Uuid;
+
+use crate::api_tokens::{authenticate, bearer_token, ApiForbidden};
+use crate::audit::{self, EventFilter, RequestOrigin};
+use crate::authentication::AuthError;
+use crate::domain::ApiScope;
+use crate::error_chain_fmt;
+use crate::pagination::Cursor;
+use crate::startup::AppState;
+
+/// Page size when the client doesn't ask for one, and the largest allowed.
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+#[derive(serde::Deserialize, Debug)]
+pub struct AuditEventsQuery {
+    action: Option<String>,
+    username: Option<String>,
+    /// `next_after` of the previous page.
+    after: Option<Cursor>,
+    limit: Option<i64>,
+}
+
+#[derive(serde::Serialize)]
+pub struct AuditEventEntry {
+    id: Uuid,
+    user_id: Option<Uuid>,
+    username: Option<String>,
+    action: String,
+    target: Option<String>,
+    client_ip: Option<String>,
+    user_agent: Option<String>,
+    /// RFC 3339.
+    created_at: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct AuditEventsPage {
+    events: Vec<AuditEventEntry>,
+    /// Pass as `after` to get the next page, `null` on the last one.
+    next_after: Option<Cursor>,
+}
+
+#[derive(thiserror::Error)]
+pub enum AuditEventsApiError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error("Authentication failed: {0}")]
+    AuthError(#[source] anyhow::Error),
+    #[error(transparent)]
+    Forbidden(#[from] ApiForbidden),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for AuditEventsApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl IntoResponse for AuditEventsApiError {
+    fn into_response(self) -> Response {
+        match self {
+            AuditEventsApiError::ValidationError(e) => {
+                (StatusCode::BAD_REQUEST, e).into_response()
+            }
+            AuditEventsApiError::AuthError(_) => (
+                StatusCode::UNAUTHORIZED,
+                [(http::header::WWW_AUTHENTICATE, r#"Bearer realm="read""#)],
+            )
+                .into_response(),
+            AuditEventsApiError::Forbidden(e) => e.into_response(),
+            AuditEventsApiError::UnexpectedError(e) => {
+                tracing::error!("{:?}", e);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+/// Audit events as JSON for API tokens with the `audit:read` scope, with
+/// the same filters and pagination as the admin page.
+#[tracing::instrument(
+    name = "List audit events with an API token",
+    skip(state, origin, headers),
+    fields(user_id=tracing::field::Empty, token_id=tracing::field::Empty)
+)]
+pub async fn get_audit_events(
+    State(state): State<AppState>,
+    origin: RequestOrigin,
+    headers: HeaderMap,
+    Query(query): Query<AuditEventsQuery>,
+) -> Result<Json<AuditEventsPage>, AuditEventsApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(AuditEventsApiError::ValidationError(format!(
+            "limit must be from 1 to {}",
+            MAX_LIMIT
+        )));
+    }
+    let filter = EventFilter {
+        action: query.action.as_deref(),
+        username: query.username.as_deref(),
+        after: query.after,
+    };
+    filter
+        .validate()
+        .map_err(AuditEventsApiError::ValidationError)?;
+
+    let client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let token = bearer_token(&headers)
+        .map_err(AuditEventsApiError::AuthError)?
+        .ok_or_else(|| {
+            AuditEventsApiError::AuthError(anyhow::anyhow!(
+                "An API token is required"
+            ))
+        })?;
+    let caller = authenticate(&token, &client).await.map_err(|e| match e {
+        AuthError::InvalidCredentials(_) => {
+            AuditEventsApiError::AuthError(e.into())
+        }
+        AuthError::UnexpectedError(e) => {
+            AuditEventsApiError::UnexpectedError(e)
+        }
+    })?;
+    let span = tracing::Span::current();
+    span.record("user_id", tracing::field::display(&caller.user_id));
+    span.record("token_id", tracing::field::display(&caller.token_id));
+    caller
+        .require(&state.pool, &origin, ApiScope::AuditRead, "/audit_events")
+        .await?;
+
+    let (rows, next_after) =
+        audit::list_events(&client, &filter, limit).await?;
+    let events = rows
+        .into_iter()
+        .map(|row| {
+            Ok(AuditEventEntry {
+                id: row.id,
+                user_id: row.user_id,
+                username: row.username,
+                action: row.action,
+                target: row.target,
+                client_ip: row.client_ip,
+                user_agent: row.user_agent,
+                created_at: row
+                    .created_at
+                    .format(&Rfc3339)
+                    .context("Failed to format event time")?,
+            })
+        })
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+    Ok(Json(AuditEventsPage { events, next_after }))
+}
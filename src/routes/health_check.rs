@@ -1,8 +1,20 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::Duration;
+
+use axum::extract::State;
 use axum::response::IntoResponse;
 
 use axum::body::Body;
+use axum::Json;
 use hyper::Request;
 use hyper::StatusCode;
+use serde::Serialize;
+
+use crate::startup::db_migration::{
+    applied_migrations, latest_embedded_migration,
+};
+use crate::startup::AppState;
 
 pub async fn health_check(_: Request<Body>) -> impl IntoResponse {
     tracing::info!("Healthy!");
@@ -12,3 +24,155 @@ pub async fn health_check(_: Request<Body>) -> impl IntoResponse {
 pub async fn get_hello(_: Request<Body>) -> impl IntoResponse {
     (StatusCode::OK, "Hello from rust-backend!")
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Error,
+    Skipped,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckReport {
+    pub status: CheckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Version of the newest embedded migration.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
+}
+
+impl CheckReport {
+    fn ok() -> Self {
+        Self {
+            status: CheckStatus::Ok,
+            error: None,
+            version: None,
+        }
+    }
+
+    fn error(e: impl std::fmt::Display) -> Self {
+        Self {
+            status: CheckStatus::Error,
+            error: Some(e.to_string()),
+            version: None,
+        }
+    }
+
+    fn skipped() -> Self {
+        Self {
+            status: CheckStatus::Skipped,
+            error: None,
+            version: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    pub status: CheckStatus,
+    pub checks: BTreeMap<&'static str, CheckReport>,
+}
+
+/// The process is up and serves requests, dependencies are not checked.
+pub async fn health_live() -> Json<HealthReport> {
+    Json(HealthReport {
+        status: CheckStatus::Ok,
+        checks: BTreeMap::new(),
+    })
+}
+
+/// Ready to serve traffic: a pool connection can be acquired, the newest
+/// embedded migration is applied and, if configured, the email delivery
+/// service can be reached. `503` with the failed checks otherwise.
+#[tracing::instrument(name = "Readiness probe", skip_all)]
+pub async fn health_ready(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<HealthReport>) {
+    let timeout = state.health_settings.timeout();
+    let (database, migrations, email_provider) = tokio::join!(
+        check_database(&state, timeout),
+        check_migrations(&state, timeout),
+        check_email_provider(&state, timeout),
+    );
+    let checks = BTreeMap::from([
+        ("database", database),
+        ("migrations", migrations),
+        ("email_provider", email_provider),
+    ]);
+
+    let failed: Vec<_> = checks
+        .iter()
+        .filter(|(_, check)| check.status == CheckStatus::Error)
+        .map(|(name, check)| (name, check.error.as_deref().unwrap_or("")))
+        .collect();
+    let (status_code, status) = if failed.is_empty() {
+        (StatusCode::OK, CheckStatus::Ok)
+    } else {
+        tracing::warn!("Not ready: {:?}", failed);
+        (StatusCode::SERVICE_UNAVAILABLE, CheckStatus::Error)
+    };
+    (status_code, Json(HealthReport { status, checks }))
+}
+
+async fn with_timeout<F, T>(
+    timeout: Duration,
+    check: F,
+) -> Result<T, anyhow::Error>
+where
+    F: Future<Output = Result<T, anyhow::Error>>,
+{
+    tokio::time::timeout(timeout, check)
+        .await
+        .unwrap_or_else(|_| Err(anyhow::anyhow!("Timed out after {timeout:?}")))
+}
+
+async fn check_database(state: &AppState, timeout: Duration) -> CheckReport {
+    match with_timeout(timeout, async {
+        state.pool.get().await.map_err(anyhow::Error::from)
+    })
+    .await
+    {
+        Ok(_) => CheckReport::ok(),
+        Err(e) => CheckReport::error(e),
+    }
+}
+
+async fn check_migrations(state: &AppState, timeout: Duration) -> CheckReport {
+    let Some(latest) = latest_embedded_migration() else {
+        return CheckReport::ok();
+    };
+    let version = Some(i64::from(latest.version()));
+    match with_timeout(timeout, applied_migrations(&state.pool)).await {
+        Ok(applied)
+            if applied.iter().any(|m| m.version() == latest.version()) =>
+        {
+            CheckReport {
+                version,
+                ..CheckReport::ok()
+            }
+        }
+        Ok(_) => CheckReport {
+            version,
+            ..CheckReport::error(format!("Migration {} is not applied", latest))
+        },
+        Err(e) => CheckReport {
+            version,
+            ..CheckReport::error(format!("{e:#}"))
+        },
+    }
+}
+
+async fn check_email_provider(
+    state: &AppState,
+    timeout: Duration,
+) -> CheckReport {
+    if !state.health_settings.check_email_provider {
+        return CheckReport::skipped();
+    }
+    match with_timeout(timeout, state.email_client.check_connection()).await {
+        Ok(()) => CheckReport::ok(),
+        Err(e) => CheckReport::error(format!("{e:#}")),
+    }
+}
@@ -1,51 +1,41 @@
 //! src/routes/login/get.rs
 
-use axum::{body::Body, extract::Query, response::IntoResponse};
-use http::{header::CONTENT_TYPE, HeaderMap, StatusCode};
+use askama::Template;
+use axum::extract::State;
+use axum::response::{Html, IntoResponse, Response};
+use http::header::SET_COOKIE;
+use http::{HeaderMap, StatusCode};
 
-#[derive(serde::Deserialize)]
-pub struct QueryParams {
-    error: Option<String>,
-}
+use crate::csrf::CsrfToken;
+use crate::flash::{FlashMessage, IncomingFlashMessage};
+use crate::html_template_gen::LoginTemplate;
+use crate::startup::AppState;
 
-pub async fn login_form(Query(query): Query<QueryParams>) -> impl IntoResponse {
-    let error_html = match query.error {
-        Some(error_message) => format!("<p><i>{error_message}</i></p>"),
-        None => "".into(),
-    };
-    let mut headers = HeaderMap::new();
-    headers.insert(CONTENT_TYPE, "text/html".parse().unwrap());
-    (
-        StatusCode::OK,
-        headers,
-        Body::new(format!(
-            r#"<!DOCTYPE html>
-                <html lang="en">
-                    <head>
-                        <meta http-equiv="content-type" content="text/html; charset=utf-8">
-                        <title>Login</title>
-                    </head>
-                    <body>
-                        {error_html}
-                        <form action="/login" method="post">
-                            <label>Username
-                                <input
-                                    type="text"
-                                    placeholder="Enter Username"
-                                    name="username"
-                                >
-                            </label>
-                            <label>Password
-                                <input
-                                    type="password"
-                                    placeholder="Enter Password"
-                                    name="password"
-                                >
-                            </label>
-                            <button type="submit">Login</button>
-                        </form>
-                    </body>
-                </html>"#
-        )),
-    )
+pub async fn login_form(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    IncomingFlashMessage(flash): IncomingFlashMessage,
+) -> Response {
+    let (csrf_token, csrf_cookie) =
+        CsrfToken::for_login_form(&headers, &state.session_settings);
+    match LoginTemplate::new(flash.as_ref(), &csrf_token).render() {
+        Ok(page) => {
+            let mut response = (
+                [(
+                    SET_COOKIE,
+                    FlashMessage::removal_cookie(&state.session_settings),
+                )],
+                Html(page),
+            )
+                .into_response();
+            if let Some(cookie) = csrf_cookie {
+                response.headers_mut().append(SET_COOKIE, cookie);
+            }
+            response
+        }
+        Err(e) => {
+            tracing::error!("Failed to render login page: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
 }
@@ -1,17 +1,22 @@
-//! src/routes/login/get.rs
+//! src/routes/login/post.rs
 
 use axum::extract::State;
-use axum::response::{IntoResponse, Response};
+use axum::response::{IntoResponse, Redirect, Response};
 use axum::Form;
-use http::header::LOCATION;
+use http::header::{LOCATION, SET_COOKIE};
 use http::{HeaderMap, StatusCode};
 use secrecy::Secret;
 
+use crate::audit::{self, RequestOrigin};
 use crate::authentication;
 use crate::authentication::validate_credentials;
 use crate::authentication::Credentials;
 use crate::error_chain_fmt;
+use crate::flash::FlashMessage;
+use crate::login_lockout::{LockoutError, LoginAttempt};
+use crate::session::{start_session, SessionId};
 use crate::startup::AppState;
+use crate::two_factor;
 
 #[derive(serde::Deserialize, Debug)]
 pub struct FormData {
@@ -23,6 +28,8 @@ pub struct FormData {
 pub enum LoginError {
     #[error("Authentication failed")]
     AuthError(#[source] anyhow::Error),
+    #[error(transparent)]
+    LockedOut(LockoutError),
     #[error("Something went wrong")]
     UnexpectedError(#[from] anyhow::Error),
 }
@@ -33,57 +40,47 @@ impl std::fmt::Debug for LoginError {
     }
 }
 
-impl IntoResponse for LoginError {
-    fn into_response(self) -> Response {
-        let encoded_error = urlencoding::Encoded::new(self.to_string());
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            LOCATION,
-            format!("/login?error={}", encoded_error).parse().unwrap(),
-        );
-        headers.into_response()
-        // match self {
-        //     LoginError::AuthError(_) => {
-        //         StatusCode::UNAUTHORIZED.into_response()
-        //     }
-        //     LoginError::UnexpectedError(_) => {
-        //         StatusCode::INTERNAL_SERVER_ERROR.into_response()
-        //     }
-        // PublishError::UnexpectedError(_) => {
-        //     StatusCode::INTERNAL_SERVER_ERROR.into_response()
-        // }
-        // PublishError::InternalError => {
-        //     StatusCode::INTERNAL_SERVER_ERROR.into_response()
-        // }
-        // PublishError::AuthError(_) => axum::response::Response::builder()
-        //     .status(StatusCode::UNAUTHORIZED)
-        //     .header(
-        //         http::header::WWW_AUTHENTICATE,
-        //         http::HeaderValue::from_str(r#"Basic realm="publish""#)
-        //             .unwrap(),
-        //     )
-        //     .body(axum::body::Body::empty())
-        //     .unwrap(),
-        // }
-    }
-}
-
+/// Failures are shown on the login form as a flash message.
 #[tracing::instrument(
-    skip(state, form),
+    skip(state, origin, form),
     fields(username=tracing::field::Empty, user_id=tracing::field::Empty)
 )]
 pub async fn login(
     State(state): State<AppState>,
-    form: Form<FormData>,
+    origin: RequestOrigin,
+    request_headers: HeaderMap,
+    Form(form): Form<FormData>,
+) -> Response {
+    match try_login(&state, &origin, &request_headers, form).await {
+        Ok(response) => response,
+        Err(e) => {
+            if let LoginError::UnexpectedError(_) = e {
+                tracing::error!("{:?}", e);
+            }
+            let flash = FlashMessage::error(e.to_string());
+            (
+                [(SET_COOKIE, flash.cookie(&state.session_settings))],
+                Redirect::to("/login"),
+            )
+                .into_response()
+        }
+    }
+}
+
+async fn try_login(
+    state: &AppState,
+    origin: &RequestOrigin,
+    request_headers: &HeaderMap,
+    form: FormData,
 ) -> Result<Response, LoginError> {
     let mut headers = HeaderMap::new();
-    headers.insert(LOCATION, "/".parse().unwrap());
+    headers.insert(LOCATION, "/admin/dashboard".parse().unwrap());
     let credentials = Credentials {
-        username: form.0.username,
-        password: form.0.password,
+        username: form.username,
+        password: form.password,
     };
     tracing::Span::current()
-        .record("username", &tracing::field::display(&credentials.username));
+        .record("username", tracing::field::display(&credentials.username));
 
     let connection = match state.pool.get().await {
         Ok(c) => c,
@@ -95,19 +92,68 @@ pub async fn login(
         }
     };
 
-    tracing::info!("Checking credentials");
-    let user_id = validate_credentials(credentials, &connection)
+    let username = credentials.username.clone();
+    let attempt = LoginAttempt {
+        username: &username,
+        origin,
+    };
+    let now = state.clock.now();
+    attempt
+        .ensure_not_locked(&connection, now)
         .await
         .map_err(|e| match e {
-            authentication::AuthError::InvalidCredentials(_) => {
-                LoginError::AuthError(e.into())
-            }
-            authentication::AuthError::UnexpectedError(_) => {
-                LoginError::UnexpectedError(e.into())
-            }
+            LockoutError::Locked { .. } => LoginError::LockedOut(e),
+            LockoutError::UnexpectedError(e) => LoginError::UnexpectedError(e),
         })?;
+
+    tracing::info!("Checking credentials");
+    let user_id = match validate_credentials(
+        credentials,
+        &state.password_hashing,
+        &connection,
+    )
+    .await
+    {
+        Ok(user_id) => user_id,
+        Err(e @ authentication::AuthError::InvalidCredentials(_)) => {
+            attempt
+                .record_failure(&connection, &state.lockout_settings, now)
+                .await?;
+            return Err(LoginError::AuthError(e.into()));
+        }
+        Err(e @ authentication::AuthError::UnexpectedError(_)) => {
+            return Err(LoginError::UnexpectedError(e.into()))
+        }
+    };
     tracing::Span::current()
-        .record("user_id", &tracing::field::display(&user_id));
-    tracing::info!("Redirect to /");
+        .record("user_id", tracing::field::display(&user_id));
+
+    // The session is started by `/login/2fa` once the code is checked,
+    // failures are only reset there.
+    if two_factor::is_enabled(&connection, user_id).await? {
+        let challenge =
+            two_factor::start_challenge(&connection, user_id, now).await?;
+        headers.insert(LOCATION, "/login/2fa".parse().unwrap());
+        headers.insert(SET_COOKIE, challenge.cookie(&state.session_settings));
+        tracing::info!("Redirect to /login/2fa");
+        return Ok((StatusCode::SEE_OTHER, headers).into_response());
+    }
+
+    attempt.record_success(&connection).await?;
+    // Rotate the session id on login to prevent session fixation.
+    let previous_session = SessionId::from_headers(request_headers);
+    let session_id = start_session(
+        &connection,
+        user_id,
+        previous_session.as_ref(),
+        &state.session_settings,
+    )
+    .await
+    .map_err(LoginError::UnexpectedError)?;
+    audit::record_event(&connection, origin, Some(user_id), audit::LOGIN, None)
+        .await?;
+    headers.insert(SET_COOKIE, session_id.cookie(&state.session_settings));
+
+    tracing::info!("Redirect to /admin/dashboard");
     Ok((StatusCode::SEE_OTHER, headers).into_response())
 }
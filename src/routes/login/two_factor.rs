@@ -0,0 +1,211 @@
+//! src/routes/login/two_factor.rs
+
+use anyhow::Context;
+use askama::Template;
+use axum::extract::State;
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum::Form;
+use http::header::SET_COOKIE;
+use http::{HeaderMap, StatusCode};
+
+use crate::audit::{self, RequestOrigin};
+use crate::cornucopia::queries::users;
+use crate::csrf::CsrfToken;
+use crate::flash::{FlashMessage, IncomingFlashMessage};
+use crate::html_template_gen::LoginTwoFactorTemplate;
+use crate::login_lockout::{LockoutError, LoginAttempt};
+use crate::session::{start_session, SessionId};
+use crate::startup::AppState;
+use crate::two_factor::{self, LoginChallenge, TwoFactorError};
+
+#[derive(serde::Deserialize)]
+pub struct TwoFactorFormData {
+    /// TOTP code or recovery code.
+    code: String,
+}
+
+/// Second login step, only reachable with a pending login challenge.
+pub async fn login_two_factor_form(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    IncomingFlashMessage(flash): IncomingFlashMessage,
+) -> Response {
+    if LoginChallenge::from_headers(&headers).is_none() {
+        return Redirect::to("/login").into_response();
+    }
+    let (csrf_token, csrf_cookie) =
+        CsrfToken::for_login_form(&headers, &state.session_settings);
+    match LoginTwoFactorTemplate::new(flash.as_ref(), &csrf_token).render() {
+        Ok(page) => {
+            let mut response = (
+                [(
+                    SET_COOKIE,
+                    FlashMessage::removal_cookie(&state.session_settings),
+                )],
+                Html(page),
+            )
+                .into_response();
+            if let Some(cookie) = csrf_cookie {
+                response.headers_mut().append(SET_COOKIE, cookie);
+            }
+            response
+        }
+        Err(e) => {
+            tracing::error!("Failed to render 2FA page: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Checks the code of a pending login and starts the session. After too
+/// many wrong codes the challenge is dropped and the password has to be
+/// entered again. Wrong codes also count as failed logins, so restarting
+/// the challenge doesn't allow to guess codes past the lockout.
+#[tracing::instrument(skip_all, fields(user_id=tracing::field::Empty))]
+pub async fn login_two_factor(
+    State(state): State<AppState>,
+    origin: RequestOrigin,
+    headers: HeaderMap,
+    Form(form): Form<TwoFactorFormData>,
+) -> Response {
+    match try_login_two_factor(&state, &origin, &headers, &form).await {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::error!("{:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn try_login_two_factor(
+    state: &AppState,
+    origin: &RequestOrigin,
+    headers: &HeaderMap,
+    form: &TwoFactorFormData,
+) -> Result<Response, anyhow::Error> {
+    let Some(challenge) = LoginChallenge::from_headers(headers) else {
+        return Ok(Redirect::to("/login").into_response());
+    };
+    let now = state.clock.now();
+    let mut client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let Some(user_id) =
+        two_factor::challenge_user(&client, &challenge, now).await?
+    else {
+        return Ok(back_to_login(
+            state,
+            "Your login has expired, please log in again.",
+        ));
+    };
+    tracing::Span::current()
+        .record("user_id", tracing::field::display(&user_id));
+    let username = users::get_username()
+        .bind(&client, &user_id)
+        .one()
+        .await
+        .context("Failed to fetch a username")?;
+    let attempt = LoginAttempt {
+        username: &username,
+        origin,
+    };
+    match attempt.ensure_not_locked(&client, now).await {
+        Ok(()) => {}
+        Err(e @ LockoutError::Locked { .. }) => {
+            return Ok(back_to_login(state, &e.to_string()));
+        }
+        Err(LockoutError::UnexpectedError(e)) => return Err(e),
+    }
+
+    let transaction = client
+        .transaction()
+        .await
+        .context("Failed to start transaction on pg connection")?;
+    match two_factor::verify(
+        &transaction,
+        origin,
+        user_id,
+        &form.code,
+        &state.authentication_settings.recovery_code_key,
+        now,
+    )
+    .await
+    {
+        Ok(()) => {}
+        Err(TwoFactorError::InvalidCode) => {
+            let retry =
+                two_factor::fail_challenge(&transaction, &challenge).await?;
+            attempt
+                .record_failure(&transaction, &state.lockout_settings, now)
+                .await?;
+            transaction
+                .commit()
+                .await
+                .context("Failed to commit a failed 2FA attempt")?;
+            tracing::warn!("Wrong second factor");
+            if !retry {
+                return Ok(back_to_login(
+                    state,
+                    "Too many wrong codes, please log in again.",
+                ));
+            }
+            let flash = FlashMessage::error("The code is not valid.");
+            return Ok((
+                [(SET_COOKIE, flash.cookie(&state.session_settings))],
+                Redirect::to("/login/2fa"),
+            )
+                .into_response());
+        }
+        Err(TwoFactorError::UnexpectedError(e)) => return Err(e),
+    }
+    two_factor::end_challenge(&transaction, &challenge).await?;
+    attempt.record_success(&transaction).await?;
+    audit::record_event(
+        &transaction,
+        origin,
+        Some(user_id),
+        audit::LOGIN,
+        None,
+    )
+    .await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit a 2FA login")?;
+
+    // Rotate the session id on login to prevent session fixation.
+    let previous_session = SessionId::from_headers(headers);
+    let session_id = start_session(
+        &client,
+        user_id,
+        previous_session.as_ref(),
+        &state.session_settings,
+    )
+    .await?;
+    let mut response = Redirect::to("/admin/dashboard").into_response();
+    let response_headers = response.headers_mut();
+    response_headers
+        .append(SET_COOKIE, session_id.cookie(&state.session_settings));
+    response_headers.append(
+        SET_COOKIE,
+        LoginChallenge::removal_cookie(&state.session_settings),
+    );
+    Ok(response)
+}
+
+/// Drop the pending login and send the user to the password form.
+fn back_to_login(state: &AppState, error: &str) -> Response {
+    let mut response = Redirect::to("/login").into_response();
+    let headers = response.headers_mut();
+    headers.append(
+        SET_COOKIE,
+        LoginChallenge::removal_cookie(&state.session_settings),
+    );
+    headers.append(
+        SET_COOKIE,
+        FlashMessage::error(error).cookie(&state.session_settings),
+    );
+    response
+}
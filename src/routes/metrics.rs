@@ -0,0 +1,128 @@
+//! src/routes/metrics.rs
+
+use axum::extract::State;
+use axum::response::{IntoResponse, Response};
+use http::header::CONTENT_TYPE;
+use http::{HeaderMap, StatusCode};
+use secrecy::ExposeSecret;
+
+use crate::client_ip::ClientIp;
+use crate::configuration::MetricsSettings;
+use crate::constant_time_eq;
+use crate::cornucopia::queries::newsletters;
+use crate::metrics::Gauges;
+use crate::startup::AppState;
+
+/// Content type of the Prometheus text format.
+const TEXT_FORMAT: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Only served to the allowed client IPs, or with the bearer token.
+pub async fn get_metrics(
+    State(state): State<AppState>,
+    client_ip: ClientIp,
+    headers: HeaderMap,
+) -> Response {
+    if !is_allowed(&state.metrics_settings, client_ip, &headers) {
+        tracing::warn!("Metrics requested by {}", client_ip);
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let status = state.pool.status();
+    let gauges = Gauges {
+        pool_max_size: status.max_size,
+        pool_size: status.size,
+        pool_available: status.available,
+        pool_waiting: status.waiting,
+        pending_deliveries: count_delivery_tasks(&state).await,
+    };
+    ([(CONTENT_TYPE, TEXT_FORMAT)], state.metrics.render(&gauges))
+        .into_response()
+}
+
+fn is_allowed(
+    settings: &MetricsSettings,
+    client_ip: ClientIp,
+    headers: &HeaderMap,
+) -> bool {
+    if settings.allowed_ips.contains(&client_ip.0) {
+        return true;
+    }
+    let Some(expected) = &settings.bearer_token else {
+        return false;
+    };
+    headers
+        .get(http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| {
+            constant_time_eq(token.trim(), expected.expose_secret())
+        })
+}
+
+/// The rest of the metrics are still served when the database is down.
+async fn count_delivery_tasks(state: &AppState) -> Option<i64> {
+    let client = match state.pool.get().await {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!("Failed to get connection from pool: {e}");
+            return None;
+        }
+    };
+    match newsletters::count_delivery_tasks()
+        .bind(&client)
+        .one()
+        .await
+    {
+        Ok(count) => Some(count),
+        Err(e) => {
+            tracing::error!("Failed to count delivery tasks: {e}");
+            None
+        }
+    }
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderMap;
+    use secrecy::Secret;
+
+    use super::is_allowed;
+    use crate::client_ip::ClientIp;
+    use crate::configuration::MetricsSettings;
+
+    fn client(ip: &str) -> ClientIp {
+        ClientIp(ip.parse().unwrap())
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            http::header::AUTHORIZATION,
+            format!("Bearer {token}").parse().unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn only_loopback_is_allowed_by_default() {
+        let settings = MetricsSettings::default();
+        let headers = HeaderMap::new();
+        assert!(is_allowed(&settings, client("127.0.0.1"), &headers));
+        assert!(is_allowed(&settings, client("::1"), &headers));
+        assert!(!is_allowed(&settings, client("203.0.113.7"), &headers));
+        assert!(!is_allowed(&settings, client("203.0.113.7"), &bearer("")));
+    }
+
+    #[test]
+    fn other_clients_need_the_bearer_token() {
+        let settings = MetricsSettings {
+            bearer_token: Some(Secret::new("scrape-me".to_string())),
+            ..Default::default()
+        };
+        let ip = client("203.0.113.7");
+        assert!(is_allowed(&settings, ip, &bearer("scrape-me")));
+        assert!(!is_allowed(&settings, ip, &bearer("scrape-you")));
+        assert!(!is_allowed(&settings, ip, &HeaderMap::new()));
+    }
+}
@@ -1,13 +1,27 @@
+pub use admin::*;
+pub use audit_events::*;
 pub use health_check::*;
 pub use home::*;
 pub use login::*;
+pub use metrics::*;
 pub use newsletters::*;
+pub use password_reset::*;
+pub use subscribers::*;
 pub use subscriptions::*;
 pub use subscriptions_confirm::*;
+pub use subscriptions_resend::*;
+pub use subscriptions_unsubscribe::*;
 
+mod admin;
+mod audit_events;
 mod health_check;
 mod home;
 mod login;
+mod metrics;
 mod newsletters;
+mod password_reset;
+mod subscribers;
 mod subscriptions;
 mod subscriptions_confirm;
+mod subscriptions_resend;
+mod subscriptions_unsubscribe;
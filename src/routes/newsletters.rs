@@ -3,33 +3,51 @@ use axum::extract::State;
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use base64::Engine;
-use deadpool_postgres::Client;
+use deadpool_postgres::Transaction;
 use http::HeaderMap;
 use hyper::StatusCode;
 use secrecy::Secret;
+use time::OffsetDateTime;
+use uuid::Uuid;
 
+use crate::api_tokens::{authenticate, bearer_token, ApiForbidden};
+use crate::audit::{self, RequestOrigin};
+use crate::authentication::{authorize, get_user_role, Forbidden};
 use crate::authentication::{validate_credentials, AuthError, Credentials};
-use crate::cornucopia::queries::newsletters::query_confirmed_subscribers;
-use crate::domain::SubscriberEmail;
+use crate::cornucopia::queries::newsletters;
+use crate::domain::{ApiScope, Permission};
 use crate::error_chain_fmt;
+use crate::idempotency::{save_response, try_processing};
+use crate::idempotency::{IdempotencyKey, NextAction};
+use crate::login_lockout::{LockoutError, LoginAttempt};
 use crate::startup::AppState;
 
 #[derive(serde::Deserialize)]
 pub struct BodyData {
-    title: String,
-    content: Content,
+    pub(crate) title: String,
+    pub(crate) content: Content,
 }
 
 #[derive(serde::Deserialize)]
 pub struct Content {
-    html: String,
-    text: String,
+    pub(crate) html: String,
+    pub(crate) text: String,
 }
 
 #[derive(thiserror::Error)]
 pub enum PublishError {
+    #[error("{0}")]
+    ValidationError(String),
     #[error("Authentication failed: {0}")]
     AuthError(#[source] anyhow::Error),
+    #[error("API token authentication failed: {0}")]
+    BearerAuthError(#[source] anyhow::Error),
+    #[error(transparent)]
+    LockedOut(LockoutError),
+    #[error(transparent)]
+    Forbidden(#[from] Forbidden),
+    #[error(transparent)]
+    ApiForbidden(#[from] ApiForbidden),
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
     #[error("Internal error")]
@@ -46,38 +64,137 @@ impl std::fmt::Debug for PublishError {
 impl IntoResponse for PublishError {
     fn into_response(self) -> Response {
         match self {
+            PublishError::ValidationError(e) => {
+                (StatusCode::BAD_REQUEST, e).into_response()
+            }
             PublishError::UnexpectedError(_) => {
                 StatusCode::INTERNAL_SERVER_ERROR.into_response()
             }
             PublishError::InternalError => {
                 StatusCode::INTERNAL_SERVER_ERROR.into_response()
             }
-            PublishError::AuthError(_) => axum::response::Response::builder()
-                .status(StatusCode::UNAUTHORIZED)
-                .header(
-                    http::header::WWW_AUTHENTICATE,
-                    http::HeaderValue::from_str(r#"Basic realm="publish""#)
-                        .unwrap(),
-                )
-                .body(axum::body::Body::empty())
-                .unwrap(),
+            PublishError::LockedOut(LockoutError::Locked { retry_after }) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(http::header::RETRY_AFTER, retry_after.to_string())],
+            )
+                .into_response(),
+            PublishError::LockedOut(LockoutError::UnexpectedError(_)) => {
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+            PublishError::Forbidden(e) => e.into_response(),
+            PublishError::ApiForbidden(e) => e.into_response(),
+            PublishError::AuthError(_) => {
+                unauthorized(r#"Basic realm="publish""#)
+            }
+            PublishError::BearerAuthError(_) => {
+                unauthorized(r#"Bearer realm="publish""#)
+            }
         }
     }
 }
 
 #[tracing::instrument(
     name = "Publish a newsletter issue",
-    skip(state, body),
-    fields(username=tracing::field::Empty, user_id=tracing::field::Empty)
+    skip(state, origin, body),
+    fields(
+        username=tracing::field::Empty,
+        user_id=tracing::field::Empty,
+        token_id=tracing::field::Empty
+    )
 )]
 #[axum::debug_handler]
 pub async fn publish_newsletters(
     State(state): State<AppState>,
+    origin: RequestOrigin,
     headers: HeaderMap,
     Json(body): Json<BodyData>,
-) -> Result<StatusCode, PublishError> {
+) -> Result<Response, PublishError> {
+    let mut connection = match state.pool.get().await {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::error!("Failed to get connection from pool: {}", e);
+            return Err(PublishError::InternalError);
+        }
+    };
+
+    let token = bearer_token(&headers).map_err(|e| {
+        tracing::error!("Failed to auth: {e}");
+        PublishError::BearerAuthError(e)
+    })?;
+    let user_id = match token {
+        Some(token) => {
+            let caller =
+                authenticate(&token, &connection).await.map_err(|e| {
+                    tracing::error!("Error: {e}");
+                    match e {
+                        AuthError::InvalidCredentials(_) => {
+                            PublishError::BearerAuthError(e.into())
+                        }
+                        AuthError::UnexpectedError(_) => {
+                            PublishError::UnexpectedError(e.into())
+                        }
+                    }
+                })?;
+            tracing::Span::current()
+                .record("token_id", tracing::field::display(&caller.token_id));
+            caller
+                .require(
+                    &state.pool,
+                    &origin,
+                    ApiScope::NewslettersPublish,
+                    "/newsletters",
+                )
+                .await?;
+            caller.user_id
+        }
+        None if state.authentication_settings.basic_auth_enabled => {
+            basic_auth_user(&state, &origin, &headers, &connection).await?
+        }
+        None => {
+            tracing::error!("Failed to auth: no API token");
+            return Err(PublishError::BearerAuthError(anyhow::anyhow!(
+                "An API token is required, Basic auth is disabled"
+            )));
+        }
+    };
+
+    tracing::Span::current()
+        .record("user_id", tracing::field::display(&user_id));
+
+    let idempotency_key = match headers.get("Idempotency-Key") {
+        Some(value) => {
+            let key = value
+                .to_str()
+                .map_err(|_| {
+                    "The 'Idempotency-Key' header was not a valid UTF8 string."
+                })
+                .and_then(IdempotencyKey::parse)
+                .map_err(|e| PublishError::ValidationError(e.to_string()))?;
+            Some(key)
+        }
+        None => None,
+    };
+
+    publish_issue(
+        &mut connection,
+        &origin,
+        user_id,
+        idempotency_key.as_ref(),
+        &body,
+        StatusCode::ACCEPTED.into_response(),
+    )
+    .await
+}
+
+/// The username and password flow, kept for clients without API tokens.
+async fn basic_auth_user(
+    state: &AppState,
+    origin: &RequestOrigin,
+    headers: &HeaderMap,
+    connection: &deadpool_postgres::Client,
+) -> Result<Uuid, PublishError> {
     let credentials =
-        match basic_authentication(&headers).map_err(PublishError::AuthError) {
+        match basic_authentication(headers).map_err(PublishError::AuthError) {
             Ok(c) => c,
             Err(e) => {
                 tracing::error!("Failed to auth: {e}");
@@ -86,23 +203,42 @@ pub async fn publish_newsletters(
         };
 
     tracing::Span::current()
-        .record("username", &tracing::field::display(&credentials.username));
+        .record("username", tracing::field::display(&credentials.username));
 
-    let connection = match state.pool.get().await {
-        Ok(c) => c,
-        Err(e) => {
-            tracing::error!("Failed to get connection from pool: {}", e);
-            return Err(PublishError::InternalError);
-        }
+    let username = credentials.username.clone();
+    let attempt = LoginAttempt {
+        username: &username,
+        origin,
     };
+    let now = state.clock.now();
+    attempt
+        .ensure_not_locked(connection, now)
+        .await
+        .map_err(|e| {
+            tracing::error!("Error: {e}");
+            PublishError::LockedOut(e)
+        })?;
 
-    let user_id = match validate_credentials(credentials, &connection).await {
+    let user_id = match validate_credentials(
+        credentials,
+        &state.password_hashing,
+        connection,
+    )
+    .await
+    {
         Ok(id) => id,
         Err(e) => {
             tracing::error!("Error: {e}");
             match e {
                 AuthError::InvalidCredentials(_) => {
-                    return Err(PublishError::AuthError(e.into()))
+                    attempt
+                        .record_failure(
+                            connection,
+                            &state.lockout_settings,
+                            now,
+                        )
+                        .await?;
+                    return Err(PublishError::AuthError(e.into()));
                 }
                 AuthError::UnexpectedError(_) => {
                     return Err(PublishError::UnexpectedError(e.into()))
@@ -110,47 +246,122 @@ pub async fn publish_newsletters(
             }
         }
     };
+    attempt.record_success(connection).await?;
 
-    tracing::Span::current()
-        .record("user_id", &tracing::field::display(&user_id));
-
-    let subscribers = fetch_confirmed_subscribers(connection).await?;
-    for subscriber in subscribers.into_iter() {
-        state
-            .email_client
-            .send_email(
-                &subscriber,
-                &body.title,
-                &body.content.html,
-                &body.content.text,
-            )
-            .await
-            .map_err(|e| anyhow::Error::new(e))?;
-    }
-    Ok(StatusCode::OK)
+    let role = get_user_role(user_id, connection).await?;
+    authorize(
+        &state.pool,
+        origin,
+        user_id,
+        role,
+        Permission::PublishNewsletters,
+        "/newsletters",
+    )
+    .await?;
+    Ok(user_id)
 }
 
-#[tracing::instrument(name = "Get confirmed subscribers", skip(client))]
-async fn fetch_confirmed_subscribers(
-    client: Client,
-) -> Result<Vec<SubscriberEmail>, anyhow::Error> {
-    query_confirmed_subscribers()
-        .bind(&client)
-        .map(|e| match SubscriberEmail::parse(e) {
-            Ok(s) => Some(s),
-            Err(e) => {
-                tracing::warn!("Failed to parse subscriber email: {}", e);
-                None
+/// Stores the issue and enqueues its delivery in a single transaction,
+/// then returns `response`. With an idempotency key, a retried request
+/// gets the saved response instead of publishing the issue twice.
+/// Shared by the JSON API and the admin form.
+#[tracing::instrument(skip(connection, origin, body, response))]
+pub(crate) async fn publish_issue(
+    connection: &mut deadpool_postgres::Client,
+    origin: &RequestOrigin,
+    user_id: Uuid,
+    idempotency_key: Option<&IdempotencyKey>,
+    body: &BodyData,
+    response: Response,
+) -> Result<Response, PublishError> {
+    let transaction = connection
+        .transaction()
+        .await
+        .context("Failed to start transaction on pg connection")?;
+
+    // The key row stays locked until commit, so concurrent retries wait
+    // for us and then get the saved response instead of publishing again.
+    if let Some(key) = idempotency_key {
+        match try_processing(&transaction, key, user_id).await? {
+            NextAction::StartProcessing => {}
+            NextAction::ReturnSavedResponse(saved_response) => {
+                tracing::info!(
+                    "Returning saved response for a retried request"
+                );
+                return Ok(saved_response);
             }
-        })
-        .all()
+        }
+    }
+
+    let issue_id = insert_newsletter_issue(&transaction, body).await?;
+    enqueue_delivery_tasks(&transaction, issue_id).await?;
+    audit::record_event(
+        &transaction,
+        origin,
+        Some(user_id),
+        audit::NEWSLETTER_PUBLISHED,
+        Some(&issue_id.to_string()),
+    )
+    .await?;
+
+    let response = match idempotency_key {
+        Some(key) => {
+            save_response(&transaction, key, user_id, response).await?
+        }
+        None => response,
+    };
+    transaction.commit().await.context(
+        "Failed to commit SQL transaction to store a newsletter issue",
+    )?;
+
+    Ok(response)
+}
+
+#[tracing::instrument(name = "Store newsletter issue", skip_all)]
+async fn insert_newsletter_issue(
+    transaction: &Transaction<'_>,
+    body: &BodyData,
+) -> Result<Uuid, anyhow::Error> {
+    let newsletter_issue_id = Uuid::new_v4();
+    newsletters::insert_newsletter_issue()
+        .bind(
+            transaction,
+            &newsletter_issue_id,
+            &body.title.as_str(),
+            &body.content.text.as_str(),
+            &body.content.html.as_str(),
+            &OffsetDateTime::now_utc(),
+        )
         .await
-        .and_then(|v| Ok(v.into_iter().flatten().collect()))
-        .context("Failed to fetch confirmed subscribers")
+        .context("Failed to store newsletter issue details")?;
+    Ok(newsletter_issue_id)
+}
+
+#[tracing::instrument(name = "Enqueue delivery tasks", skip(transaction))]
+async fn enqueue_delivery_tasks(
+    transaction: &Transaction<'_>,
+    newsletter_issue_id: Uuid,
+) -> Result<(), anyhow::Error> {
+    newsletters::enqueue_delivery_tasks()
+        .bind(transaction, &newsletter_issue_id)
+        .await
+        .context("Failed to enqueue delivery tasks")?;
+    Ok(())
 }
 
 // ───── Helpers ──────────────────────────────────────────────────────────── //
 
+fn unauthorized(challenge: &'static str) -> Response {
+    axum::response::Response::builder()
+        .status(StatusCode::UNAUTHORIZED)
+        .header(
+            http::header::WWW_AUTHENTICATE,
+            http::HeaderValue::from_static(challenge),
+        )
+        .body(axum::body::Body::empty())
+        .unwrap()
+}
+
 /// Great example where `anyhow` is awesome. It can wrap different error types,
 /// and return single. It very convenient if we shouldn't sort all kind of errors,
 /// but instead just print error to logs.
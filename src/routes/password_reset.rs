@@ -0,0 +1,371 @@
+//! src/routes/password_reset.rs
+
+use anyhow::Context;
+use askama::Template;
+use axum::extract::{Query, State};
+use axum::response::{Html, IntoResponse, Response};
+use axum::Form;
+use http::header::SET_COOKIE;
+use http::{HeaderMap, HeaderValue};
+use hyper::StatusCode;
+use secrecy::{ExposeSecret, Secret};
+use time::OffsetDateTime;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::audit::{self, RequestOrigin};
+use crate::authentication;
+use crate::client_ip::ClientIp;
+use crate::cornucopia::queries::sessions;
+use crate::cornucopia::queries::users;
+use crate::csrf::CsrfToken;
+use crate::domain::{AdminPassword, SubscriberEmail};
+use crate::error_chain_fmt;
+use crate::html_template_gen::{ForgotPasswordTemplate, ResetPasswordTemplate};
+use crate::rate_limit::RateLimitError;
+use crate::startup::AppState;
+use crate::validation::password_reset_token::PasswordResetToken;
+
+#[derive(serde::Deserialize, Debug)]
+pub struct ForgotPasswordFormData {
+    email: String,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct ResetPasswordParameters {
+    token: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ResetPasswordFormData {
+    token: String,
+    new_password: Secret<String>,
+    new_password_check: Secret<String>,
+}
+
+#[derive(thiserror::Error)]
+pub enum PasswordResetError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error("The password reset link is invalid or has already been used")]
+    UnknownToken,
+    #[error("The password reset link has expired")]
+    ExpiredToken,
+    #[error(transparent)]
+    RateLimited(#[from] RateLimitError),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for PasswordResetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl IntoResponse for PasswordResetError {
+    fn into_response(self) -> Response {
+        match self {
+            PasswordResetError::ValidationError(e) => {
+                (StatusCode::BAD_REQUEST, e).into_response()
+            }
+            PasswordResetError::UnknownToken => {
+                (StatusCode::NOT_FOUND, self.to_string()).into_response()
+            }
+            PasswordResetError::ExpiredToken => (
+                StatusCode::GONE,
+                "The password reset link has expired, \
+                please request a new one at /password/forgot",
+            )
+                .into_response(),
+            PasswordResetError::RateLimited(e) => e.into_response(),
+            PasswordResetError::UnexpectedError(e) => {
+                tracing::error!("{:?}", e);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+pub async fn forgot_password_form(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, PasswordResetError> {
+    let (csrf_token, csrf_cookie) =
+        CsrfToken::for_login_form(&headers, &state.session_settings);
+    let page = ForgotPasswordTemplate::form(&csrf_token)
+        .render()
+        .context("Failed to render forgot password page")?;
+    Ok(with_csrf_cookie(Html(page), csrf_cookie))
+}
+
+/// Emails a reset link if the address belongs to an admin. The answer is
+/// the same for unknown addresses, so it can't be used to find accounts:
+/// the lookup and the email happen after the response. Requests are rate
+/// limited per client IP and per email.
+#[tracing::instrument(
+    name = "Request a password reset",
+    skip_all,
+    fields(user_id=tracing::field::Empty)
+)]
+pub async fn forgot_password(
+    State(state): State<AppState>,
+    client_ip: ClientIp,
+    Form(form): Form<ForgotPasswordFormData>,
+) -> Result<Html<String>, PasswordResetError> {
+    let email = SubscriberEmail::parse(form.email.trim())
+        .map_err(|e| PasswordResetError::ValidationError(e.into()))?;
+    state
+        .rate_limiter
+        .check(
+            "password_forgot",
+            &state.rate_limit_settings.password_forgot,
+            client_ip,
+            email.as_ref(),
+            state.clock.now(),
+        )
+        .await?;
+
+    let page = ForgotPasswordTemplate::sent()
+        .render()
+        .context("Failed to render forgot password page")?;
+    // Failures are only logged, otherwise the response would tell that
+    // the address is known. Shutdown waits for the email.
+    let background_tasks = state.background_tasks.clone();
+    background_tasks.spawn(
+        async move {
+            if let Err(e) = send_reset_link(&state, &email).await {
+                tracing::error!("Failed to send password reset link: {:?}", e);
+            }
+        }
+        .instrument(tracing::Span::current()),
+    );
+    Ok(Html(page))
+}
+
+/// Shows the form for a new password, if the link is still valid.
+#[tracing::instrument(name = "Show reset password form", skip_all)]
+pub async fn reset_password_form(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(parameters): Query<ResetPasswordParameters>,
+) -> Result<Response, PasswordResetError> {
+    let token = PasswordResetToken::parse(&parameters.token)
+        .map_err(|_| PasswordResetError::UnknownToken)?;
+    let client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let expires_at = users::get_password_reset_token_expiry()
+        .bind(&client, &token.hash().as_str())
+        .opt()
+        .await
+        .context("Failed to fetch password reset token")?
+        .ok_or(PasswordResetError::UnknownToken)?;
+    if expires_at < OffsetDateTime::now_utc() {
+        return Err(PasswordResetError::ExpiredToken);
+    }
+
+    let (csrf_token, csrf_cookie) =
+        CsrfToken::for_login_form(&headers, &state.session_settings);
+    let page = ResetPasswordTemplate::form(token.as_ref(), &csrf_token, None)
+        .render()
+        .context("Failed to render reset password page")?;
+    Ok(with_csrf_cookie(Html(page), csrf_cookie))
+}
+
+/// Sets the new password and burns the link. All sessions of the user
+/// are ended, whoever knew the old password is logged out.
+#[tracing::instrument(
+    name = "Reset password",
+    skip_all,
+    fields(user_id=tracing::field::Empty)
+)]
+pub async fn reset_password(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    origin: RequestOrigin,
+    Form(form): Form<ResetPasswordFormData>,
+) -> Result<Response, PasswordResetError> {
+    let token = PasswordResetToken::parse(&form.token)
+        .map_err(|_| PasswordResetError::UnknownToken)?;
+    let rejected = if form.new_password.expose_secret()
+        != form.new_password_check.expose_secret()
+    {
+        Err("You entered two different new passwords - \
+            the field values must match."
+            .to_string())
+    } else {
+        AdminPassword::parse(form.new_password)
+    };
+    let new_password = match rejected {
+        Ok(password) => password,
+        Err(message) => {
+            // The CSRF middleware has checked the cookie, it is there.
+            let (csrf_token, _) =
+                CsrfToken::for_login_form(&headers, &state.session_settings);
+            let page = ResetPasswordTemplate::form(
+                token.as_ref(),
+                &csrf_token,
+                Some(message.as_str()),
+            )
+            .render()
+            .context("Failed to render reset password page")?;
+            return Ok((StatusCode::BAD_REQUEST, Html(page)).into_response());
+        }
+    };
+
+    let mut client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let transaction = client
+        .transaction()
+        .await
+        .context("Failed to start transaction on pg connection")?;
+
+    // Deleting the token makes the link single-use even for
+    // concurrent requests.
+    let reset_token = users::consume_password_reset_token()
+        .bind(&transaction, &token.hash().as_str())
+        .opt()
+        .await
+        .context("Failed to consume password reset token")?
+        .ok_or(PasswordResetError::UnknownToken)?;
+    if reset_token.expires_at < OffsetDateTime::now_utc() {
+        transaction
+            .commit()
+            .await
+            .context("Failed to delete expired password reset token")?;
+        return Err(PasswordResetError::ExpiredToken);
+    }
+    let user_id = reset_token.user_id;
+    tracing::Span::current()
+        .record("user_id", tracing::field::display(&user_id));
+
+    authentication::change_password(
+        user_id,
+        new_password,
+        &state.password_hashing,
+        &transaction,
+    )
+    .await?;
+    users::delete_password_reset_tokens()
+        .bind(&transaction, &user_id)
+        .await
+        .context("Failed to delete other password reset tokens")?;
+    sessions::delete_user_sessions()
+        .bind(&transaction, &user_id)
+        .await
+        .context("Failed to end user's sessions")?;
+    audit::record_event(
+        &transaction,
+        &origin,
+        Some(user_id),
+        audit::PASSWORD_RESET,
+        None,
+    )
+    .await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit password reset transaction")?;
+    tracing::info!("Password reset");
+
+    let page = ResetPasswordTemplate::done()
+        .render()
+        .context("Failed to render reset password page")?;
+    Ok(Html(page).into_response())
+}
+
+/// Adds the `Set-Cookie` of a new CSRF token, if one was generated.
+fn with_csrf_cookie(
+    page: Html<String>,
+    cookie: Option<HeaderValue>,
+) -> Response {
+    let mut response = page.into_response();
+    if let Some(cookie) = cookie {
+        response.headers_mut().append(SET_COOKIE, cookie);
+    }
+    response
+}
+
+/// Link to put into the password reset email.
+pub fn password_reset_link(
+    base_url: &str,
+    token: &PasswordResetToken,
+) -> String {
+    format!("{}/password/reset?token={}", base_url, token.as_ref())
+}
+
+async fn send_reset_link(
+    state: &AppState,
+    email: &SubscriberEmail,
+) -> Result<(), anyhow::Error> {
+    // The connection goes back to the pool before the email client
+    // retries.
+    let token = {
+        let client = state
+            .pool
+            .get()
+            .await
+            .context("Failed to get connection from pool")?;
+        let user_id = users::get_user_id_by_email()
+            .bind(&client, &email.as_ref())
+            .opt()
+            .await
+            .context("Failed to fetch user by email")?;
+        let Some(user_id) = user_id else {
+            tracing::info!("Password reset requested for unknown email");
+            return Ok(());
+        };
+        tracing::Span::current()
+            .record("user_id", tracing::field::display(&user_id));
+        store_reset_token(state, &client, user_id).await?
+    };
+    send_reset_email(state, email, &token).await
+}
+
+async fn store_reset_token(
+    state: &AppState,
+    client: &deadpool_postgres::Client,
+    user_id: Uuid,
+) -> Result<PasswordResetToken, anyhow::Error> {
+    let token = PasswordResetToken::generate();
+    let expires_at = OffsetDateTime::now_utc()
+        + state.authentication_settings.password_reset_token_ttl();
+    users::insert_password_reset_token()
+        .bind(client, &token.hash().as_str(), &user_id, &expires_at)
+        .await
+        .context("Failed to store password reset token")?;
+    Ok(token)
+}
+
+#[tracing::instrument(name = "Send password reset email", skip_all)]
+async fn send_reset_email(
+    state: &AppState,
+    email: &SubscriberEmail,
+    token: &PasswordResetToken,
+) -> Result<(), anyhow::Error> {
+    let link = password_reset_link(&state.base_url, token);
+    let minutes = state.authentication_settings.password_reset_token_ttl / 60;
+    let html = format!(
+        "Somebody asked to reset the password of your account.<br />\
+        Click <a href=\"{link}\">here</a> to choose a new one, \
+        the link is valid for {minutes} minutes.<br />\
+        If it wasn't you, just ignore this email."
+    );
+    let text = format!(
+        "Somebody asked to reset the password of your account.\n\
+        Visit {link} to choose a new one, \
+        the link is valid for {minutes} minutes.\n\
+        If it wasn't you, just ignore this email."
+    );
+    state
+        .email_client
+        .send_email(email, "Reset your password", &html, &text)
+        .await?;
+    Ok(())
+}
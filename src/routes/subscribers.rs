@@ -0,0 +1,179 @@
+//! src/routes/subscribers.rs
+
+use anyhow::Context;
+use axum::extract::{Query, State};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use http::{HeaderMap, StatusCode};
+use time::format_description::well_known::Rfc3339;
+use uuid::Uuid;
+
+use crate::api_tokens::{authenticate, bearer_token, ApiForbidden};
+use crate::audit::RequestOrigin;
+use crate::authentication::AuthError;
+use crate::cornucopia::queries::subscriptions;
+use crate::domain::ApiScope;
+use crate::error_chain_fmt;
+use crate::pagination::Cursor;
+use crate::startup::AppState;
+
+/// Page size when the client doesn't ask for one, and the largest allowed.
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+#[derive(serde::Deserialize, Debug)]
+pub struct SubscribersQuery {
+    status: Option<String>,
+    /// `next_after` of the previous page.
+    after: Option<Cursor>,
+    limit: Option<i64>,
+}
+
+#[derive(serde::Serialize)]
+pub struct SubscriberEntry {
+    id: Uuid,
+    email: String,
+    name: String,
+    status: String,
+    /// RFC 3339.
+    subscribed_at: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct SubscribersPage {
+    subscribers: Vec<SubscriberEntry>,
+    /// Pass as `after` to get the next page, `null` on the last one.
+    next_after: Option<Cursor>,
+}
+
+#[derive(thiserror::Error)]
+pub enum SubscribersApiError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error("Authentication failed: {0}")]
+    AuthError(#[source] anyhow::Error),
+    #[error(transparent)]
+    Forbidden(#[from] ApiForbidden),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for SubscribersApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl IntoResponse for SubscribersApiError {
+    fn into_response(self) -> Response {
+        match self {
+            SubscribersApiError::ValidationError(e) => {
+                (StatusCode::BAD_REQUEST, e).into_response()
+            }
+            SubscribersApiError::AuthError(_) => (
+                StatusCode::UNAUTHORIZED,
+                [(http::header::WWW_AUTHENTICATE, r#"Bearer realm="read""#)],
+            )
+                .into_response(),
+            SubscribersApiError::Forbidden(e) => e.into_response(),
+            SubscribersApiError::UnexpectedError(e) => {
+                tracing::error!("{:?}", e);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+/// Subscribers as JSON for API tokens with the `subscribers:read` scope,
+/// newest first, with the same keyset pagination as the admin page.
+#[tracing::instrument(
+    name = "List subscribers with an API token",
+    skip(state, origin, headers),
+    fields(user_id=tracing::field::Empty, token_id=tracing::field::Empty)
+)]
+pub async fn get_subscribers(
+    State(state): State<AppState>,
+    origin: RequestOrigin,
+    headers: HeaderMap,
+    Query(query): Query<SubscribersQuery>,
+) -> Result<Json<SubscribersPage>, SubscribersApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(SubscribersApiError::ValidationError(format!(
+            "limit must be from 1 to {}",
+            MAX_LIMIT
+        )));
+    }
+
+    let client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let token = bearer_token(&headers)
+        .map_err(SubscribersApiError::AuthError)?
+        .ok_or_else(|| {
+            SubscribersApiError::AuthError(anyhow::anyhow!(
+                "An API token is required"
+            ))
+        })?;
+    let caller = authenticate(&token, &client).await.map_err(|e| match e {
+        AuthError::InvalidCredentials(_) => {
+            SubscribersApiError::AuthError(e.into())
+        }
+        AuthError::UnexpectedError(e) => {
+            SubscribersApiError::UnexpectedError(e)
+        }
+    })?;
+    let span = tracing::Span::current();
+    span.record("user_id", tracing::field::display(&caller.user_id));
+    span.record("token_id", tracing::field::display(&caller.token_id));
+    caller
+        .require(
+            &state.pool,
+            &origin,
+            ApiScope::SubscribersRead,
+            "/subscribers",
+        )
+        .await?;
+
+    // Fetch one extra row to know whether there is a next page.
+    let mut rows = subscriptions::list_subscribers()
+        .bind(
+            &client,
+            &query.status.as_deref(),
+            &None::<&str>,
+            &query.after.map(|after| after.at),
+            &query.after.map(|after| after.id),
+            &(limit + 1),
+        )
+        .all()
+        .await
+        .context("Failed to fetch subscribers")?;
+    let next_after = if rows.len() > limit as usize {
+        rows.truncate(limit as usize);
+        rows.last().map(|last| Cursor::new(last.subscribed_at, last.id))
+    } else {
+        None
+    };
+
+    let subscribers = rows
+        .into_iter()
+        .map(|row| {
+            Ok(SubscriberEntry {
+                id: row.id,
+                email: row.email,
+                name: row.name,
+                status: row.status,
+                subscribed_at: row
+                    .subscribed_at
+                    .format(&Rfc3339)
+                    .context("Failed to format subscription time")?,
+            })
+        })
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+    Ok(Json(SubscribersPage {
+        subscribers,
+        next_after,
+    }))
+}
@@ -10,19 +10,22 @@ use tokio_postgres::error::SqlState;
 use tokio_postgres::Transaction;
 use uuid::Uuid;
 
+use crate::client_ip::ClientIp;
 use crate::cornucopia::queries::subscriptions;
 use crate::domain::NewSubscriber;
-use crate::email_client::EmailClient;
+use crate::email_client::{EmailClient, SendEmailError};
 use crate::error_chain_fmt;
+use crate::rate_limit::RateLimitError;
 use crate::startup::AppState;
 use crate::validation::subscriber_token::SubscriberToken;
 
 // TODO: WRITE HOW IT WORKS VERY DETAILED
 // 1. We get request with form: email, name. If not correct return BAD_REQUEST.
 // 2. We check database: is that email in db already? We check subscriber stataus.
-// 3. If there are no such email in db, we generate unique token, generate request id (subscriber_id), and store them in db.
+// 3. If there are no such email in db, we generate unique token, generate subscriber id, and store them in db.
 // 4. If there are such email, check its status:
 //     - If pending, we update token, and send new email with this confirmation token.
+//     - If unsubscribed, we make it pending again and do the same.
 //     - If confirmed, return CONFLICT response.
 
 #[derive(Deserialize, Debug)]
@@ -36,10 +39,11 @@ enum SubscriberStatus {
     NonExisting,
     Pending,
     Confirmed,
+    Unsubscribed,
 }
 
 #[derive(thiserror::Error)]
-#[allow(dead_code)]
+#[allow(dead_code, clippy::enum_variant_names)]
 pub enum SubscribeError {
     #[error("{0}")]
     ValidationError(String),
@@ -52,7 +56,7 @@ pub enum SubscribeError {
     #[error("Failed to commit SQL transaction to store a new subscriber.")]
     TransactionCommitError(#[source] tokio_postgres::Error),
     #[error("Failed to send a confirmation email.")]
-    SendEmailError(#[from] reqwest::Error),
+    SendEmailError(#[from] SendEmailError),
     // Transparent delegates both `Display`'s and `source`'s implementation
     // to the type wrapped by `UnexpectedError`.
     #[error(transparent)]
@@ -67,22 +71,40 @@ impl std::fmt::Debug for SubscribeError {
     }
 }
 
+/// Every call may send an email, so it is rate limited per client IP and
+/// per email before anything else.
+pub async fn subscribe_handler(
+    State(state): State<AppState>,
+    client_ip: ClientIp,
+    form: Form<FormData>,
+) -> Result<StatusCode, RateLimitError> {
+    state
+        .rate_limiter
+        .check(
+            "subscriptions",
+            &state.rate_limit_settings.subscriptions,
+            client_ip,
+            &form.email,
+            state.clock.now(),
+        )
+        .await?;
+    Ok(add_subscriber(state, form).await)
+}
+
 #[tracing::instrument(
     name = "Adding a new subscriber",
     skip_all,
     fields(
-        request_id,
+        subscriber_id,
         subscriber_email = %form.email,
         subscriber_name = %form.name,
     )
     level = "info"
 )]
-pub async fn subscribe_handler(
-    State(state): State<AppState>,
-    form: Form<FormData>,
-) -> StatusCode {
-    let request_id = uuid::Uuid::new_v4();
-    tracing::Span::current().record("request_id", request_id.to_string());
+async fn add_subscriber(state: AppState, form: Form<FormData>) -> StatusCode {
+    let subscriber_id = uuid::Uuid::new_v4();
+    tracing::Span::current()
+        .record("subscriber_id", tracing::field::display(&subscriber_id));
 
     // TRY to convert `&FormData` into a `NewSubscriber`.
     let new_subscriber = match (&form.0).try_into() {
@@ -143,7 +165,7 @@ pub async fn subscribe_handler(
             match insert_subscriber_to_db(
                 &new_subscriber,
                 &mut transaction,
-                request_id,
+                subscriber_id,
             )
             .await
             {
@@ -162,13 +184,37 @@ pub async fn subscribe_handler(
                 }
             }
 
-            if let Err(e) =
-                store_token(&mut transaction, request_id, &subscription_token)
-                    .await
+            if let Err(e) = store_token(
+                &mut transaction,
+                subscriber_id,
+                &subscription_token,
+            )
+            .await
             {
                 tracing::error!("Failed to store token in db, error: {e}");
                 return StatusCode::INTERNAL_SERVER_ERROR;
             }
+
+            if let Err(e) =
+                store_unsubscribe_token(&mut transaction, subscriber_id).await
+            {
+                tracing::error!(
+                    "Failed to store unsubscribe token in db, error: {e}"
+                );
+                return StatusCode::INTERNAL_SERVER_ERROR;
+            }
+        }
+        SubscriberStatus::Unsubscribed => {
+            if let Err(e) = resubscribe(
+                &mut transaction,
+                new_subscriber.email.as_ref(),
+                &subscription_token,
+            )
+            .await
+            {
+                tracing::error!("Failed to resubscribe in db, error: {e}");
+                return StatusCode::INTERNAL_SERVER_ERROR;
+            }
         }
         SubscriberStatus::Pending => {
             if let Err(e) = update_token(
@@ -188,7 +234,14 @@ pub async fn subscribe_handler(
         }
     }
 
-    // We are ignoring email delivery errors for now.
+    if let Err(e) = transaction.commit().await {
+        tracing::error!("Failed to commit subscriber, error: {e}");
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+    tracing::info!("Db transaction commited!");
+    // Don't hold the connection while the email client retries.
+    drop(connection);
+
     if let Err(e) = send_confirmation_email(
         &state.email_client,
         new_subscriber,
@@ -197,17 +250,16 @@ pub async fn subscribe_handler(
     )
     .await
     {
-        tracing::error!("Failed to send confirmation email, error: {e}");
-        return StatusCode::INTERNAL_SERVER_ERROR;
-    }
-
-    if let Err(e) = transaction.commit().await {
-        tracing::error!("Failed to send confirmation email, error: {e}");
-        StatusCode::INTERNAL_SERVER_ERROR
-    } else {
-        tracing::info!("Db transaction commited!");
-        StatusCode::OK
+        tracing::error!("Failed to send confirmation email, error: {e:?}");
+        // The subscriber is stored as pending, trying again later sends
+        // a new token.
+        return if e.is_transient() {
+            StatusCode::SERVICE_UNAVAILABLE
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        };
     }
+    StatusCode::OK
 }
 
 #[tracing::instrument(
@@ -219,7 +271,7 @@ pub async fn send_confirmation_email(
     new_subscriber: NewSubscriber,
     base_url: &str,
     subscription_token: &SubscriberToken,
-) -> Result<(), reqwest::Error> {
+) -> Result<(), SendEmailError> {
     let confirmation_link = format!(
         "{}/subscriptions/confirm?subscription_token={}",
         base_url,
@@ -276,24 +328,58 @@ async fn store_token<'a>(
     Ok(())
 }
 
+/// Unsubscribe token is generated once per subscriber, it is sent with
+/// every newsletter issue.
+#[tracing::instrument(name = "Store unsubscribe token in the database" skip(transaction))]
+async fn store_unsubscribe_token<'a>(
+    transaction: &mut Transaction<'a>,
+    subscriber_id: Uuid,
+) -> Result<(), tokio_postgres::Error> {
+    subscriptions::insert_unsubscribe_token()
+        .bind(
+            transaction,
+            &SubscriberToken::generate().as_ref(),
+            &subscriber_id,
+        )
+        .await?;
+    Ok(())
+}
+
+/// Subscriber left the list before, make them pending again,
+/// the unsubscribe token stays the same.
+#[tracing::instrument(
+    name = "Resubscribe an unsubscribed subscriber"
+    skip(subscription_token, transaction)
+)]
+async fn resubscribe<'a>(
+    transaction: &mut Transaction<'a>,
+    email: &str,
+    subscription_token: &SubscriberToken,
+) -> Result<(), tokio_postgres::Error> {
+    subscriptions::resubscribe()
+        .bind(transaction, &email)
+        .await?;
+    update_token(transaction, email, subscription_token).await
+}
+
 #[tracing::instrument(
     name = "Update subscriber token in db"
     skip(subscription_token, transaction)
 )]
-async fn update_token<'a>(
+pub(super) async fn update_token<'a>(
     transaction: &mut Transaction<'a>,
     email: &str,
     subscription_token: &SubscriberToken,
 ) -> Result<(), tokio_postgres::Error> {
     tracing::info!("Updating subscriber token in db...");
-    let rows_modified = subscriptions::delete_token_by_email()
+    // Imported subscribers may have no token yet, so there can be nothing
+    // to delete.
+    subscriptions::delete_token_by_email()
         .bind(transaction, &email)
         .await?;
-    assert_eq!(rows_modified, 1);
-    let rows_modified = subscriptions::insert_token_by_email()
+    subscriptions::insert_token_by_email()
         .bind(transaction, &subscription_token.as_ref(), &email)
         .await?;
-    assert_eq!(rows_modified, 1);
     Ok(())
 }
 
@@ -315,6 +401,7 @@ async fn get_subscriber_status<'a>(
         match row.as_str() {
             "confirmed" => Ok(SubscriberStatus::Confirmed),
             "pending_confirmation" => Ok(SubscriberStatus::Pending),
+            "unsubscribed" => Ok(SubscriberStatus::Unsubscribed),
             _ => unreachable!(),
         }
     } else {
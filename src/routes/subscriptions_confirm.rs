@@ -1,12 +1,15 @@
 //! src/routes/subscriptions_confirm.rs
 
 use axum::extract::{Query, State};
+use axum::response::{IntoResponse, Response};
 use deadpool_postgres::Client;
 use hyper::StatusCode;
+use time::OffsetDateTime;
 use uuid::Uuid;
 
 use crate::{
-    cornucopia::queries::subscriptions, startup::AppState,
+    cornucopia::queries::subscriptions,
+    cornucopia::queries::subscriptions::GetTokenDetails, startup::AppState,
     validation::subscriber_token::SubscriberToken,
 };
 
@@ -22,13 +25,13 @@ pub struct Parameters {
 pub async fn confirm(
     State(state): State<AppState>,
     Query(parameters): Query<Parameters>,
-) -> StatusCode {
+) -> Response {
     let subscriber_token =
         match SubscriberToken::parse(&parameters.subscription_token) {
             Ok(t) => t,
             Err(e) => {
                 tracing::error!("Failed to parse subscriber token {}", e);
-                return StatusCode::BAD_REQUEST;
+                return StatusCode::BAD_REQUEST.into_response();
             }
         };
 
@@ -36,45 +39,56 @@ pub async fn confirm(
         Ok(c) => c,
         Err(e) => {
             tracing::error!("Failed to get db connection from pool: {e}");
-            return StatusCode::INTERNAL_SERVER_ERROR;
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
         }
     };
 
-    let subscriber_id =
-        match get_subscriber_id_from_token(&client, &subscriber_token).await {
-            Ok(Some(id)) => id,
-            Ok(None) => {
-                tracing::warn!("Attempt to confirm unexistent token");
-                return StatusCode::NOT_FOUND;
-            }
-            Err(e) => {
-                tracing::error!("Failed to load token from db, error: {e}");
-                return StatusCode::INTERNAL_SERVER_ERROR;
-            }
-        };
+    let token = match get_token_details(&client, &subscriber_token).await {
+        Ok(Some(token)) => token,
+        Ok(None) => {
+            tracing::warn!("Attempt to confirm unexistent token");
+            return StatusCode::NOT_FOUND.into_response();
+        }
+        Err(e) => {
+            tracing::error!("Failed to load token from db, error: {e}");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let ttl = state.subscription_settings.confirmation_token_ttl();
+    if token.created_at + ttl < OffsetDateTime::now_utc() {
+        tracing::warn!("Attempt to confirm with expired token");
+        return (
+            StatusCode::GONE,
+            "The confirmation link has expired, \
+            please request a new one at /subscriptions/resend",
+        )
+            .into_response();
+    }
+
+    let subscriber_id = token.subscriber_id;
     if let Err(e) = confirm_subscriber(&client, subscriber_id).await {
         tracing::error!(
             "Failed to change status to \'confirmed\' in db, error: {e}"
         );
-        return StatusCode::INTERNAL_SERVER_ERROR;
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
     }
     tracing::info!("Subscriber with uuid: {} confirmed", subscriber_id);
-    return StatusCode::OK;
+    StatusCode::OK.into_response()
 }
 
 #[tracing::instrument(
-    name = "Get subscriber_id from token",
+    name = "Get subscriber_id and creation time of the token",
     skip(subscription_token, client)
 )]
-async fn get_subscriber_id_from_token(
+async fn get_token_details(
     client: &Client,
     subscription_token: &SubscriberToken,
-) -> Result<Option<Uuid>, tokio_postgres::Error> {
-    let id = subscriptions::get_subscriber_id_from_token()
+) -> Result<Option<GetTokenDetails>, tokio_postgres::Error> {
+    subscriptions::get_token_details()
         .bind(client, &subscription_token.as_ref())
         .opt()
-        .await?;
-    Ok(id)
+        .await
 }
 
 #[tracing::instrument(
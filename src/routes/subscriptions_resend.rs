@@ -0,0 +1,164 @@
+//! src/routes/subscriptions_resend.rs
+
+use anyhow::Context;
+use axum::extract::State;
+use axum::response::{IntoResponse, Response};
+use axum::Form;
+use hyper::StatusCode;
+use time::OffsetDateTime;
+
+use super::subscriptions::{send_confirmation_email, update_token};
+use crate::client_ip::ClientIp;
+use crate::cornucopia::queries::subscriptions;
+use crate::domain::{NewSubscriber, SubscriberEmail, SubscriberName};
+use crate::email_client::SendEmailError;
+use crate::error_chain_fmt;
+use crate::rate_limit::RateLimitError;
+use crate::startup::AppState;
+use crate::validation::subscriber_token::SubscriberToken;
+
+#[derive(serde::Deserialize, Debug)]
+pub struct ResendFormData {
+    pub email: String,
+}
+
+#[derive(thiserror::Error)]
+pub enum ResendError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error("Confirmation email was sent recently")]
+    TooManyRequests { retry_after: u64 },
+    #[error("Failed to send a confirmation email.")]
+    SendEmailError(#[from] SendEmailError),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for ResendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl From<RateLimitError> for ResendError {
+    fn from(e: RateLimitError) -> Self {
+        match e {
+            RateLimitError::Exceeded { retry_after } => {
+                ResendError::TooManyRequests { retry_after }
+            }
+            RateLimitError::UnexpectedError(e) => {
+                ResendError::UnexpectedError(e)
+            }
+        }
+    }
+}
+
+impl IntoResponse for ResendError {
+    fn into_response(self) -> Response {
+        match self {
+            ResendError::ValidationError(e) => {
+                (StatusCode::BAD_REQUEST, e).into_response()
+            }
+            ResendError::TooManyRequests { retry_after } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(http::header::RETRY_AFTER, retry_after.to_string())],
+            )
+                .into_response(),
+            ResendError::SendEmailError(e) if e.is_transient() => {
+                tracing::error!("{:?}", e);
+                StatusCode::SERVICE_UNAVAILABLE.into_response()
+            }
+            ResendError::SendEmailError(_)
+            | ResendError::UnexpectedError(_) => {
+                tracing::error!("{:?}", self);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+/// Issues a fresh confirmation token to a pending subscriber and sends
+/// it by email. To not disclose who is on the list, unknown and already
+/// confirmed addresses get `200 OK` too, but no email is sent. Requests
+/// are rate limited per client IP and per email on top of the cooldown.
+/// The token is committed before the email is sent, a failed send waits
+/// out the cooldown like a successful one.
+#[tracing::instrument(
+    name = "Resend a confirmation email",
+    skip_all,
+    fields(subscriber_email = %form.email)
+)]
+pub async fn resend_confirmation(
+    State(state): State<AppState>,
+    client_ip: ClientIp,
+    Form(form): Form<ResendFormData>,
+) -> Result<StatusCode, ResendError> {
+    let email = SubscriberEmail::parse(&form.email)
+        .map_err(|e| ResendError::ValidationError(e.to_string()))?;
+    state
+        .rate_limiter
+        .check(
+            "subscriptions_resend",
+            &state.rate_limit_settings.subscriptions_resend,
+            client_ip,
+            email.as_ref(),
+            state.clock.now(),
+        )
+        .await?;
+
+    let mut connection = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let mut transaction = connection
+        .transaction()
+        .await
+        .context("Failed to start transaction on pg connection")?;
+
+    let subscriber = subscriptions::get_pending_subscriber()
+        .bind(&transaction, &email.as_ref())
+        .opt()
+        .await
+        .context("Failed to fetch a pending subscriber")?;
+    let subscriber = match subscriber {
+        Some(s) => s,
+        None => {
+            tracing::info!("No pending subscription for this email");
+            return Ok(StatusCode::OK);
+        }
+    };
+
+    let next_allowed_at = subscriber.token_created_at
+        + state.subscription_settings.resend_cooldown();
+    let now = OffsetDateTime::now_utc();
+    if next_allowed_at > now {
+        let retry_after = (next_allowed_at - now).whole_seconds() + 1;
+        return Err(ResendError::TooManyRequests {
+            retry_after: retry_after as u64,
+        });
+    }
+
+    let name = SubscriberName::parse(&subscriber.name).map_err(|e| {
+        anyhow::anyhow!("Stored subscriber name is invalid: {e}")
+    })?;
+    let subscription_token = SubscriberToken::generate();
+    update_token(&mut transaction, email.as_ref(), &subscription_token)
+        .await
+        .context("Failed to update token in db")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit resend transaction")?;
+    // Don't hold the connection while the email client retries.
+    drop(connection);
+
+    send_confirmation_email(
+        &state.email_client,
+        NewSubscriber { email, name },
+        &state.base_url,
+        &subscription_token,
+    )
+    .await?;
+    Ok(StatusCode::OK)
+}
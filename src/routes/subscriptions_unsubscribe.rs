@@ -0,0 +1,149 @@
+//! src/routes/subscriptions_unsubscribe.rs
+
+use anyhow::Context;
+use askama::Template;
+use axum::extract::{Query, State};
+use axum::response::{Html, IntoResponse, Response};
+use hyper::StatusCode;
+
+use crate::cornucopia::queries::newsletters;
+use crate::cornucopia::queries::subscriptions;
+use crate::cornucopia::queries::subscriptions::GetSubscriberByUnsubscribeToken;
+use crate::error_chain_fmt;
+use crate::html_template_gen::UnsubscribeTemplate;
+use crate::startup::AppState;
+use crate::validation::subscriber_token::SubscriberToken;
+
+#[derive(serde::Deserialize, Debug)]
+pub struct Parameters {
+    unsubscribe_token: String,
+}
+
+#[derive(thiserror::Error)]
+pub enum UnsubscribeError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error("Unknown unsubscribe token")]
+    UnknownToken,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for UnsubscribeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl IntoResponse for UnsubscribeError {
+    fn into_response(self) -> Response {
+        match self {
+            UnsubscribeError::ValidationError(e) => {
+                (StatusCode::BAD_REQUEST, e).into_response()
+            }
+            UnsubscribeError::UnknownToken => {
+                StatusCode::NOT_FOUND.into_response()
+            }
+            UnsubscribeError::UnexpectedError(e) => {
+                tracing::error!("{:?}", e);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+/// Link to put into the `List-Unsubscribe` header.
+pub fn unsubscribe_link(base_url: &str, unsubscribe_token: &str) -> String {
+    format!(
+        "{}/subscriptions/unsubscribe?unsubscribe_token={}",
+        base_url, unsubscribe_token
+    )
+}
+
+/// Shows the page asking the subscriber to confirm the unsubscription.
+/// `GET` should never change the state: mail scanners follow links.
+#[tracing::instrument(name = "Show unsubscribe form", skip(parameters, state))]
+pub async fn unsubscribe_form(
+    State(state): State<AppState>,
+    Query(parameters): Query<Parameters>,
+) -> Result<Html<String>, UnsubscribeError> {
+    let token = parse_token(&parameters)?;
+    let client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let subscriber = subscriptions::get_subscriber_by_unsubscribe_token()
+        .bind(&client, &token.as_ref())
+        .opt()
+        .await
+        .context("Failed to fetch subscriber by unsubscribe token")?
+        .ok_or(UnsubscribeError::UnknownToken)?;
+
+    let action = unsubscribe_link(&state.base_url, token.as_ref());
+    let page = UnsubscribeTemplate::form(&subscriber.email, &action)
+        .render()
+        .context("Failed to render unsubscribe page")?;
+    Ok(Html(page))
+}
+
+/// Handles both the form submission and RFC 8058 one-click unsubscribe,
+/// mail clients send `List-Unsubscribe=One-Click` body here, which we don't
+/// need to inspect. Unsubscribing twice is not an error.
+#[tracing::instrument(
+    name = "Unsubscribe a subscriber",
+    skip(parameters, state),
+    fields(subscriber_id=tracing::field::Empty)
+)]
+pub async fn unsubscribe(
+    State(state): State<AppState>,
+    Query(parameters): Query<Parameters>,
+) -> Result<Html<String>, UnsubscribeError> {
+    let token = parse_token(&parameters)?;
+    let mut client = state
+        .pool
+        .get()
+        .await
+        .context("Failed to get connection from pool")?;
+    let transaction = client
+        .transaction()
+        .await
+        .context("Failed to start transaction on pg connection")?;
+
+    let subscriber: GetSubscriberByUnsubscribeToken =
+        subscriptions::get_subscriber_by_unsubscribe_token()
+            .bind(&transaction, &token.as_ref())
+            .opt()
+            .await
+            .context("Failed to fetch subscriber by unsubscribe token")?
+            .ok_or(UnsubscribeError::UnknownToken)?;
+    tracing::Span::current()
+        .record("subscriber_id", tracing::field::display(&subscriber.id));
+
+    subscriptions::unsubscribe_subscriber()
+        .bind(&transaction, &subscriber.id)
+        .await
+        .context("Failed to mark subscriber as unsubscribed")?;
+    // Issues already queued for this subscriber should not be delivered.
+    newsletters::delete_tasks_for_subscriber()
+        .bind(&transaction, &subscriber.email.as_str())
+        .await
+        .context("Failed to delete pending delivery tasks")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit unsubscribe transaction")?;
+    tracing::info!("Subscriber unsubscribed");
+
+    let page = UnsubscribeTemplate::done(&subscriber.email)
+        .render()
+        .context("Failed to render unsubscribe page")?;
+    Ok(Html(page))
+}
+
+fn parse_token(
+    parameters: &Parameters,
+) -> Result<SubscriberToken, UnsubscribeError> {
+    SubscriberToken::parse(&parameters.unsubscribe_token)
+        .map_err(|e| UnsubscribeError::ValidationError(e.to_string()))
+}
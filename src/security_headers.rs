@@ -0,0 +1,143 @@
+//! src/security_headers.rs
+
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use http::header::{
+    CONTENT_SECURITY_POLICY, REFERRER_POLICY, STRICT_TRANSPORT_SECURITY,
+    X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+};
+use http::{HeaderName, HeaderValue};
+
+use crate::configuration::SecurityHeadersSettings;
+
+/// Policy of the newsletter preview: the issue is shown in a sandboxed
+/// iframe, with the inline styles and remote images newsletters use.
+pub const PREVIEW_CONTENT_SECURITY_POLICY: &str = "default-src 'self'; \
+    img-src * data:; style-src 'self' 'unsafe-inline'; object-src 'none'; \
+    base-uri 'none'; form-action 'self'; frame-ancestors 'none'";
+
+/// Not in the `http` crate yet.
+const PERMISSIONS_POLICY: HeaderName =
+    HeaderName::from_static("permissions-policy");
+
+/// Headers from `SecurityHeadersSettings`, checked once at startup.
+#[derive(Debug, Clone)]
+pub struct SecurityHeaders(Arc<[(HeaderName, HeaderValue)]>);
+
+impl SecurityHeaders {
+    pub fn new(settings: &SecurityHeadersSettings) -> Result<Self, String> {
+        let value = |name: &HeaderName, value: &str| {
+            HeaderValue::from_str(value)
+                .map_err(|_| format!("Invalid value for {}: {:?}", name, value))
+        };
+        let mut headers = vec![
+            (
+                CONTENT_SECURITY_POLICY,
+                value(
+                    &CONTENT_SECURITY_POLICY,
+                    &settings.content_security_policy,
+                )?,
+            ),
+            (X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff")),
+            (
+                REFERRER_POLICY,
+                value(&REFERRER_POLICY, &settings.referrer_policy)?,
+            ),
+            (
+                X_FRAME_OPTIONS,
+                value(&X_FRAME_OPTIONS, &settings.frame_options)?,
+            ),
+            (
+                PERMISSIONS_POLICY,
+                value(&PERMISSIONS_POLICY, &settings.permissions_policy)?,
+            ),
+        ];
+        if settings.hsts_enabled {
+            headers.push((
+                STRICT_TRANSPORT_SECURITY,
+                value(
+                    &STRICT_TRANSPORT_SECURITY,
+                    &format!(
+                        "max-age={}; includeSubDomains",
+                        settings.hsts_max_age
+                    ),
+                )?,
+            ));
+        }
+        // Empty values switch a header off.
+        headers.retain(|(_, value)| !value.is_empty());
+        Ok(SecurityHeaders(headers.into()))
+    }
+}
+
+/// Adds the configured headers the handler didn't set itself.
+pub async fn set_security_headers(
+    State(headers): State<SecurityHeaders>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    let response_headers = response.headers_mut();
+    for (name, value) in headers.0.iter() {
+        response_headers
+            .entry(name)
+            .or_insert_with(|| value.clone());
+    }
+    response
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use http::header::{CONTENT_SECURITY_POLICY, STRICT_TRANSPORT_SECURITY};
+
+    use super::SecurityHeaders;
+    use crate::configuration::SecurityHeadersSettings;
+
+    fn header<'a>(headers: &'a SecurityHeaders, name: &str) -> Option<&'a str> {
+        headers
+            .0
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.to_str().unwrap())
+    }
+
+    #[test]
+    fn hsts_is_sent_only_when_enabled() {
+        let mut settings = SecurityHeadersSettings::default();
+        let headers = SecurityHeaders::new(&settings).unwrap();
+        assert_eq!(header(&headers, STRICT_TRANSPORT_SECURITY.as_str()), None);
+
+        settings.hsts_enabled = true;
+        settings.hsts_max_age = 600;
+        let headers = SecurityHeaders::new(&settings).unwrap();
+        assert_eq!(
+            header(&headers, STRICT_TRANSPORT_SECURITY.as_str()),
+            Some("max-age=600; includeSubDomains")
+        );
+    }
+
+    #[test]
+    fn empty_value_switches_a_header_off() {
+        let settings = SecurityHeadersSettings {
+            permissions_policy: String::new(),
+            ..Default::default()
+        };
+        let headers = SecurityHeaders::new(&settings).unwrap();
+        assert_eq!(header(&headers, "permissions-policy"), None);
+        assert!(header(&headers, CONTENT_SECURITY_POLICY.as_str()).is_some());
+    }
+
+    #[test]
+    fn invalid_value_is_rejected() {
+        let settings = SecurityHeadersSettings {
+            content_security_policy: "default-src 'self'\n".to_string(),
+            ..Default::default()
+        };
+        assert!(SecurityHeaders::new(&settings).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! src/session.rs
+
+use anyhow::Context;
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::response::{IntoResponse, Redirect, Response};
+use cookie::{Cookie, SameSite};
+use deadpool_postgres::Client;
+use http::request::Parts;
+use http::{HeaderMap, HeaderValue, StatusCode};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::audit::RequestOrigin;
+use crate::authentication::{authorize, Forbidden};
+use crate::configuration::SessionSettings;
+use crate::cornucopia::queries::sessions;
+use crate::csrf::CsrfToken;
+use crate::domain::{Permission, UserRole};
+use crate::startup::AppState;
+
+/// Name of the cookie with the session id.
+pub const SESSION_COOKIE: &str = "session_id";
+
+/// Opaque random identifier of a server-side session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionId(String);
+
+impl SessionId {
+    /// 48 alphanumeric characters give us ~285 bits of entropy.
+    pub fn generate() -> SessionId {
+        let mut rng = thread_rng();
+        SessionId(
+            std::iter::repeat_with(|| rng.sample(Alphanumeric))
+                .map(char::from)
+                .take(48)
+                .collect(),
+        )
+    }
+
+    /// Take the session id from the `Cookie` request header, if any.
+    pub fn from_headers(headers: &HeaderMap) -> Option<SessionId> {
+        headers
+            .get_all(http::header::COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(Cookie::split_parse)
+            .filter_map(Result::ok)
+            .find(|cookie| cookie.name() == SESSION_COOKIE)
+            .map(|cookie| SessionId(cookie.value().to_string()))
+    }
+
+    /// `Set-Cookie` value storing this session id in the browser.
+    pub fn cookie(&self, settings: &SessionSettings) -> HeaderValue {
+        let max_age = cookie::time::Duration::seconds(settings.ttl as i64);
+        let cookie = Cookie::build((SESSION_COOKIE, self.0.as_str()))
+            .path("/")
+            .http_only(true)
+            .secure(settings.secure_cookie)
+            .same_site(SameSite::Strict)
+            .max_age(max_age)
+            .build();
+        HeaderValue::from_str(&cookie.to_string()).unwrap()
+    }
+
+    /// `Set-Cookie` value removing the session cookie from the browser.
+    pub fn removal_cookie(settings: &SessionSettings) -> HeaderValue {
+        let mut cookie = Cookie::build((SESSION_COOKIE, ""))
+            .path("/")
+            .http_only(true)
+            .secure(settings.secure_cookie)
+            .same_site(SameSite::Strict)
+            .build();
+        cookie.make_removal();
+        HeaderValue::from_str(&cookie.to_string()).unwrap()
+    }
+}
+
+impl AsRef<str> for SessionId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Start a new session for the user. The previous session from the same
+/// browser, if any, is deleted, so the id and the CSRF token are rotated
+/// on every login.
+#[tracing::instrument(skip(client, previous, settings))]
+pub async fn start_session(
+    client: &Client,
+    user_id: Uuid,
+    previous: Option<&SessionId>,
+    settings: &SessionSettings,
+) -> Result<SessionId, anyhow::Error> {
+    if let Some(previous) = previous {
+        end_session(client, previous).await?;
+    }
+    sessions::delete_expired_user_sessions()
+        .bind(client, &user_id)
+        .await
+        .context("Failed to delete expired sessions")?;
+
+    let session_id = SessionId::generate();
+    let csrf_token = CsrfToken::generate();
+    let expires_at = OffsetDateTime::now_utc() + settings.ttl();
+    sessions::insert_session()
+        .bind(
+            client,
+            &session_id.as_ref(),
+            &user_id,
+            &csrf_token.as_ref(),
+            &expires_at,
+        )
+        .await
+        .context("Failed to store a new session")?;
+    Ok(session_id)
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn end_session(
+    client: &Client,
+    session_id: &SessionId,
+) -> Result<(), anyhow::Error> {
+    sessions::delete_session()
+        .bind(client, &session_id.as_ref())
+        .await
+        .context("Failed to delete a session")?;
+    Ok(())
+}
+
+/// Extractor guarding `/admin/*` routes. Requests without a valid session
+/// are redirected to the login form.
+#[derive(Debug)]
+pub struct AuthenticatedUser {
+    pub user_id: Uuid,
+    pub session_id: SessionId,
+    pub role: UserRole,
+    /// Rendered into the forms of the admin pages.
+    pub csrf_token: CsrfToken,
+    /// Stored with the audit events of this request.
+    pub origin: RequestOrigin,
+}
+
+impl AuthenticatedUser {
+    /// Guard for handlers changing data, see `authentication::authorize`.
+    pub async fn require(
+        &self,
+        state: &AppState,
+        permission: Permission,
+        target: &str,
+    ) -> Result<(), Forbidden> {
+        authorize(
+            &state.pool,
+            &self.origin,
+            self.user_id,
+            self.role,
+            permission,
+            target,
+        )
+        .await
+    }
+}
+
+pub enum SessionRejection {
+    Unauthenticated,
+    UnexpectedError(anyhow::Error),
+}
+
+impl IntoResponse for SessionRejection {
+    fn into_response(self) -> Response {
+        match self {
+            SessionRejection::Unauthenticated => {
+                Redirect::to("/login").into_response()
+            }
+            SessionRejection::UnexpectedError(e) => {
+                tracing::error!("Failed to load a session: {:?}", e);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for AuthenticatedUser {
+    type Rejection = SessionRejection;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let session_id = SessionId::from_headers(&parts.headers)
+            .ok_or(SessionRejection::Unauthenticated)?;
+        let client = state
+            .pool
+            .get()
+            .await
+            .context("Failed to get connection from pool")
+            .map_err(SessionRejection::UnexpectedError)?;
+        let session = sessions::get_session_user()
+            .bind(&client, &session_id.as_ref())
+            .opt()
+            .await
+            .context("Failed to fetch a session")
+            .map_err(SessionRejection::UnexpectedError)?
+            .ok_or(SessionRejection::Unauthenticated)?;
+        let role = UserRole::parse(&session.role)
+            .map_err(anyhow::Error::msg)
+            .map_err(SessionRejection::UnexpectedError)?;
+        let origin = RequestOrigin::from_request_parts(parts, state)
+            .await
+            .map_err(|_| {
+                SessionRejection::UnexpectedError(anyhow::anyhow!(
+                    "Failed to get the origin of the request"
+                ))
+            })?;
+        Ok(AuthenticatedUser {
+            user_id: session.user_id,
+            session_id,
+            role,
+            csrf_token: session.csrf_token.into(),
+            origin,
+        })
+    }
+}
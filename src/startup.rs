@@ -1,4 +1,11 @@
 // use native_tls::Identity;
+use std::future::{Future, IntoFuture};
+use std::net::SocketAddr;
+
+use axum::extract::connect_info::IntoMakeServiceWithConnectInfo;
+use axum::extract::ConnectInfo;
+use axum::middleware;
+use axum::middleware::AddExtension;
 use axum::routing;
 use axum::serve::Serve;
 use axum::Router;
@@ -9,19 +16,81 @@ use deadpool_postgres::Pool;
 use secrecy::ExposeSecret;
 
 use tokio::net::TcpListener;
+use tokio::sync::watch;
 use tokio_postgres::NoTls;
+use tokio_util::task::TaskTracker;
 
+use crate::authentication::PasswordHashing;
+use crate::clock::Clock;
+use crate::configuration::AuthenticationSettings;
 use crate::configuration::DatabaseSettings;
+use crate::configuration::HealthSettings;
+use crate::configuration::IdempotencySettings;
+use crate::configuration::LockoutSettings;
+use crate::configuration::MetricsSettings;
+use crate::configuration::RateLimitSettings;
+use crate::configuration::SessionSettings;
 use crate::configuration::Settings;
+use crate::configuration::ShutdownSettings;
+use crate::configuration::SubscriptionSettings;
+use crate::csrf::require_login_csrf_token;
+use crate::csrf::require_session_csrf_token;
 use crate::email_client::EmailClient;
+use crate::idempotency::run_expiry_until_stopped;
+use crate::issue_delivery_worker::run_worker_until_stopped;
+use crate::metrics::track_http_metrics;
+use crate::metrics::Metrics;
+use crate::pending_subscribers_cleanup::run_cleanup_until_stopped;
+use crate::rate_limit::run_bucket_cleanup_until_stopped;
+use crate::rate_limit::RateLimiter;
+use crate::request_id::propagate_request_id;
+use crate::routes::admin_dashboard;
+use crate::routes::audit_log;
+use crate::routes::change_password;
+use crate::routes::change_password_form;
+use crate::routes::change_user_role;
 use crate::routes::confirm;
+use crate::routes::confirm_subscriber;
+use crate::routes::create_token;
+use crate::routes::create_user;
+use crate::routes::delete_subscriber;
+use crate::routes::delete_user;
+use crate::routes::disable_two_factor;
+use crate::routes::enable_two_factor;
+use crate::routes::forgot_password;
+use crate::routes::forgot_password_form;
+use crate::routes::get_audit_events;
 use crate::routes::get_hello;
+use crate::routes::get_metrics;
+use crate::routes::get_subscribers;
 use crate::routes::health_check;
+use crate::routes::health_live;
+use crate::routes::health_ready;
 use crate::routes::home;
+use crate::routes::list_subscribers;
+use crate::routes::list_tokens;
+use crate::routes::list_users;
+use crate::routes::log_out;
 use crate::routes::login;
 use crate::routes::login_form;
+use crate::routes::login_two_factor;
+use crate::routes::login_two_factor_form;
+use crate::routes::preview_newsletter;
+use crate::routes::publish_newsletter;
+use crate::routes::publish_newsletter_form;
 use crate::routes::publish_newsletters;
+use crate::routes::regenerate_recovery_codes;
+use crate::routes::resend_confirmation;
+use crate::routes::reset_password;
+use crate::routes::reset_password_form;
+use crate::routes::revoke_token;
+use crate::routes::start_two_factor_setup;
 use crate::routes::subscribe_handler;
+use crate::routes::two_factor_settings;
+use crate::routes::unsubscribe;
+use crate::routes::unsubscribe_form;
+use crate::security_headers::set_security_headers;
+use crate::security_headers::SecurityHeaders;
 
 pub mod db_migration;
 
@@ -29,7 +98,21 @@ pub mod db_migration;
 /// for both production and testing purposes.
 pub struct Application {
     port: u16,
-    serve: Serve<Router, Router>,
+    serve: Serve<
+        IntoMakeServiceWithConnectInfo<Router, SocketAddr>,
+        AddExtension<Router, ConnectInfo<SocketAddr>>,
+    >,
+    pool: Pool,
+    email_client: EmailClient,
+    base_url: String,
+    subscription_settings: SubscriptionSettings,
+    idempotency_settings: IdempotencySettings,
+    rate_limiter: RateLimiter,
+    rate_limit_settings: RateLimitSettings,
+    password_hashing: PasswordHashing,
+    shutdown_settings: ShutdownSettings,
+    clock: Clock,
+    background_tasks: TaskTracker,
 }
 
 /// Shareable type, we insert it to the main `Router` as state,
@@ -39,6 +122,21 @@ pub struct AppState {
     pub base_url: String,
     pub pool: Pool,
     pub email_client: EmailClient,
+    pub subscription_settings: SubscriptionSettings,
+    pub session_settings: SessionSettings,
+    pub authentication_settings: AuthenticationSettings,
+    pub password_hashing: PasswordHashing,
+    pub lockout_settings: LockoutSettings,
+    pub rate_limit_settings: RateLimitSettings,
+    pub rate_limiter: RateLimiter,
+    pub trust_forwarded_for: bool,
+    pub metrics_settings: MetricsSettings,
+    pub metrics: Metrics,
+    pub health_settings: HealthSettings,
+    pub clock: Clock,
+    /// Work a handler leaves running after its response, waited for on
+    /// shutdown like the background tasks.
+    pub background_tasks: TaskTracker,
 }
 
 impl Application {
@@ -48,82 +146,364 @@ impl Application {
     /// It also configures a pool of connections to the PostgreSQL database.
     pub async fn build(
         configuration: Settings,
+    ) -> Result<Application, std::io::Error> {
+        let email_client = EmailClient::from_settings(
+            &configuration.email_client,
+            &configuration.email_delivery_service,
+        )
+        .map_err(std::io::Error::other)?;
+        Self::build_with_email_client(configuration, email_client).await
+    }
+
+    /// Same as `build`, but with already constructed `EmailClient`,
+    /// e.g. backed by a transport we want to inspect.
+    pub async fn build_with_email_client(
+        configuration: Settings,
+        email_client: EmailClient,
+    ) -> Result<Application, std::io::Error> {
+        Self::build_with_clock(configuration, email_client, Clock::System).await
+    }
+
+    /// Same as `build_with_email_client`, with the clock used for TOTP
+    /// codes, login challenges and lockouts, so tests can control time.
+    pub async fn build_with_clock(
+        configuration: Settings,
+        email_client: EmailClient,
+        clock: Clock,
     ) -> Result<Application, std::io::Error> {
         let postgres_connection =
             get_postgres_connection_pool(&configuration.database);
 
         db_migration::run_migration(&postgres_connection).await;
 
-        let timeout = configuration.email_client.timeout_millis();
-
-        let sender_email =
-            configuration.email_client.sender().map_err(|e| {
-                std::io::Error::new(std::io::ErrorKind::Other, e.to_string())
-            })?;
-
-        let email_client = EmailClient::new(
-            configuration.email_client.base_url,
-            sender_email,
-            configuration.email_client.authorization_token,
-            timeout,
-            configuration.email_delivery_service,
-        )
-        .map_err(|e| {
-            std::io::Error::new(std::io::ErrorKind::Other, e.to_string())
-        })?;
-
         let address =
             format!("{}:{}", configuration.app_addr, configuration.app_port);
         tracing::info!("running on {} address", address);
         let listener = TcpListener::bind(address).await?;
         let port = listener.local_addr()?.port();
+        let password_hashing =
+            PasswordHashing::new(&configuration.authentication.password_hash)
+                .map_err(|e| {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, e)
+            })?;
+        let security_headers =
+            SecurityHeaders::new(&configuration.security_headers).map_err(
+                |e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e),
+            )?;
+        // Emails sent by the handlers and by the delivery worker are
+        // counted in the registry served at `/metrics`.
+        let metrics = Metrics::default();
+        let email_client = email_client.with_metrics(metrics.clone());
+        let rate_limiter =
+            RateLimiter::new(&configuration.rate_limit, &postgres_connection);
+        let background_tasks = TaskTracker::new();
 
-        let serve = Self::build_server(
-            &configuration.app_base_url,
-            listener,
-            postgres_connection,
-            email_client,
-        );
+        // We do not wrap pool into arc because internally it alreaday has an
+        // `Arc`, and copying is cheap.
+        let app_state = AppState {
+            base_url: configuration.app_base_url.clone(),
+            pool: postgres_connection.clone(),
+            email_client: email_client.clone(),
+            subscription_settings: configuration.subscriptions.clone(),
+            session_settings: configuration.session,
+            authentication_settings: configuration.authentication,
+            password_hashing: password_hashing.clone(),
+            lockout_settings: configuration.lockout,
+            rate_limiter: rate_limiter.clone(),
+            rate_limit_settings: configuration.rate_limit.clone(),
+            trust_forwarded_for: configuration.trust_forwarded_for,
+            metrics_settings: configuration.metrics,
+            metrics,
+            health_settings: configuration.health,
+            clock: clock.clone(),
+            background_tasks: background_tasks.clone(),
+        };
+        let serve = Self::build_server(listener, app_state, security_headers);
 
-        Ok(Self { serve, port })
+        Ok(Self {
+            serve,
+            port,
+            pool: postgres_connection,
+            email_client,
+            base_url: configuration.app_base_url,
+            subscription_settings: configuration.subscriptions,
+            idempotency_settings: configuration.idempotency,
+            rate_limiter,
+            rate_limit_settings: configuration.rate_limit,
+            password_hashing,
+            shutdown_settings: configuration.shutdown,
+            clock,
+            background_tasks,
+        })
     }
 
     pub fn port(&self) -> u16 {
         self.port
     }
 
-    /// This function only returns when the application is stopped.
+    /// The client given to `build_with_email_client`, now recording into
+    /// the metrics of the application.
+    pub fn email_client(&self) -> &EmailClient {
+        &self.email_client
+    }
+
+    /// Hashing with the configured costs, e.g. to store users directly.
+    pub fn password_hashing(&self) -> &PasswordHashing {
+        &self.password_hashing
+    }
+
+    /// This function only returns when the application is stopped, by
+    /// SIGTERM or SIGINT. The newsletter delivery worker and the cleanup
+    /// of expired pending subscribers run next to the server and are
+    /// stopped together with it.
     pub async fn run_until_stopped(self) -> Result<(), std::io::Error> {
-        self.serve.await?;
-        Ok(())
+        self.run_until(shutdown_signal()).await
+    }
+
+    /// Same as `run_until_stopped`, but stopped when `signal` completes.
+    ///
+    /// On shutdown the server stops accepting connections, the background
+    /// tasks stop picking up new work, and all of them get
+    /// `drain_timeout` to finish what they are doing. If that is not
+    /// enough, the server and the background workers are aborted and a
+    /// `TimedOut` error is returned. Requests still being served and the
+    /// work handlers left behind run on until the runtime is dropped, i.e.
+    /// until the process exits. The connection pool is closed at the end.
+    pub async fn run_until<F>(self, signal: F) -> Result<(), std::io::Error>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let (stop_sender, stop) = watch::channel(false);
+        let mut server_stop = stop.clone();
+        let mut server = tokio::spawn(
+            self.serve
+                .with_graceful_shutdown(async move {
+                    let _ = server_stop.wait_for(|stop| *stop).await;
+                })
+                .into_future(),
+        );
+        let cleanup = tokio::spawn(run_cleanup_until_stopped(
+            self.pool.clone(),
+            self.subscription_settings,
+            stop.clone(),
+        ));
+        let idempotency_expiry = tokio::spawn(run_expiry_until_stopped(
+            self.pool.clone(),
+            self.idempotency_settings,
+            stop.clone(),
+        ));
+        let bucket_cleanup = tokio::spawn(run_bucket_cleanup_until_stopped(
+            self.rate_limiter,
+            self.rate_limit_settings,
+            self.clock,
+            stop.clone(),
+        ));
+        let worker = tokio::spawn(run_worker_until_stopped(
+            self.pool.clone(),
+            self.email_client,
+            self.base_url,
+            stop,
+        ));
+
+        // The server only stops by itself on an error.
+        let server_result = tokio::select! {
+            result = &mut server => Some(result),
+            _ = signal => None,
+        };
+        let drain_timeout = self.shutdown_settings.drain_timeout();
+        tracing::info!(
+            "Shutting down, waiting up to {:?} for open requests and \
+            background tasks",
+            drain_timeout
+        );
+        let _ = stop_sender.send(true);
+        let background_tasks = self.background_tasks;
+        background_tasks.close();
+
+        let abort_handles = [
+            server.abort_handle(),
+            worker.abort_handle(),
+            cleanup.abort_handle(),
+            idempotency_expiry.abort_handle(),
+            bucket_cleanup.abort_handle(),
+        ];
+        let drain = async move {
+            let server_result = match server_result {
+                Some(result) => result,
+                None => server.await,
+            };
+            let _ = tokio::join!(
+                worker,
+                cleanup,
+                idempotency_expiry,
+                bucket_cleanup,
+                background_tasks.wait()
+            );
+            server_result
+        };
+        let result = match tokio::time::timeout(drain_timeout, drain).await {
+            Ok(server_result) => {
+                tracing::info!("Open requests and background tasks finished");
+                server_result.unwrap_or_else(|e| Err(std::io::Error::other(e)))
+            }
+            Err(_) => {
+                tracing::warn!(
+                    "Drain timeout elapsed, cutting off the remaining work"
+                );
+                for handle in abort_handles {
+                    handle.abort();
+                }
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "Open requests or background tasks did not finish within \
+                    the drain timeout",
+                ))
+            }
+        };
+        self.pool.close();
+        result
     }
 
     /// Configure `Server`.
     fn build_server(
-        base_url: &str,
         listener: TcpListener,
-        pool: Pool,
-        email_client: EmailClient,
-    ) -> Serve<Router, Router> {
-        // We do not wrap pool into arc because internally it alreaday has an
-        // `Arc`, and copying is cheap.
-        let app_state = AppState {
-            pool,
-            email_client,
-            base_url: base_url.to_string(),
-        };
-        let app = Router::new()
+        app_state: AppState,
+        security_headers: SecurityHeaders,
+    ) -> Serve<
+        IntoMakeServiceWithConnectInfo<Router, SocketAddr>,
+        AddExtension<Router, ConnectInfo<SocketAddr>>,
+    > {
+        // Every state-changing html form carries a CSRF token.
+        let login_forms = Router::new()
+            .route("/login", routing::get(login_form))
+            .route("/login", routing::post(login))
+            .route(
+                "/login/2fa",
+                routing::get(login_two_factor_form).post(login_two_factor),
+            )
+            .route(
+                "/password/forgot",
+                routing::get(forgot_password_form).post(forgot_password),
+            )
+            .route(
+                "/password/reset",
+                routing::get(reset_password_form).post(reset_password),
+            )
+            .route_layer(middleware::from_fn(require_login_csrf_token));
+        let admin_forms = Router::new()
+            .route("/admin/dashboard", routing::get(admin_dashboard))
+            .route("/admin/audit", routing::get(audit_log))
+            .route(
+                "/admin/password",
+                routing::get(change_password_form).post(change_password),
+            )
+            .route("/admin/logout", routing::post(log_out))
+            .route("/admin/2fa", routing::get(two_factor_settings))
+            .route("/admin/2fa/setup", routing::post(start_two_factor_setup))
+            .route("/admin/2fa/enable", routing::post(enable_two_factor))
+            .route("/admin/2fa/disable", routing::post(disable_two_factor))
+            .route(
+                "/admin/2fa/recovery-codes",
+                routing::post(regenerate_recovery_codes),
+            )
+            .route(
+                "/admin/newsletters",
+                routing::get(publish_newsletter_form).post(publish_newsletter),
+            )
+            .route(
+                "/admin/newsletters/preview",
+                routing::post(preview_newsletter),
+            )
+            .route("/admin/subscribers", routing::get(list_subscribers))
+            .route(
+                "/admin/subscribers/:subscriber_id/confirm",
+                routing::post(confirm_subscriber),
+            )
+            .route(
+                "/admin/subscribers/:subscriber_id/delete",
+                routing::post(delete_subscriber),
+            )
+            .route(
+                "/admin/tokens",
+                routing::get(list_tokens).post(create_token),
+            )
+            .route(
+                "/admin/tokens/:token_id/revoke",
+                routing::post(revoke_token),
+            )
+            .route("/admin/users", routing::get(list_users).post(create_user))
+            .route(
+                "/admin/users/:user_id/role",
+                routing::post(change_user_role),
+            )
+            .route("/admin/users/:user_id/delete", routing::post(delete_user))
+            .route_layer(middleware::from_fn_with_state(
+                app_state.clone(),
+                require_session_csrf_token,
+            ));
+
+        let mut app = Router::new()
             .route("/health_check", routing::get(health_check))
+            .route("/health/live", routing::get(health_live))
+            .route("/health/ready", routing::get(health_ready))
             .route("/hello", routing::get(get_hello))
             .route("/subscriptions", routing::post(subscribe_handler))
             .route("/subscriptions/confirm", routing::get(confirm))
+            .route("/subscriptions/resend", routing::post(resend_confirmation))
+            .route(
+                "/subscriptions/unsubscribe",
+                routing::get(unsubscribe_form).post(unsubscribe),
+            )
             .route("/newsletters", routing::post(publish_newsletters))
-            .route("/login", routing::get(login_form))
-            .route("/login", routing::post(login))
-            .route("/", routing::get(home))
-            .with_state(app_state);
+            .route("/subscribers", routing::get(get_subscribers))
+            .route("/audit_events", routing::get(get_audit_events))
+            .merge(login_forms)
+            .merge(admin_forms)
+            .route("/", routing::get(home));
+        if app_state.metrics_settings.enabled {
+            app = app.route("/metrics", routing::get(get_metrics));
+        }
+        let metrics = app_state.metrics.clone();
+        let app = app
+            .with_state(app_state)
+            .layer(middleware::from_fn_with_state(
+                security_headers,
+                set_security_headers,
+            ))
+            .layer(middleware::from_fn_with_state(metrics, track_http_metrics))
+            // Outermost, so everything else logs within the request span.
+            .layer(middleware::from_fn(propagate_request_id));
+
+        // Peer addresses are needed for per client IP lockouts.
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+    }
+}
+
+/// Completes on SIGINT (Ctrl+C) or, on Unix, SIGTERM which Docker sends.
+async fn shutdown_signal() {
+    let interrupt = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install the SIGINT handler");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(
+            tokio::signal::unix::SignalKind::terminate(),
+        )
+        .expect("Failed to install the SIGTERM handler")
+        .recv()
+        .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
 
-        axum::serve(listener, app)
+    tokio::select! {
+        _ = interrupt => tracing::info!("Received SIGINT"),
+        _ = terminate => tracing::info!("Received SIGTERM"),
     }
 }
 
@@ -136,8 +516,7 @@ pub fn get_postgres_connection_pool(configuration: &DatabaseSettings) -> Pool {
         recycling_method: deadpool_postgres::RecyclingMethod::Fast,
     };
     let manager = Manager::from_config(pg_config, connector, manager_config);
-    let pool = Pool::builder(manager).max_size(16).build().unwrap();
-    pool
+    Pool::builder(manager).max_size(16).build().unwrap()
 }
 
 fn get_pg_conf(configuration: &DatabaseSettings) -> tokio_postgres::Config {
@@ -145,7 +524,7 @@ fn get_pg_conf(configuration: &DatabaseSettings) -> tokio_postgres::Config {
     config.user(&configuration.username);
     config.dbname(&configuration.database_name);
     config.host(&configuration.host);
-    config.password(&configuration.password.expose_secret());
+    config.password(configuration.password.expose_secret());
     config
 }
 
@@ -1,21 +1,16 @@
 use std::ops::DerefMut;
 
+use anyhow::Context;
 use deadpool_postgres::Pool;
-use refinery::embed_migrations;
+use refinery::{embed_migrations, Migration, Report};
 
 embed_migrations!("./migrations");
 
 pub async fn run_migration(pool: &Pool) {
-    let mut connection = pool
-        .get()
-        .await
-        .expect("Failed to get postgres pool connection to run migrations");
-    let client = connection.deref_mut().deref_mut();
-
-    let report = match migrations::runner().run_async(client).await {
+    let report = match apply_migrations(pool).await {
         Ok(r) => r,
         Err(e) => {
-            tracing::warn!("Can't run migration on db: {}", e);
+            tracing::warn!("Can't run migration on db: {:?}", e);
             return;
         }
     };
@@ -28,3 +23,55 @@ pub async fn run_migration(pool: &Pool) {
         tracing::info!("Migration: {}", migration);
     }
 }
+
+/// Apply every embedded migration missing in the database.
+pub async fn apply_migrations(pool: &Pool) -> Result<Report, anyhow::Error> {
+    let mut connection = pool
+        .get()
+        .await
+        .context("Failed to get postgres pool connection to run migrations")?;
+    let client = connection.deref_mut().deref_mut();
+    migrations::runner()
+        .run_async(client)
+        .await
+        .context("Failed to run migrations")
+}
+
+/// Migrations recorded in the database as applied, oldest first.
+pub async fn applied_migrations(
+    pool: &Pool,
+) -> Result<Vec<Migration>, anyhow::Error> {
+    let mut connection = pool
+        .get()
+        .await
+        .context("Failed to get postgres pool connection")?;
+    let client = connection.deref_mut().deref_mut();
+    migrations::runner()
+        .get_applied_migrations_async(client)
+        .await
+        .context("Failed to fetch applied migrations")
+}
+
+/// Embedded migrations `apply_migrations` would apply, oldest first.
+pub async fn pending_migrations(
+    pool: &Pool,
+) -> Result<Vec<Migration>, anyhow::Error> {
+    let applied = applied_migrations(pool).await?;
+    let mut pending: Vec<Migration> = migrations::runner()
+        .get_migrations()
+        .iter()
+        .filter(|m| !applied.iter().any(|a| a.version() == m.version()))
+        .cloned()
+        .collect();
+    pending.sort_by_key(|m| m.version());
+    Ok(pending)
+}
+
+/// The newest embedded migration, the schema this build expects.
+pub fn latest_embedded_migration() -> Option<Migration> {
+    migrations::runner()
+        .get_migrations()
+        .iter()
+        .max_by_key(|m| m.version())
+        .cloned()
+}
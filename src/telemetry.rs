@@ -1,4 +1,137 @@
+//! src/telemetry.rs
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use opentelemetry::trace::TraceError;
 use tokio::task::JoinHandle;
+use tracing::Subscriber;
+use tracing_subscriber::filter::ParseError;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::EnvFilter;
+
+use crate::configuration::{LogFormat, TelemetrySettings};
+use crate::error_chain_fmt;
+
+#[derive(thiserror::Error)]
+pub enum TelemetryError {
+    #[error("Invalid log filter directives")]
+    InvalidFilter(#[from] ParseError),
+    #[error("Failed to set up the Jaeger exporter")]
+    Exporter(#[from] TraceError),
+    #[error("Failed to set the global subscriber")]
+    GlobalDefault(#[from] tracing::subscriber::SetGlobalDefaultError),
+}
+
+impl std::fmt::Debug for TelemetryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+/// Compose the subscriber from the settings, writing logs to `sink`.
+///
+/// With a Jaeger agent endpoint every span is sent over UDP as it closes.
+pub fn get_subscriber<Sink>(
+    settings: &TelemetrySettings,
+    sink: Sink,
+) -> Result<impl Subscriber + Send + Sync, TelemetryError>
+where
+    Sink: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let filter = EnvFilter::try_new(&settings.log_filter)?;
+    let opentelemetry = match settings.jaeger_agent_endpoint {
+        Some(ref endpoint) => {
+            let tracer = opentelemetry_jaeger::new_agent_pipeline()
+                .with_endpoint(endpoint)
+                .with_service_name(&settings.service_name)
+                .install_simple()?;
+            Some(tracing_opentelemetry::layer().with_tracer(tracer))
+        }
+        None => None,
+    };
+    // Only one of them is `Some`.
+    let (compact, json) = match settings.log_format {
+        LogFormat::Compact => (
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .with_timer(
+                        tracing_subscriber::fmt::time::ChronoLocal::default(),
+                    )
+                    .compact()
+                    .with_level(true)
+                    .with_writer(sink),
+            ),
+            None,
+        ),
+        LogFormat::Json => (
+            None,
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .flatten_event(true)
+                    .with_current_span(true)
+                    .with_span_list(true)
+                    .with_writer(sink),
+            ),
+        ),
+    };
+    Ok(tracing_subscriber::registry()
+        .with(filter)
+        .with(opentelemetry)
+        .with(compact)
+        .with(json))
+}
+
+/// Install the subscriber for the whole process, logging to stdout.
+/// `RUST_LOG`, when set, replaces the configured filter directives.
+pub fn init_telemetry(
+    settings: &TelemetrySettings,
+) -> Result<(), TelemetryError> {
+    let mut settings = settings.clone();
+    if let Ok(directives) = std::env::var(EnvFilter::DEFAULT_ENV) {
+        settings.log_filter = directives;
+    }
+    let subscriber = get_subscriber(&settings, std::io::stdout)?;
+    tracing::subscriber::set_global_default(subscriber)?;
+    Ok(())
+}
+
+/// Flush the span exporter before exiting.
+pub fn shutdown_telemetry() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+/// Keeps the formatted logs in memory, for tests to look at. Clones share
+/// the same buffer.
+#[derive(Debug, Clone, Default)]
+pub struct LogCapture(Arc<Mutex<Vec<u8>>>);
+
+impl LogCapture {
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+impl Write for LogCapture {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogCapture {
+    type Writer = LogCapture;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
 
 /// We can now easily reach for it every time we need to offload
 /// some CPU-intensive computation to a dedicated threadpool.
@@ -10,3 +143,83 @@ where
     let current_span = tracing::Span::current();
     tokio::task::spawn_blocking(move || current_span.in_scope(f))
 }
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use super::{get_subscriber, LogCapture};
+    use crate::configuration::{LogFormat, TelemetrySettings};
+
+    fn capture(settings: &TelemetrySettings) -> LogCapture {
+        let logs = LogCapture::default();
+        let subscriber = get_subscriber(settings, logs.clone()).unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("Handle request", user = "ghashy");
+            let _guard = span.enter();
+            tracing::info!(attempt = 2, "Sending email");
+            tracing::debug!("Not shown with the default filter");
+        });
+        logs
+    }
+
+    #[test]
+    fn json_logs_have_one_object_per_event() {
+        let settings = TelemetrySettings {
+            log_format: LogFormat::Json,
+            ..Default::default()
+        };
+        let logs = capture(&settings).contents();
+        let lines: Vec<_> = logs.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let event: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(event["level"], "INFO");
+        assert_eq!(event["message"], "Sending email");
+        assert_eq!(event["attempt"], 2);
+        assert_eq!(event["span"]["name"], "Handle request");
+        assert_eq!(event["span"]["user"], "ghashy");
+    }
+
+    #[test]
+    fn compact_logs_are_plain_lines() {
+        let logs = capture(&TelemetrySettings::default()).contents();
+        assert_eq!(logs.lines().count(), 1);
+        assert!(logs.contains("INFO"));
+        assert!(logs.contains("Sending email"));
+        assert!(serde_json::from_str::<serde_json::Value>(&logs).is_err());
+    }
+
+    #[test]
+    fn filter_directives_are_applied() {
+        let settings = TelemetrySettings {
+            log_filter: "warn,zero2prod_axum=debug".to_string(),
+            ..Default::default()
+        };
+        let logs = capture(&settings).contents();
+        assert!(logs.contains("Not shown with the default filter"));
+
+        let settings = TelemetrySettings {
+            log_filter: "warn".to_string(),
+            ..Default::default()
+        };
+        assert!(capture(&settings).contents().is_empty());
+    }
+
+    #[test]
+    fn invalid_filter_directives_are_rejected() {
+        let settings = TelemetrySettings {
+            log_filter: "info,[unclosed".to_string(),
+            ..Default::default()
+        };
+        assert!(get_subscriber(&settings, LogCapture::default()).is_err());
+    }
+
+    #[test]
+    fn jaeger_exporter_is_set_up_with_an_agent_endpoint() {
+        let settings = TelemetrySettings {
+            jaeger_agent_endpoint: Some("127.0.0.1:6831".to_string()),
+            ..Default::default()
+        };
+        assert!(get_subscriber(&settings, LogCapture::default()).is_ok());
+    }
+}
@@ -0,0 +1,383 @@
+//! src/two_factor.rs
+//!
+//! Optional TOTP second factor for admin logins, with single-use recovery
+//! codes. Times come from the caller, see `clock::Clock`.
+
+use anyhow::Context;
+use cookie::{Cookie, SameSite};
+use cornucopia_async::GenericClient;
+use http::{HeaderMap, HeaderValue};
+use qrcode::render::svg;
+use qrcode::QrCode;
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use secrecy::Secret;
+use sha2::{Digest, Sha256};
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+use crate::audit::{self, RequestOrigin};
+use crate::configuration::SessionSettings;
+use crate::cornucopia::queries::two_factor;
+use crate::error_chain_fmt;
+use crate::validation::recovery_code::RecoveryCode;
+use crate::validation::totp::TotpSecret;
+
+/// Name of the cookie with the pending login.
+pub const CHALLENGE_COOKIE: &str = "login_challenge";
+/// Issuer shown in authenticator apps.
+pub const TOTP_ISSUER: &str = "zero2prod";
+/// How many recovery codes a user gets at once.
+pub const RECOVERY_CODES: usize = 10;
+/// Time to type in the code after the password was accepted.
+const CHALLENGE_TTL: Duration = Duration::minutes(5);
+/// Wrong codes after which the password has to be entered again.
+const MAX_FAILED_ATTEMPTS: i32 = 5;
+
+/// Audit actions.
+pub const TWO_FACTOR_ENABLED: &str = "2fa_enabled";
+pub const TWO_FACTOR_DISABLED: &str = "2fa_disabled";
+pub const RECOVERY_CODE_USED: &str = "recovery_code_used";
+
+#[derive(thiserror::Error)]
+pub enum TwoFactorError {
+    #[error("The code is not valid.")]
+    InvalidCode,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for TwoFactorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+/// A login which passed the password check and waits for the second
+/// factor. Only its hash is stored.
+#[derive(Debug)]
+pub struct LoginChallenge(String);
+
+impl LoginChallenge {
+    pub fn generate() -> LoginChallenge {
+        let mut rng = thread_rng();
+        LoginChallenge(
+            std::iter::repeat_with(|| rng.sample(Alphanumeric))
+                .map(char::from)
+                .take(48)
+                .collect(),
+        )
+    }
+
+    /// Take the challenge from the `Cookie` request header, if any.
+    pub fn from_headers(headers: &HeaderMap) -> Option<LoginChallenge> {
+        headers
+            .get_all(http::header::COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(Cookie::split_parse)
+            .filter_map(Result::ok)
+            .find(|cookie| cookie.name() == CHALLENGE_COOKIE)
+            .map(|cookie| LoginChallenge(cookie.value().to_string()))
+    }
+
+    /// `Set-Cookie` value, only sent back to the `/login` pages.
+    pub fn cookie(&self, settings: &SessionSettings) -> HeaderValue {
+        let max_age =
+            cookie::time::Duration::seconds(CHALLENGE_TTL.whole_seconds());
+        let cookie = Cookie::build((CHALLENGE_COOKIE, self.0.as_str()))
+            .path("/login")
+            .http_only(true)
+            .secure(settings.secure_cookie)
+            .same_site(SameSite::Strict)
+            .max_age(max_age)
+            .build();
+        HeaderValue::from_str(&cookie.to_string()).unwrap()
+    }
+
+    pub fn removal_cookie(settings: &SessionSettings) -> HeaderValue {
+        let mut cookie = Cookie::build((CHALLENGE_COOKIE, ""))
+            .path("/login")
+            .http_only(true)
+            .secure(settings.secure_cookie)
+            .same_site(SameSite::Strict)
+            .build();
+        cookie.make_removal();
+        HeaderValue::from_str(&cookie.to_string()).unwrap()
+    }
+
+    fn hash(&self) -> String {
+        hex::encode(Sha256::digest(self.0.as_bytes()))
+    }
+}
+
+/// Whether logins of the user need the second factor.
+pub async fn is_enabled<C: GenericClient>(
+    client: &C,
+    user_id: Uuid,
+) -> Result<bool, anyhow::Error> {
+    let settings = two_factor::get_totp_settings()
+        .bind(client, &user_id)
+        .one()
+        .await
+        .context("Failed to fetch TOTP settings")?;
+    Ok(settings.totp_enabled)
+}
+
+/// Store a new secret for the user, it is enforced only after
+/// `confirm_enrollment`. `None` if 2FA is already enabled.
+#[tracing::instrument(name = "Start TOTP enrollment", skip(client))]
+pub async fn start_enrollment<C: GenericClient>(
+    client: &C,
+    user_id: Uuid,
+) -> Result<Option<TotpSecret>, anyhow::Error> {
+    let secret = TotpSecret::generate();
+    let updated = two_factor::start_totp_enrollment()
+        .bind(client, &secret.to_base32().as_str(), &user_id)
+        .await
+        .context("Failed to store a TOTP secret")?;
+    Ok((updated > 0).then_some(secret))
+}
+
+/// Enable 2FA once the user proved their app has the secret. Returns
+/// fresh recovery codes, to be shown to the user this one time. They are
+/// hashed with `recovery_code_key`.
+#[tracing::instrument(
+    name = "Confirm TOTP enrollment",
+    skip(client, origin, code, recovery_code_key)
+)]
+pub async fn confirm_enrollment<C: GenericClient>(
+    client: &C,
+    origin: &RequestOrigin,
+    user_id: Uuid,
+    code: &str,
+    recovery_code_key: &Secret<String>,
+    now: OffsetDateTime,
+) -> Result<Vec<RecoveryCode>, TwoFactorError> {
+    let settings = two_factor::get_totp_settings()
+        .bind(client, &user_id)
+        .one()
+        .await
+        .context("Failed to fetch TOTP settings")?;
+    let Some(secret) = settings.totp_secret.filter(|_| !settings.totp_enabled)
+    else {
+        return Err(TwoFactorError::InvalidCode);
+    };
+    let secret = TotpSecret::parse(&secret).map_err(anyhow::Error::msg)?;
+    let step = secret
+        .verify(code, now)
+        .ok_or(TwoFactorError::InvalidCode)?;
+    two_factor::enable_totp()
+        .bind(client, &step, &user_id)
+        .await
+        .context("Failed to enable TOTP")?;
+    let codes =
+        replace_recovery_codes(client, user_id, recovery_code_key).await?;
+    audit::record_event(
+        client,
+        origin,
+        Some(user_id),
+        TWO_FACTOR_ENABLED,
+        None,
+    )
+    .await?;
+    Ok(codes)
+}
+
+/// Check the second factor: a TOTP code, each accepted at most once,
+/// or an unused recovery code.
+#[tracing::instrument(
+    name = "Verify second factor",
+    skip(client, origin, code, recovery_code_key)
+)]
+pub async fn verify<C: GenericClient>(
+    client: &C,
+    origin: &RequestOrigin,
+    user_id: Uuid,
+    code: &str,
+    recovery_code_key: &Secret<String>,
+    now: OffsetDateTime,
+) -> Result<(), TwoFactorError> {
+    let settings = two_factor::get_totp_settings()
+        .bind(client, &user_id)
+        .one()
+        .await
+        .context("Failed to fetch TOTP settings")?;
+    let Some(secret) = settings.totp_secret.filter(|_| settings.totp_enabled)
+    else {
+        return Err(TwoFactorError::InvalidCode);
+    };
+    let secret = TotpSecret::parse(&secret).map_err(anyhow::Error::msg)?;
+
+    if let Some(step) = secret.verify(code, now) {
+        // Refused when this or a later code was already used.
+        let accepted = two_factor::use_totp_step()
+            .bind(client, &step, &user_id)
+            .await
+            .context("Failed to store the last TOTP step")?;
+        return if accepted > 0 {
+            Ok(())
+        } else {
+            tracing::warn!("TOTP code replayed");
+            Err(TwoFactorError::InvalidCode)
+        };
+    }
+
+    let code =
+        RecoveryCode::parse(code).map_err(|_| TwoFactorError::InvalidCode)?;
+    let code_hash = code.hash(recovery_code_key, user_id);
+    let used = two_factor::use_recovery_code()
+        .bind(client, &now, &user_id, &code_hash.as_str())
+        .await
+        .context("Failed to use a recovery code")?;
+    if used == 0 {
+        return Err(TwoFactorError::InvalidCode);
+    }
+    tracing::info!("Recovery code used");
+    audit::record_event(
+        client,
+        origin,
+        Some(user_id),
+        RECOVERY_CODE_USED,
+        None,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Turn 2FA off, the secret and recovery codes are deleted.
+#[tracing::instrument(name = "Disable TOTP", skip(client, origin))]
+pub async fn disable<C: GenericClient>(
+    client: &C,
+    origin: &RequestOrigin,
+    user_id: Uuid,
+) -> Result<(), anyhow::Error> {
+    two_factor::disable_totp()
+        .bind(client, &user_id)
+        .await
+        .context("Failed to disable TOTP")?;
+    two_factor::delete_recovery_codes()
+        .bind(client, &user_id)
+        .await
+        .context("Failed to delete recovery codes")?;
+    audit::record_event(
+        client,
+        origin,
+        Some(user_id),
+        TWO_FACTOR_DISABLED,
+        None,
+    )
+    .await
+}
+
+/// Replace all recovery codes of the user with new ones.
+#[tracing::instrument(
+    name = "Replace recovery codes",
+    skip(client, recovery_code_key)
+)]
+pub async fn replace_recovery_codes<C: GenericClient>(
+    client: &C,
+    user_id: Uuid,
+    recovery_code_key: &Secret<String>,
+) -> Result<Vec<RecoveryCode>, anyhow::Error> {
+    two_factor::delete_recovery_codes()
+        .bind(client, &user_id)
+        .await
+        .context("Failed to delete recovery codes")?;
+    let codes: Vec<RecoveryCode> =
+        std::iter::repeat_with(RecoveryCode::generate)
+            .take(RECOVERY_CODES)
+            .collect();
+    for code in &codes {
+        let code_hash = code.hash(recovery_code_key, user_id);
+        two_factor::insert_recovery_code()
+            .bind(client, &Uuid::new_v4(), &user_id, &code_hash.as_str())
+            .await
+            .context("Failed to store a recovery code")?;
+    }
+    Ok(codes)
+}
+
+pub async fn unused_recovery_codes<C: GenericClient>(
+    client: &C,
+    user_id: Uuid,
+) -> Result<i64, anyhow::Error> {
+    two_factor::count_unused_recovery_codes()
+        .bind(client, &user_id)
+        .one()
+        .await
+        .context("Failed to count recovery codes")
+}
+
+/// Remember that the user passed the password check.
+#[tracing::instrument(name = "Start login challenge", skip(client))]
+pub async fn start_challenge<C: GenericClient>(
+    client: &C,
+    user_id: Uuid,
+    now: OffsetDateTime,
+) -> Result<LoginChallenge, anyhow::Error> {
+    two_factor::delete_expired_login_challenges()
+        .bind(client, &now)
+        .await
+        .context("Failed to delete expired login challenges")?;
+    let challenge = LoginChallenge::generate();
+    two_factor::insert_login_challenge()
+        .bind(
+            client,
+            &challenge.hash().as_str(),
+            &user_id,
+            &(now + CHALLENGE_TTL),
+        )
+        .await
+        .context("Failed to store a login challenge")?;
+    Ok(challenge)
+}
+
+/// The user of a pending login, `None` if it is unknown or expired.
+pub async fn challenge_user<C: GenericClient>(
+    client: &C,
+    challenge: &LoginChallenge,
+    now: OffsetDateTime,
+) -> Result<Option<Uuid>, anyhow::Error> {
+    two_factor::get_login_challenge_user()
+        .bind(client, &challenge.hash().as_str(), &now)
+        .opt()
+        .await
+        .context("Failed to fetch a login challenge")
+}
+
+/// Count a wrong code. Returns `false` when the challenge is used up
+/// and was deleted.
+pub async fn fail_challenge<C: GenericClient>(
+    client: &C,
+    challenge: &LoginChallenge,
+) -> Result<bool, anyhow::Error> {
+    let failed_attempts = two_factor::record_failed_challenge()
+        .bind(client, &challenge.hash().as_str())
+        .opt()
+        .await
+        .context("Failed to count a wrong code")?
+        .unwrap_or(MAX_FAILED_ATTEMPTS);
+    if failed_attempts < MAX_FAILED_ATTEMPTS {
+        return Ok(true);
+    }
+    end_challenge(client, challenge).await?;
+    Ok(false)
+}
+
+pub async fn end_challenge<C: GenericClient>(
+    client: &C,
+    challenge: &LoginChallenge,
+) -> Result<(), anyhow::Error> {
+    two_factor::delete_login_challenge()
+        .bind(client, &challenge.hash().as_str())
+        .await
+        .context("Failed to delete a login challenge")?;
+    Ok(())
+}
+
+/// `otpauth://` URI as an inline SVG QR code.
+pub fn qr_code_svg(uri: &str) -> Result<String, anyhow::Error> {
+    let code = QrCode::new(uri.as_bytes())
+        .context("Failed to encode the URI as a QR code")?;
+    Ok(code.render::<svg::Color>().min_dimensions(200, 200).build())
+}
@@ -0,0 +1,104 @@
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use sha2::{Digest, Sha256};
+
+const MARKER: &str = "z2p";
+const PREFIX_LEN: usize = 8;
+const SECRET_LEN: usize = 32;
+
+/// API token sent as `Authorization: Bearer`, shaped like
+/// `z2p_<prefix>_<secret>`. The prefix is stored in clear to find the
+/// token, the whole token only as a hash.
+#[derive(Debug)]
+pub struct ApiToken(String);
+
+impl ApiToken {
+    pub fn parse(token: &str) -> Result<ApiToken, &'static str> {
+        let mut parts = token.split('_');
+        let (Some(MARKER), Some(prefix), Some(secret), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err("API token is malformed");
+        };
+        let is_wrong_length =
+            prefix.len() != PREFIX_LEN || secret.len() != SECRET_LEN;
+        let contains_forbidden_chars = prefix
+            .chars()
+            .chain(secret.chars())
+            .any(|c| !c.is_ascii_alphanumeric());
+
+        if is_wrong_length {
+            Err("API token has a wrong length")
+        } else if contains_forbidden_chars {
+            Err("API token contains forbidden chars")
+        } else {
+            Ok(ApiToken(token.to_string()))
+        }
+    }
+
+    /// The secret part alone gives us ~190 bits of entropy.
+    pub fn generate() -> ApiToken {
+        let mut rng = thread_rng();
+        let mut random = |n| -> String {
+            std::iter::repeat_with(|| rng.sample(Alphanumeric))
+                .map(char::from)
+                .take(n)
+                .collect()
+        };
+        let prefix = random(PREFIX_LEN);
+        let secret = random(SECRET_LEN);
+        ApiToken(format!("{}_{}_{}", MARKER, prefix, secret))
+    }
+
+    /// Public part used to look the token up.
+    pub fn prefix(&self) -> &str {
+        &self.0[MARKER.len() + 1..MARKER.len() + 1 + PREFIX_LEN]
+    }
+
+    /// Hex encoded SHA-256 of the token, what we keep in the database.
+    pub fn hash(&self) -> String {
+        hex::encode(Sha256::digest(self.0.as_bytes()))
+    }
+}
+
+impl AsRef<str> for ApiToken {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use super::ApiToken;
+
+    #[test]
+    fn generated_tokens_are_valid() {
+        let token = ApiToken::generate();
+        let parsed = ApiToken::parse(token.as_ref()).unwrap();
+        assert_eq!(parsed.prefix(), token.prefix());
+        assert_eq!(token.prefix().len(), 8);
+        assert!(token
+            .as_ref()
+            .starts_with(&format!("z2p_{}_", token.prefix())));
+    }
+
+    #[test]
+    fn malformed_tokens_are_rejected() {
+        let secret = "a".repeat(32);
+        assert!(ApiToken::parse("").is_err());
+        assert!(ApiToken::parse(&format!("abc_abcdefgh_{}", secret)).is_err());
+        assert!(ApiToken::parse(&format!("z2p_abcdefg_{}", secret)).is_err());
+        assert!(ApiToken::parse(&format!("z2p_abcdefg-_{}", secret)).is_err());
+        assert!(ApiToken::parse(&format!("z2p_abcdefgh_{}_", secret)).is_err());
+        assert!(ApiToken::parse(&format!("z2p_abcdefgh_{}", secret)).is_ok());
+    }
+
+    #[test]
+    fn hash_is_stable_and_does_not_reveal_the_token() {
+        let token = ApiToken::generate();
+        assert_eq!(token.hash(), token.hash());
+        assert_eq!(token.hash().len(), 64);
+        assert!(!token.hash().contains(token.as_ref()));
+    }
+}
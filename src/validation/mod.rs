@@ -1 +1,5 @@
+pub mod api_token;
 pub mod subscriber_token;
+pub mod password_reset_token;
+pub mod recovery_code;
+pub mod totp;
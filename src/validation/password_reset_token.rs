@@ -0,0 +1,72 @@
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use sha2::{Digest, Sha256};
+
+/// Secret part of a password reset link. Only its hash is stored,
+/// so a leaked database doesn't allow resetting passwords.
+#[derive(Debug)]
+pub struct PasswordResetToken(String);
+
+impl PasswordResetToken {
+    pub fn parse(token: &str) -> Result<PasswordResetToken, &'static str> {
+        let is_wrong_length = token.chars().count() != 43;
+        let contains_forbidden_chars =
+            token.chars().any(|c| !c.is_ascii_alphanumeric());
+
+        if is_wrong_length {
+            Err("Password reset token must be 43 characters long")
+        } else if contains_forbidden_chars {
+            Err("Password reset token contains forbidden chars")
+        } else {
+            Ok(PasswordResetToken(token.to_string()))
+        }
+    }
+
+    /// 43 alphanumeric characters give us ~256 bits of entropy.
+    pub fn generate() -> PasswordResetToken {
+        let mut rng = thread_rng();
+        PasswordResetToken(
+            std::iter::repeat_with(|| rng.sample(Alphanumeric))
+                .map(char::from)
+                .take(43)
+                .collect(),
+        )
+    }
+
+    /// Hex encoded SHA-256 of the token, what we keep in the database.
+    pub fn hash(&self) -> String {
+        hex::encode(Sha256::digest(self.0.as_bytes()))
+    }
+}
+
+impl AsRef<str> for PasswordResetToken {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use super::PasswordResetToken;
+
+    #[test]
+    fn generated_tokens_are_valid() {
+        let token = PasswordResetToken::generate();
+        assert!(PasswordResetToken::parse(token.as_ref()).is_ok());
+    }
+
+    #[test]
+    fn malformed_tokens_are_rejected() {
+        assert!(PasswordResetToken::parse("short").is_err());
+        assert!(PasswordResetToken::parse(&"-".repeat(43)).is_err());
+    }
+
+    #[test]
+    fn hash_is_stable_and_does_not_reveal_the_token() {
+        let token = PasswordResetToken::generate();
+        assert_eq!(token.hash(), token.hash());
+        assert_eq!(token.hash().len(), 64);
+        assert!(!token.hash().contains(token.as_ref()));
+    }
+}
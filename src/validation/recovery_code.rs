@@ -0,0 +1,123 @@
+use hmac::{Hmac, Mac};
+use rand::{distributions::Uniform, thread_rng, Rng};
+use secrecy::{ExposeSecret, Secret};
+use sha2::Sha256;
+use uuid::Uuid;
+
+/// Letters and digits without the look-alikes `0`, `1`, `l` and `o`,
+/// the codes are typed in by hand.
+const ALPHABET: &[u8] = b"23456789abcdefghijkmnpqrstuvwxyz";
+const HALF_LEN: usize = 5;
+
+/// Single-use code replacing a TOTP code when the phone is lost, shown
+/// as `xxxxx-xxxxx`. Only its keyed hash is stored.
+#[derive(Debug)]
+pub struct RecoveryCode(String);
+
+impl RecoveryCode {
+    /// Case, spaces and the dash are ignored, as people copy the codes
+    /// in different ways.
+    pub fn parse(code: &str) -> Result<RecoveryCode, &'static str> {
+        let normalized: String = code
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .flat_map(char::to_lowercase)
+            .collect();
+        if normalized.chars().count() != 2 * HALF_LEN {
+            Err("Recovery code must be 10 characters long")
+        } else if !normalized.bytes().all(|c| ALPHABET.contains(&c)) {
+            Err("Recovery code contains forbidden chars")
+        } else {
+            Ok(RecoveryCode(format!(
+                "{}-{}",
+                &normalized[..HALF_LEN],
+                &normalized[HALF_LEN..]
+            )))
+        }
+    }
+
+    /// 10 characters from 32 give us 50 bits of entropy, enough for
+    /// a code that also needs the password and works once.
+    pub fn generate() -> RecoveryCode {
+        let mut rng = thread_rng();
+        let mut half = || -> String {
+            (&mut rng)
+                .sample_iter(Uniform::from(0..ALPHABET.len()))
+                .take(HALF_LEN)
+                .map(|i| ALPHABET[i] as char)
+                .collect()
+        };
+        let first = half();
+        RecoveryCode(format!("{}-{}", first, half()))
+    }
+
+    /// Hex encoded HMAC-SHA256 of the code and the id of its user, what
+    /// we keep in the database. The key isn't stored there, so the 50 bits
+    /// can't be brute forced from a dump of the table.
+    pub fn hash(&self, key: &Secret<String>, user_id: Uuid) -> String {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(key.expose_secret().as_bytes())
+                .expect("HMAC accepts keys of any length");
+        mac.update(user_id.as_bytes());
+        mac.update(self.0.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+}
+
+impl AsRef<str> for RecoveryCode {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use secrecy::Secret;
+    use uuid::Uuid;
+
+    use super::RecoveryCode;
+
+    fn key() -> Secret<String> {
+        Secret::new("key".to_string())
+    }
+
+    #[test]
+    fn generated_codes_are_valid() {
+        let code = RecoveryCode::generate();
+        assert_eq!(code.as_ref().len(), 11);
+        let parsed = RecoveryCode::parse(code.as_ref()).unwrap();
+        let user_id = Uuid::new_v4();
+        assert_eq!(parsed.hash(&key(), user_id), code.hash(&key(), user_id));
+    }
+
+    #[test]
+    fn codes_are_normalized_before_hashing() {
+        let code = RecoveryCode::parse("abcde-fghij").unwrap();
+        let user_id = Uuid::new_v4();
+        for typed in ["ABCDE-FGHIJ", "abcdefghij", " abcde fghij "] {
+            assert_eq!(
+                RecoveryCode::parse(typed).unwrap().hash(&key(), user_id),
+                code.hash(&key(), user_id)
+            );
+        }
+    }
+
+    #[test]
+    fn hashes_depend_on_the_key_and_the_user() {
+        let code = RecoveryCode::parse("abcde-fghij").unwrap();
+        let user_id = Uuid::new_v4();
+        let hash = code.hash(&key(), user_id);
+        let other_key = Secret::new("other key".to_string());
+        assert_ne!(code.hash(&other_key, user_id), hash);
+        assert_ne!(code.hash(&key(), Uuid::new_v4()), hash);
+    }
+
+    #[test]
+    fn malformed_codes_are_rejected() {
+        assert!(RecoveryCode::parse("abcde").is_err());
+        assert!(RecoveryCode::parse("abcde-fghi0").is_err());
+        assert!(RecoveryCode::parse("abcde-fghi!").is_err());
+    }
+}
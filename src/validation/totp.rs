@@ -0,0 +1,171 @@
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha1::Sha1;
+use time::OffsetDateTime;
+
+use crate::constant_time_eq;
+
+/// Seconds a code stays valid, what authenticator apps expect.
+const STEP: i64 = 30;
+const DIGITS: u32 = 6;
+/// Steps accepted before and after the current one, to allow for clock
+/// drift between the server and the phone.
+const DRIFT_STEPS: i64 = 1;
+
+/// Shared secret of RFC 6238 TOTP with the defaults every authenticator
+/// app supports: HMAC-SHA1, 6 digits, 30 second steps. Stored and shown
+/// base32 encoded.
+#[derive(Debug, Clone)]
+pub struct TotpSecret(Vec<u8>);
+
+impl TotpSecret {
+    /// 160 bits, the key size RFC 4226 recommends for HMAC-SHA1.
+    pub fn generate() -> TotpSecret {
+        let mut secret = vec![0; 20];
+        rand::thread_rng().fill_bytes(&mut secret);
+        TotpSecret(secret)
+    }
+
+    pub fn parse(base32: &str) -> Result<TotpSecret, &'static str> {
+        BASE32_NOPAD
+            .decode(base32.as_bytes())
+            .map(TotpSecret)
+            .map_err(|_| "TOTP secret is not valid base32")
+    }
+
+    pub fn to_base32(&self) -> String {
+        BASE32_NOPAD.encode(&self.0)
+    }
+
+    /// `otpauth://` URI for authenticator apps, usually scanned as
+    /// a QR code.
+    pub fn otpauth_uri(&self, issuer: &str, account: &str) -> String {
+        let issuer = urlencoding::encode(issuer);
+        format!(
+            "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}\
+             &algorithm=SHA1&digits={DIGITS}&period={STEP}",
+            account = urlencoding::encode(account),
+            secret = self.to_base32(),
+        )
+    }
+
+    /// The code for the step containing `time`.
+    pub fn code_at(&self, time: OffsetDateTime) -> String {
+        self.code_for_step(step_of(time))
+    }
+
+    /// Step of the accepted code, so the caller can refuse to accept
+    /// the same code twice. `None` if the code is wrong.
+    pub fn verify(&self, code: &str, time: OffsetDateTime) -> Option<i64> {
+        let code = code.trim();
+        if code.len() != DIGITS as usize
+            || !code.chars().all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+        let current = step_of(time);
+        (current - DRIFT_STEPS..=current + DRIFT_STEPS)
+            .find(|step| constant_time_eq(&self.code_for_step(*step), code))
+    }
+
+    /// HOTP of RFC 4226 with the step as the counter.
+    fn code_for_step(&self, step: i64) -> String {
+        let mut mac = Hmac::<Sha1>::new_from_slice(&self.0)
+            .expect("HMAC takes keys of any size");
+        mac.update(&step.to_be_bytes());
+        let hash = mac.finalize().into_bytes();
+        let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            hash[offset] & 0x7f,
+            hash[offset + 1],
+            hash[offset + 2],
+            hash[offset + 3],
+        ]);
+        format!(
+            "{:0width$}",
+            binary % 10u32.pow(DIGITS),
+            width = DIGITS as usize
+        )
+    }
+}
+
+fn step_of(time: OffsetDateTime) -> i64 {
+    time.unix_timestamp().div_euclid(STEP)
+}
+
+// ───── Unit tests ───────────────────────────────────────────────────────── //
+
+#[cfg(test)]
+mod tests {
+    use time::{Duration, OffsetDateTime};
+
+    use super::TotpSecret;
+
+    /// The SHA1 key of the RFC 6238 test vectors.
+    fn rfc_secret() -> TotpSecret {
+        TotpSecret(b"12345678901234567890".to_vec())
+    }
+
+    fn at(unix: i64) -> OffsetDateTime {
+        OffsetDateTime::from_unix_timestamp(unix).unwrap()
+    }
+
+    #[test]
+    fn codes_match_the_rfc_6238_test_vectors() {
+        // The RFC lists 8 digit codes, we use the last 6.
+        let secret = rfc_secret();
+        assert_eq!(secret.code_at(at(59)), "287082");
+        assert_eq!(secret.code_at(at(1111111109)), "081804");
+        assert_eq!(secret.code_at(at(1111111111)), "050471");
+        assert_eq!(secret.code_at(at(1234567890)), "005924");
+        assert_eq!(secret.code_at(at(2000000000)), "279037");
+    }
+
+    #[test]
+    fn neighbour_steps_are_accepted() {
+        let secret = TotpSecret::generate();
+        let now = at(1_700_000_000);
+        let step = now.unix_timestamp() / 30;
+        let code = secret.code_at(now);
+        assert_eq!(secret.verify(&code, now), Some(step));
+        assert_eq!(
+            secret.verify(&code, now + Duration::seconds(30)),
+            Some(step)
+        );
+        assert_eq!(
+            secret.verify(&code, now - Duration::seconds(30)),
+            Some(step)
+        );
+        assert_eq!(secret.verify(&code, now + Duration::seconds(90)), None);
+    }
+
+    #[test]
+    fn malformed_codes_are_rejected() {
+        let secret = rfc_secret();
+        assert_eq!(secret.verify("28708", at(59)), None);
+        assert_eq!(secret.verify("2870822", at(59)), None);
+        assert_eq!(secret.verify("28708a", at(59)), None);
+        assert_eq!(secret.verify(" 287082 ", at(59)), Some(1));
+    }
+
+    #[test]
+    fn secret_round_trips_through_base32() {
+        let secret = TotpSecret::generate();
+        let parsed = TotpSecret::parse(&secret.to_base32()).unwrap();
+        assert_eq!(parsed.0, secret.0);
+        assert_eq!(secret.to_base32().len(), 32);
+        assert!(TotpSecret::parse("not base32!").is_err());
+    }
+
+    #[test]
+    fn otpauth_uri_has_the_secret_and_issuer() {
+        let secret = rfc_secret();
+        assert_eq!(
+            secret.otpauth_uri("zero2prod", "ursula le guin"),
+            "otpauth://totp/zero2prod:ursula%20le%20guin\
+             ?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=zero2prod\
+             &algorithm=SHA1&digits=6&period=30"
+        );
+    }
+}
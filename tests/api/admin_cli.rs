@@ -0,0 +1,270 @@
+//! tests/api/admin_cli.rs
+use secrecy::Secret;
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+use zero2prod_axum::audit::Actor;
+use zero2prod_axum::configuration::Settings;
+use zero2prod_axum::domain::UserRole;
+use zero2prod_axum::management::{self, ChangeUserError, ImportReport};
+use zero2prod_axum::pending_subscribers_cleanup::delete_expired_pending_subscribers;
+use zero2prod_axum::startup::db_migration;
+
+use crate::helpers::{
+    assert_is_redirect_to, newsletter_request_body, TestApp,
+};
+
+#[tokio::test]
+async fn created_user_can_log_in() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let username = Uuid::new_v4().to_string();
+    let password = Uuid::new_v4().to_string();
+
+    // Act
+    management::create_user(
+        &app.pool,
+        &app.password_hashing,
+        &username,
+        Secret::new(password.clone()),
+        Some("ursula@example.com"),
+        UserRole::Owner,
+        Actor::CommandLine,
+    )
+    .await
+    .unwrap();
+
+    // Assert
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &username,
+            "password": &password,
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/dashboard");
+}
+
+#[tokio::test]
+async fn create_user_rejects_a_weak_password() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let result = management::create_user(
+        &app.pool,
+        &app.password_hashing,
+        "ursula",
+        Secret::new("short".to_string()),
+        None,
+        UserRole::Viewer,
+        Actor::CommandLine,
+    )
+    .await;
+
+    // Assert
+    assert!(result.is_err());
+    let users = management::list_users(&app.pool).await.unwrap();
+    assert!(users.iter().all(|u| u.username != "ursula"));
+}
+
+#[tokio::test]
+async fn deleted_user_is_not_listed() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_other_owner(&app).await;
+    let username = &app.test_user.username;
+    let users = management::list_users(&app.pool).await.unwrap();
+    assert!(users.iter().any(|u| &u.username == username));
+
+    // Act
+    let deleted = management::delete_user(&app.pool, username).await.unwrap();
+
+    // Assert
+    assert!(deleted);
+    let users = management::list_users(&app.pool).await.unwrap();
+    assert!(users.iter().all(|u| &u.username != username));
+    assert!(!management::delete_user(&app.pool, username).await.unwrap());
+}
+
+#[tokio::test]
+async fn last_owner_cannot_be_deleted() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let result =
+        management::delete_user(&app.pool, &app.test_user.username).await;
+
+    // Assert
+    assert!(matches!(result, Err(ChangeUserError::LastOwner)));
+    let users = management::list_users(&app.pool).await.unwrap();
+    assert!(users.iter().any(|u| u.username == app.test_user.username));
+}
+
+#[tokio::test]
+async fn user_with_saved_responses_can_be_deleted() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let response = app
+        .post_newsletters_with_idempotency_key(
+            &newsletter_request_body(),
+            &Uuid::new_v4().to_string(),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 202);
+    create_other_owner(&app).await;
+
+    // Act
+    let deleted = management::delete_user(&app.pool, &app.test_user.username)
+        .await
+        .unwrap();
+
+    // Assert
+    assert!(deleted);
+}
+
+#[tokio::test]
+async fn exported_subscribers_can_be_imported_back() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let csv = "email,name,status,subscribed_at\n\
+        ursula@example.com,Ursula,confirmed,2023-12-01T10:00:00Z\n\
+        le_guin@example.com,Le Guin,,\n";
+
+    // Act
+    let report = management::import_subscribers(&app.pool, csv.as_bytes())
+        .await
+        .unwrap();
+    let mut exported = Vec::new();
+    let count = management::export_subscribers(&app.pool, &mut exported)
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(
+        report,
+        ImportReport {
+            imported: 2,
+            skipped: 0
+        }
+    );
+    assert_eq!(count, 2);
+    let exported = String::from_utf8(exported).unwrap();
+    assert!(exported.starts_with("email,name,status,subscribed_at\n"));
+    assert!(exported
+        .contains("ursula@example.com,Ursula,confirmed,2023-12-01T10:00:00Z"));
+
+    // Importing the export again skips every known address
+    let report = management::import_subscribers(&app.pool, exported.as_bytes())
+        .await
+        .unwrap();
+    assert_eq!(
+        report,
+        ImportReport {
+            imported: 0,
+            skipped: 2
+        }
+    );
+}
+
+#[tokio::test]
+async fn import_with_an_invalid_row_writes_nothing() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let csv = "email,name\n\
+        ursula@example.com,Ursula\n\
+        not-an-email,Le Guin\n";
+
+    // Act
+    let result =
+        management::import_subscribers(&app.pool, csv.as_bytes()).await;
+
+    // Assert
+    let error = format!("{:?}", result.unwrap_err());
+    assert!(error.contains("line 3"), "{}", error);
+    let mut exported = Vec::new();
+    let count = management::export_subscribers(&app.pool, &mut exported)
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+}
+
+#[tokio::test]
+async fn imported_pending_subscribers_are_not_cleaned_up() {
+    // Arrange
+    let config = Settings::load_configuration().unwrap();
+    let settings = config.subscriptions.clone();
+    let app = TestApp::spawn_app(config).await;
+    let csv = "email,name,status\n\
+        ursula_le_guin@gmail.com,Ursula,pending_confirmation\n";
+
+    // Act
+    management::import_subscribers(&app.pool, csv.as_bytes())
+        .await
+        .unwrap();
+    let deleted = delete_expired_pending_subscribers(&app.pool, &settings)
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(deleted, 0);
+}
+
+#[tokio::test]
+async fn imported_subscribers_can_subscribe_again() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let csv = "email,name,status\n\
+        ursula_le_guin@gmail.com,Ursula,unsubscribed\n";
+    management::import_subscribers(&app.pool, csv.as_bytes())
+        .await
+        .unwrap();
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com")
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let confirmation_link = app.get_confirmation_link(email_request);
+    let response = reqwest::get(confirmation_link.0).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn no_migrations_are_pending_after_startup() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let pending = db_migration::pending_migrations(&app.pool).await.unwrap();
+    let applied = db_migration::applied_migrations(&app.pool).await.unwrap();
+
+    // Assert
+    assert!(pending.is_empty());
+    assert!(!applied.is_empty());
+}
+
+/// The test user is the only owner, it can't be deleted without another.
+async fn create_other_owner(app: &TestApp) {
+    management::create_user(
+        &app.pool,
+        &app.password_hashing,
+        &Uuid::new_v4().to_string(),
+        Secret::new(Uuid::new_v4().to_string()),
+        None,
+        UserRole::Owner,
+        Actor::CommandLine,
+    )
+    .await
+    .unwrap();
+}
@@ -0,0 +1,44 @@
+//! tests/api/admin_dashboard.rs
+use zero2prod_axum::configuration::Settings;
+
+use crate::helpers::{assert_is_redirect_to, TestApp};
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_access_the_admin_dashboard() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let response = app.get_admin_dashboard().await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn admin_dashboard_greets_the_logged_in_user() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+
+    // Act
+    let html_page = app.get_admin_dashboard_html().await;
+
+    // Assert
+    assert!(html_page.contains(&format!("Welcome {}", app.test_user.username)));
+}
+
+#[tokio::test]
+async fn logout_clears_the_session() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    assert_is_redirect_to(&app.login().await, "/admin/dashboard");
+
+    // Act
+    let response = app.post_logout().await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    let response = app.get_admin_dashboard().await;
+    assert_is_redirect_to(&response, "/login");
+}
@@ -0,0 +1,148 @@
+//! tests/api/admin_newsletters.rs
+use wiremock::matchers::{any, method, path};
+use wiremock::{Mock, ResponseTemplate};
+use zero2prod_axum::configuration::Settings;
+
+use crate::helpers::{
+    assert_is_redirect_to, create_confirmed_subscriber, newsletter_form,
+    TestApp,
+};
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_see_the_newsletter_form() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let response = app.get_publish_newsletter().await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_publish_a_newsletter() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_publish_newsletter(&newsletter_form()).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    app.dispatch_all_pending_emails().await;
+}
+
+#[tokio::test]
+async fn newsletter_form_publishes_an_issue_to_confirmed_subscribers() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+    app.login().await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act - Part 1 - Submit the form
+    let response = app.post_publish_newsletter(&newsletter_form()).await;
+    assert_is_redirect_to(&response, "/admin/newsletters");
+
+    // Act - Part 2 - Follow the redirect
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("The newsletter issue has been accepted"));
+
+    // Act - Part 3 - The flash message is shown only once
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(!html_page.contains("The newsletter issue has been accepted"));
+
+    app.dispatch_all_pending_emails().await;
+    // Mock verifies on Drop that we have sent the newsletter email
+}
+
+#[tokio::test]
+async fn double_submitted_form_publishes_the_issue_once() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+    app.login().await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let form = newsletter_form();
+    let response = app.post_publish_newsletter(&form).await;
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    let response = app.post_publish_newsletter(&form).await;
+    assert_is_redirect_to(&response, "/admin/newsletters");
+
+    // Assert
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("The newsletter issue has been accepted"));
+    app.dispatch_all_pending_emails().await;
+}
+
+#[tokio::test]
+async fn invalid_form_shows_an_error_and_publishes_nothing() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+    app.login().await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let mut form = newsletter_form();
+    form["title"] = "  ".into();
+    let response = app.post_publish_newsletter(&form).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("The title must not be empty."));
+    app.dispatch_all_pending_emails().await;
+}
+
+#[tokio::test]
+async fn preview_shows_both_bodies_without_publishing() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+    app.login().await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_preview_newsletter(&newsletter_form()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let html_page = response.text().await.unwrap();
+    assert!(html_page.contains("Newsletter body as plain text"));
+    // The HTML body is escaped into the `srcdoc` of a sandboxed iframe.
+    assert!(html_page.contains("&lt;p&gt;Newsletter body as HTML&lt;/p&gt;"));
+    assert!(!html_page.contains("<p>Newsletter body as HTML</p>"));
+    app.dispatch_all_pending_emails().await;
+}
@@ -0,0 +1,167 @@
+//! tests/api/admin_roles.rs
+use uuid::Uuid;
+use wiremock::matchers::{any, method, path};
+use wiremock::{Mock, ResponseTemplate};
+use zero2prod_axum::configuration::Settings;
+use zero2prod_axum::domain::UserRole;
+
+use crate::helpers::{
+    assert_is_redirect_to, create_confirmed_subscriber, newsletter_form,
+    newsletter_request_body, TestApp,
+};
+
+#[tokio::test]
+async fn viewers_can_not_publish_from_the_admin_form() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+    let viewer = app.add_user(UserRole::Viewer).await;
+    app.login_as(&viewer).await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_publish_newsletter(&newsletter_form()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+    app.dispatch_all_pending_emails().await;
+    assert_eq!(
+        app.audit_actions(viewer.user_id).await,
+        vec![(
+            "forbidden".to_string(),
+            "publish_newsletters /admin/newsletters".to_string()
+        )]
+    );
+}
+
+#[tokio::test]
+async fn viewers_can_not_publish_with_basic_auth() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+    let viewer = app.add_user(UserRole::Viewer).await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_newsletters_as(&viewer, newsletter_request_body())
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+    app.dispatch_all_pending_emails().await;
+    assert_eq!(app.audit_actions(viewer.user_id).await.len(), 1);
+}
+
+#[tokio::test]
+async fn editors_can_publish_newsletters() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+    let editor = app.add_user(UserRole::Editor).await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&app.email_server)
+        .await;
+
+    // Act - Part 1 - JSON API
+    let response = app
+        .post_newsletters_as(&editor, newsletter_request_body())
+        .await;
+    assert_eq!(response.status().as_u16(), 202);
+
+    // Act - Part 2 - Admin form
+    app.login_as(&editor).await;
+    let response = app.post_publish_newsletter(&newsletter_form()).await;
+    assert_is_redirect_to(&response, "/admin/newsletters");
+
+    // Assert
+    app.dispatch_all_pending_emails().await;
+    let actions = app.audit_actions(editor.user_id).await;
+    assert_eq!(actions.len(), 2);
+    assert!(actions.iter().all(|(a, _)| a == "newsletter_published"));
+}
+
+#[tokio::test]
+async fn viewers_get_a_read_only_dashboard() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+    let viewer = app.add_user(UserRole::Viewer).await;
+    app.login_as(&viewer).await;
+
+    // Act
+    let dashboard = app.get_admin_dashboard_html().await;
+    let subscribers = app.get_admin_subscribers_html("").await;
+
+    // Assert
+    assert!(dashboard.contains("You are signed in as viewer"));
+    assert!(dashboard.contains(r#"href="/admin/subscribers""#));
+    assert!(!dashboard.contains(r#"href="/admin/newsletters""#));
+    assert!(!dashboard.contains(r#"href="/admin/users""#));
+    assert!(dashboard.contains(r#"href="/admin/tokens""#));
+    assert!(subscribers.contains("ursula_le_guin@gmail.com"));
+    assert!(!subscribers.contains("/delete"));
+}
+
+#[tokio::test]
+async fn viewers_can_not_delete_subscribers() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+    let subscriber_id: Uuid = app
+        .pool
+        .get()
+        .await
+        .unwrap()
+        .query_one("SELECT id FROM subscriptions", &[])
+        .await
+        .unwrap()
+        .get(0);
+    let viewer = app.add_user(UserRole::Viewer).await;
+    app.login_as(&viewer).await;
+
+    // Act
+    let response = app
+        .post_admin_subscriber_action(subscriber_id, "delete")
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+    let subscribers = app.get_admin_subscribers_html("").await;
+    assert!(subscribers.contains("ursula_le_guin@gmail.com"));
+    let actions = app.audit_actions(viewer.user_id).await;
+    assert_eq!(actions.len(), 1);
+    assert_eq!(
+        actions[0].1,
+        format!(
+            "manage_subscribers /admin/subscribers/{}/delete",
+            subscriber_id
+        )
+    );
+}
+
+#[tokio::test]
+async fn owners_see_every_action_on_the_dashboard() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+
+    // Act
+    let dashboard = app.get_admin_dashboard_html().await;
+
+    // Assert
+    assert!(dashboard.contains("You are signed in as owner"));
+    assert!(dashboard.contains(r#"href="/admin/newsletters""#));
+    assert!(dashboard.contains(r#"href="/admin/users""#));
+}
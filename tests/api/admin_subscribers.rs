@@ -0,0 +1,235 @@
+//! tests/api/admin_subscribers.rs
+use time::OffsetDateTime;
+use uuid::Uuid;
+use zero2prod_axum::configuration::Settings;
+
+use crate::helpers::{assert_is_redirect_to, TestApp};
+
+/// Insert a subscriber directly, `age` seconds ago.
+async fn insert_subscriber(
+    app: &TestApp,
+    email: &str,
+    name: &str,
+    status: &str,
+    age: i64,
+) -> Uuid {
+    let id = Uuid::new_v4();
+    let subscribed_at =
+        OffsetDateTime::now_utc() - time::Duration::seconds(age);
+    app.pool
+        .get()
+        .await
+        .unwrap()
+        .execute(
+            "INSERT INTO subscriptions (id, email, name, subscribed_at, status)
+            VALUES ($1, $2, $3, $4, $5)",
+            &[&id, &email, &name, &subscribed_at, &status],
+        )
+        .await
+        .unwrap();
+    id
+}
+
+async fn get_status(app: &TestApp, id: Uuid) -> Option<String> {
+    app.pool
+        .get()
+        .await
+        .unwrap()
+        .query_opt("SELECT status FROM subscriptions WHERE id = $1", &[&id])
+        .await
+        .unwrap()
+        .map(|row| row.get("status"))
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_see_subscribers() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let response = app.get_admin_subscribers("").await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn subscribers_can_be_filtered_by_status_and_searched_by_prefix() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+    insert_subscriber(&app, "alice@example.com", "Alice", "confirmed", 3).await;
+    insert_subscriber(
+        &app,
+        "bob@example.com",
+        "Bob",
+        "pending_confirmation",
+        2,
+    )
+    .await;
+    insert_subscriber(&app, "carol@example.com", "Alina", "unsubscribed", 1)
+        .await;
+
+    // Act
+    let all = app.get_admin_subscribers_html("").await;
+    let confirmed = app.get_admin_subscribers_html("?status=confirmed").await;
+    let by_prefix = app.get_admin_subscribers_html("?search=AL").await;
+    let by_both = app
+        .get_admin_subscribers_html("?status=unsubscribed&search=al")
+        .await;
+
+    // Assert
+    for email in ["alice@", "bob@", "carol@"] {
+        assert!(all.contains(email));
+    }
+    assert!(confirmed.contains("alice@"));
+    assert!(!confirmed.contains("bob@"));
+    assert!(!confirmed.contains("carol@"));
+    // Prefix of the email for Alice, of the name for Carol.
+    assert!(by_prefix.contains("alice@"));
+    assert!(by_prefix.contains("carol@"));
+    assert!(!by_prefix.contains("bob@"));
+    assert!(!by_both.contains("alice@"));
+    assert!(by_both.contains("carol@"));
+}
+
+#[tokio::test]
+async fn unknown_status_filter_is_rejected_with_a_400() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+
+    // Act
+    let response = app.get_admin_subscribers("?status=bogus").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn subscribers_are_paginated_newest_first() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+    for i in 0..25 {
+        let email = format!("user{:02}@example.com", i);
+        insert_subscriber(&app, &email, "User", "confirmed", i).await;
+    }
+
+    // Act - Part 1 - First page
+    let first_page = app.get_admin_subscribers_html("").await;
+
+    // Assert
+    assert!(first_page.contains("user00@"));
+    assert!(first_page.contains("user19@"));
+    assert!(!first_page.contains("user20@"));
+    let next_page = first_page
+        .split("href=\"")
+        .filter_map(|s| s.split('"').next())
+        .find(|link| link.starts_with("/admin/subscribers?after="))
+        .expect("No link to the next page");
+
+    // Act - Part 2 - Second page
+    let second_page = app
+        .get_admin_subscribers_html(
+            next_page.trim_start_matches("/admin/subscribers"),
+        )
+        .await;
+
+    // Assert
+    assert!(!second_page.contains("user19@"));
+    assert!(second_page.contains("user20@"));
+    assert!(second_page.contains("user24@"));
+    assert!(!second_page.contains("/admin/subscribers?after="));
+}
+
+#[tokio::test]
+async fn next_page_survives_deletion_of_the_last_shown_subscriber() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+    let mut ids = Vec::new();
+    for i in 0..25 {
+        let email = format!("user{:02}@example.com", i);
+        ids.push(insert_subscriber(&app, &email, "User", "confirmed", i).await);
+    }
+    let first_page = app.get_admin_subscribers_html("").await;
+    let next_page = first_page
+        .split("href=\"")
+        .filter_map(|s| s.split('"').next())
+        .find(|link| link.starts_with("/admin/subscribers?after="))
+        .expect("No link to the next page");
+
+    // Act
+    app.pool
+        .get()
+        .await
+        .unwrap()
+        .execute("DELETE FROM subscriptions WHERE id = $1", &[&ids[19]])
+        .await
+        .unwrap();
+    let second_page = app
+        .get_admin_subscribers_html(
+            next_page.trim_start_matches("/admin/subscribers"),
+        )
+        .await;
+
+    // Assert
+    assert!(second_page.contains("user20@"));
+    assert!(second_page.contains("user24@"));
+}
+
+#[tokio::test]
+async fn admin_can_confirm_a_pending_subscriber() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+    let id = insert_subscriber(
+        &app,
+        "bob@example.com",
+        "Bob",
+        "pending_confirmation",
+        0,
+    )
+    .await;
+
+    // Act
+    let response = app.post_admin_subscriber_action(id, "confirm").await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/subscribers");
+    assert_eq!(get_status(&app, id).await.as_deref(), Some("confirmed"));
+}
+
+#[tokio::test]
+async fn admin_can_delete_a_subscriber() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+    let id =
+        insert_subscriber(&app, "bob@example.com", "Bob", "confirmed", 0).await;
+
+    // Act
+    let response = app.post_admin_subscriber_action(id, "delete").await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/subscribers");
+    assert_eq!(get_status(&app, id).await, None);
+    let response = app.post_admin_subscriber_action(id, "delete").await;
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_delete_a_subscriber() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let id =
+        insert_subscriber(&app, "bob@example.com", "Bob", "confirmed", 0).await;
+
+    // Act
+    let response = app.post_admin_subscriber_action(id, "delete").await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    assert_eq!(get_status(&app, id).await.as_deref(), Some("confirmed"));
+}
@@ -0,0 +1,319 @@
+//! tests/api/admin_users.rs
+use uuid::Uuid;
+use zero2prod_axum::configuration::Settings;
+use zero2prod_axum::domain::UserRole;
+
+use crate::helpers::{
+    assert_is_redirect_to, extract_csrf_token, newsletter_request_body,
+    TestApp, TestUser,
+};
+
+/// Client with its own cookie jar, logged in as `user`, and the CSRF token
+/// of its session.
+async fn logged_in_client(
+    app: &TestApp,
+    user: &TestUser,
+) -> (reqwest::Client, String) {
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .cookie_store(true)
+        .build()
+        .unwrap();
+    let get_html = |path: &'static str| {
+        let request = client.get(format!("{}{}", app.address, path));
+        async move { request.send().await.unwrap().text().await.unwrap() }
+    };
+    let csrf_token = extract_csrf_token(&get_html("/login").await);
+    let response = client
+        .post(format!("{}/login", app.address))
+        .form(&[
+            ("username", user.username.as_str()),
+            ("password", user.password.as_str()),
+            ("csrf_token", &csrf_token),
+        ])
+        .send()
+        .await
+        .unwrap();
+    assert_is_redirect_to(&response, "/admin/dashboard");
+    let csrf_token = extract_csrf_token(&get_html("/admin/dashboard").await);
+    (client, csrf_token)
+}
+
+async fn count_owners(app: &TestApp) -> i64 {
+    app.pool
+        .get()
+        .await
+        .unwrap()
+        .query_one("SELECT count(*) FROM users WHERE role = 'owner'", &[])
+        .await
+        .unwrap()
+        .get(0)
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_see_the_users_page() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let response = app.get_admin_users().await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn only_owners_can_see_the_users_page() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let editor = app.add_user(UserRole::Editor).await;
+    app.login_as(&editor).await;
+
+    // Act
+    let response = app.get_admin_users().await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+    assert_eq!(
+        app.audit_actions(editor.user_id).await,
+        vec![(
+            "forbidden".to_string(),
+            "manage_users /admin/users".to_string()
+        )]
+    );
+}
+
+#[tokio::test]
+async fn editors_can_not_create_users() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let editor = app.add_user(UserRole::Editor).await;
+    app.login_as(&editor).await;
+    let username = Uuid::new_v4().to_string();
+
+    // Act
+    let response = app
+        .post_admin_users(&serde_json::json!({
+            "username": &username,
+            "email": "",
+            "password": Uuid::new_v4().to_string(),
+            "role": "owner",
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+    app.login().await;
+    assert!(!app.get_admin_users_html().await.contains(&username));
+}
+
+#[tokio::test]
+async fn owner_can_create_a_user_with_a_role() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+    let username = Uuid::new_v4().to_string();
+    let password = Uuid::new_v4().to_string();
+
+    // Act - Part 1 - Submit the form
+    let response = app
+        .post_admin_users(&serde_json::json!({
+            "username": &username,
+            "email": "editor@example.com",
+            "password": &password,
+            "role": "editor",
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/users");
+
+    // Act - Part 2 - Follow the redirect
+    let html_page = app.get_admin_users_html().await;
+    assert!(html_page.contains(&format!("User {} has been created.", username)));
+    assert!(html_page.contains("editor@example.com"));
+
+    // Act - Part 3 - The new user has the role
+    app.post_logout().await;
+    app.post_login(&serde_json::json!({
+        "username": &username,
+        "password": &password,
+    }))
+    .await;
+    let dashboard = app.get_admin_dashboard_html().await;
+    assert!(dashboard.contains("You are signed in as editor"));
+}
+
+#[tokio::test]
+async fn new_user_with_a_weak_password_is_rejected() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+
+    // Act
+    let response = app
+        .post_admin_users(&serde_json::json!({
+            "username": "ursula",
+            "email": "",
+            "password": "short",
+            "role": "viewer",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/users");
+    let html_page = app.get_admin_users_html().await;
+    assert!(html_page.contains("at least 12 characters"));
+    assert!(!html_page.contains("<td>ursula</td>"));
+}
+
+#[tokio::test]
+async fn owner_can_change_the_role_of_another_user() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let editor = app.add_user(UserRole::Editor).await;
+    app.login().await;
+
+    // Act
+    let response = app.post_admin_user_role(editor.user_id, "viewer").await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/users");
+    assert!(app
+        .get_admin_users_html()
+        .await
+        .contains("The role has been changed."));
+    // The demoted editor can't publish anymore
+    let response = app
+        .post_newsletters_as(
+            &editor,
+            newsletter_request_body(),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn owner_can_not_change_their_own_role() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+
+    // Act
+    let response = app
+        .post_admin_user_role(app.test_user.user_id, "viewer")
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/users");
+    assert!(app
+        .get_admin_users_html()
+        .await
+        .contains("You can&#x27;t change your own role."));
+}
+
+#[tokio::test]
+async fn unknown_role_is_rejected() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let viewer = app.add_user(UserRole::Viewer).await;
+    app.login().await;
+
+    // Act
+    let response = app.post_admin_user_role(viewer.user_id, "admin").await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/users");
+    assert!(app
+        .get_admin_users_html()
+        .await
+        .contains("admin is not a valid user role."));
+}
+
+#[tokio::test]
+async fn deleted_user_can_not_log_in() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let viewer = app.add_user(UserRole::Viewer).await;
+    app.login().await;
+
+    // Act
+    let response = app.post_admin_user_delete(viewer.user_id).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/users");
+    let html_page = app.get_admin_users_html().await;
+    assert!(html_page.contains("The user has been deleted."));
+    assert!(!html_page.contains(&viewer.username));
+    app.post_logout().await;
+    app.login_as(&viewer).await;
+    let response = app.get_admin_dashboard().await;
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn owner_can_not_delete_themselves() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+
+    // Act
+    let response = app.post_admin_user_delete(app.test_user.user_id).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/users");
+    assert!(app
+        .get_admin_users_html()
+        .await
+        .contains("You can&#x27;t delete yourself."));
+}
+
+#[tokio::test]
+async fn owners_demoting_each_other_leave_one_owner() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let other_owner = app.add_user(UserRole::Owner).await;
+    let (first, first_csrf) = logged_in_client(&app, &app.test_user).await;
+    let (second, second_csrf) = logged_in_client(&app, &other_owner).await;
+    let demote = |client: &reqwest::Client, csrf_token: &str, user_id| {
+        client
+            .post(format!("{}/admin/users/{}/role", app.address, user_id))
+            .form(&[("role", "editor"), ("csrf_token", csrf_token)])
+            .send()
+    };
+
+    // Act
+    let (first_response, second_response) = tokio::join!(
+        demote(&first, &first_csrf, other_owner.user_id),
+        demote(&second, &second_csrf, app.test_user.user_id),
+    );
+
+    // Assert - Whichever change runs second is refused
+    first_response.unwrap();
+    second_response.unwrap();
+    assert_eq!(count_owners(&app).await, 1);
+}
+
+#[tokio::test]
+async fn owners_deleting_each_other_leave_one_owner() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let other_owner = app.add_user(UserRole::Owner).await;
+    let (first, first_csrf) = logged_in_client(&app, &app.test_user).await;
+    let (second, second_csrf) = logged_in_client(&app, &other_owner).await;
+    let delete = |client: &reqwest::Client, csrf_token: &str, user_id| {
+        client
+            .post(format!("{}/admin/users/{}/delete", app.address, user_id))
+            .form(&[("csrf_token", csrf_token)])
+            .send()
+    };
+
+    // Act
+    let (first_response, second_response) = tokio::join!(
+        delete(&first, &first_csrf, other_owner.user_id),
+        delete(&second, &second_csrf, app.test_user.user_id),
+    );
+
+    // Assert
+    first_response.unwrap();
+    second_response.unwrap();
+    assert_eq!(count_owners(&app).await, 1);
+}
@@ -0,0 +1,357 @@
+//! tests/api/api_tokens.rs
+use uuid::Uuid;
+use wiremock::matchers::{any, method, path};
+use wiremock::{Mock, ResponseTemplate};
+use zero2prod_axum::configuration::Settings;
+use zero2prod_axum::domain::{ApiScope, UserRole};
+use zero2prod_axum::management;
+
+use crate::helpers::{
+    assert_is_redirect_to, create_confirmed_subscriber,
+    newsletter_request_body, TestApp,
+};
+
+/// Returns the id and the token itself.
+async fn create_token(
+    app: &TestApp,
+    username: &str,
+    scopes: &[ApiScope],
+) -> (Uuid, String) {
+    let (token_id, token) =
+        management::create_api_token(&app.pool, username, "ci", scopes, None)
+            .await
+            .unwrap();
+    (token_id, token.as_ref().to_string())
+}
+
+#[tokio::test]
+async fn token_with_the_publish_scope_can_publish() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+    let (_, token) = create_token(
+        &app,
+        &app.test_user.username,
+        &[ApiScope::NewslettersPublish],
+    )
+    .await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_newsletters_with_token(&token, newsletter_request_body())
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 202);
+    app.dispatch_all_pending_emails().await;
+}
+
+#[tokio::test]
+async fn token_without_the_publish_scope_is_forbidden() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+    let (_, token) = create_token(
+        &app,
+        &app.test_user.username,
+        &[ApiScope::SubscribersRead],
+    )
+    .await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_newsletters_with_token(&token, newsletter_request_body())
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+    app.dispatch_all_pending_emails().await;
+    assert_eq!(
+        app.audit_actions(app.test_user.user_id).await,
+        vec![(
+            "forbidden".to_string(),
+            "newsletters:publish /newsletters".to_string()
+        )]
+    );
+}
+
+#[tokio::test]
+async fn tokens_of_a_demoted_user_can_not_publish() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let editor = app.add_user(UserRole::Editor).await;
+    let (_, token) =
+        create_token(&app, &editor.username, &[ApiScope::NewslettersPublish])
+            .await;
+    app.login().await;
+    app.post_admin_user_role(editor.user_id, "viewer").await;
+
+    // Act
+    let response = app
+        .post_newsletters_with_token(&token, newsletter_request_body())
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn viewers_can_not_get_a_publish_token() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let viewer = app.add_user(UserRole::Viewer).await;
+
+    // Act
+    let result = management::create_api_token(
+        &app.pool,
+        &viewer.username,
+        "ci",
+        &[ApiScope::NewslettersPublish],
+        None,
+    )
+    .await;
+
+    // Assert
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn revoked_and_expired_tokens_are_rejected() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let username = &app.test_user.username;
+    let scopes = [ApiScope::NewslettersPublish];
+    let (revoked_id, revoked) = create_token(&app, username, &scopes).await;
+    let (expired_id, expired) = create_token(&app, username, &scopes).await;
+    assert!(management::revoke_api_token(&app.pool, revoked_id)
+        .await
+        .unwrap());
+    app.pool
+        .get()
+        .await
+        .unwrap()
+        .execute(
+            "UPDATE api_tokens SET expires_at = now() - interval '1 day' \
+             WHERE id = $1",
+            &[&expired_id],
+        )
+        .await
+        .unwrap();
+    let unknown = format!("z2p_abcdefgh_{}", "a".repeat(32));
+
+    for token in [revoked, expired, unknown, "z2p_nonsense".to_string()] {
+        // Act
+        let response = app
+            .post_newsletters_with_token(&token, newsletter_request_body())
+            .await;
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 401, "token: {}", token);
+        assert_eq!(
+            r#"Bearer realm="publish""#,
+            response.headers()["WWW-Authenticate"]
+        );
+    }
+}
+
+#[tokio::test]
+async fn basic_auth_is_rejected_when_disabled() {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.authentication.basic_auth_enabled = false;
+    let app = TestApp::spawn_app(config).await;
+
+    // Act
+    let response = app
+        .post_newsletters_as(&app.test_user, newsletter_request_body())
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(
+        r#"Bearer realm="publish""#,
+        response.headers()["WWW-Authenticate"]
+    );
+}
+
+#[tokio::test]
+async fn tokens_still_work_when_basic_auth_is_disabled() {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.authentication.basic_auth_enabled = false;
+    let app = TestApp::spawn_app(config).await;
+    let (_, token) = create_token(
+        &app,
+        &app.test_user.username,
+        &[ApiScope::NewslettersPublish],
+    )
+    .await;
+
+    // Act
+    let response = app
+        .post_newsletters_with_token(&token, newsletter_request_body())
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 202);
+}
+
+#[tokio::test]
+async fn subscribers_can_be_read_with_the_read_scope() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+    let viewer = app.add_user(UserRole::Viewer).await;
+    let (_, token) =
+        create_token(&app, &viewer.username, &[ApiScope::SubscribersRead])
+            .await;
+
+    // Act
+    let response = app.get_subscribers_with_token(&token, "limit=10").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let page: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(page["subscribers"][0]["email"], "ursula_le_guin@gmail.com");
+    assert_eq!(page["subscribers"][0]["status"], "confirmed");
+    assert!(page["next_after"].is_null());
+}
+
+#[tokio::test]
+async fn reading_subscribers_requires_the_read_scope() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let (_, token) = create_token(
+        &app,
+        &app.test_user.username,
+        &[ApiScope::NewslettersPublish],
+    )
+    .await;
+
+    // Act
+    let forbidden = app.get_subscribers_with_token(&token, "").await;
+    let anonymous = reqwest::Client::new()
+        .get(format!("{}/subscribers", app.address))
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(forbidden.status().as_u16(), 403);
+    assert_eq!(anonymous.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn token_created_in_the_admin_ui_is_shown_once_and_works() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+
+    // Act - Part 1 - Create the token
+    let response = app
+        .post_admin_tokens(&[
+            ("name", "deploy script"),
+            ("scope", "newsletters:publish"),
+            ("scope", "subscribers:read"),
+            ("expires_in_days", "30"),
+        ])
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let html_page = response.text().await.unwrap();
+    let token = html_page
+        .split(r#"<code id="new-token">"#)
+        .nth(1)
+        .and_then(|rest| rest.split("</code>").next())
+        .unwrap()
+        .to_string();
+
+    // Act - Part 2 - The token is listed, but not shown anymore
+    let html_page = app.get_admin_tokens_html().await;
+    assert!(html_page.contains("deploy script"));
+    assert!(html_page.contains("newsletters:publish subscribers:read"));
+    assert!(!html_page.contains(&token));
+
+    // Act - Part 3 - The token works
+    let response = app.get_subscribers_with_token(&token, "").await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn viewers_only_get_read_scopes_in_the_admin_ui() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let viewer = app.add_user(UserRole::Viewer).await;
+    app.login_as(&viewer).await;
+
+    // Act - Part 1 - The form doesn't offer the scope
+    let html_page = app.get_admin_tokens_html().await;
+    assert!(html_page.contains(r#"value="subscribers:read""#));
+    assert!(!html_page.contains(r#"value="newsletters:publish""#));
+
+    // Act - Part 2 - Nor accepts it
+    let response = app
+        .post_admin_tokens(&[("name", "ci"), ("scope", "newsletters:publish")])
+        .await;
+    assert_is_redirect_to(&response, "/admin/tokens");
+    let html_page = app.get_admin_tokens_html().await;
+    assert!(html_page.contains("The viewer role can&#x27;t have the"));
+    assert!(!html_page.contains("<td>ci</td>"));
+}
+
+#[tokio::test]
+async fn revoked_token_from_the_admin_ui_stops_working() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let (token_id, token) = create_token(
+        &app,
+        &app.test_user.username,
+        &[ApiScope::SubscribersRead],
+    )
+    .await;
+    app.login().await;
+
+    // Act
+    let response = app.post_admin_token_revoke(token_id).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/tokens");
+    let html_page = app.get_admin_tokens_html().await;
+    assert!(html_page.contains("The token has been revoked."));
+    assert!(html_page.contains("<td>revoked</td>"));
+    let response = app.get_subscribers_with_token(&token, "").await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn users_can_not_revoke_tokens_of_others() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let editor = app.add_user(UserRole::Editor).await;
+    let (token_id, token) =
+        create_token(&app, &editor.username, &[ApiScope::SubscribersRead])
+            .await;
+    app.login().await;
+
+    // Act
+    let response = app.post_admin_token_revoke(token_id).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/tokens");
+    assert!(app
+        .get_admin_tokens_html()
+        .await
+        .contains("Token not found."));
+    let response = app.get_subscribers_with_token(&token, "").await;
+    assert_eq!(response.status().as_u16(), 200);
+}
@@ -0,0 +1,444 @@
+//! tests/api/audit_log.rs
+use secrecy::Secret;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use zero2prod_axum::audit::Actor;
+use zero2prod_axum::configuration::Settings;
+use zero2prod_axum::domain::{ApiScope, UserRole};
+use zero2prod_axum::management;
+
+use crate::helpers::{
+    assert_is_redirect_to, create_unconfirmed_subscriber, TestApp,
+    TEST_USER_AGENT,
+};
+
+/// Insert `count` events directly, one second apart, the newest with
+/// target `event-0`.
+async fn insert_events(app: &TestApp, count: i64) {
+    let client = app.pool.get().await.unwrap();
+    for i in 0..count {
+        let created_at = OffsetDateTime::now_utc() - time::Duration::seconds(i);
+        client
+            .execute(
+                "INSERT INTO audit_events (id, action, target, created_at)
+                VALUES ($1, 'forbidden', $2, $3)",
+                &[&Uuid::new_v4(), &format!("event-{i}"), &created_at],
+            )
+            .await
+            .unwrap();
+    }
+}
+
+async fn subscriber_id(app: &TestApp) -> Uuid {
+    app.pool
+        .get()
+        .await
+        .unwrap()
+        .query_one("SELECT id FROM subscriptions", &[])
+        .await
+        .unwrap()
+        .get(0)
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_see_the_audit_log() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let response = app.get_admin_audit("").await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn only_owners_can_see_the_audit_log() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let editor = app.add_user(UserRole::Editor).await;
+    app.login_as(&editor).await;
+
+    // Act
+    let response = app.get_admin_audit("").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+    assert!(!app
+        .get_admin_dashboard_html()
+        .await
+        .contains("/admin/audit"));
+}
+
+#[tokio::test]
+async fn logins_are_logged_with_client_ip_and_user_agent() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.post_login(&serde_json::json!({
+        "username": &app.test_user.username,
+        "password": "wrong-password",
+    }))
+    .await;
+
+    // Act
+    app.login().await;
+
+    // Assert
+    let rows = app
+        .pool
+        .get()
+        .await
+        .unwrap()
+        .query(
+            "SELECT action, user_id, target, client_ip, user_agent
+                FROM audit_events ORDER BY created_at",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].get::<_, &str>("action"), "login_failed");
+    assert_eq!(rows[0].get::<_, Option<Uuid>>("user_id"), None);
+    assert_eq!(
+        rows[0].get::<_, Option<&str>>("target"),
+        Some(app.test_user.username.as_str())
+    );
+    assert_eq!(rows[1].get::<_, &str>("action"), "login");
+    assert_eq!(
+        rows[1].get::<_, Option<Uuid>>("user_id"),
+        Some(app.test_user.user_id)
+    );
+    for row in rows {
+        assert_eq!(row.get::<_, Option<&str>>("client_ip"), Some("127.0.0.1"));
+        assert_eq!(
+            row.get::<_, Option<&str>>("user_agent"),
+            Some(TEST_USER_AGENT)
+        );
+    }
+    let html_page = app.get_admin_audit_html("").await;
+    assert!(html_page.contains("login_failed"));
+    assert!(html_page.contains(TEST_USER_AGENT));
+}
+
+#[tokio::test]
+async fn user_and_subscriber_management_is_logged() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let editor = app.add_user(UserRole::Editor).await;
+    create_unconfirmed_subscriber(&app).await;
+    let subscriber_id = subscriber_id(&app).await;
+    app.login().await;
+
+    // Act
+    app.post_admin_users(&serde_json::json!({
+        "username": "ursula",
+        "email": "",
+        "password": Uuid::new_v4().to_string(),
+        "role": "viewer",
+    }))
+    .await;
+    app.post_admin_user_role(editor.user_id, "viewer").await;
+    app.post_admin_user_delete(editor.user_id).await;
+    app.post_admin_subscriber_action(subscriber_id, "confirm")
+        .await;
+    app.post_admin_subscriber_action(subscriber_id, "delete")
+        .await;
+
+    // Assert
+    let subscriber_id = subscriber_id.to_string();
+    let pairs = [
+        ("user_created", "ursula viewer"),
+        ("user_role_changed", &format!("{} viewer", editor.username)),
+        ("user_deleted", &editor.username),
+        ("subscriber_confirmed", &subscriber_id),
+        ("subscriber_deleted", &subscriber_id),
+    ];
+    let expected: Vec<(String, String)> = pairs
+        .iter()
+        .map(|(action, target)| (action.to_string(), target.to_string()))
+        .collect();
+    assert_eq!(app.audit_actions(app.test_user.user_id).await, expected);
+}
+
+#[tokio::test]
+async fn audit_log_can_be_filtered_by_action_and_username() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let editor = app.add_user(UserRole::Editor).await;
+    app.login_as(&editor).await;
+    app.get_admin_users().await;
+    app.post_logout().await;
+    app.login().await;
+
+    // Act - Part 1 - By action
+    let html_page = app.get_admin_audit_html("?action=forbidden").await;
+
+    // Assert
+    assert!(html_page.contains("manage_users /admin/users"));
+    assert!(!html_page.contains("<td>login</td>"));
+
+    // Act - Part 2 - By username
+    let html_page = app
+        .get_admin_audit_html(&format!("?username={}", app.test_user.username))
+        .await;
+
+    // Assert
+    assert!(html_page.contains("<td>login</td>"));
+    assert!(!html_page.contains("manage_users /admin/users"));
+    assert!(!html_page.contains(&editor.username));
+}
+
+#[tokio::test]
+async fn unknown_action_filter_is_rejected_with_a_400() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+
+    // Act
+    let response = app.get_admin_audit("?action=hacked").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn audit_log_is_paginated_newest_first() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+    insert_events(&app, 60).await;
+
+    // Act - Part 1 - First page
+    let first_page = app.get_admin_audit_html("?action=forbidden").await;
+
+    // Assert
+    assert!(first_page.contains("<td>event-0</td>"));
+    assert!(first_page.contains("<td>event-49</td>"));
+    assert!(!first_page.contains("<td>event-50</td>"));
+    let next_page = first_page
+        .split("href=\"")
+        .filter_map(|s| s.split('"').next())
+        .find(|link| link.starts_with("/admin/audit?after="))
+        .expect("No link to the next page");
+    assert!(next_page.ends_with("&amp;action=forbidden"));
+
+    // Act - Part 2 - Second page
+    let second_page = app
+        .get_admin_audit_html(
+            &next_page
+                .trim_start_matches("/admin/audit")
+                .replace("&amp;", "&"),
+        )
+        .await;
+
+    // Assert
+    assert!(!second_page.contains("<td>event-49</td>"));
+    assert!(second_page.contains("<td>event-50</td>"));
+    assert!(second_page.contains("<td>event-59</td>"));
+    assert!(!second_page.contains("/admin/audit?after="));
+}
+
+#[tokio::test]
+async fn audit_events_can_be_read_with_the_audit_scope() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    insert_events(&app, 3).await;
+    let (_, token) = management::create_api_token(
+        &app.pool,
+        &app.test_user.username,
+        "siem",
+        &[ApiScope::AuditRead],
+        None,
+    )
+    .await
+    .unwrap();
+
+    // Act - Part 1 - First page, without the `api_token_created` event
+    let response = app
+        .get_audit_events_with_token(token.as_ref(), "limit=2&action=forbidden")
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let page: serde_json::Value = response.json().await.unwrap();
+    let events = page["events"].as_array().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0]["target"], "event-0");
+    assert_eq!(events[0]["action"], "forbidden");
+    assert!(events[0]["created_at"].as_str().unwrap().contains('T'));
+    let next_after = page["next_after"].as_str().unwrap();
+
+    // Act - Part 2 - Last page
+    let page: serde_json::Value = app
+        .get_audit_events_with_token(
+            token.as_ref(),
+            &format!("limit=2&action=forbidden&after={next_after}"),
+        )
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(page["events"][0]["target"], "event-2");
+    assert!(page["next_after"].is_null());
+}
+
+#[tokio::test]
+async fn next_page_survives_deletion_of_the_last_returned_event() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    insert_events(&app, 3).await;
+    let (_, token) = management::create_api_token(
+        &app.pool,
+        &app.test_user.username,
+        "siem",
+        &[ApiScope::AuditRead],
+        None,
+    )
+    .await
+    .unwrap();
+    let page: serde_json::Value = app
+        .get_audit_events_with_token(token.as_ref(), "limit=2&action=forbidden")
+        .await
+        .json()
+        .await
+        .unwrap();
+    let next_after = page["next_after"].as_str().unwrap();
+
+    // Act
+    app.pool
+        .get()
+        .await
+        .unwrap()
+        .execute("DELETE FROM audit_events WHERE target = 'event-1'", &[])
+        .await
+        .unwrap();
+    let page: serde_json::Value = app
+        .get_audit_events_with_token(
+            token.as_ref(),
+            &format!("limit=2&action=forbidden&after={next_after}"),
+        )
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(page["events"][0]["target"], "event-2");
+}
+
+#[tokio::test]
+async fn reading_audit_events_requires_the_audit_scope() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let (_, token) = management::create_api_token(
+        &app.pool,
+        &app.test_user.username,
+        "ci",
+        &[ApiScope::SubscribersRead],
+        None,
+    )
+    .await
+    .unwrap();
+
+    // Act
+    let response = app.get_audit_events_with_token(token.as_ref(), "").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+    let response = app.get_audit_events_with_token("nonsense", "").await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn editors_can_not_get_an_audit_token() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let editor = app.add_user(UserRole::Editor).await;
+
+    // Act
+    let result = management::create_api_token(
+        &app.pool,
+        &editor.username,
+        "siem",
+        &[ApiScope::AuditRead],
+        None,
+    )
+    .await;
+
+    // Assert
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn admin_cli_changes_are_logged_without_client_ip() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let username = Uuid::new_v4().to_string();
+    management::create_user(
+        &app.pool,
+        &app.password_hashing,
+        &username,
+        Secret::new(Uuid::new_v4().to_string()),
+        None,
+        UserRole::Viewer,
+        Actor::CommandLine,
+    )
+    .await
+    .unwrap();
+    let (token_id, _) = management::create_api_token(
+        &app.pool,
+        &username,
+        "ci",
+        &[ApiScope::SubscribersRead],
+        None,
+    )
+    .await
+    .unwrap();
+
+    // Act
+    management::revoke_api_token(&app.pool, token_id)
+        .await
+        .unwrap();
+    management::delete_user(&app.pool, &username).await.unwrap();
+
+    // Assert
+    let rows = app
+        .pool
+        .get()
+        .await
+        .unwrap()
+        .query(
+            "SELECT action, user_id, target, client_ip, user_agent
+                FROM audit_events ORDER BY created_at",
+            &[],
+        )
+        .await
+        .unwrap();
+    let actions: Vec<&str> = rows.iter().map(|r| r.get("action")).collect();
+    assert_eq!(
+        actions,
+        [
+            "user_created",
+            "api_token_created",
+            "api_token_revoked",
+            "user_deleted"
+        ]
+    );
+    assert_eq!(
+        rows[0].get::<_, Option<String>>("target"),
+        Some(format!("{} viewer", username))
+    );
+    assert_eq!(
+        rows[1].get::<_, Option<String>>("target"),
+        Some(token_id.to_string())
+    );
+    for row in rows {
+        assert_eq!(row.get::<_, Option<Uuid>>("user_id"), None);
+        assert_eq!(row.get::<_, Option<&str>>("client_ip"), None);
+        assert!(row
+            .get::<_, &str>("user_agent")
+            .starts_with("zero2prod-admin (user "));
+    }
+}
@@ -0,0 +1,170 @@
+//! tests/api/csrf.rs
+use wiremock::matchers::any;
+use wiremock::{Mock, ResponseTemplate};
+use zero2prod_axum::configuration::Settings;
+
+use crate::helpers::{assert_is_redirect_to, extract_csrf_token, TestApp};
+
+#[tokio::test]
+async fn admin_pages_carry_the_session_csrf_token() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+    let csrf_token = app.session_csrf_token().await;
+
+    // Assert
+    assert_eq!(csrf_token.len(), 32);
+    for html_page in [
+        app.get_admin_users_html().await,
+        app.get_change_password_html().await,
+        app.get_publish_newsletter_html().await,
+        app.get_admin_tokens_html().await,
+        app.get_admin_two_factor_html().await,
+    ] {
+        assert_eq!(extract_csrf_token(&html_page), csrf_token);
+    }
+}
+
+#[tokio::test]
+async fn admin_form_without_csrf_token_is_rejected() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+
+    // Act
+    let response = app
+        .api_client
+        .post(format!("{}/admin/users", app.address))
+        .form(&[
+            ("username", "mallory"),
+            ("email", ""),
+            ("password", "a-long-enough-password"),
+            ("role", "admin"),
+        ])
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+    assert!(!app.get_admin_users_html().await.contains("mallory"));
+}
+
+#[tokio::test]
+async fn admin_form_with_wrong_csrf_token_is_rejected() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+
+    // Act
+    let response = app
+        .api_client
+        .post(format!("{}/admin/logout", app.address))
+        .form(&[("csrf_token", "not-the-token")])
+        .send()
+        .await
+        .unwrap();
+
+    // Assert - Still logged in
+    assert_eq!(response.status().as_u16(), 403);
+    assert_eq!(app.get_admin_dashboard().await.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn csrf_token_of_another_session_is_rejected() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+    let old_token = app.session_csrf_token().await;
+    app.post_logout().await;
+    app.login().await;
+    assert_ne!(app.session_csrf_token().await, old_token);
+
+    // Act
+    let response = app
+        .api_client
+        .post(format!("{}/admin/logout", app.address))
+        .form(&[("csrf_token", old_token.as_str())])
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn admin_form_without_session_still_redirects_to_login() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let response = app
+        .api_client
+        .post(format!("{}/admin/password", app.address))
+        .form(&[("current_password", "x")])
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn password_reset_forms_carry_the_login_csrf_token() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let csrf_token = app.login_csrf_token().await;
+
+    // Act
+    let html_page = app
+        .api_client
+        .get(format!("{}/password/forgot", app.address))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(extract_csrf_token(&html_page), csrf_token);
+}
+
+#[tokio::test]
+async fn password_reset_forms_without_csrf_token_are_rejected() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login_csrf_token().await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let forgot = app
+        .api_client
+        .post(format!("{}/password/forgot", app.address))
+        .form(&[("email", app.test_user.email.as_str())])
+        .send()
+        .await
+        .unwrap();
+    let reset = app
+        .api_client
+        .post(format!("{}/password/reset", app.address))
+        .form(&[
+            ("token", "x"),
+            ("new_password", "a-long-enough-password"),
+            ("new_password_check", "a-long-enough-password"),
+            ("csrf_token", "not-the-token"),
+        ])
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(forgot.status().as_u16(), 403);
+    assert_eq!(reset.status().as_u16(), 403);
+}
@@ -0,0 +1,117 @@
+//! tests/api/graceful_shutdown.rs
+use std::time::{Duration, Instant};
+
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+use zero2prod_axum::configuration::Settings;
+
+use crate::helpers::TestApp;
+
+const BODY: &str = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+
+/// Email API which takes `delay` to answer, so a subscription request is
+/// still open when the shutdown starts.
+async fn slow_email_server(app: &TestApp, delay: Duration) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_delay(delay))
+        .mount(&app.email_server)
+        .await;
+}
+
+fn spawn_subscription(
+    app: &TestApp,
+) -> tokio::task::JoinHandle<reqwest::Result<reqwest::Response>> {
+    let request = reqwest::Client::new()
+        .post(format!("{}/subscriptions", app.address))
+        .header("content-type", "application/x-www-form-urlencoded")
+        .body(BODY)
+        .send();
+    tokio::spawn(request)
+}
+
+#[tokio::test]
+async fn open_requests_finish_before_the_app_stops() {
+    // Arrange
+    let mut app =
+        TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    slow_email_server(&app, Duration::from_secs(2)).await;
+    let request = spawn_subscription(&app);
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    // Act
+    app.shut_down().await.unwrap();
+
+    // Assert - The subscription went through
+    let response = request.await.unwrap().unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    let saved = app
+        .pool
+        .get()
+        .await
+        .unwrap()
+        .query_one("SELECT email FROM subscriptions", &[])
+        .await
+        .unwrap();
+    assert_eq!(saved.get::<_, String>(0), "ursula_le_guin@gmail.com");
+    // And new connections are refused
+    assert!(reqwest::get(format!("{}/health_check", app.address))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn password_reset_emails_are_sent_before_the_app_stops() {
+    // Arrange
+    let mut app =
+        TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    slow_email_server(&app, Duration::from_secs(2)).await;
+    // Answered right away, the email is sent after the response
+    let response = app.post_forgot_password(&app.test_user.email).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Act
+    let started = Instant::now();
+    app.shut_down().await.unwrap();
+
+    // Assert - The shutdown waited for the email
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    let received = app.email_server.received_requests().await.unwrap();
+    assert_eq!(received.len(), 1);
+}
+
+#[tokio::test]
+async fn shutdown_gives_up_after_the_drain_timeout() {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.shutdown.drain_timeout = 1;
+    let mut app = TestApp::spawn_app(config).await;
+    slow_email_server(&app, Duration::from_secs(30)).await;
+    let _request = spawn_subscription(&app);
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    // Act
+    let started = Instant::now();
+    let result = app.shut_down().await;
+
+    // Assert
+    let error = result.unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_secs(1), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_secs(5), "{:?}", elapsed);
+}
+
+#[tokio::test]
+async fn idle_app_stops_right_away() {
+    // Arrange
+    let mut app =
+        TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let started = Instant::now();
+    app.shut_down().await.unwrap();
+
+    // Assert - The background tasks don't hold up the shutdown
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
@@ -0,0 +1,142 @@
+//! tests/api/health.rs
+use std::time::Duration;
+
+use wiremock::matchers::method;
+use wiremock::{Mock, ResponseTemplate};
+use zero2prod_axum::configuration::Settings;
+use zero2prod_axum::startup::db_migration::latest_embedded_migration;
+
+use crate::helpers::TestApp;
+
+/// Version of the newest file in `migrations/`.
+fn latest_migration() -> u32 {
+    latest_embedded_migration().unwrap().version()
+}
+
+async fn get_health(app: &TestApp, probe: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(format!("{}/health/{}", &app.address, probe))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn liveness_probe_returns_ok() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let response = get_health(&app, "live").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "ok");
+}
+
+#[tokio::test]
+async fn readiness_probe_reports_every_check() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let response = get_health(&app, "ready").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "status": "ok",
+            "checks": {
+                "database": { "status": "ok" },
+                "migrations": { "status": "ok", "version": latest_migration() },
+                "email_provider": { "status": "skipped" },
+            }
+        })
+    );
+}
+
+#[tokio::test]
+async fn readiness_probe_fails_when_the_latest_migration_is_missing() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.pool
+        .get()
+        .await
+        .unwrap()
+        .execute(
+            "DELETE FROM refinery_schema_history WHERE version = $1",
+            &[&(latest_migration() as i32)],
+        )
+        .await
+        .unwrap();
+
+    // Act
+    let response = get_health(&app, "ready").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 503);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "error");
+    assert_eq!(body["checks"]["database"]["status"], "ok");
+    let migrations = &body["checks"]["migrations"];
+    assert_eq!(migrations["status"], "error");
+    assert!(migrations["error"]
+        .as_str()
+        .unwrap()
+        .contains(&format!("V{}", latest_migration())));
+}
+
+#[tokio::test]
+async fn readiness_probe_checks_the_email_provider_if_configured() {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.health.check_email_provider = true;
+    let app = TestApp::spawn_app(config).await;
+    // Any response means the service can be reached.
+    Mock::given(method("HEAD"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = get_health(&app, "ready").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["checks"]["email_provider"]["status"], "ok");
+}
+
+#[tokio::test]
+async fn readiness_probe_fails_when_a_check_times_out() {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.health.check_email_provider = true;
+    config.health.timeout = 200;
+    let app = TestApp::spawn_app(config).await;
+    Mock::given(method("HEAD"))
+        .respond_with(
+            ResponseTemplate::new(200).set_delay(Duration::from_secs(5)),
+        )
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = get_health(&app, "ready").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 503);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let email_provider = &body["checks"]["email_provider"];
+    assert_eq!(email_provider["status"], "error");
+    assert!(email_provider["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("Timed out"));
+    assert_eq!(body["checks"]["database"]["status"], "ok");
+}
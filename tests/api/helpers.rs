@@ -1,49 +1,84 @@
 //! This is a module with common initialization functions.
 
-use argon2::{password_hash::SaltString, Argon2, Params, PasswordHasher};
+use std::sync::Once;
+
 use deadpool_postgres::{Client, Pool};
 use secrecy::{ExposeSecret, Secret};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
 use tokio_postgres::NoTls;
 use uuid::Uuid;
-use wiremock::MockServer;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
 
 use zero2prod_axum::{
-    configuration::{DatabaseSettings, Settings},
+    authentication::PasswordHashing,
+    clock::Clock,
+    configuration::{DatabaseSettings, Settings, TelemetrySettings},
+    domain::UserRole,
+    email_client::{EmailClient, InMemoryTransport},
+    issue_delivery_worker::{try_execute_task, ExecutionOutcome},
     startup::{get_postgres_connection_pool, Application},
+    telemetry::{get_subscriber, init_telemetry, LogCapture},
 };
 
+/// Sent by `api_client`, audit events store it.
+pub const TEST_USER_AGENT: &str = "zero2prod-tests";
+
+/// Capture the logs of the current test until the guard is dropped. The app
+/// of a `#[tokio::test]` runs on the thread of the test, so logs of other
+/// tests running meanwhile stay out.
+pub fn capture_logs(
+    settings: &TelemetrySettings,
+) -> (LogCapture, tracing::subscriber::DefaultGuard) {
+    let logs = LogCapture::default();
+    let subscriber = get_subscriber(settings, logs.clone())
+        .expect("Failed to set up tracing");
+    (logs, tracing::subscriber::set_default(subscriber))
+}
+
 pub struct TestUser {
     pub user_id: Uuid,
     pub username: String,
     pub password: String,
+    pub email: String,
+    pub role: UserRole,
 }
 
 impl TestUser {
     pub fn generate() -> Self {
+        Self::with_role(UserRole::Owner)
+    }
+
+    pub fn with_role(role: UserRole) -> Self {
         Self {
             user_id: Uuid::new_v4(),
             username: Uuid::new_v4().to_string(),
             password: Uuid::new_v4().to_string(),
+            email: format!("{}@example.com", Uuid::new_v4().simple()),
+            role,
         }
     }
 
-    async fn store_in_db(&self, client: &Client) {
-        let salt = SaltString::generate(&mut rand::thread_rng());
-        // We don't care about the exact Argon2 parameters here
-        // given that it's for testing purposes!
-        let password_hash = Argon2::new(
-            argon2::Algorithm::Argon2id,
-            argon2::Version::V0x13,
-            Params::new(15000, 2, 1, None).unwrap(),
-        )
-        .hash_password(self.password.as_bytes(), &salt)
-        .unwrap()
-        .to_string();
+    /// The hash has the configured costs, so logging in doesn't upgrade
+    /// it.
+    async fn store_in_db(&self, client: &Client, hashing: &PasswordHashing) {
+        let password_hash = hashing
+            .hash(Secret::new(self.password.clone()))
+            .unwrap()
+            .expose_secret()
+            .clone();
         client
             .execute(
-                "INSERT INTO users (user_id, username, password_hash)
-                    VALUES ($1, $2, $3)",
-                &[&self.user_id, &self.username, &password_hash],
+                "INSERT INTO users (user_id, username, password_hash, email, role)
+                    VALUES ($1, $2, $3, $4, $5)",
+                &[
+                    &self.user_id,
+                    &self.username,
+                    &password_hash,
+                    &self.email,
+                    &self.role.as_str(),
+                ],
             )
             .await
             .expect("Failed to create test users");
@@ -60,49 +95,36 @@ pub struct TestApp {
     pub address: String,
     pub pool: Pool,
     pub email_server: MockServer,
+    pub email_client: EmailClient,
+    /// Stays empty unless `email_delivery_service` is `in_memory`.
+    pub email_outbox: InMemoryTransport,
+    /// `app_base_url` from config, used by the app to build links.
+    pub base_url: String,
     pub port: u16,
+    /// Keeps cookies between requests and doesn't follow redirects.
+    pub api_client: reqwest::Client,
+    /// Fixed at the start of the test, moved with `Clock::advance`.
+    pub clock: Clock,
+    /// Same costs as the application.
+    pub password_hashing: PasswordHashing,
+    /// Stands in for SIGTERM, see `shut_down`.
+    shutdown: Option<oneshot::Sender<()>>,
+    server: Option<JoinHandle<Result<(), std::io::Error>>>,
 }
 
 /// Confirmation links embedded in the request to the email API.
 pub struct ConfirmationLink(pub reqwest::Url);
 
 impl TestApp {
-    /// Toggle tracing output by commenting/uncommenting
-    /// the first lines in this function.
+    /// Set `TEST_TRACING` to see the logs.
     pub async fn spawn_app(mut config: Settings) -> TestApp {
-        if let Ok(_) = std::env::var("TEST_TRACING") {
-            use tracing_subscriber::fmt::format::FmtSpan;
-            use tracing_subscriber::layer::SubscriberExt;
-            use tracing_subscriber::util::SubscriberInitExt;
-            use tracing_subscriber::EnvFilter;
-            use tracing_subscriber::Layer;
-
-            // Opentelemetry
-            let tracer = opentelemetry_jaeger::new_agent_pipeline()
-                .with_service_name("zero2prod_test")
-                .install_simple()
-                .unwrap();
-            let opentelemetry =
-                tracing_opentelemetry::layer().with_tracer(tracer);
-            // Console
-            let filter = EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| EnvFilter::new("info"));
-            let layer = tracing_subscriber::fmt::layer()
-                .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE)
-                .with_filter(filter);
-
-            tracing_subscriber::registry()
-                .with(opentelemetry)
-                .with(layer)
-                .try_init()
-                .unwrap();
-            // let subscriber = tracing_subscriber::fmt()
-            //     .with_max_level(tracing::Level::INFO)
-            //     // .without_time()
-            //     .with_span_events(FmtSpan::NEW | FmtSpan::CLOSE)
-            //     .with_level(true)
-            //     .finish();
-            // let _ = tracing::subscriber::set_global_default(subscriber);
+        if std::env::var("TEST_TRACING").is_ok() {
+            // Logs of every test go to stdout, filtered with `RUST_LOG`.
+            static INIT: Once = Once::new();
+            INIT.call_once(|| {
+                init_telemetry(&config.telemetry)
+                    .expect("Failed to set up tracing");
+            });
         }
 
         // We should randomize app port
@@ -139,19 +161,41 @@ impl TestApp {
         // Store db_config with test user in config destined for Application::build
         config.database = db_config;
 
-        let application = Application::build(config)
-            .await
-            .expect("Failed to build application");
+        let email_client = EmailClient::from_settings(
+            &config.email_client,
+            &config.email_delivery_service,
+        )
+        .unwrap();
+
+        let base_url = config.app_base_url.clone();
+        let clock = Clock::fixed(time::OffsetDateTime::now_utc());
+        let application = Application::build_with_clock(
+            config,
+            email_client.clone(),
+            clock.clone(),
+        )
+        .await
+        .expect("Failed to build application");
 
         let port = application.port();
+        let password_hashing = application.password_hashing().clone();
+        // Sends through it count in the metrics of the application, it
+        // also drains the delivery queue without waiting for the worker.
+        let email_client = application.email_client().clone();
+        let email_outbox = email_client.outbox().cloned().unwrap_or_default();
 
         let address = format!("http://127.0.0.1:{}", port);
 
         // Very important step
-        let _ = tokio::spawn(application.run_until_stopped());
+        let (shutdown, signal) = oneshot::channel();
+        let server = tokio::spawn(application.run_until(async {
+            let _ = signal.await;
+        }));
 
         let test_user = TestUser::generate();
-        test_user.store_in_db(&pool.get().await.unwrap()).await;
+        test_user
+            .store_in_db(&pool.get().await.unwrap(), &password_hashing)
+            .await;
 
         TestApp {
             db_username,
@@ -159,11 +203,79 @@ impl TestApp {
             address,
             pool,
             email_server,
+            email_client,
+            email_outbox,
+            base_url,
             port,
             test_user,
+            api_client: reqwest::Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .cookie_store(true)
+                .user_agent(TEST_USER_AGENT)
+                .build()
+                .unwrap(),
+            clock,
+            password_hashing,
+            shutdown: Some(shutdown),
+            server: Some(server),
         }
     }
 
+    /// Stop the application the way SIGTERM does and wait until it is
+    /// done draining.
+    pub async fn shut_down(&mut self) -> Result<(), std::io::Error> {
+        let _ = self.shutdown.take().expect("Already shut down").send(());
+        self.server.take().unwrap().await.unwrap()
+    }
+
+    /// Execute delivery tasks until the queue is empty, so tests don't
+    /// depend on the background worker schedule.
+    pub async fn dispatch_all_pending_emails(&self) {
+        loop {
+            if let ExecutionOutcome::EmptyQueue =
+                try_execute_task(&self.pool, &self.email_client, &self.base_url)
+                    .await
+                    .unwrap()
+            {
+                // Tasks locked by the application's own worker are skipped,
+                // so wait until it is done with them too.
+                let pending: i64 = self
+                    .pool
+                    .get()
+                    .await
+                    .unwrap()
+                    .query_one(
+                        "SELECT COUNT(*) FROM issue_delivery_queue
+                        WHERE execute_after <= now()",
+                        &[],
+                    )
+                    .await
+                    .unwrap()
+                    .get(0);
+                if pending == 0 {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        }
+    }
+
+    /// Wait until the email API received `count` requests, for emails
+    /// sent after the response.
+    pub async fn wait_for_email_requests(
+        &self,
+        count: usize,
+    ) -> Vec<wiremock::Request> {
+        for _ in 0..500 {
+            let requests = self.email_server.received_requests().await.unwrap();
+            if requests.len() >= count {
+                return requests;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("The email API didn't receive {} requests", count);
+    }
+
     /// This function sends Post request to our TestApp,
     /// to /subscriptions path. If successful, it will create
     /// a line in postgres db.
@@ -172,7 +284,7 @@ impl TestApp {
         body: &'static str,
     ) -> reqwest::Response {
         reqwest::Client::new()
-            .post(&format!("{}/subscriptions", &self.address))
+            .post(format!("{}/subscriptions", &self.address))
             .header("content-type", "application/x-www-form-urlencoded")
             .body(body)
             .send()
@@ -203,18 +315,509 @@ impl TestApp {
             confirmation_link
         };
 
-        let link = get_link(&body["text_body"].as_str().unwrap());
+        let link = get_link(body["text_body"].as_str().unwrap());
 
         ConfirmationLink(link)
     }
 
+    /// Extract the link from the `List-Unsubscribe` header
+    /// of the request to the email API.
+    pub fn get_unsubscribe_link(
+        &self,
+        email_request: &wiremock::Request,
+    ) -> reqwest::Url {
+        let body: serde_json::Value =
+            serde_json::from_slice(&email_request.body).unwrap();
+        let header = body["headers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|h| h["name"] == "List-Unsubscribe")
+            .expect("No List-Unsubscribe header in the email");
+        let raw_link = header["value"]
+            .as_str()
+            .unwrap()
+            .trim_start_matches('<')
+            .trim_end_matches('>');
+        let mut unsubscribe_link = reqwest::Url::parse(raw_link).unwrap();
+        assert_eq!(unsubscribe_link.host_str().unwrap(), "127.0.0.1");
+        unsubscribe_link.set_port(Some(self.port)).unwrap();
+        unsubscribe_link
+    }
+
+    /// Token of the login forms. Getting the form also sets the
+    /// `login_csrf` cookie the token is checked against.
+    pub async fn login_csrf_token(&self) -> String {
+        extract_csrf_token(&self.get_login_html().await)
+    }
+
+    pub async fn get_login_html(&self) -> String {
+        self.api_client
+            .get(format!("{}/login", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    /// Token of the current session, empty without one.
+    pub async fn session_csrf_token(&self) -> String {
+        extract_csrf_token(&self.get_admin_dashboard_html().await)
+    }
+
+    pub async fn post_login<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let csrf_token = self.login_csrf_token().await;
+        self.api_client
+            .post(format!("{}/login", &self.address))
+            .form(&with_csrf_token(body, &csrf_token))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Log in as the test user.
+    pub async fn login(&self) -> reqwest::Response {
+        self.login_as(&self.test_user).await
+    }
+
+    pub async fn login_as(&self, user: &TestUser) -> reqwest::Response {
+        self.post_login(&serde_json::json!({
+            "username": &user.username,
+            "password": &user.password,
+        }))
+        .await
+    }
+
+    /// Store another admin user besides `test_user`.
+    pub async fn add_user(&self, role: UserRole) -> TestUser {
+        let user = TestUser::with_role(role);
+        user.store_in_db(
+            &self.pool.get().await.unwrap(),
+            &self.password_hashing,
+        )
+        .await;
+        user
+    }
+
+    /// Actions recorded in `audit_events` for the user, oldest first.
+    /// Logins and logouts are left out, most tests log in as part of the
+    /// setup.
+    pub async fn audit_actions(&self, user_id: Uuid) -> Vec<(String, String)> {
+        self.pool
+            .get()
+            .await
+            .unwrap()
+            .query(
+                "SELECT action, coalesce(target, '') FROM audit_events
+                    WHERE user_id = $1 AND action NOT IN ('login', 'logout')
+                    ORDER BY created_at",
+                &[&user_id],
+            )
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| (row.get(0), row.get(1)))
+            .collect()
+    }
+
+    pub async fn get_admin_users(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/users", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_users_html(&self) -> String {
+        self.get_admin_users().await.text().await.unwrap()
+    }
+
+    pub async fn post_admin_users<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let csrf_token = self.session_csrf_token().await;
+        self.api_client
+            .post(format!("{}/admin/users", &self.address))
+            .form(&with_csrf_token(body, &csrf_token))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_admin_user_role(
+        &self,
+        user_id: Uuid,
+        role: &str,
+    ) -> reqwest::Response {
+        let csrf_token = self.session_csrf_token().await;
+        self.api_client
+            .post(format!("{}/admin/users/{}/role", &self.address, user_id))
+            .form(&[("role", role), ("csrf_token", &csrf_token)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_admin_user_delete(
+        &self,
+        user_id: Uuid,
+    ) -> reqwest::Response {
+        let csrf_token = self.session_csrf_token().await;
+        self.api_client
+            .post(format!("{}/admin/users/{}/delete", &self.address, user_id))
+            .form(&[("csrf_token", &csrf_token)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_dashboard(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/dashboard", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_dashboard_html(&self) -> String {
+        self.get_admin_dashboard().await.text().await.unwrap()
+    }
+
+    /// `query` is appended to the url as is, e.g. `?status=confirmed`.
+    pub async fn get_admin_subscribers(
+        &self,
+        query: &str,
+    ) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/subscribers{}", &self.address, query))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_subscribers_html(&self, query: &str) -> String {
+        self.get_admin_subscribers(query)
+            .await
+            .text()
+            .await
+            .unwrap()
+    }
+
+    /// `query` is appended to the url as is, e.g. `?action=login`.
+    pub async fn get_admin_audit(&self, query: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/audit{}", &self.address, query))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_audit_html(&self, query: &str) -> String {
+        self.get_admin_audit(query).await.text().await.unwrap()
+    }
+
+    /// `action` is either `confirm` or `delete`.
+    pub async fn post_admin_subscriber_action(
+        &self,
+        subscriber_id: Uuid,
+        action: &str,
+    ) -> reqwest::Response {
+        let csrf_token = self.session_csrf_token().await;
+        self.api_client
+            .post(format!(
+                "{}/admin/subscribers/{}/{}",
+                &self.address, subscriber_id, action
+            ))
+            .form(&[("csrf_token", &csrf_token)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_publish_newsletter(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/newsletters", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_publish_newsletter_html(&self) -> String {
+        self.get_publish_newsletter().await.text().await.unwrap()
+    }
+
+    pub async fn post_publish_newsletter<Body>(
+        &self,
+        body: &Body,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let csrf_token = self.session_csrf_token().await;
+        self.api_client
+            .post(format!("{}/admin/newsletters", &self.address))
+            .form(&with_csrf_token(body, &csrf_token))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_preview_newsletter<Body>(
+        &self,
+        body: &Body,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let csrf_token = self.session_csrf_token().await;
+        self.api_client
+            .post(format!("{}/admin/newsletters/preview", &self.address))
+            .form(&with_csrf_token(body, &csrf_token))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_change_password(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/password", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_change_password_html(&self) -> String {
+        self.get_change_password().await.text().await.unwrap()
+    }
+
+    pub async fn post_change_password<Body>(
+        &self,
+        body: &Body,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let csrf_token = self.session_csrf_token().await;
+        self.api_client
+            .post(format!("{}/admin/password", &self.address))
+            .form(&with_csrf_token(body, &csrf_token))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_forgot_password(&self, email: &str) -> reqwest::Response {
+        let csrf_token = self.login_csrf_token().await;
+        self.api_client
+            .post(format!("{}/password/forgot", &self.address))
+            .form(&[("email", email), ("csrf_token", &csrf_token)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_reset_password<Body>(
+        &self,
+        body: &Body,
+    ) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        let csrf_token = self.login_csrf_token().await;
+        self.api_client
+            .post(format!("{}/password/reset", &self.address))
+            .form(&with_csrf_token(body, &csrf_token))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_logout(&self) -> reqwest::Response {
+        let csrf_token = self.session_csrf_token().await;
+        self.api_client
+            .post(format!("{}/admin/logout", &self.address))
+            .form(&[("csrf_token", &csrf_token)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_login_two_factor(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/login/2fa", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_login_two_factor(&self, code: &str) -> reqwest::Response {
+        let csrf_token = self.login_csrf_token().await;
+        self.api_client
+            .post(format!("{}/login/2fa", &self.address))
+            .form(&[("code", code), ("csrf_token", &csrf_token)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_two_factor_html(&self) -> String {
+        self.api_client
+            .get(format!("{}/admin/2fa", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    /// `action` is `setup`, `enable`, `disable` or `recovery-codes`,
+    /// all but `setup` need a code.
+    pub async fn post_admin_two_factor(
+        &self,
+        action: &str,
+        code: &str,
+    ) -> reqwest::Response {
+        let csrf_token = self.session_csrf_token().await;
+        self.api_client
+            .post(format!("{}/admin/2fa/{}", &self.address, action))
+            .form(&[("code", code), ("csrf_token", &csrf_token)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_resend_confirmation(
+        &self,
+        body: &'static str,
+    ) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/subscriptions/resend", &self.address))
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn post_newsletters(
         &self,
         body: serde_json::Value,
+    ) -> reqwest::Response {
+        self.post_newsletters_as(&self.test_user, body).await
+    }
+
+    pub async fn post_newsletters_as(
+        &self,
+        user: &TestUser,
+        body: serde_json::Value,
+    ) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/newsletters", self.address))
+            .json(&body)
+            .basic_auth(&user.username, Some(&user.password))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_newsletters_with_token(
+        &self,
+        token: &str,
+        body: serde_json::Value,
     ) -> reqwest::Response {
         reqwest::Client::new()
-            .post(&format!("{}/newsletters", self.address))
+            .post(format!("{}/newsletters", self.address))
             .json(&body)
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_subscribers_with_token(
+        &self,
+        token: &str,
+        query: &str,
+    ) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/subscribers?{}", self.address, query))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_audit_events_with_token(
+        &self,
+        token: &str,
+        query: &str,
+    ) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/audit_events?{}", self.address, query))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_tokens(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/tokens", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_admin_tokens_html(&self) -> String {
+        self.get_admin_tokens().await.text().await.unwrap()
+    }
+
+    /// A list of pairs, the form has a `scope` field per checked scope.
+    pub async fn post_admin_tokens(
+        &self,
+        form: &[(&str, &str)],
+    ) -> reqwest::Response {
+        let csrf_token = self.session_csrf_token().await;
+        self.api_client
+            .post(format!("{}/admin/tokens", &self.address))
+            .form(&with_csrf_token(&form, &csrf_token))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_admin_token_revoke(
+        &self,
+        token_id: Uuid,
+    ) -> reqwest::Response {
+        let csrf_token = self.session_csrf_token().await;
+        self.api_client
+            .post(format!(
+                "{}/admin/tokens/{}/revoke",
+                &self.address, token_id
+            ))
+            .form(&[("csrf_token", &csrf_token)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_newsletters_with_idempotency_key(
+        &self,
+        body: &serde_json::Value,
+        idempotency_key: &str,
+    ) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/newsletters", self.address))
+            .header("Idempotency-Key", idempotency_key)
+            .json(body)
             .basic_auth(
                 &self.test_user.username,
                 Some(&self.test_user.password),
@@ -236,6 +839,14 @@ impl Drop for TestApp {
         // spawn a new tokio runtime, we should do it inside new thread.
         let _ = std::thread::spawn(move || {
             let mut client = get_sync_postgres_client(&db_config);
+            // The runtime is blocked here, so connections of the app under
+            // test (e.g. the delivery worker) can't finish their transactions.
+            let terminate = format!(
+                "SELECT pg_terminate_backend(pid) FROM pg_stat_activity
+                WHERE usename = '{0}';",
+                db_username
+            );
+            client.simple_query(&terminate).unwrap();
             let create_role = format!("DROP SCHEMA {0} CASCADE;", db_username);
             let create_schema = format!("DROP ROLE {0};", db_username);
             client.simple_query(&create_role).unwrap();
@@ -269,3 +880,124 @@ pub fn get_sync_postgres_client(
     )
     .unwrap()
 }
+
+// ───── Helpers ──────────────────────────────────────────────────────────── //
+
+pub async fn create_unconfirmed_subscriber(app: &TestApp) -> ConfirmationLink {
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+
+    let _mock_guard = Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .named("Create unconfirmed subscriber")
+        .expect(1)
+        .mount_as_scoped(&app.email_server)
+        .await;
+
+    app.post_subscriptions(body)
+        .await
+        .error_for_status()
+        .unwrap();
+
+    // We now inspect the requests received by the mock email
+    // delivery service to retrieve the confirmation link and
+    // return it
+    let email_request = &app
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    app.get_confirmation_link(email_request)
+}
+
+pub async fn create_confirmed_subscriber(app: &TestApp) {
+    let confirmation_link = create_unconfirmed_subscriber(app).await;
+    reqwest::get(confirmation_link.0)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+}
+
+/// Move creation time of all confirmation tokens a month back.
+pub async fn expire_confirmation_tokens(app: &TestApp) {
+    app.pool
+        .get()
+        .await
+        .unwrap()
+        .execute(
+            "UPDATE subscription_tokens
+            SET created_at = created_at - interval '30 days'",
+            &[],
+        )
+        .await
+        .expect("Failed to expire confirmation tokens.");
+}
+
+/// Fields of the `/admin/newsletters` form, with a fresh idempotency key.
+pub fn newsletter_form() -> serde_json::Value {
+    serde_json::json!({
+        "title": "Newsletter title",
+        "text": "Newsletter body as plain text",
+        "html": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": Uuid::new_v4().to_string(),
+    })
+}
+
+/// Json body of `POST /newsletters`.
+pub fn newsletter_request_body() -> serde_json::Value {
+    serde_json::json!({
+        "title": "Newsletter title",
+        "content": {
+            "text": "Newsletter body as a plain text",
+            "html": "<p>Newsletter body as HTML</p>",
+        }
+    })
+}
+
+/// Value of the first `csrf_token` hidden field of the page, empty if
+/// there is none.
+pub fn extract_csrf_token(html: &str) -> String {
+    let marker = r#"name="csrf_token" value=""#;
+    html.split_once(marker)
+        .and_then(|(_, rest)| rest.split_once('"'))
+        .map(|(token, _)| token.to_string())
+        .unwrap_or_default()
+}
+
+/// Form fields of `body`, a json object or a list of pairs, with the csrf
+/// token added.
+fn with_csrf_token<Body>(body: &Body, csrf_token: &str) -> Vec<(String, String)>
+where
+    Body: serde::Serialize,
+{
+    let as_string = |value: serde_json::Value| match value {
+        serde_json::Value::String(s) => s,
+        other => other.to_string(),
+    };
+    let mut fields: Vec<(String, String)> =
+        match serde_json::to_value(body).unwrap() {
+            serde_json::Value::Object(map) => map
+                .into_iter()
+                .map(|(key, value)| (key, as_string(value)))
+                .collect(),
+            serde_json::Value::Array(pairs) => pairs
+                .into_iter()
+                .map(|pair| {
+                    let (key, value): (String, serde_json::Value) =
+                        serde_json::from_value(pair).unwrap();
+                    (key, as_string(value))
+                })
+                .collect(),
+            other => panic!("Not a form body: {}", other),
+        };
+    fields.push(("csrf_token".to_string(), csrf_token.to_string()));
+    fields
+}
+
+pub fn assert_is_redirect_to(response: &reqwest::Response, location: &str) {
+    assert_eq!(response.status().as_u16(), 303);
+    assert_eq!(response.headers().get("Location").unwrap(), location);
+}
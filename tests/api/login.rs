@@ -0,0 +1,267 @@
+//! tests/api/login.rs
+use argon2::password_hash::SaltString;
+use argon2::{Algorithm, Argon2, Params, PasswordHasher, Version};
+use zero2prod_axum::configuration::Settings;
+
+use crate::helpers::{assert_is_redirect_to, TestApp};
+
+fn session_cookie(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get_all("Set-Cookie")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .find(|v| v.starts_with("session_id="))
+        .map(str::to_string)
+}
+
+async fn stored_password_hash(app: &TestApp) -> String {
+    app.pool
+        .get()
+        .await
+        .unwrap()
+        .query_one(
+            "SELECT password_hash FROM users WHERE user_id = $1",
+            &[&app.test_user.user_id],
+        )
+        .await
+        .unwrap()
+        .get(0)
+}
+
+/// Replace the test user's hash with one of much lower costs.
+async fn store_weak_password_hash(app: &TestApp) -> String {
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    let password_hash = Argon2::new(
+        Algorithm::Argon2id,
+        Version::V0x13,
+        Params::new(4096, 1, 1, None).unwrap(),
+    )
+    .hash_password(app.test_user.password.as_bytes(), &salt)
+    .unwrap()
+    .to_string();
+    app.pool
+        .get()
+        .await
+        .unwrap()
+        .execute(
+            "UPDATE users SET password_hash = $1 WHERE user_id = $2",
+            &[&password_hash, &app.test_user.user_id],
+        )
+        .await
+        .unwrap();
+    password_hash
+}
+
+#[tokio::test]
+async fn successful_login_sets_a_session_cookie_and_redirects_to_dashboard() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let response = app.login().await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/dashboard");
+    let cookie = session_cookie(&response).expect("No session cookie set");
+    assert!(cookie.contains("HttpOnly"));
+    assert!(cookie.contains("SameSite=Strict"));
+    assert!(cookie.contains("Path=/"));
+}
+
+#[tokio::test]
+async fn invalid_credentials_do_not_start_a_session() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": "wrong-password",
+        }))
+        .await;
+
+    // Assert
+    assert!(session_cookie(&response).is_none());
+    let response = app.get_admin_dashboard().await;
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn session_id_is_rotated_on_login() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let first = session_cookie(&app.login().await).unwrap();
+
+    // Act
+    let second = session_cookie(&app.login().await).unwrap();
+
+    // Assert
+    assert_ne!(first, second);
+    // The previous session id is no longer accepted.
+    let old_id = first.split(';').next().unwrap();
+    let response = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap()
+        .get(format!("{}/admin/dashboard", app.address))
+        .header("Cookie", old_id)
+        .send()
+        .await
+        .unwrap();
+    assert_is_redirect_to(&response, "/login");
+    // While the new one is.
+    assert_eq!(app.get_admin_dashboard().await.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn outdated_password_hash_is_upgraded_on_login() {
+    // Arrange
+    let config = Settings::load_configuration().unwrap();
+    let costs = &config.authentication.password_hash;
+    let expected_params = format!(
+        "m={},t={},p={}",
+        costs.memory_cost, costs.time_cost, costs.parallelism
+    );
+    let app = TestApp::spawn_app(config).await;
+    let weak_hash = store_weak_password_hash(&app).await;
+
+    // Act
+    let response = app.login().await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/dashboard");
+    let upgraded_hash = stored_password_hash(&app).await;
+    assert_ne!(upgraded_hash, weak_hash);
+    assert!(upgraded_hash.starts_with("$argon2id$v=19$"));
+    assert!(upgraded_hash.contains(&expected_params));
+    // The password still works with the new hash.
+    app.post_logout().await;
+    assert_is_redirect_to(&app.login().await, "/admin/dashboard");
+    assert_eq!(stored_password_hash(&app).await, upgraded_hash);
+}
+
+#[tokio::test]
+async fn failed_login_does_not_upgrade_the_password_hash() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let weak_hash = store_weak_password_hash(&app).await;
+
+    // Act
+    app.post_login(&serde_json::json!({
+        "username": &app.test_user.username,
+        "password": "wrong-password",
+    }))
+    .await;
+
+    // Assert
+    assert_eq!(stored_password_hash(&app).await, weak_hash);
+}
+
+#[tokio::test]
+async fn login_error_is_shown_once_and_escaped() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act - Part 1 - Fail a login
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": "<script>alert('x')</script>",
+            "password": "wrong-password",
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/login");
+
+    // Act - Part 2 - The error is on the next page, the username is not
+    let html_page = app.get_login_html().await;
+    assert!(html_page.contains("Authentication failed"));
+    assert!(!html_page.contains("<script>"));
+
+    // Act - Part 3 - And gone after a reload
+    let html_page = app.get_login_html().await;
+    assert!(!html_page.contains("Authentication failed"));
+}
+
+#[tokio::test]
+async fn error_query_parameter_is_not_rendered() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let html_page = app
+        .api_client
+        .get(format!(
+            "{}/login?error=%3Cscript%3Ealert(1)%3C%2Fscript%3E",
+            app.address
+        ))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    // Assert
+    assert!(!html_page.contains("<script>"));
+    assert!(!html_page.contains("alert(1)"));
+}
+
+#[tokio::test]
+async fn forged_flash_cookie_is_not_rendered() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let html_page = reqwest::Client::new()
+        .get(format!("{}/login", app.address))
+        .header("Cookie", "flash=0000.error:Your account was deleted")
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    // Assert
+    assert!(!html_page.contains("Your account was deleted"));
+}
+
+#[tokio::test]
+async fn login_without_csrf_token_is_rejected() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let credentials = [
+        ("username", app.test_user.username.as_str()),
+        ("password", app.test_user.password.as_str()),
+    ];
+
+    // Act - Part 1 - No login_csrf cookie
+    let response = app
+        .api_client
+        .post(format!("{}/login", app.address))
+        .form(&credentials)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 403);
+    assert!(session_cookie(&response).is_none());
+
+    // Act - Part 2 - Cookie set, but a different token in the form
+    app.login_csrf_token().await;
+    let response = app
+        .api_client
+        .post(format!("{}/login", app.address))
+        .form(&[
+            credentials[0],
+            credentials[1],
+            ("csrf_token", "not-the-token"),
+        ])
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+    assert!(session_cookie(&response).is_none());
+}
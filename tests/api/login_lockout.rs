@@ -0,0 +1,197 @@
+//! tests/api/login_lockout.rs
+use time::Duration;
+use zero2prod_axum::configuration::Settings;
+
+use crate::helpers::{
+    assert_is_redirect_to, newsletter_request_body, TestApp, TestUser,
+};
+
+const LOCKED_ERROR: &str =
+    "Too many failed login attempts, please try again later.";
+
+async fn spawn_app(max_per_username: i64, max_per_ip: i64) -> TestApp {
+    let mut config = Settings::load_configuration().unwrap();
+    config.lockout.max_failures_per_username = max_per_username;
+    config.lockout.max_failures_per_ip = max_per_ip;
+    config.lockout.window = 15 * 60;
+    config.lockout.lockout_duration = 10 * 60;
+    TestApp::spawn_app(config).await
+}
+
+async fn fail_login(app: &TestApp, username: &str) {
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": username,
+            "password": "wrong-password",
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/login");
+    assert!(app.get_login_html().await.contains("Authentication failed"));
+}
+
+/// Targets of the lockout events, oldest first.
+async fn lockout_events(app: &TestApp, action: &str) -> Vec<String> {
+    app.pool
+        .get()
+        .await
+        .unwrap()
+        .query(
+            "SELECT target FROM audit_events
+                WHERE action = $1 ORDER BY created_at",
+            &[&action],
+        )
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| row.get(0))
+        .collect()
+}
+
+#[tokio::test]
+async fn username_is_locked_after_too_many_failures() {
+    // Arrange
+    let app = spawn_app(3, 100).await;
+    let username = app.test_user.username.clone();
+    for _ in 0..3 {
+        fail_login(&app, &username).await;
+    }
+
+    // Act - The right password is rejected as well
+    let response = app.login().await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    assert!(app.get_login_html().await.contains(LOCKED_ERROR));
+    assert_is_redirect_to(&app.get_admin_dashboard().await, "/login");
+    assert_eq!(
+        lockout_events(&app, "login_locked").await,
+        vec![format!("username:{username}")]
+    );
+}
+
+#[tokio::test]
+async fn lockout_is_lifted_after_its_duration() {
+    // Arrange
+    let app = spawn_app(3, 100).await;
+    let username = app.test_user.username.clone();
+    for _ in 0..3 {
+        fail_login(&app, &username).await;
+    }
+
+    // Act
+    app.clock.advance(Duration::minutes(11));
+    let response = app.login().await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/dashboard");
+    assert_eq!(
+        lockout_events(&app, "login_unlocked").await,
+        vec![format!("username:{username}")]
+    );
+}
+
+#[tokio::test]
+async fn client_ip_is_locked_after_failures_on_many_usernames() {
+    // Arrange
+    let app = spawn_app(100, 4).await;
+    for i in 0..4 {
+        fail_login(&app, &format!("guess-{i}")).await;
+    }
+
+    // Act
+    let response = app.login().await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    assert!(app.get_login_html().await.contains(LOCKED_ERROR));
+    assert_eq!(
+        lockout_events(&app, "login_locked").await,
+        vec!["ip:127.0.0.1".to_string()]
+    );
+}
+
+#[tokio::test]
+async fn successful_login_resets_the_username_failures() {
+    // Arrange
+    let app = spawn_app(3, 100).await;
+    let username = app.test_user.username.clone();
+    for _ in 0..2 {
+        fail_login(&app, &username).await;
+    }
+    app.login().await;
+    app.post_logout().await;
+
+    // Act
+    for _ in 0..2 {
+        fail_login(&app, &username).await;
+    }
+    let response = app.login().await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/dashboard");
+}
+
+#[tokio::test]
+async fn failures_outside_the_window_are_not_counted() {
+    // Arrange
+    let app = spawn_app(3, 100).await;
+    let username = app.test_user.username.clone();
+    for _ in 0..2 {
+        fail_login(&app, &username).await;
+    }
+
+    // Act
+    app.clock.advance(Duration::minutes(16));
+    fail_login(&app, &username).await;
+    let response = app.login().await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/dashboard");
+}
+
+#[tokio::test]
+async fn basic_auth_is_locked_with_retry_after() {
+    // Arrange
+    let app = spawn_app(3, 100).await;
+    let wrong_user = TestUser {
+        password: "wrong-password".into(),
+        username: app.test_user.username.clone(),
+        email: app.test_user.email.clone(),
+        ..TestUser::with_role(app.test_user.role)
+    };
+    let body = newsletter_request_body();
+    for _ in 0..3 {
+        let response = app.post_newsletters_as(&wrong_user, body.clone()).await;
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    // Act
+    let response = app.post_newsletters(body).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 429);
+    let retry_after: u64 = response.headers()["Retry-After"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(retry_after > 0 && retry_after <= 10 * 60 + 1);
+}
+
+#[tokio::test]
+async fn password_form_and_basic_auth_share_the_failure_count() {
+    // Arrange
+    let app = spawn_app(3, 100).await;
+    let username = app.test_user.username.clone();
+    for _ in 0..3 {
+        fail_login(&app, &username).await;
+    }
+
+    // Act
+    let response = app
+        .post_newsletters(newsletter_request_body())
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 429);
+}
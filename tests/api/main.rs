@@ -1,5 +1,28 @@
+mod admin_cli;
+mod admin_dashboard;
+mod admin_newsletters;
+mod admin_roles;
+mod admin_subscribers;
+mod admin_users;
+mod api_tokens;
+mod audit_log;
+mod change_password;
+mod csrf;
+mod graceful_shutdown;
+mod health;
 mod health_check;
 mod helpers;
+mod login;
+mod login_lockout;
+mod metrics;
 mod newsletter;
+mod password_reset;
+mod rate_limit;
+mod request_id;
+mod security_headers;
 mod subscriptions;
 mod subscriptions_confirm;
+mod subscriptions_resend;
+mod subscriptions_unsubscribe;
+mod telemetry;
+mod two_factor;
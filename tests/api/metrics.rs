@@ -0,0 +1,181 @@
+//! tests/api/metrics.rs
+use secrecy::Secret;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+use zero2prod_axum::configuration::Settings;
+
+use crate::helpers::{
+    create_confirmed_subscriber, newsletter_request_body, TestApp,
+};
+
+async fn get_metrics(app: &TestApp, token: Option<&str>) -> reqwest::Response {
+    let mut request =
+        reqwest::Client::new().get(format!("{}/metrics", &app.address));
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    request.send().await.expect("Failed to execute request.")
+}
+
+async fn get_metrics_text(app: &TestApp) -> String {
+    let response = get_metrics(app, None).await;
+    assert_eq!(response.status().as_u16(), 200);
+    response.text().await.unwrap()
+}
+
+fn has_line(metrics: &str, expected: &str) -> bool {
+    metrics.lines().any(|line| line == expected)
+}
+
+#[tokio::test]
+async fn metrics_are_served_in_prometheus_text_format() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.api_client
+        .get(format!("{}/health_check", &app.address))
+        .send()
+        .await
+        .unwrap();
+
+    // Act
+    let response = get_metrics(&app, None).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.headers()["content-type"],
+        "text/plain; version=0.0.4; charset=utf-8"
+    );
+    let metrics = response.text().await.unwrap();
+    for expected in [
+        r#"http_requests_total{method="GET",route="/health_check",status="200"} 1"#,
+        r#"http_request_duration_seconds_count{method="GET",route="/health_check",status="200"} 1"#,
+        "# TYPE db_pool_size gauge",
+        "# TYPE db_pool_available gauge",
+        "# TYPE db_pool_waiting gauge",
+        "newsletter_deliveries_pending 0",
+    ] {
+        assert!(has_line(&metrics, expected), "{expected}\n{metrics}");
+    }
+}
+
+#[tokio::test]
+async fn requests_are_labelled_by_route_not_by_path() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let subscriber_id = uuid::Uuid::new_v4();
+
+    // Act
+    app.api_client
+        .post(format!(
+            "{}/admin/subscribers/{}/delete",
+            &app.address, subscriber_id
+        ))
+        .send()
+        .await
+        .unwrap();
+    app.api_client
+        .get(format!("{}/no/such/{}", &app.address, subscriber_id))
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    let metrics = get_metrics_text(&app).await;
+    assert!(
+        metrics.contains(r#"route="/admin/subscribers/:subscriber_id/delete""#)
+    );
+    assert!(has_line(
+        &metrics,
+        r#"http_requests_total{method="GET",route="unmatched",status="404"} 1"#
+    ));
+    assert!(!metrics.contains(&subscriber_id.to_string()));
+}
+
+#[tokio::test]
+async fn email_sends_and_newsletter_deliveries_are_counted() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_newsletters(newsletter_request_body())
+        .await;
+    assert_eq!(response.status().as_u16(), 202);
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let metrics = get_metrics_text(&app).await;
+    for expected in [
+        // The confirmation email and the newsletter issue.
+        r#"email_send_attempts_total{service="postmark"} 2"#,
+        r#"email_send_failures_total{service="postmark"} 0"#,
+        r#"email_send_duration_seconds_count{service="postmark"} 2"#,
+        r#"newsletter_deliveries_total{outcome="sent"} 1"#,
+        "newsletter_deliveries_pending 0",
+    ] {
+        assert!(has_line(&metrics, expected), "{expected}\n{metrics}");
+    }
+}
+
+#[tokio::test]
+async fn failed_email_attempts_are_counted() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(400))
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    app.post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com")
+        .await;
+
+    // Assert
+    let metrics = get_metrics_text(&app).await;
+    assert!(has_line(
+        &metrics,
+        r#"email_send_failures_total{service="postmark"} 1"#
+    ));
+}
+
+#[tokio::test]
+async fn clients_outside_allowed_ips_need_the_bearer_token() {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.metrics.allowed_ips = Vec::new();
+    config.metrics.bearer_token = Some(Secret::new("scrape-me".to_string()));
+    let app = TestApp::spawn_app(config).await;
+
+    // Act
+    let without_token = get_metrics(&app, None).await;
+    let wrong_token = get_metrics(&app, Some("scrape-you")).await;
+    let with_token = get_metrics(&app, Some("scrape-me")).await;
+
+    // Assert
+    assert_eq!(without_token.status().as_u16(), 403);
+    assert_eq!(wrong_token.status().as_u16(), 403);
+    assert_eq!(with_token.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn metrics_are_not_served_when_disabled() {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.metrics.enabled = false;
+    let app = TestApp::spawn_app(config).await;
+
+    // Act
+    let response = get_metrics(&app, None).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
@@ -1,10 +1,15 @@
 //! tests/api/newsletter.rs
 
-use crate::helpers::{ConfirmationLink, TestApp};
+use crate::helpers::{
+    create_confirmed_subscriber, create_unconfirmed_subscriber,
+    newsletter_request_body, TestApp,
+};
 use uuid::Uuid;
 use wiremock::matchers::{any, method, path};
 use wiremock::{Mock, ResponseTemplate};
 use zero2prod_axum::configuration::Settings;
+use zero2prod_axum::email_client::RetryPolicy;
+use zero2prod_axum::idempotency::delete_expired_idempotency_keys;
 
 #[tokio::test]
 async fn newsletters_are_not_delivered_to_unconfirmed_subscribers() {
@@ -23,17 +28,11 @@ async fn newsletters_are_not_delivered_to_unconfirmed_subscribers() {
 
     // A sketch of the newsletter payload structure.
     // We might change it later on.
-    let newsletter_request_body = serde_json::json!({
-        "title": "Newsletter title",
-        "content": {
-            "text": "Newsletter body as a plain text",
-            "html": "<p>Newsletter body as HTML</p>",
-        }
-    });
-    let response = app.post_newsletters(newsletter_request_body).await;
+    let response = app.post_newsletters(newsletter_request_body()).await;
 
     // Assert
-    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.status().as_u16(), 202);
+    app.dispatch_all_pending_emails().await;
     // Mock verifies on Drop that we haven't sent the newsletter email
 }
 
@@ -51,20 +50,82 @@ async fn newsletter_are_delivered_to_confirmed_subscribers() {
         .await;
 
     // Act
-    let newsletter_request_body = serde_json::json!({
-        "title": "Newsletter title",
-        "content": {
-            "text": "Newsletter body as a plain text",
-            "html": "<p>Newsletter body as HTML</p>",
-        }
-    });
-    let response = app.post_newsletters(newsletter_request_body).await;
+    let response = app.post_newsletters(newsletter_request_body()).await;
 
     // Assert
-    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.status().as_u16(), 202);
+    app.dispatch_all_pending_emails().await;
     // Mock verifies on Drop that we have sent the newsletter email
 }
 
+#[tokio::test]
+async fn failed_deliveries_are_kept_in_the_queue_for_a_retry() {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.email_client.retry_policy = RetryPolicy::no_retries();
+    let app = TestApp::spawn_app(config).await;
+    create_confirmed_subscriber(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_newsletters(newsletter_request_body()).await;
+    assert_eq!(response.status().as_u16(), 202);
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let task = app
+        .pool
+        .get()
+        .await
+        .unwrap()
+        .query_one(
+            "SELECT n_retries, execute_after > now() AS postponed
+            FROM issue_delivery_queue",
+            &[],
+        )
+        .await
+        .expect("Failed to fetch the delivery task.");
+    assert_eq!(task.get::<&str, i32>("n_retries"), 1);
+    assert!(task.get::<&str, bool>("postponed"));
+}
+
+#[tokio::test]
+async fn rejected_deliveries_are_removed_from_the_queue() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(422))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_newsletters(newsletter_request_body()).await;
+    assert_eq!(response.status().as_u16(), 202);
+    app.dispatch_all_pending_emails().await;
+
+    // Assert
+    let remaining_tasks: i64 = app
+        .pool
+        .get()
+        .await
+        .unwrap()
+        .query_one("SELECT COUNT(*) FROM issue_delivery_queue", &[])
+        .await
+        .expect("Failed to count delivery tasks.")
+        .get(0);
+    assert_eq!(remaining_tasks, 0);
+}
+
 #[tokio::test]
 async fn newsletters_returns_422_for_invalid_data() {
     // Arrange
@@ -102,14 +163,8 @@ async fn requests_missing_authorization_are_rejected() {
     let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
 
     let response = reqwest::Client::new()
-        .post(&format!("{}/newsletters", &app.address))
-        .json(&serde_json::json!({
-            "title": "Newsletter title",
-            "content": {
-                "text": "Newsletter body as a plain text",
-                "html": "<p>Newsletter body as HTML</p>",
-            }
-        })).send().await.expect("Failed to execute request.");
+        .post(format!("{}/newsletters", &app.address))
+        .json(&newsletter_request_body()).send().await.expect("Failed to execute request.");
 
     // Assert
     assert_eq!(401, response.status().as_u16());
@@ -127,15 +182,7 @@ async fn non_existing_user_is_rejected() {
     let response = reqwest::Client::new()
         .post(format!("{}/newsletters", &app.address))
         .basic_auth(username, Some(password))
-        .json(
-            &serde_json::json!({
-                "title": "Newsletter title",
-                "content": {
-                    "text": "Newsletter body as a plain text",
-                    "html": "<p>Newsletter body as HTML</p>",
-                }
-            })
-        )
+        .json(&newsletter_request_body())
         .send()
         .await
         .expect("Failed to execute request.");
@@ -157,15 +204,7 @@ async fn invalid_password_is_rejected() {
     let response = reqwest::Client::new()
         .post(format!("{}/newsletters", &app.address))
         .basic_auth(username, Some(password))
-        .json(
-            &serde_json::json!({
-                "title": "Newsletter title",
-                "content": {
-                    "text": "Newsletter body as a plain text",
-                    "html": "<p>Newsletter body as HTML</p>",
-                }
-            })
-        )
+        .json(&newsletter_request_body())
         .send()
         .await
         .expect("Failed to execute request.");
@@ -175,42 +214,186 @@ async fn invalid_password_is_rejected() {
 
 }
 
-// ───── Helpers ──────────────────────────────────────────────────────────── //
+#[tokio::test]
+async fn newsletter_creation_is_idempotent() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
 
-async fn create_unconfirmed_subscriber(app: &TestApp) -> ConfirmationLink {
-    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    let body = newsletter_request_body();
+    let idempotency_key = Uuid::new_v4().to_string();
+
+    // Act - Part 1 - Submit newsletter
+    let response = app
+        .post_newsletters_with_idempotency_key(
+            &body,
+            &idempotency_key,
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 202);
 
-    let _mock_guard = Mock::given(path("/email"))
+    // Act - Part 2 - Submit newsletter again
+    let response = app
+        .post_newsletters_with_idempotency_key(
+            &body,
+            &idempotency_key,
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 202);
+
+    app.dispatch_all_pending_emails().await;
+    // Mock verifies on Drop that we have sent the newsletter email once
+}
+
+#[tokio::test]
+async fn concurrent_form_submission_is_handled_gracefully() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+
+    Mock::given(path("/email"))
         .and(method("POST"))
         .respond_with(ResponseTemplate::new(200))
-        .named("Create unconfirmed subscriber")
         .expect(1)
-        .mount_as_scoped(&app.email_server)
+        .mount(&app.email_server)
+        .await;
+
+    // Act - Submit two newsletter forms concurrently
+    let body = newsletter_request_body();
+    let idempotency_key = Uuid::new_v4().to_string();
+    let response1 = app.post_newsletters_with_idempotency_key(
+        &body,
+        &idempotency_key,
+    );
+    let response2 = app.post_newsletters_with_idempotency_key(
+        &body,
+        &idempotency_key,
+    );
+    let (response1, response2) = tokio::join!(response1, response2);
+
+    // Assert
+    assert_eq!(response1.status(), response2.status());
+    assert_eq!(
+        response1.text().await.unwrap(),
+        response2.text().await.unwrap()
+    );
+
+    app.dispatch_all_pending_emails().await;
+    // Mock verifies on Drop that we have sent the newsletter email once
+}
+
+#[tokio::test]
+async fn invalid_idempotency_key_is_rejected() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let body = newsletter_request_body();
+
+    // Act
+    let response = app
+        .post_newsletters_with_idempotency_key(
+            &body,
+            &"a".repeat(51),
+        )
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn expired_idempotency_keys_are_deleted() {
+    // Arrange
+    let config = Settings::load_configuration().unwrap();
+    let settings = config.idempotency.clone();
+    let app = TestApp::spawn_app(config).await;
+    let body = newsletter_request_body();
+    let idempotency_key = Uuid::new_v4().to_string();
+    let response = app
+        .post_newsletters_with_idempotency_key(
+            &body,
+            &idempotency_key,
+        )
         .await;
+    assert_eq!(response.status().as_u16(), 202);
 
-    app.post_subscriptions(body)
+    // Act - Part 1 - Key is fresh
+    let deleted = delete_expired_idempotency_keys(&app.pool, &settings)
         .await
-        .error_for_status()
         .unwrap();
+    assert_eq!(deleted, 0);
 
-    // We now inspect the requests received by the mock email
-    // delivery service to retrieve the confirmation link and
-    // return it
-    let email_request = &app
-        .email_server
-        .received_requests()
+    // Act - Part 2 - Key was saved long ago
+    app.pool
+        .get()
         .await
         .unwrap()
-        .pop()
+        .execute(
+            "UPDATE idempotency SET created_at = now() - interval '1 year'",
+            &[],
+        )
+        .await
         .unwrap();
-    app.get_confirmation_link(&email_request)
+    let deleted = delete_expired_idempotency_keys(&app.pool, &settings)
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(deleted, 1);
 }
 
-async fn create_confirmed_subscriber(app: &TestApp) {
-    let confirmation_link = create_unconfirmed_subscriber(app).await;
-    reqwest::get(confirmation_link.0)
+#[tokio::test]
+async fn queued_issues_are_not_delivered_to_subscribers_who_left() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // The issue was queued for them before they unsubscribed
+    let client = app.pool.get().await.unwrap();
+    let newsletter_issue_id = Uuid::new_v4();
+    client
+        .execute(
+            "INSERT INTO newsletter_issues
+                VALUES ($1, 'Title', 'Text', '<p>Html</p>', now())",
+            &[&newsletter_issue_id],
+        )
+        .await
+        .unwrap();
+    client
+        .execute(
+            "INSERT INTO issue_delivery_queue
+                (newsletter_issue_id, subscriber_email)
+                SELECT $1, email FROM subscriptions",
+            &[&newsletter_issue_id],
+        )
         .await
-        .unwrap()
-        .error_for_status()
         .unwrap();
+    client
+        .execute("UPDATE subscriptions SET status = 'unsubscribed'", &[])
+        .await
+        .unwrap();
+
+    // Act
+    app.dispatch_all_pending_emails().await;
+
+    // Assert - The task is dropped, the mock verifies on Drop that no
+    // email was sent
+    let pending: i64 = client
+        .query_one("SELECT count(*) FROM issue_delivery_queue", &[])
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(pending, 0);
 }
@@ -0,0 +1,211 @@
+//! tests/api/password_reset.rs
+use uuid::Uuid;
+use wiremock::matchers::{any, method, path};
+use wiremock::{Mock, ResponseTemplate};
+use zero2prod_axum::configuration::Settings;
+
+use crate::helpers::{assert_is_redirect_to, TestApp};
+
+/// Request a reset link for the test user and return it.
+async fn request_reset_link(app: &TestApp) -> reqwest::Url {
+    let _mock_guard = Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount_as_scoped(&app.email_server)
+        .await;
+    let response = app.post_forgot_password(&app.test_user.email).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let email_request = app.wait_for_email_requests(1).await.pop().unwrap();
+    app.get_confirmation_link(&email_request).0
+}
+
+fn token_of(link: &reqwest::Url) -> String {
+    link.query_pairs()
+        .find(|(name, _)| name == "token")
+        .map(|(_, value)| value.to_string())
+        .unwrap()
+}
+
+fn reset_form(link: &reqwest::Url, password: &str) -> serde_json::Value {
+    serde_json::json!({
+        "token": token_of(link),
+        "new_password": password,
+        "new_password_check": password,
+    })
+}
+
+#[tokio::test]
+async fn unknown_email_gets_the_same_answer_and_no_email() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_forgot_password("nobody@example.com").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("If an account with this email exists"));
+}
+
+#[tokio::test]
+async fn known_email_is_answered_before_the_email_is_sent() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let email_api_delay = std::time::Duration::from_secs(3);
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_delay(email_api_delay))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let started = std::time::Instant::now();
+    let response = app.post_forgot_password(&app.test_user.email).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(started.elapsed() < email_api_delay);
+    app.wait_for_email_requests(1).await;
+}
+
+#[tokio::test]
+async fn reset_link_shows_a_form() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let link = request_reset_link(&app).await;
+
+    // Act
+    let response = reqwest::get(link.clone()).await.unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response.text().await.unwrap().contains(&token_of(&link)));
+}
+
+#[tokio::test]
+async fn password_can_be_reset_with_the_emailed_link() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let link = request_reset_link(&app).await;
+    let new_password = Uuid::new_v4().to_string();
+
+    // Act
+    let response = app
+        .post_reset_password(&reset_form(&link, &new_password))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("Your password has been reset."));
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &new_password,
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/dashboard");
+}
+
+#[tokio::test]
+async fn reset_link_can_be_used_only_once() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let link = request_reset_link(&app).await;
+    let form = reset_form(&link, &Uuid::new_v4().to_string());
+    app.post_reset_password(&form)
+        .await
+        .error_for_status()
+        .unwrap();
+
+    // Act
+    let response = app.post_reset_password(&form).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+    let response = reqwest::get(link).await.unwrap();
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn expired_reset_links_are_rejected_with_a_410() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let link = request_reset_link(&app).await;
+    app.pool
+        .get()
+        .await
+        .unwrap()
+        .execute(
+            "UPDATE password_reset_tokens
+            SET expires_at = expires_at - interval '1 day'",
+            &[],
+        )
+        .await
+        .unwrap();
+
+    // Act
+    let form_response = reqwest::get(link.clone()).await.unwrap();
+    let reset_response = app
+        .post_reset_password(&reset_form(&link, &Uuid::new_v4().to_string()))
+        .await;
+
+    // Assert
+    assert_eq!(form_response.status().as_u16(), 410);
+    assert_eq!(reset_response.status().as_u16(), 410);
+    // The old password still works.
+    assert_is_redirect_to(&app.login().await, "/admin/dashboard");
+}
+
+#[tokio::test]
+async fn new_password_must_satisfy_the_policy() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let link = request_reset_link(&app).await;
+
+    // Act
+    let response = app.post_reset_password(&reset_form(&link, "short")).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("at least 12 characters"));
+    // The link is not burnt by a rejected attempt.
+    let response = reqwest::get(link).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn password_reset_ends_existing_sessions() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    assert_is_redirect_to(&app.login().await, "/admin/dashboard");
+    let link = request_reset_link(&app).await;
+
+    // Act
+    app.post_reset_password(&reset_form(&link, &Uuid::new_v4().to_string()))
+        .await
+        .error_for_status()
+        .unwrap();
+
+    // Assert
+    let response = app.get_admin_dashboard().await;
+    assert_is_redirect_to(&response, "/login");
+}
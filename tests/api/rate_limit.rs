@@ -0,0 +1,274 @@
+//! tests/api/rate_limit.rs
+use time::Duration;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+use zero2prod_axum::configuration::{
+    BucketSettings, RateLimitBackend, Settings,
+};
+use zero2prod_axum::rate_limit::RateLimiter;
+
+use crate::helpers::TestApp;
+
+const LIMIT: BucketSettings = BucketSettings {
+    capacity: 2,
+    refill_interval: 600,
+};
+const NO_LIMIT: BucketSettings = BucketSettings {
+    capacity: 1000,
+    refill_interval: 1,
+};
+
+/// `post_subscriptions` only takes static bodies.
+fn subscribe_body(email: &str) -> &'static str {
+    format!("name=le%20guin&email={}", urlencoding::encode(email)).leak()
+}
+
+async fn mount_email_server(app: &TestApp, expected_emails: u64) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(expected_emails)
+        .mount(&app.email_server)
+        .await;
+}
+
+fn retry_after(response: &reqwest::Response) -> u64 {
+    response.headers()["retry-after"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn subscriptions_are_limited_per_email() {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.rate_limit.subscriptions.per_ip = NO_LIMIT;
+    config.rate_limit.subscriptions.per_email = LIMIT;
+    let app = TestApp::spawn_app(config).await;
+    mount_email_server(&app, 3).await;
+
+    // Act
+    for _ in 0..2 {
+        let response = app
+            .post_subscriptions(subscribe_body("ursula@gmail.com"))
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+    // The case of the address doesn't make a new bucket
+    let response = app
+        .post_subscriptions(subscribe_body("Ursula@gmail.com"))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 429);
+    assert_eq!(retry_after(&response), 600);
+    // Other addresses are not affected
+    let response = app.post_subscriptions(subscribe_body("le@gmail.com")).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn subscriptions_are_limited_per_client_ip() {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.rate_limit.subscriptions.per_ip = LIMIT;
+    config.rate_limit.subscriptions.per_email = NO_LIMIT;
+    let app = TestApp::spawn_app(config).await;
+    mount_email_server(&app, 2).await;
+
+    // Act
+    for email in ["a@gmail.com", "b@gmail.com"] {
+        let response = app.post_subscriptions(subscribe_body(email)).await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+    let response = app.post_subscriptions(subscribe_body("c@gmail.com")).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 429);
+    assert!(response.headers().contains_key("retry-after"));
+}
+
+#[tokio::test]
+async fn limit_is_lifted_as_tokens_refill() {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.rate_limit.subscriptions.per_ip = LIMIT;
+    let app = TestApp::spawn_app(config).await;
+    mount_email_server(&app, 3).await;
+    for email in ["a@gmail.com", "b@gmail.com"] {
+        app.post_subscriptions(subscribe_body(email)).await;
+    }
+    let response = app.post_subscriptions(subscribe_body("c@gmail.com")).await;
+    assert_eq!(response.status().as_u16(), 429);
+
+    // Act
+    app.clock.advance(Duration::seconds(300));
+    let too_early = app.post_subscriptions(subscribe_body("c@gmail.com")).await;
+    app.clock.advance(Duration::seconds(300));
+    let response = app.post_subscriptions(subscribe_body("c@gmail.com")).await;
+
+    // Assert
+    assert_eq!(too_early.status().as_u16(), 429);
+    assert_eq!(retry_after(&too_early), 300);
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+/// The third request for the same email is refused by its bucket, which
+/// must leave the token of the client IP for the next address.
+async fn assert_refused_request_keeps_the_ip_token(backend: RateLimitBackend) {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.rate_limit.backend = backend;
+    config.rate_limit.subscriptions.per_ip = BucketSettings {
+        capacity: 3,
+        ..LIMIT
+    };
+    config.rate_limit.subscriptions.per_email = LIMIT;
+    let app = TestApp::spawn_app(config).await;
+    mount_email_server(&app, 3).await;
+    for _ in 0..2 {
+        app.post_subscriptions(subscribe_body("ursula@gmail.com"))
+            .await;
+    }
+    let response = app
+        .post_subscriptions(subscribe_body("ursula@gmail.com"))
+        .await;
+    assert_eq!(response.status().as_u16(), 429);
+
+    // Act
+    let response = app.post_subscriptions(subscribe_body("le@gmail.com")).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn refused_request_keeps_the_ip_token_in_memory() {
+    assert_refused_request_keeps_the_ip_token(RateLimitBackend::Memory).await;
+}
+
+#[tokio::test]
+async fn refused_request_keeps_the_ip_token_in_postgres() {
+    assert_refused_request_keeps_the_ip_token(RateLimitBackend::Postgres).await;
+}
+
+#[tokio::test]
+async fn resend_confirmation_is_limited_per_email() {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.subscriptions.resend_cooldown = 0;
+    config.rate_limit.subscriptions_resend.per_email = LIMIT;
+    let app = TestApp::spawn_app(config).await;
+    // One email for the subscription, two resent ones.
+    mount_email_server(&app, 3).await;
+    app.post_subscriptions(subscribe_body("ursula@gmail.com"))
+        .await;
+
+    // Act
+    for _ in 0..2 {
+        let response = app
+            .post_resend_confirmation("email=ursula%40gmail.com")
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+    let response = app
+        .post_resend_confirmation("email=ursula%40gmail.com")
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 429);
+    assert_eq!(retry_after(&response), 600);
+}
+
+#[tokio::test]
+async fn forgot_password_is_limited_per_email() {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.rate_limit.password_forgot.per_email = LIMIT;
+    let app = TestApp::spawn_app(config).await;
+    mount_email_server(&app, 2).await;
+
+    // Act
+    for _ in 0..2 {
+        let response = app.post_forgot_password(&app.test_user.email).await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+    let response = app.post_forgot_password(&app.test_user.email).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 429);
+    assert_eq!(retry_after(&response), 600);
+    app.wait_for_email_requests(2).await;
+}
+
+#[tokio::test]
+async fn postgres_backend_shares_buckets_through_the_database() {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.rate_limit.backend = RateLimitBackend::Postgres;
+    config.rate_limit.subscriptions.per_ip = NO_LIMIT;
+    config.rate_limit.subscriptions.per_email = LIMIT;
+    let app = TestApp::spawn_app(config).await;
+    mount_email_server(&app, 2).await;
+
+    // Act
+    for _ in 0..2 {
+        let response = app
+            .post_subscriptions(subscribe_body("ursula@gmail.com"))
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+    let response = app
+        .post_subscriptions(subscribe_body("ursula@gmail.com"))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 429);
+    assert_eq!(retry_after(&response), 600);
+    let tokens: f64 = app
+        .pool
+        .get()
+        .await
+        .unwrap()
+        .query_one(
+            "SELECT tokens FROM rate_limit_buckets WHERE key = $1",
+            &[&"subscriptions:email:ursula@gmail.com"],
+        )
+        .await
+        .unwrap()
+        .get(0);
+    // Timestamps lose their nanoseconds in Postgres, hence not exactly 0.
+    assert!(tokens < 0.01);
+}
+
+#[tokio::test]
+async fn refilled_postgres_buckets_are_deleted() {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.rate_limit.backend = RateLimitBackend::Postgres;
+    config.rate_limit.subscriptions.per_email = LIMIT;
+    let settings = config.rate_limit.clone();
+    let app = TestApp::spawn_app(config).await;
+    let rate_limiter = RateLimiter::new(&settings, &app.pool);
+    mount_email_server(&app, 1).await;
+    app.post_subscriptions(subscribe_body("ursula@gmail.com"))
+        .await;
+
+    // Act - Part 1 - The buckets are still refilling
+    let deleted = rate_limiter
+        .delete_full_buckets(app.clock.now())
+        .await
+        .unwrap();
+    assert_eq!(deleted, 0);
+
+    // Act - Part 2 - Both are full again
+    let deleted = rate_limiter
+        .delete_full_buckets(app.clock.now() + Duration::seconds(600))
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(deleted, 2);
+}
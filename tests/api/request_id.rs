@@ -0,0 +1,180 @@
+//! tests/api/request_id.rs
+use uuid::Uuid;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, ResponseTemplate};
+use zero2prod_axum::configuration::{LogFormat, Settings, TelemetrySettings};
+
+use crate::helpers::{capture_logs, TestApp};
+
+const SUBSCRIPTION: &str = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+
+async fn get_health_check(
+    app: &TestApp,
+    request_id: Option<&str>,
+) -> reqwest::Response {
+    let mut request =
+        reqwest::Client::new().get(format!("{}/health_check", &app.address));
+    if let Some(request_id) = request_id {
+        request = request.header("X-Request-Id", request_id);
+    }
+    request.send().await.expect("Failed to execute request.")
+}
+
+async fn post_subscriptions_with_request_id(
+    app: &TestApp,
+    request_id: &str,
+) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("{}/subscriptions", &app.address))
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .header("X-Request-Id", request_id)
+        .body(SUBSCRIPTION)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+fn response_request_id(response: &reqwest::Response) -> String {
+    response.headers()["x-request-id"]
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+#[tokio::test]
+async fn every_response_gets_a_new_request_id() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let first = get_health_check(&app, None).await;
+    let second = get_health_check(&app, None).await;
+    let not_found = app
+        .api_client
+        .get(format!("{}/no/such/page", &app.address))
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    let first = response_request_id(&first);
+    let second = response_request_id(&second);
+    assert!(Uuid::parse_str(&first).is_ok());
+    assert_ne!(first, second);
+    assert_eq!(not_found.status().as_u16(), 404);
+    assert!(not_found.headers().contains_key("x-request-id"));
+}
+
+#[tokio::test]
+async fn request_id_of_the_client_is_echoed() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let response = get_health_check(&app, Some("lb-7f3a.42")).await;
+
+    // Assert
+    assert_eq!(response_request_id(&response), "lb-7f3a.42");
+}
+
+#[tokio::test]
+async fn invalid_request_id_of_the_client_is_replaced() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let too_long = "a".repeat(200);
+
+    for invalid in ["with space", "quote\"d", too_long.as_str()] {
+        // Act
+        let response = get_health_check(&app, Some(invalid)).await;
+
+        // Assert
+        let request_id = response_request_id(&response);
+        assert_ne!(request_id, invalid);
+        assert!(Uuid::parse_str(&request_id).is_ok());
+    }
+}
+
+#[tokio::test]
+async fn request_id_is_forwarded_to_the_email_delivery_service() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let request_id = Uuid::new_v4().to_string();
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .and(header("X-Request-Id", request_id.as_str()))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = post_subscriptions_with_request_id(&app, &request_id).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response_request_id(&response), request_id);
+    // Mock asserts on drop
+}
+
+#[tokio::test]
+async fn subscriber_id_is_not_the_request_id() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let request_id = Uuid::new_v4();
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response =
+        post_subscriptions_with_request_id(&app, &request_id.to_string()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let subscriber_id: Uuid = app
+        .pool
+        .get()
+        .await
+        .unwrap()
+        .query_one("SELECT id FROM subscriptions", &[])
+        .await
+        .unwrap()
+        .get("id");
+    assert_ne!(subscriber_id, request_id);
+}
+
+#[tokio::test]
+async fn logs_of_a_request_carry_its_id() {
+    // Arrange
+    let settings = TelemetrySettings {
+        log_format: LogFormat::Json,
+        ..Default::default()
+    };
+    let (logs, _guard) = capture_logs(&settings);
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let response = app
+        .post_subscriptions("name=&email=ursula_le_guin%40gmail.com")
+        .await;
+
+    // Assert
+    let request_id = response_request_id(&response);
+    let logs = logs.contents();
+    let event: serde_json::Value = logs
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .find(|e: &serde_json::Value| {
+            e["message"]
+                .as_str()
+                .is_some_and(|m| m.starts_with("Bad request"))
+        })
+        .expect("No log of the rejected subscription");
+    let root = &event["spans"][0];
+    assert_eq!(root["name"], "HTTP request");
+    assert_eq!(root["request_id"], request_id.as_str());
+    assert_eq!(root["method"], "POST");
+    assert_eq!(root["path"], "/subscriptions");
+}
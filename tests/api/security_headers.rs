@@ -0,0 +1,131 @@
+//! tests/api/security_headers.rs
+use zero2prod_axum::configuration::Settings;
+
+use crate::helpers::TestApp;
+
+const HEADERS: [&str; 5] = [
+    "content-security-policy",
+    "x-content-type-options",
+    "referrer-policy",
+    "x-frame-options",
+    "permissions-policy",
+];
+
+fn assert_has_security_headers(response: &reqwest::Response) {
+    for name in HEADERS {
+        assert!(
+            response.headers().contains_key(name),
+            "{} is missing on {}",
+            name,
+            response.url()
+        );
+    }
+    assert_eq!(response.headers()["x-content-type-options"], "nosniff");
+    assert_eq!(response.headers()["x-frame-options"], "DENY");
+}
+
+#[tokio::test]
+async fn html_pages_have_security_headers() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act - Part 1 - Public pages
+    for path in ["/login", "/password/forgot"] {
+        let response = app
+            .api_client
+            .get(format!("{}{}", app.address, path))
+            .send()
+            .await
+            .unwrap();
+        assert_has_security_headers(&response);
+        assert!(!response.headers().contains_key("strict-transport-security"));
+    }
+
+    // Act - Part 2 - Admin pages
+    app.login().await;
+    assert_has_security_headers(&app.get_admin_dashboard().await);
+    assert_has_security_headers(&app.get_admin_users().await);
+    assert_has_security_headers(&app.get_publish_newsletter().await);
+}
+
+#[tokio::test]
+async fn redirects_and_errors_have_security_headers() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let redirect = app.get_admin_dashboard().await;
+    let not_found = app
+        .api_client
+        .get(format!("{}/no-such-page", app.address))
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(redirect.status().as_u16(), 303);
+    assert_has_security_headers(&redirect);
+    assert_eq!(not_found.status().as_u16(), 404);
+    assert_has_security_headers(&not_found);
+}
+
+#[tokio::test]
+async fn newsletter_preview_relaxes_content_security_policy() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+    let default_policy = app.get_publish_newsletter().await.headers()
+        ["content-security-policy"]
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    // Act
+    let response = app
+        .post_preview_newsletter(&serde_json::json!({
+            "title": "Newsletter title",
+            "text": "Newsletter body as plain text",
+            "html": "<p style=\"color: red\">Newsletter body as HTML</p>",
+            "idempotency_key": uuid::Uuid::new_v4().to_string(),
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let policy = response.headers()["content-security-policy"]
+        .to_str()
+        .unwrap();
+    assert_ne!(policy, default_policy);
+    assert!(policy.contains("style-src 'self' 'unsafe-inline'"));
+    assert!(policy.contains("frame-ancestors 'none'"));
+    assert_has_security_headers(&response);
+}
+
+#[tokio::test]
+async fn headers_follow_the_configuration() {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.security_headers.hsts_enabled = true;
+    config.security_headers.hsts_max_age = 600;
+    config.security_headers.content_security_policy =
+        "default-src 'none'".to_string();
+    let app = TestApp::spawn_app(config).await;
+
+    // Act
+    let response = app
+        .api_client
+        .get(format!("{}/login", app.address))
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(
+        response.headers()["strict-transport-security"],
+        "max-age=600; includeSubDomains"
+    );
+    assert_eq!(
+        response.headers()["content-security-policy"],
+        "default-src 'none'"
+    );
+}
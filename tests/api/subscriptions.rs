@@ -1,6 +1,7 @@
-use wiremock::matchers::{method, path};
+use wiremock::matchers::{any, method, path};
 use wiremock::{Mock, ResponseTemplate};
 use zero2prod_axum::configuration::Settings;
+use zero2prod_axum::email_client::EmailDeliveryService;
 
 use crate::helpers::TestApp;
 
@@ -128,7 +129,7 @@ async fn req_subscribe_sends_a_confirmation_email_with_a_link() {
         .await;
 
     // Send request to OUR app, 'POST, create a new subscription`
-    let _response = app.post_subscriptions(body.into()).await;
+    let _response = app.post_subscriptions(body).await;
     // println!("\nReponse: {}", response.text().await.unwrap().as_str());
 
     // Assert
@@ -174,3 +175,106 @@ async fn req_subscribe_fails_if_there_is_a_fatal_database_error() {
 
     assert_eq!(response.status().as_u16(), 500);
 }
+
+#[tokio::test]
+async fn subscribe_returns_a_503_if_email_service_is_temporarily_unavailable() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(3)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_subscriptions(body).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 503);
+}
+
+#[tokio::test]
+async fn subscribe_can_be_retried_after_the_email_service_was_unavailable() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(3)
+        .expect(3)
+        .mount(&app.email_server)
+        .await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act - Part 1 - Email service unavailable
+    let response = app.post_subscriptions(body).await;
+    assert_eq!(response.status().as_u16(), 503);
+
+    // Act - Part 2 - Try again
+    let response = app.post_subscriptions(body).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let email_request = &app.email_server.received_requests().await.unwrap()[3];
+    let confirmation_link = app.get_confirmation_link(email_request);
+    let response = reqwest::get(confirmation_link.0).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn subscribe_returns_a_500_if_email_service_rejects_the_request() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(422))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_subscriptions(body).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 500);
+}
+
+#[tokio::test]
+async fn confirmation_email_is_kept_by_in_memory_transport() {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.email_delivery_service = EmailDeliveryService::InMemory;
+    let app = TestApp::spawn_app(config).await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        // Nothing should reach the HTTP API
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_subscriptions(body).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let emails = app.email_outbox.sent_emails();
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0].to, "ursula_le_guin@gmail.com");
+    assert!(emails[0]
+        .text_content
+        .contains("/subscriptions/confirm?subscription_token="));
+}
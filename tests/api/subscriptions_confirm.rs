@@ -5,7 +5,9 @@ use wiremock::{
 };
 use zero2prod_axum::configuration::Settings;
 
-use crate::helpers::TestApp;
+use crate::helpers::{
+    create_unconfirmed_subscriber, expire_confirmation_tokens, TestApp,
+};
 
 #[tokio::test]
 async fn confirmations_without_token_are_rejected_with_a_400() {
@@ -34,7 +36,7 @@ async fn the_link_returned_by_subscribe_returns_a_200_if_called() {
         .mount(&app.email_server)
         .await;
 
-    app.post_subscriptions(body.into()).await;
+    app.post_subscriptions(body).await;
     let email_request = &app.email_server.received_requests().await.unwrap()[0];
     let confirmation_links = app.get_confirmation_link(email_request);
     // Act
@@ -101,3 +103,18 @@ async fn clicking_on_the_confirmation_link_confirms_a_subscriber_in_db() {
         .await
         .expect("Failed to fetch saved subscription.");
 }
+
+#[tokio::test]
+async fn expired_confirmation_links_are_rejected_with_a_410() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let confirmation_link = create_unconfirmed_subscriber(&app).await;
+    expire_confirmation_tokens(&app).await;
+
+    // Act
+    let response = reqwest::get(confirmation_link.0).await.unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 410);
+    assert!(response.text().await.unwrap().contains("expired"));
+}
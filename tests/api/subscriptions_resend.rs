@@ -0,0 +1,182 @@
+//! tests/api/subscriptions_resend.rs
+
+use wiremock::matchers::{any, method, path};
+use wiremock::{Mock, ResponseTemplate};
+use zero2prod_axum::configuration::Settings;
+use zero2prod_axum::pending_subscribers_cleanup::delete_expired_pending_subscribers;
+
+use crate::helpers::{
+    create_confirmed_subscriber, create_unconfirmed_subscriber,
+    expire_confirmation_tokens, TestApp,
+};
+
+const RESEND_BODY: &str = "email=ursula_le_guin%40gmail.com";
+
+#[tokio::test]
+async fn resend_issues_a_fresh_confirmation_link() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    let old_link = create_unconfirmed_subscriber(&app).await;
+    expire_confirmation_tokens(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_resend_confirmation(RESEND_BODY).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let email_request = app
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let new_link = app.get_confirmation_link(&email_request);
+    assert_ne!(old_link.0, new_link.0);
+    // Old token is gone, the new one works
+    let response = reqwest::get(old_link.0).await.unwrap();
+    assert_eq!(response.status().as_u16(), 404);
+    let response = reqwest::get(new_link.0).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn resend_maps_email_failures_to_503_and_500() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_unconfirmed_subscriber(&app).await;
+
+    // Act - Part 1 - Email service unavailable
+    expire_confirmation_tokens(&app).await;
+    let guard = Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(3)
+        .mount_as_scoped(&app.email_server)
+        .await;
+    let response = app.post_resend_confirmation(RESEND_BODY).await;
+    drop(guard);
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 503);
+
+    // Act - Part 2 - Email rejected
+    expire_confirmation_tokens(&app).await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(422))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+    let response = app.post_resend_confirmation(RESEND_BODY).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 500);
+}
+
+#[tokio::test]
+async fn resend_is_rate_limited() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_unconfirmed_subscriber(&app).await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act - confirmation email was sent just now
+    let response = app.post_resend_confirmation(RESEND_BODY).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 429);
+    assert!(response.headers().contains_key("Retry-After"));
+}
+
+#[tokio::test]
+async fn resend_does_not_send_emails_to_unknown_or_confirmed_addresses() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+    expire_confirmation_tokens(&app).await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let confirmed = app.post_resend_confirmation(RESEND_BODY).await;
+    let unknown = app
+        .post_resend_confirmation("email=unknown%40gmail.com")
+        .await;
+
+    // Assert
+    assert_eq!(confirmed.status().as_u16(), 200);
+    assert_eq!(unknown.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn resend_returns_400_for_invalid_email() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let response = app.post_resend_confirmation("email=not-an-email").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn expired_pending_subscribers_are_deleted_after_grace_period() {
+    // Arrange
+    let config = Settings::load_configuration().unwrap();
+    let mut settings = config.subscriptions.clone();
+    settings.confirmation_token_ttl = 60 * 60;
+    settings.cleanup_grace_period = 60 * 60;
+    let app = TestApp::spawn_app(config).await;
+    create_unconfirmed_subscriber(&app).await;
+
+    // Act - Part 1 - Token is fresh
+    let deleted = delete_expired_pending_subscribers(&app.pool, &settings)
+        .await
+        .unwrap();
+    assert_eq!(deleted, 0);
+
+    // Act - Part 2 - Token expired long ago
+    expire_confirmation_tokens(&app).await;
+    let deleted = delete_expired_pending_subscribers(&app.pool, &settings)
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(deleted, 1);
+}
+
+#[tokio::test]
+async fn confirmed_subscribers_are_never_cleaned_up() {
+    // Arrange
+    let config = Settings::load_configuration().unwrap();
+    let settings = config.subscriptions.clone();
+    let app = TestApp::spawn_app(config).await;
+    create_confirmed_subscriber(&app).await;
+    expire_confirmation_tokens(&app).await;
+
+    // Act
+    let deleted = delete_expired_pending_subscribers(&app.pool, &settings)
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(deleted, 0);
+}
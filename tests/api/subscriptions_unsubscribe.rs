@@ -0,0 +1,186 @@
+//! tests/api/subscriptions_unsubscribe.rs
+
+use wiremock::matchers::{any, method, path};
+use wiremock::{Mock, ResponseTemplate};
+use zero2prod_axum::configuration::Settings;
+
+use crate::helpers::{
+    create_confirmed_subscriber, newsletter_request_body, TestApp,
+};
+
+#[tokio::test]
+async fn newsletters_carry_list_unsubscribe_headers() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    publish_newsletter(&app).await;
+
+    // Assert
+    let email_request = app.email_server.received_requests().await.unwrap();
+    let body: serde_json::Value =
+        serde_json::from_slice(&email_request.last().unwrap().body).unwrap();
+    let headers = body["headers"].as_array().unwrap();
+    assert!(headers.iter().any(|h| h["name"] == "List-Unsubscribe-Post"
+        && h["value"] == "List-Unsubscribe=One-Click"));
+    let link = app.get_unsubscribe_link(email_request.last().unwrap());
+    assert_eq!(link.path(), "/subscriptions/unsubscribe");
+}
+
+#[tokio::test]
+async fn unsubscribe_page_asks_for_confirmation() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+    let link = unsubscribe_link(&app).await;
+
+    // Act
+    let response = reqwest::get(link).await.unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let html_page = response.text().await.unwrap();
+    assert!(html_page.contains("ursula_le_guin@gmail.com"));
+    assert!(html_page.contains(r#"method="post""#));
+    // Just looking at the page doesn't unsubscribe
+    assert_eq!(subscriber_status(&app).await, "confirmed");
+}
+
+#[tokio::test]
+async fn unsubscribe_returns_404_for_unknown_token() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let response = reqwest::get(format!(
+        "{}/subscriptions/unsubscribe?unsubscribe_token={}",
+        app.address,
+        "a".repeat(25)
+    ))
+    .await
+    .unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn unsubscribe_returns_400_for_malformed_token() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let response = reqwest::get(format!(
+        "{}/subscriptions/unsubscribe?unsubscribe_token=short",
+        app.address
+    ))
+    .await
+    .unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn one_click_unsubscribe_stops_newsletter_delivery() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+    let link = unsubscribe_link(&app).await;
+
+    // Act - Part 1 - RFC 8058 one-click request, sent by mail clients
+    let response = reqwest::Client::new()
+        .post(link.clone())
+        .form(&[("List-Unsubscribe", "One-Click")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(subscriber_status(&app).await, "unsubscribed");
+
+    // Act - Part 2 - Unsubscribing twice is fine
+    let response = reqwest::Client::new().post(link).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Act - Part 3 - No newsletter for unsubscribed
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+    publish_newsletter(&app).await;
+}
+
+#[tokio::test]
+async fn unsubscribed_can_subscribe_again() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    create_confirmed_subscriber(&app).await;
+    let link = unsubscribe_link(&app).await;
+    reqwest::Client::new()
+        .post(link)
+        .send()
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com")
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(subscriber_status(&app).await, "pending_confirmation");
+}
+
+// ───── Helpers ──────────────────────────────────────────────────────────── //
+
+async fn publish_newsletter(app: &TestApp) {
+    let response = app.post_newsletters(newsletter_request_body()).await;
+    assert_eq!(response.status().as_u16(), 202);
+    app.dispatch_all_pending_emails().await;
+}
+
+async fn unsubscribe_link(app: &TestApp) -> String {
+    let token: String = app
+        .pool
+        .get()
+        .await
+        .unwrap()
+        .query_one("SELECT unsubscribe_token FROM unsubscribe_tokens", &[])
+        .await
+        .expect("Failed to fetch unsubscribe token.")
+        .get(0);
+    format!(
+        "{}/subscriptions/unsubscribe?unsubscribe_token={}",
+        app.address, token
+    )
+}
+
+async fn subscriber_status(app: &TestApp) -> String {
+    app.pool
+        .get()
+        .await
+        .unwrap()
+        .query_one("SELECT status FROM subscriptions", &[])
+        .await
+        .expect("Failed to fetch subscriber status.")
+        .get(0)
+}
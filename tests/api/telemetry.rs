@@ -0,0 +1,60 @@
+//! tests/api/telemetry.rs
+use zero2prod_axum::configuration::{LogFormat, Settings, TelemetrySettings};
+
+use crate::helpers::{capture_logs, TestApp};
+
+const INVALID_SUBSCRIPTION: &str = "name=&email=ursula_le_guin%40gmail.com";
+
+#[tokio::test]
+async fn request_logs_are_json_objects_with_their_span() {
+    // Arrange
+    let settings = TelemetrySettings {
+        log_format: LogFormat::Json,
+        ..Default::default()
+    };
+    let (logs, _guard) = capture_logs(&settings);
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let response = app.post_subscriptions(INVALID_SUBSCRIPTION).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+    let logs = logs.contents();
+    let events: Vec<serde_json::Value> = logs
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let event = events
+        .iter()
+        .find(|e| {
+            e["message"]
+                .as_str()
+                .is_some_and(|m| m.starts_with("Bad request"))
+        })
+        .expect("No log of the rejected subscription");
+    assert_eq!(event["level"], "WARN");
+    assert_eq!(event["span"]["name"], "Adding a new subscriber");
+    assert_eq!(
+        event["span"]["subscriber_email"],
+        "ursula_le_guin@gmail.com"
+    );
+}
+
+#[tokio::test]
+async fn logs_below_the_filter_level_are_dropped() {
+    // Arrange
+    let settings = TelemetrySettings {
+        log_filter: "error".to_string(),
+        ..Default::default()
+    };
+    let (logs, _guard) = capture_logs(&settings);
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let response = app.post_subscriptions(INVALID_SUBSCRIPTION).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+    assert!(!logs.contents().contains("Bad request"));
+}
@@ -0,0 +1,373 @@
+//! tests/api/two_factor.rs
+use sha2::{Digest, Sha256};
+use time::Duration;
+use zero2prod_axum::configuration::Settings;
+use zero2prod_axum::validation::totp::TotpSecret;
+
+use crate::helpers::{assert_is_redirect_to, TestApp};
+
+/// Text between `start` and the next `<` of the first match.
+fn extract_after<'a>(html: &'a str, start: &str) -> &'a str {
+    html.split(start).nth(1).unwrap().split('<').next().unwrap()
+}
+
+/// Turn 2FA on for the logged in user. Returns the secret and the
+/// recovery codes.
+async fn enable_two_factor(app: &TestApp) -> (TotpSecret, Vec<String>) {
+    let html_page = app
+        .post_admin_two_factor("setup", "")
+        .await
+        .text()
+        .await
+        .unwrap();
+    let secret = TotpSecret::parse(extract_after(
+        &html_page,
+        r#"<code id="totp-secret">"#,
+    ))
+    .unwrap();
+    let html_page = app
+        .post_admin_two_factor("enable", &secret.code_at(app.clock.now()))
+        .await
+        .text()
+        .await
+        .unwrap();
+    let codes = html_page
+        .split(r#"<ul id="recovery-codes">"#)
+        .nth(1)
+        .unwrap()
+        .split("</ul>")
+        .next()
+        .unwrap()
+        .split("<code>")
+        .skip(1)
+        .map(|code| code.split('<').next().unwrap().to_string())
+        .collect();
+    // The code used to enable 2FA can't be used again.
+    app.clock.advance(Duration::seconds(30));
+    (secret, codes)
+}
+
+async fn log_in_again(app: &TestApp) -> reqwest::Response {
+    app.post_logout().await;
+    app.login().await
+}
+
+#[tokio::test]
+async fn setup_shows_the_secret_but_does_not_enforce_it_yet() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+
+    // Act
+    let response = app.post_admin_two_factor("setup", "").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let html_page = response.text().await.unwrap();
+    assert!(html_page.contains("<svg"));
+    assert!(html_page.contains(&format!(
+        "otpauth://totp/zero2prod:{}?secret=",
+        app.test_user.username
+    )));
+    let response = log_in_again(&app).await;
+    assert_is_redirect_to(&response, "/admin/dashboard");
+}
+
+#[tokio::test]
+async fn recovery_codes_are_shown_once_when_2fa_is_enabled() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+
+    // Act
+    let (_, codes) = enable_two_factor(&app).await;
+
+    // Assert
+    assert_eq!(codes.len(), 10);
+    let html_page = app.get_admin_two_factor_html().await;
+    assert!(html_page.contains("You have 10 unused recovery codes."));
+    assert!(!html_page.contains(&codes[0]));
+    assert_eq!(
+        app.audit_actions(app.test_user.user_id).await,
+        vec![("2fa_enabled".to_string(), "".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn wrong_code_does_not_enable_2fa() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+    app.post_admin_two_factor("setup", "").await;
+
+    // Act
+    let response = app.post_admin_two_factor("enable", "000000").await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/2fa");
+    let html_page = app.get_admin_two_factor_html().await;
+    assert!(html_page.contains("please start the setup again"));
+    assert!(html_page.contains("Two-factor authentication is off."));
+}
+
+#[tokio::test]
+async fn login_with_2fa_needs_a_code() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+    let (secret, _) = enable_two_factor(&app).await;
+
+    // Act - Part 1 - The password alone doesn't start a session
+    let response = log_in_again(&app).await;
+    assert_is_redirect_to(&response, "/login/2fa");
+    assert_is_redirect_to(&app.get_admin_dashboard().await, "/login");
+    assert_eq!(app.get_login_two_factor().await.status().as_u16(), 200);
+
+    // Act - Part 2 - The code does
+    let response = app
+        .post_login_two_factor(&secret.code_at(app.clock.now()))
+        .await;
+    assert_is_redirect_to(&response, "/admin/dashboard");
+    assert_eq!(app.get_admin_dashboard().await.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn totp_code_is_accepted_only_once() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+    let (secret, _) = enable_two_factor(&app).await;
+    let code = secret.code_at(app.clock.now());
+    log_in_again(&app).await;
+    app.post_login_two_factor(&code).await;
+
+    // Act
+    log_in_again(&app).await;
+    let response = app.post_login_two_factor(&code).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login/2fa");
+    let html_page = app.get_login_two_factor().await.text().await.unwrap();
+    assert!(html_page.contains("The code is not valid."));
+    assert_is_redirect_to(&app.get_admin_dashboard().await, "/login");
+}
+
+#[tokio::test]
+async fn recovery_code_works_once() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+    let (_, codes) = enable_two_factor(&app).await;
+
+    // Act - Part 1 - First use
+    log_in_again(&app).await;
+    let response = app.post_login_two_factor(&codes[3].to_uppercase()).await;
+    assert_is_redirect_to(&response, "/admin/dashboard");
+    let html_page = app.get_admin_two_factor_html().await;
+    assert!(html_page.contains("You have 9 unused recovery codes."));
+
+    // Act - Part 2 - Second use
+    log_in_again(&app).await;
+    let response = app.post_login_two_factor(&codes[3]).await;
+    assert_is_redirect_to(&response, "/login/2fa");
+}
+
+#[tokio::test]
+async fn recovery_codes_are_not_stored_as_plain_hashes() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+
+    // Act
+    let (_, codes) = enable_two_factor(&app).await;
+
+    // Assert
+    let stored: Vec<String> = app
+        .pool
+        .get()
+        .await
+        .unwrap()
+        .query(
+            "SELECT code_hash FROM recovery_codes WHERE user_id = $1",
+            &[&app.test_user.user_id],
+        )
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+    assert_eq!(stored.len(), codes.len());
+    for code in &codes {
+        let sha256 = hex::encode(Sha256::digest(code.as_bytes()));
+        assert!(!stored.contains(&sha256));
+    }
+}
+
+#[tokio::test]
+async fn too_many_wrong_codes_restart_the_login() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+    let (secret, _) = enable_two_factor(&app).await;
+    log_in_again(&app).await;
+
+    // Act
+    for _ in 0..4 {
+        let response = app.post_login_two_factor("000000").await;
+        assert_is_redirect_to(&response, "/login/2fa");
+    }
+    let response = app.post_login_two_factor("000000").await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    assert!(app
+        .get_login_html()
+        .await
+        .contains("Too many wrong codes, please log in again."));
+    // The right code doesn't help anymore
+    let response = app
+        .post_login_two_factor(&secret.code_at(app.clock.now()))
+        .await;
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn wrong_codes_count_towards_the_login_lockout() {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.lockout.max_failures_per_username = 3;
+    let app = TestApp::spawn_app(config).await;
+    app.login().await;
+    let (secret, _) = enable_two_factor(&app).await;
+    log_in_again(&app).await;
+
+    // Act - Restarting the challenge doesn't reset the failures
+    for _ in 0..2 {
+        app.post_login_two_factor("000000").await;
+    }
+    let response = app.login().await;
+    assert_is_redirect_to(&response, "/login/2fa");
+    app.post_login_two_factor("000000").await;
+
+    // Assert
+    let response = app.login().await;
+    assert_is_redirect_to(&response, "/login");
+    assert!(app
+        .get_login_html()
+        .await
+        .contains("Too many failed login attempts, please try again later."));
+    let response = app
+        .post_login_two_factor(&secret.code_at(app.clock.now()))
+        .await;
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn pending_login_expires() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+    let (secret, _) = enable_two_factor(&app).await;
+    log_in_again(&app).await;
+
+    // Act
+    app.clock.advance(Duration::minutes(6));
+    let response = app
+        .post_login_two_factor(&secret.code_at(app.clock.now()))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    assert!(app
+        .get_login_html()
+        .await
+        .contains("Your login has expired, please log in again."));
+    assert_is_redirect_to(&app.get_admin_dashboard().await, "/login");
+}
+
+#[tokio::test]
+async fn second_step_needs_the_password_first() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+
+    // Act
+    let form = app.get_login_two_factor().await;
+    let response = app.post_login_two_factor("123456").await;
+
+    // Assert
+    assert_is_redirect_to(&form, "/login");
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn disabling_2fa_needs_a_current_code() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+    let (secret, _) = enable_two_factor(&app).await;
+
+    // Act - Part 1 - Wrong code
+    let response = app.post_admin_two_factor("disable", "000000").await;
+    assert_is_redirect_to(&response, "/admin/2fa");
+    let html_page = app.get_admin_two_factor_html().await;
+    assert!(html_page.contains("The code is not valid."));
+    assert!(html_page.contains("Two-factor authentication is on."));
+
+    // Act - Part 2 - Right code
+    let response = app
+        .post_admin_two_factor("disable", &secret.code_at(app.clock.now()))
+        .await;
+    assert_is_redirect_to(&response, "/admin/2fa");
+    let html_page = app.get_admin_two_factor_html().await;
+    assert!(html_page.contains("Two-factor authentication is now off."));
+
+    // Act - Part 3 - The password is enough again
+    let response = log_in_again(&app).await;
+    assert_is_redirect_to(&response, "/admin/dashboard");
+}
+
+#[tokio::test]
+async fn wrong_current_codes_lock_the_2fa_settings() {
+    // Arrange
+    let mut config = Settings::load_configuration().unwrap();
+    config.lockout.max_failures_per_username = 3;
+    let app = TestApp::spawn_app(config).await;
+    app.login().await;
+    let (secret, _) = enable_two_factor(&app).await;
+    for _ in 0..3 {
+        app.post_admin_two_factor("disable", "000000").await;
+    }
+
+    // Act
+    let response = app
+        .post_admin_two_factor("disable", &secret.code_at(app.clock.now()))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/2fa");
+    let html_page = app.get_admin_two_factor_html().await;
+    assert!(html_page
+        .contains("Too many failed login attempts, please try again later."));
+    assert!(html_page.contains("Two-factor authentication is on."));
+}
+
+#[tokio::test]
+async fn new_recovery_codes_replace_the_old_ones() {
+    // Arrange
+    let app = TestApp::spawn_app(Settings::load_configuration().unwrap()).await;
+    app.login().await;
+    let (_, old_codes) = enable_two_factor(&app).await;
+
+    // Act
+    let response = app
+        .post_admin_two_factor("recovery-codes", &old_codes[0])
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let html_page = response.text().await.unwrap();
+    assert!(html_page.contains("Your old recovery codes no longer work."));
+    log_in_again(&app).await;
+    let response = app.post_login_two_factor(&old_codes[1]).await;
+    assert_is_redirect_to(&response, "/login/2fa");
+}